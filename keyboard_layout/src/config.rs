@@ -2,11 +2,11 @@ use crate::keyboard::KeyboardYAML;
 use crate::neo_layout_generator::BaseLayoutYAML;
 
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::{fs::File, str::FromStr};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct LayoutConfig {
     pub keyboard: KeyboardYAML,
     pub base_layout: BaseLayoutYAML,
@@ -26,6 +26,16 @@ impl LayoutConfig {
         self.base_layout.validate()?;
         Ok(())
     }
+
+    /// Serialize the config into a YAML string that can be read with [`LayoutConfig::from_str`].
+    pub fn to_yaml_string(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Serialize the config into a JSON string.
+    pub fn to_json_string(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
 }
 
 impl FromStr for LayoutConfig {
@@ -37,3 +47,26 @@ impl FromStr for LayoutConfig {
         Ok(cfg)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::Keyboard;
+
+    #[test]
+    fn yaml_round_trip() {
+        let cfg_str = include_str!("../../config/keyboard/standard.yml");
+        let cfg = LayoutConfig::from_str(cfg_str).unwrap();
+
+        let round_tripped = LayoutConfig::from_str(&cfg.to_yaml_string().unwrap()).unwrap();
+        assert_eq!(cfg, round_tripped);
+
+        let from_json: LayoutConfig = serde_json::from_str(&cfg.to_json_string().unwrap()).unwrap();
+        assert_eq!(cfg, from_json);
+
+        let keyboard = Keyboard::from_yaml_object(cfg.keyboard);
+        let keyboard_round_tripped =
+            Keyboard::from_yaml_str(&keyboard.to_yaml_string().unwrap()).unwrap();
+        assert_eq!(keyboard.keys, keyboard_round_tripped.keys);
+    }
+}
@@ -1,8 +1,8 @@
 use crate::key::Hand;
 use crate::keyboard::Keyboard;
-use crate::layout::{LayerModifierLocations, Layout};
+use crate::layout::{LayerModifierLocations, Layout, EMPTY_SYMBOL};
 use crate::layout_generator::LayoutGenerator;
-use crate::neo_layout_generator::BaseLayoutYAML;
+use crate::neo_layout_generator::{base_layout_key_symbols, BaseLayoutYAML};
 
 use ahash::{AHashMap, AHashSet};
 use anyhow::Result;
@@ -36,7 +36,7 @@ impl GroupedLayoutGenerator {
             .keys
             .iter()
             .flatten()
-            .map(|layers| layers.iter().filter_map(|l| l.chars().next()).collect())
+            .map(|layers| base_layout_key_symbols(layers))
             .collect();
         let fixed_keys: Vec<bool> = base.fixed_keys.iter().flatten().cloned().collect();
 
@@ -52,6 +52,7 @@ impl GroupedLayoutGenerator {
                         .iter()
                         .enumerate()
                         .step_by(base.grouped_layers as usize)
+                        .filter(|(_, c)| **c != EMPTY_SYMBOL)
                         .for_each(|(layer, c)| {
                             permutable_key_map
                                 .entry(*c)
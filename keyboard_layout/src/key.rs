@@ -1,12 +1,12 @@
 //! This module provides structs for representing physical properties of keys in a keyboard
 
 use ahash::AHashMap;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::slice;
 
 /// Row and columnar location on the keyboard
-#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, Hash, Debug)]
 pub struct MatrixPosition(
     /// Index of column
     pub u8,
@@ -15,7 +15,7 @@ pub struct MatrixPosition(
 );
 
 /// 2D position on the keyboard
-#[derive(Clone, Copy, Deserialize, PartialEq, Debug)]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Debug)]
 pub struct Position(
     /// Horizontal positioning
     pub f64,
@@ -39,7 +39,7 @@ impl Default for Position {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[repr(u8)]
 pub enum Finger {
     Thumb,  // 0
@@ -63,7 +63,7 @@ impl Finger {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[repr(u8)]
 pub enum Hand {
     Left,  // 0
@@ -293,7 +293,7 @@ impl<T: Copy + fmt::Display> fmt::Display for HandFingerMap<T> {
 /// The [`Key`] struct represents a physical key on the keyboard. It provides various information about the location
 /// of the key it represents and how it is (supposed to be) used, e.g. which hand and finger shall press it, how
 /// "uncomfortable" it is to reach it (in terms of a cost valua), or if it forces the hand off the home row.
-#[derive(Default, PartialEq, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug)]
 pub struct Key {
     /// Hand of the finger used to press the key
     pub hand: Hand,
@@ -315,4 +315,35 @@ pub struct Key {
 
     /// How strongly does the hand need to move away from the home row (start position) horizontally and vertically
     pub unbalancing: Position,
+
+    /// Width of the key in units of a standard key ("1u"), e.g. 2.0 for a 2u thumb key
+    pub width: f64,
+}
+
+impl Default for Key {
+    fn default() -> Self {
+        Self {
+            hand: Hand::default(),
+            finger: Finger::default(),
+            matrix_position: MatrixPosition::default(),
+            position: Position::default(),
+            symmetry_index: 0,
+            cost: 0.0,
+            unbalancing: Position::default(),
+            width: 1.0,
+        }
+    }
+}
+
+impl Key {
+    /// The distance from the given position to the closest point of the key's horizontal center
+    /// line. Wide keys can be pressed off-center, so a finger does not need to reach their center.
+    /// `key_unit` is the width of a standard key in the units of the keys' positions.
+    pub fn reach_distance(&self, from: &Position, key_unit: f64) -> f64 {
+        let half_extra_width = 0.5 * (self.width - 1.0).max(0.0) * key_unit;
+        let dx = ((self.position.0 - from.0).abs() - half_extra_width).max(0.0);
+        let dy = self.position.1 - from.1;
+
+        (dx * dx + dy * dy).sqrt()
+    }
 }
@@ -4,10 +4,123 @@ use crate::key::{Finger, Hand, HandFingerMap, Key, MatrixPosition, Position};
 
 use ahash::{AHashMap, AHashSet};
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use std::fs::File;
 use thiserror::Error;
 
+/// Options for generating a graphical representation of a keyboard.
+///
+/// With the default cell options, the `plot_template` of the keyboard's configuration is used.
+/// Otherwise, a grid is generated from the keys' matrix positions. The label options apply
+/// to both.
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(default)]
+pub struct PlotOptions {
+    /// Width of each key cell (in characters). Longer labels are truncated.
+    /// If `None`, the width of the widest label is used.
+    pub cell_width: Option<usize>,
+    /// Whether to draw borders around the keys
+    pub borders: bool,
+    /// Print each row of keys on a single line without separating lines between rows
+    pub compact: bool,
+    /// Display labels of symbols, overriding the defaults for common special keys
+    /// (see [`default_label`])
+    pub labels: AHashMap<char, String>,
+    /// Label layer modifier keys with the (first) layer they activate, e.g. "L2".
+    /// Note that the `plot_template` assumes single-character labels.
+    pub label_layer_modifiers: bool,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            cell_width: None,
+            borders: true,
+            compact: false,
+            labels: AHashMap::default(),
+            label_layer_modifiers: false,
+        }
+    }
+}
+
+impl PlotOptions {
+    /// If the options correspond to the default output generated from the `plot_template`
+    pub fn uses_template(&self) -> bool {
+        let default = Self::default();
+        self.cell_width == default.cell_width
+            && self.borders == default.borders
+            && self.compact == default.compact
+    }
+
+    /// The display label of a symbol (if it is not displayed as is)
+    pub fn label(&self, c: char) -> Option<String> {
+        self.labels
+            .get(&c)
+            .cloned()
+            .or_else(|| default_label(c).map(|l| l.to_string()))
+    }
+}
+
+/// The default display label of common special keys (if any)
+pub fn default_label(c: char) -> Option<&'static str> {
+    let label = match c {
+        ' ' => "␣",
+        '\n' => "\u{23ce}",
+        '\t' => "\u{21e5}",
+        '\u{1b}' => "\u{2327}",
+        _ => return None,
+    };
+
+    Some(label)
+}
+
+/// Number of characters of a string that are visible in a terminal, i.e. ignoring ANSI escape sequences
+fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in s.chars() {
+        if in_escape {
+            in_escape = c != 'm';
+        } else if c == '\u{1b}' {
+            in_escape = true;
+        } else {
+            width += 1;
+        }
+    }
+
+    width
+}
+
+/// Truncate or pad a string to the given visible width (keeping ANSI escape sequences intact)
+fn fit_to_width(s: &str, width: usize) -> String {
+    let mut res = String::with_capacity(s.len() + width);
+    let mut visible = 0;
+    let mut in_escape = false;
+    let mut truncated = false;
+    for c in s.chars() {
+        if in_escape {
+            in_escape = c != 'm';
+            res.push(c);
+        } else if c == '\u{1b}' {
+            in_escape = true;
+            res.push(c);
+        } else if visible < width {
+            visible += 1;
+            res.push(c);
+        } else {
+            truncated = true;
+        }
+    }
+
+    // make sure that styles of a truncated label do not leak into the rest of the plot
+    if truncated && res.contains('\u{1b}') {
+        res.push_str("\u{1b}[0m");
+    }
+
+    res.push_str(&" ".repeat(width - visible));
+    res
+}
+
 #[derive(Error, Debug)]
 pub enum KeyboardError {
     #[error("Invalid keyboard: Not the same number of keys in each keyboard list.")]
@@ -16,6 +129,117 @@ pub enum KeyboardError {
     DuplicateMatrixPositions,
     #[error("Invalid keyboard: Duplicate `positions`.")]
     DuplicatePositions,
+    #[error("Invalid keyboard: More than 256 keys.")]
+    TooManyKeys,
+    #[error("Invalid keyboard: Cluster '{0}' contains the unknown matrix position {1:?}.")]
+    UnknownClusterKey(String, MatrixPosition),
+    #[error(
+        "Invalid keyboard: Cluster '{0}' is typed by {1} distinct fingers (at most {2} allowed)."
+    )]
+    TooManyClusterFingers(String, usize, usize),
+    #[error("Invalid keyboard: Home key of {0:?} {1:?} has the unknown matrix position {2:?}.")]
+    UnknownHomeKey(Hand, Finger, MatrixPosition),
+    #[error("Invalid keyboard: Home key {2:?} of {0:?} {1:?} is not typed by that finger.")]
+    WrongHomeKeyFinger(Hand, Finger, MatrixPosition),
+}
+
+/// A named group of keys (e.g. a thumb cluster) that constraints can refer to.
+#[derive(Clone, Deserialize, Serialize, PartialEq, Debug)]
+pub struct KeyCluster {
+    /// Name of the cluster
+    pub name: String,
+    /// Matrix positions of the cluster's keys
+    pub keys: Vec<MatrixPosition>,
+    /// Maximum number of distinct fingers (of both hands) the cluster may be typed with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fingers: Option<usize>,
+}
+
+/// Checks that the clusters only contain existing keys and respect their maximum number of
+/// distinct fingers.
+fn validate_clusters(
+    clusters: &[KeyCluster],
+    keys: &[(MatrixPosition, Hand, Finger)],
+) -> Result<()> {
+    for cluster in clusters {
+        let mut fingers = AHashSet::default();
+        for pos in cluster.keys.iter() {
+            match keys.iter().find(|(p, _, _)| p == pos) {
+                Some((_, hand, finger)) => {
+                    fingers.insert((*hand, *finger));
+                }
+                None => {
+                    return Err(KeyboardError::UnknownClusterKey(cluster.name.clone(), *pos).into())
+                }
+            }
+        }
+        if let Some(max_fingers) = cluster.max_fingers {
+            if fingers.len() > max_fingers {
+                return Err(KeyboardError::TooManyClusterFingers(
+                    cluster.name.clone(),
+                    fingers.len(),
+                    max_fingers,
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the home keys exist and are typed by the finger they are the home key of.
+fn validate_home_keys(
+    home_keys: &AHashMap<Hand, AHashMap<Finger, MatrixPosition>>,
+    keys: &[(MatrixPosition, Hand, Finger)],
+) -> Result<()> {
+    for (hand, fingers) in home_keys {
+        for (finger, pos) in fingers {
+            match keys.iter().find(|(p, _, _)| p == pos) {
+                Some((_, h, f)) if h == hand && f == finger => {}
+                Some(_) => {
+                    return Err(KeyboardError::WrongHomeKeyFinger(*hand, *finger, *pos).into())
+                }
+                None => return Err(KeyboardError::UnknownHomeKey(*hand, *finger, *pos).into()),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The home keys as a [`HandFingerMap`] (fingers without an explicit home key map to `None`).
+fn home_key_map(
+    home_keys: &AHashMap<Hand, AHashMap<Finger, MatrixPosition>>,
+) -> HandFingerMap<Option<MatrixPosition>> {
+    let home_keys = home_keys
+        .iter()
+        .map(|(hand, fingers)| {
+            let fingers = fingers.iter().map(|(f, pos)| (*f, Some(*pos))).collect();
+            (*hand, fingers)
+        })
+        .collect();
+
+    HandFingerMap::with_hashmap(&home_keys, None)
+}
+
+/// The resting positions of the fingers, where fingers with an explicit home key rest on
+/// that key's position.
+fn resting_positions(
+    keys: &[Key],
+    finger_resting_positions: &AHashMap<Hand, AHashMap<Finger, Position>>,
+    home_keys: &HandFingerMap<Option<MatrixPosition>>,
+) -> HandFingerMap<Position> {
+    let mut positions = HandFingerMap::with_hashmap(finger_resting_positions, Position::default());
+    for (hand, finger) in HandFingerMap::<Position>::keys() {
+        if let Some(pos) = home_keys.get(&hand, &finger) {
+            if let Some(key) = keys.iter().find(|k| k.matrix_position == *pos) {
+                positions.set(&hand, &finger, key.position);
+            }
+        }
+    }
+
+    positions
 }
 
 /// The index of a [`Key`] in the `keys` vec of a [`Keyboard`]
@@ -26,15 +250,158 @@ pub type KeyIndex = u8;
 pub struct Keyboard {
     /// The keys of the keyboard
     pub keys: Vec<Key>,
+    /// Resting positions of the fingers (the position of the home key for fingers with an
+    /// explicit home key)
     pub finger_resting_positions: HandFingerMap<Position>,
+    /// Explicitly configured home keys of the fingers (see [`Keyboard::home_key`])
+    pub home_keys: HandFingerMap<Option<MatrixPosition>>,
+    /// Named groups of keys
+    pub clusters: Vec<KeyCluster>,
+    plot_template: String,
+    plot_template_short: String,
+}
+
+/// A builder for constructing a [`Keyboard`] programmatically (instead of from a YAML configuration).
+/// The resulting keyboard can be used like any other, e.g. for a [`NeoLayoutGenerator`](crate::neo_layout_generator::NeoLayoutGenerator).
+#[derive(Clone, Debug, Default)]
+pub struct KeyboardBuilder {
+    keys: Vec<Key>,
+    finger_resting_positions: AHashMap<Hand, AHashMap<Finger, Position>>,
+    home_keys: AHashMap<Hand, AHashMap<Finger, MatrixPosition>>,
+    clusters: Vec<KeyCluster>,
     plot_template: String,
     plot_template_short: String,
 }
 
+impl KeyboardBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key at given 2D `position` and matrix position (`row`, `col`) that is pressed
+    /// with given `finger` and `hand`.
+    ///
+    /// The key is not symmetrical to any other key and does not unbalance the hand.
+    /// Use [`KeyboardBuilder::key`] for full control over all properties.
+    pub fn add_key(
+        self,
+        position: Position,
+        finger: Finger,
+        hand: Hand,
+        row: u8,
+        col: u8,
+        cost: f64,
+    ) -> Self {
+        let symmetry_index = self.keys.len() as u8;
+        self.key(Key {
+            hand,
+            finger,
+            matrix_position: MatrixPosition(col, row),
+            position,
+            symmetry_index,
+            cost,
+            unbalancing: Position::default(),
+            width: 1.0,
+        })
+    }
+
+    /// Add a key with all its properties.
+    pub fn key(mut self, key: Key) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Set the resting position of a finger (defaults to `Position(0.0, 0.0)`).
+    pub fn finger_resting_position(
+        mut self,
+        hand: Hand,
+        finger: Finger,
+        position: Position,
+    ) -> Self {
+        self.finger_resting_positions
+            .entry(hand)
+            .or_default()
+            .insert(finger, position);
+        self
+    }
+
+    /// Set the home key of a finger, overriding the key inferred from its resting position
+    /// (see [`Keyboard::home_key`]).
+    pub fn home_key(mut self, hand: Hand, finger: Finger, matrix_position: MatrixPosition) -> Self {
+        self.home_keys
+            .entry(hand)
+            .or_default()
+            .insert(finger, matrix_position);
+        self
+    }
+
+    /// Add a named group of keys (see [`KeyCluster`]).
+    pub fn cluster(mut self, cluster: KeyCluster) -> Self {
+        self.clusters.push(cluster);
+        self
+    }
+
+    /// Set the template used for plotting the keyboard (see the keyboard configuration files).
+    /// Without a template, the keys are plotted in a grid.
+    pub fn plot_template(mut self, template: &str) -> Self {
+        self.plot_template = template.to_string();
+        self
+    }
+
+    /// Set the template used for compact plots of the keyboard.
+    /// Without a template, the keys are plotted in a compact grid.
+    pub fn plot_template_short(mut self, template: &str) -> Self {
+        self.plot_template_short = template.to_string();
+        self
+    }
+
+    /// Validate the keys and generate the [`Keyboard`].
+    pub fn build(self) -> Result<Keyboard> {
+        // keys are indexed by a `KeyIndex`
+        if self.keys.len() > KeyIndex::MAX as usize + 1 {
+            return Err(KeyboardError::TooManyKeys.into());
+        }
+
+        let matrix_positions: Vec<MatrixPosition> =
+            self.keys.iter().map(|k| k.matrix_position).collect();
+        if contains_duplicates(&matrix_positions) {
+            return Err(KeyboardError::DuplicateMatrixPositions.into());
+        }
+
+        let positions: Vec<Position> = self.keys.iter().map(|k| k.position).collect();
+        if contains_duplicates(&positions) {
+            return Err(KeyboardError::DuplicatePositions.into());
+        }
+
+        let cluster_keys: Vec<(MatrixPosition, Hand, Finger)> = self
+            .keys
+            .iter()
+            .map(|k| (k.matrix_position, k.hand, k.finger))
+            .collect();
+        validate_clusters(&self.clusters, &cluster_keys)?;
+        validate_home_keys(&self.home_keys, &cluster_keys)?;
+
+        let home_keys = home_key_map(&self.home_keys);
+
+        Ok(Keyboard {
+            finger_resting_positions: resting_positions(
+                &self.keys,
+                &self.finger_resting_positions,
+                &home_keys,
+            ),
+            home_keys,
+            keys: self.keys,
+            clusters: self.clusters,
+            plot_template: self.plot_template,
+            plot_template_short: self.plot_template_short,
+        })
+    }
+}
+
 /// A collection of all relevant properties for the keys on a keyboard (configuration).
 ///
 /// Corresponds to (parts of) a YAML configuration file.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct KeyboardYAML {
     matrix_positions: Vec<Vec<MatrixPosition>>,
     positions: Vec<Vec<Position>>,
@@ -43,11 +410,22 @@ pub struct KeyboardYAML {
     key_costs: Vec<Vec<f64>>,
     symmetries: Vec<Vec<u8>>,
     unbalancing_positions: Vec<Vec<Position>>,
+    /// Widths of the keys (in units of a standard key). All keys are 1u wide if omitted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    widths: Vec<Vec<f64>>,
     finger_resting_positions: AHashMap<Hand, AHashMap<Finger, Position>>,
+    #[serde(default, skip_serializing_if = "no_home_keys")]
+    home_keys: AHashMap<Hand, AHashMap<Finger, MatrixPosition>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    clusters: Vec<KeyCluster>,
     plot_template: String,
     plot_template_short: String,
 }
 
+fn no_home_keys(home_keys: &AHashMap<Hand, AHashMap<Finger, MatrixPosition>>) -> bool {
+    home_keys.is_empty()
+}
+
 /// Takes a slice of some iterable and checks whether that iterable contains
 /// duplicates of any of its elements.
 fn contains_duplicates<T: PartialEq>(v: &[T]) -> bool {
@@ -77,6 +455,9 @@ impl KeyboardYAML {
         lengths.insert(self.key_costs.concat().len());
         lengths.insert(self.symmetries.concat().len());
         lengths.insert(self.unbalancing_positions.concat().len());
+        if !self.widths.is_empty() {
+            lengths.insert(self.widths.concat().len());
+        }
         if lengths.len() > 1 {
             return Err(KeyboardError::WrongKeyNumber.into());
         }
@@ -91,14 +472,93 @@ impl KeyboardYAML {
             return Err(KeyboardError::DuplicatePositions.into());
         }
 
+        let cluster_keys: Vec<(MatrixPosition, Hand, Finger)> = flat_matrix_positions
+            .into_iter()
+            .zip(self.hands.concat())
+            .zip(self.fingers.concat())
+            .map(|((pos, hand), finger)| (pos, hand, finger))
+            .collect();
+        validate_clusters(&self.clusters, &cluster_keys)?;
+        validate_home_keys(&self.home_keys, &cluster_keys)?;
+
         Ok(())
     }
 }
 
+impl From<&Keyboard> for KeyboardYAML {
+    /// Generate a [`KeyboardYAML`] from a [`Keyboard`]. Keys are grouped into rows
+    /// according to changes of their matrix row.
+    fn from(keyboard: &Keyboard) -> Self {
+        let mut rows: Vec<Vec<&Key>> = Vec::new();
+        for key in keyboard.keys.iter() {
+            match rows.last_mut() {
+                Some(row) if row[0].matrix_position.1 == key.matrix_position.1 => row.push(key),
+                _ => rows.push(vec![key]),
+            }
+        }
+
+        fn per_row<T>(rows: &[Vec<&Key>], f: impl Fn(&Key) -> T) -> Vec<Vec<T>> {
+            rows.iter()
+                .map(|row| row.iter().map(|k| f(k)).collect())
+                .collect()
+        }
+
+        let mut finger_resting_positions: AHashMap<Hand, AHashMap<Finger, Position>> =
+            AHashMap::default();
+        let mut home_keys: AHashMap<Hand, AHashMap<Finger, MatrixPosition>> = AHashMap::default();
+        for (hand, finger) in HandFingerMap::<Position>::keys() {
+            finger_resting_positions.entry(hand).or_default().insert(
+                finger,
+                *keyboard.finger_resting_positions.get(&hand, &finger),
+            );
+            if let Some(pos) = keyboard.home_keys.get(&hand, &finger) {
+                home_keys.entry(hand).or_default().insert(finger, *pos);
+            }
+        }
+
+        Self {
+            matrix_positions: per_row(&rows, |k| k.matrix_position),
+            positions: per_row(&rows, |k| k.position),
+            hands: per_row(&rows, |k| k.hand),
+            fingers: per_row(&rows, |k| k.finger),
+            key_costs: per_row(&rows, |k| k.cost),
+            symmetries: per_row(&rows, |k| k.symmetry_index),
+            unbalancing_positions: per_row(&rows, |k| k.unbalancing),
+            widths: match keyboard.keys.iter().all(|k| k.width == 1.0) {
+                true => Vec::new(),
+                false => per_row(&rows, |k| k.width),
+            },
+            finger_resting_positions,
+            home_keys,
+            clusters: keyboard.clusters.clone(),
+            plot_template: keyboard.plot_template.clone(),
+            plot_template_short: keyboard.plot_template_short.clone(),
+        }
+    }
+}
+
+/// A [`Keyboard`] is serialized in the format of its [`KeyboardYAML`] configuration.
+impl Serialize for Keyboard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        KeyboardYAML::from(self).serialize(serializer)
+    }
+}
+
 impl Keyboard {
+    /// Serialize the [`Keyboard`] into a YAML string in the format of its configuration
+    pub fn to_yaml_string(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Serialize the [`Keyboard`] into a JSON string in the format of its configuration
+    pub fn to_json_string(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
     /// Generate a [`Keyboard`] from a [`KeyboardYAML`] object
     pub fn from_yaml_object(k: KeyboardYAML) -> Self {
-        let keys = k
+        let widths = k.widths.into_iter().flatten().chain(std::iter::repeat(1.0));
+        let keys: Vec<Key> = k
             .hands
             .into_iter()
             .flatten()
@@ -108,10 +568,14 @@ impl Keyboard {
             .zip(k.symmetries.into_iter().flatten())
             .zip(k.key_costs.into_iter().flatten())
             .zip(k.unbalancing_positions.into_iter().flatten())
+            .zip(widths)
             .map(
                 |(
-                    (((((hand, finger), matrix_position), position), symmetry_index), cost),
-                    unbalancing,
+                    (
+                        (((((hand, finger), matrix_position), position), symmetry_index), cost),
+                        unbalancing,
+                    ),
+                    width,
                 )| Key {
                     hand,
                     finger,
@@ -120,21 +584,128 @@ impl Keyboard {
                     symmetry_index,
                     cost,
                     unbalancing,
+                    width,
                 },
             )
             .collect();
 
+        let home_keys = home_key_map(&k.home_keys);
+
         Keyboard {
-            keys,
-            finger_resting_positions: HandFingerMap::with_hashmap(
+            finger_resting_positions: resting_positions(
+                &keys,
                 &k.finger_resting_positions,
-                Position::default(),
+                &home_keys,
             ),
+            home_keys,
+            keys,
+            clusters: k.clusters,
             plot_template: k.plot_template,
             plot_template_short: k.plot_template_short,
         }
     }
 
+    /// The home key of a finger: the explicitly configured home key, otherwise the finger's key
+    /// closest to its resting position.
+    pub fn home_key(&self, hand: &Hand, finger: &Finger) -> Option<&Key> {
+        let finger_keys = || {
+            self.keys
+                .iter()
+                .filter(move |k| k.hand == *hand && k.finger == *finger)
+        };
+
+        if let Some(pos) = self.home_keys.get(hand, finger) {
+            if let Some(key) = finger_keys().find(|k| k.matrix_position == *pos) {
+                return Some(key);
+            }
+        }
+
+        let resting_position = self.finger_resting_positions.get(hand, finger);
+        finger_keys()
+            .map(|k| (k.position.distance(resting_position), k))
+            .min_by(|(d1, _), (d2, _)| d1.partial_cmp(d2).unwrap())
+            .map(|(_, key)| key)
+    }
+
+    /// The index of the key that is symmetrical to the given one on the other hand, i.e. the
+    /// only key of the other hand with the same symmetry index (if there is exactly one).
+    ///
+    /// The configured symmetry indices pair the keys that metrics (e.g. symmetric handswitches)
+    /// consider to be typed with symmetrical movements. In contrast to
+    /// [`Keyboard::symmetry_map`], they also exist for keyboards that are not geometrically
+    /// symmetric and may pair other keys than the mirrored positions (e.g. the upper and lower
+    /// keys of the finger clusters of the sval keyboard).
+    pub fn mirrored_key_index(&self, key_index: usize) -> Option<usize> {
+        let key = self.keys.get(key_index)?;
+        let mut counterparts =
+            self.keys.iter().enumerate().filter(|(_, k)| {
+                k.hand == key.hand.other() && k.symmetry_index == key.symmetry_index
+            });
+
+        match (counterparts.next(), counterparts.next()) {
+            (Some((i, _)), None) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// The index of the geometrically mirrored key for each key (in the order of
+    /// [`Keyboard::keys`]), or `None` if the keyboard is not symmetric.
+    ///
+    /// The symmetry axis is the vertical line in the middle between the leftmost and the
+    /// rightmost key. A key's mirrored counterpart is the key closest to the key's position
+    /// reflected at that axis; keys on the axis may be their own counterpart. Deviations of the
+    /// coordinates of up to a tenth of the smallest distance between two keys are tolerated
+    /// (see [`Keyboard::symmetry_map_with_tolerance`]).
+    pub fn symmetry_map(&self) -> Option<Vec<usize>> {
+        let min_distance = self
+            .keys
+            .iter()
+            .enumerate()
+            .flat_map(|(i, k1)| {
+                self.keys[i + 1..]
+                    .iter()
+                    .map(move |k2| k1.position.distance(&k2.position))
+            })
+            .fold(f64::INFINITY, f64::min);
+
+        self.symmetry_map_with_tolerance(0.1 * min_distance)
+    }
+
+    /// Like [`Keyboard::symmetry_map`], but tolerates deviations of the (reflected) coordinates
+    /// of up to `tolerance` (in units of the keys' positions).
+    pub fn symmetry_map_with_tolerance(&self, tolerance: f64) -> Option<Vec<usize>> {
+        let min_x = self.keys.iter().map(|k| k.position.0).reduce(f64::min)?;
+        let max_x = self.keys.iter().map(|k| k.position.0).reduce(f64::max)?;
+        let axis = 0.5 * (min_x + max_x);
+
+        let map: Vec<usize> = self
+            .keys
+            .iter()
+            .map(|key| {
+                let reflected = Position(2.0 * axis - key.position.0, key.position.1);
+                self.keys
+                    .iter()
+                    .enumerate()
+                    .map(|(i, k)| (i, k.position.distance(&reflected)))
+                    .filter(|(_, d)| *d <= tolerance)
+                    .min_by(|(_, d1), (_, d2)| d1.partial_cmp(d2).unwrap())
+                    .map(|(i, _)| i)
+            })
+            .collect::<Option<_>>()?;
+
+        // the mirror image of the mirror image has to be the key itself
+        if map.iter().enumerate().all(|(i, j)| map[*j] == i) {
+            Some(map)
+        } else {
+            None
+        }
+    }
+
+    /// The cluster with the given name (if any).
+    pub fn cluster(&self, name: &str) -> Option<&KeyCluster> {
+        self.clusters.iter().find(|c| c.name == name)
+    }
+
     /// Generate a [`Keyboard`] from a YAML file
     pub fn from_yaml_file(filename: &str) -> Result<Self> {
         let f = File::open(filename)?;
@@ -149,7 +720,13 @@ impl Keyboard {
     }
 
     /// Plot a graphical representation of the keyboard with given key labels
+    ///
+    /// If the keyboard has no `plot_template`, the keys are plotted in a grid.
     pub fn plot(&self, key_labels: &[String]) -> String {
+        if self.plot_template.is_empty() {
+            return self.plot_grid(key_labels, &PlotOptions::default());
+        }
+
         let mut reg = handlebars::Handlebars::new();
         reg.register_escape_fn(handlebars::no_escape);
         let labels: AHashMap<usize, String> = key_labels
@@ -169,8 +746,104 @@ impl Keyboard {
         reg.render_template(&self.plot_template, &labels).unwrap()
     }
 
+    /// Plot a graphical representation of the keyboard with given key labels and plot options
+    pub fn plot_with_options(&self, key_labels: &[String], options: &PlotOptions) -> String {
+        if options.uses_template() {
+            return self.plot(key_labels);
+        }
+
+        self.plot_grid(key_labels, options)
+    }
+
+    /// Plot the keys in a grid according to their matrix positions
+    fn plot_grid(&self, key_labels: &[String], options: &PlotOptions) -> String {
+        // only consider columns that are in use by any key to avoid large gaps
+        let mut columns: Vec<u8> = self.keys.iter().map(|k| k.matrix_position.0).collect();
+        columns.sort_unstable();
+        columns.dedup();
+        let mut rows: Vec<u8> = self.keys.iter().map(|k| k.matrix_position.1).collect();
+        rows.sort_unstable();
+        rows.dedup();
+
+        let mut grid: Vec<Vec<Option<&str>>> = vec![vec![None; columns.len()]; rows.len()];
+        self.keys
+            .iter()
+            .zip(key_labels.iter())
+            .for_each(|(key, label)| {
+                let row = rows.binary_search(&key.matrix_position.1).unwrap();
+                let col = columns.binary_search(&key.matrix_position.0).unwrap();
+                if let Some(previous) = grid[row][col].replace(label) {
+                    log::warn!(
+                        "Multiple keys at matrix position {:?}: Label '{}' replaces '{}' in the plot",
+                        key.matrix_position,
+                        label,
+                        previous,
+                    );
+                }
+            });
+
+        let width = options.cell_width.unwrap_or_else(|| {
+            key_labels
+                .iter()
+                .map(|l| visible_width(l))
+                .max()
+                .unwrap_or(1)
+        });
+
+        let plot_rows: Vec<String> = grid
+            .iter()
+            .map(|labels| {
+                let cells: Vec<String> = labels
+                    .iter()
+                    .map(|l| fit_to_width(l.unwrap_or(""), width))
+                    .collect();
+                if options.borders {
+                    format!("│ {} │", cells.join(" │ "))
+                } else {
+                    cells.join(" ").trim_end().to_string()
+                }
+            })
+            .collect();
+
+        if options.compact {
+            return plot_rows.join("\n");
+        }
+
+        if !options.borders {
+            return plot_rows.join("\n\n");
+        }
+
+        let horizontal_line = |left: &str, middle: &str, right: &str| -> String {
+            let segments: Vec<String> = (0..columns.len()).map(|_| "─".repeat(width + 2)).collect();
+            format!("{}{}{}", left, segments.join(middle), right)
+        };
+
+        let mut lines = vec![horizontal_line("┌", "┬", "┐")];
+        plot_rows.into_iter().enumerate().for_each(|(i, row)| {
+            if i > 0 {
+                lines.push(horizontal_line("├", "┼", "┤"));
+            }
+            lines.push(row);
+        });
+        lines.push(horizontal_line("└", "┴", "┘"));
+
+        lines.join("\n")
+    }
+
     /// Plot a compact graphical representation of the keyboard with given key labels without borders (compatible with ArneBab's input strings)
+    ///
+    /// If the keyboard has no `plot_template_short`, the keys are plotted in a compact grid.
     pub fn plot_compact(&self, key_labels: &[String]) -> String {
+        if self.plot_template_short.is_empty() {
+            let options = PlotOptions {
+                cell_width: None,
+                borders: false,
+                compact: true,
+                ..Default::default()
+            };
+            return self.plot_grid(key_labels, &options);
+        }
+
         let mut reg = handlebars::Handlebars::new();
         reg.register_escape_fn(handlebars::no_escape);
         let labels: AHashMap<usize, String> = key_labels.iter().cloned().enumerate().collect();
@@ -178,6 +851,17 @@ impl Keyboard {
             .unwrap()
     }
 
+    /// A difficulty score for each key (in the order of [`Keyboard::keys`]), independent of
+    /// any character assignment. It combines the key cost (as used by the key cost metric)
+    /// with the horizontal and vertical unbalancing of the hand required to reach the key.
+    /// The strength of the fingers is only contained insofar as it is reflected in the key costs.
+    pub fn position_difficulties(&self) -> Vec<f64> {
+        self.keys
+            .iter()
+            .map(|k| k.cost + k.unbalancing.0.abs() + k.unbalancing.1.abs())
+            .collect()
+    }
+
     pub fn estimated_finger_loads(&self, exclude_thumbs: bool) -> HandFingerMap<f64> {
         let mut intended_loads: HandFingerMap<f64> = HandFingerMap::with_default(0.0);
 
@@ -209,3 +893,122 @@ impl Keyboard {
         intended_loads
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::LayoutConfig,
+        key::{Finger, Hand, Key, MatrixPosition, Position},
+        keyboard::{fit_to_width, Keyboard, KeyboardBuilder, PlotOptions},
+    };
+
+    use std::str::FromStr;
+
+    #[test]
+    fn key_widths_default_to_one_and_survive_serialization() {
+        let cfg = LayoutConfig::from_str(include_str!("../../config/keyboard/crkbd.yml")).unwrap();
+        let keyboard = Keyboard::from_yaml_object(cfg.keyboard);
+        assert!(keyboard.keys.iter().all(|k| k.width == 1.0));
+        assert!(!keyboard.to_yaml_string().unwrap().contains("widths"));
+
+        let keyboard = KeyboardBuilder::new()
+            .add_key(Position(0.0, 0.0), Finger::Index, Hand::Left, 0, 0, 1.0)
+            .key(Key {
+                hand: Hand::Left,
+                finger: Finger::Thumb,
+                matrix_position: MatrixPosition(0, 1),
+                position: Position(50.0, 50.0),
+                width: 2.0,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let keyboard = Keyboard::from_yaml_str(&keyboard.to_yaml_string().unwrap()).unwrap();
+        assert_eq!(keyboard.keys[0].width, 1.0);
+        assert_eq!(keyboard.keys[1].width, 2.0);
+
+        // a 2u key can be reached half a key closer horizontally
+        let wide = &keyboard.keys[1];
+        assert_eq!(wide.reach_distance(&Position(0.0, 50.0), 50.0), 25.0);
+        assert_eq!(wide.reach_distance(&Position(40.0, 50.0), 50.0), 0.0);
+        assert_eq!(wide.reach_distance(&Position(0.0, 50.0), 0.0), 50.0);
+    }
+
+    #[test]
+    fn symmetry_map_mirrors_keys_of_symmetric_keyboards() {
+        let cfg = LayoutConfig::from_str(include_str!("../../config/keyboard/crkbd.yml")).unwrap();
+        let keyboard = Keyboard::from_yaml_object(cfg.keyboard);
+
+        let map = keyboard.symmetry_map().unwrap();
+        assert_eq!(map.len(), keyboard.keys.len());
+        for (i, j) in map.iter().enumerate() {
+            assert_eq!(map[*j], i);
+            assert_eq!(keyboard.keys[*j].hand, keyboard.keys[i].hand.other());
+            assert_eq!(
+                keyboard.keys[*j].matrix_position.1,
+                keyboard.keys[i].matrix_position.1
+            );
+        }
+
+        let cfg =
+            LayoutConfig::from_str(include_str!("../../config/keyboard/standard.yml")).unwrap();
+        let keyboard = Keyboard::from_yaml_object(cfg.keyboard);
+        assert!(keyboard.symmetry_map().is_none());
+    }
+
+    #[test]
+    fn plot_options_use_the_template_only_for_default_cells() {
+        let options = PlotOptions::default();
+        assert!(options.uses_template());
+        assert_eq!(options.label(' '), Some("␣".to_string()));
+        assert_eq!(options.label('a'), None);
+
+        let options: PlotOptions = serde_yaml::from_str("labels: {' ': spc}").unwrap();
+        assert!(options.uses_template());
+        assert_eq!(options.label(' '), Some("spc".to_string()));
+
+        let options: PlotOptions = serde_yaml::from_str("compact: true").unwrap();
+        assert!(options.borders);
+        assert!(!options.uses_template());
+    }
+
+    #[test]
+    fn plot_with_options_draws_a_grid_of_the_matrix_positions() {
+        let keyboard = KeyboardBuilder::new()
+            .add_key(Position(0.0, 0.0), Finger::Index, Hand::Left, 0, 0, 1.0)
+            .add_key(Position(1.0, 0.0), Finger::Index, Hand::Right, 0, 1, 1.0)
+            .add_key(Position(0.0, 1.0), Finger::Middle, Hand::Left, 1, 0, 1.0)
+            .build()
+            .unwrap();
+        let labels: Vec<String> = ["a", "bc", "d"].iter().map(|l| l.to_string()).collect();
+
+        let grid = "┌────┬────┐\n│ a  │ bc │\n├────┼────┤\n│ d  │    │\n└────┴────┘";
+        assert_eq!(keyboard.plot(&labels), grid);
+        assert_eq!(
+            keyboard.plot_with_options(&labels, &PlotOptions::default()),
+            grid
+        );
+
+        let options = PlotOptions {
+            cell_width: Some(1),
+            borders: false,
+            ..Default::default()
+        };
+        assert_eq!(keyboard.plot_with_options(&labels, &options), "a b\n\nd");
+
+        let options = PlotOptions {
+            compact: true,
+            ..options
+        };
+        assert_eq!(keyboard.plot_with_options(&labels, &options), "a b\nd");
+    }
+
+    #[test]
+    fn fit_to_width_keeps_styles_of_truncated_labels_closed() {
+        assert_eq!(fit_to_width("ab", 3), "ab ");
+        assert_eq!(
+            fit_to_width("\u{1b}[1mabc\u{1b}[0m", 2),
+            "\u{1b}[1mab\u{1b}[0m\u{1b}[0m"
+        );
+    }
+}
@@ -2,16 +2,35 @@
 //! its relation to the individual keys required to generate the layout's symbols.
 //! These provide the core objects that are evaluated in the `layout_evaluation` crate.
 
-use crate::key::{Hand, Key, MatrixPosition};
-use crate::keyboard::{KeyIndex, Keyboard};
+use crate::key::{Finger, Hand, Key, MatrixPosition};
+use crate::keyboard::{KeyIndex, Keyboard, PlotOptions};
 
 use ahash::AHashMap;
 use anyhow::Result;
 use colored::Colorize;
 use core::slice;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use smallmap::Map;
 use std::{fmt, sync::Arc};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MirrorError {
+    #[error(
+        "Can not mirror the layout: The key {0:?} ('{1}') has no symmetrical key on the other hand"
+    )]
+    NoCounterpart(MatrixPosition, char),
+    #[error("Can not mirror the layout: The key {0:?} ('{1}') is symmetrical to a fixed key")]
+    FixedCounterpart(MatrixPosition, char),
+}
+
+#[derive(Error, Debug)]
+pub enum PlacementError {
+    #[error("Can not place '{1}': The layout has no non-fixed position {0}")]
+    InvalidPosition(usize, char),
+    #[error("Can not place '{1}': The position {0} is not empty")]
+    OccupiedPosition(usize, char),
+}
 
 /// The index of a [`LayerKey`] in the `layerkeys` vec of a [`Layout`]
 ///
@@ -20,6 +39,11 @@ use std::{fmt, sync::Arc};
 /// Therefore, this is not a [`usize`] or larger.
 pub type LayerKeyIndex = u16;
 
+/// The symbol on the non-fixed layers of empty positions in partial layouts (see
+/// [`LayoutGenerator::generate_partial`](crate::layout_generator::LayoutGenerator::generate_partial)).
+/// It is a Unicode noncharacter, so no ngram of a text maps to it.
+pub const EMPTY_SYMBOL: char = '\u{fffe}';
+
 /// Enum for specifying the location of a modifier relative to the keyboard.
 ///
 /// This can be a `MatrixPosition` provided by the keyboard or a symbol that a corresponding layout
@@ -28,7 +52,7 @@ pub type LayerKeyIndex = u16;
 /// used.
 /// Note that if `Symbol(char)` is used, the modifier location may move with the symbol during an
 /// optimization.
-#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
 #[serde(untagged)]
 pub enum ModifierLocation {
     Position(MatrixPosition),
@@ -73,7 +97,7 @@ impl LayerModifierType {
 
 /// Enum for configuring the way how the modifiers shall be used to access a layer.
 /// (e.g. whether the modifiers has to be held or tapped for activating a layer)
-#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
 #[serde(tag = "type", content = "value")]
 #[serde(rename_all = "snake_case")]
 pub enum LayerModifierLocations {
@@ -197,6 +221,8 @@ pub struct Layout {
     key_layers: Vec<Vec<LayerKeyIndex>>,
     /// Map for retrieving the [`LayerKey`] for the symbol it generates
     key_map: Map<char, LayerKeyIndex>,
+    /// The modifiers the layout was generated with (for generating variants of the layout)
+    modifiers: Vec<AHashMap<Hand, LayerModifierLocations>>,
 }
 
 impl fmt::Display for Layout {
@@ -339,13 +365,14 @@ impl Layout {
             mod_map.push(resolved_mods_per_hand);
         }
 
-        // resolve each Single LayerKey's modifiers
+        // resolve each Single LayerKey's modifiers (preferring those configured for the other
+        // hand, falling back to the ones of the key's own hand)
         layerkeys.iter_mut().for_each(|k| {
             let mods = if k.layer > 0 && k.layer < (modifiers.len() + 1) as u8 {
-                mod_map
-                    .get((k.layer - 1) as usize)
-                    .unwrap() // can not fail due to above check
+                let mods_per_hand = mod_map.get((k.layer - 1) as usize).unwrap(); // can not fail due to above check
+                mods_per_hand
                     .get(&k.key.hand.other())
+                    .or_else(|| mods_per_hand.get(&k.key.hand))
                     .cloned()
                     .unwrap_or_default()
             } else {
@@ -363,9 +390,129 @@ impl Layout {
             keyboard,
             layerkey_to_key_index,
             key_map,
+            modifiers,
         })
     }
 
+    /// Generate the mirror image of the layout: The symbols of each non-fixed key (on all of its
+    /// layers) move to the key at the mirrored position (see [`Keyboard::symmetry_map`]). On
+    /// keyboards that are not geometrically symmetric (e.g. staggered ones), they move to the
+    /// configured symmetrical key of the other hand instead (see
+    /// [`Keyboard::mirrored_key_index`]). Fixed keys and modifiers stay in place.
+    ///
+    /// Fails if a non-fixed key has no unique symmetrical key or if that key is fixed.
+    pub fn mirror(&self) -> Result<Layout> {
+        let key_chars = self.key_chars();
+        let fixed_keys = self.fixed_keys();
+        let symmetry_map = self.keyboard.symmetry_map();
+
+        let mut mirrored_chars = key_chars.clone();
+        for (key_index, chars) in key_chars.iter().enumerate() {
+            if fixed_keys[key_index] {
+                continue;
+            }
+            let key = &self.keyboard.keys[key_index];
+            let symbol = chars.first().cloned().unwrap_or(EMPTY_SYMBOL);
+            let mirrored_index = match &symmetry_map {
+                Some(map) => Some(map[key_index]),
+                None => self
+                    .keyboard
+                    .mirrored_key_index(key_index)
+                    .filter(|i| self.keyboard.mirrored_key_index(*i) == Some(key_index)),
+            }
+            .ok_or(MirrorError::NoCounterpart(key.matrix_position, symbol))?;
+            if fixed_keys[mirrored_index] {
+                return Err(MirrorError::FixedCounterpart(key.matrix_position, symbol).into());
+            }
+            mirrored_chars[mirrored_index] = chars.clone();
+        }
+
+        Layout::new(
+            mirrored_chars,
+            fixed_keys,
+            self.keyboard.clone(),
+            self.modifiers.clone(),
+        )
+    }
+
+    /// Generate a copy of a partial layout with the symbol placed on the base layer of the
+    /// empty non-fixed key at the given position (the index in [`Layout::as_text`]). The other
+    /// layers of the key stay as they are, so e.g. an uppercase variant of the symbol is not
+    /// placed along with it.
+    pub fn place_symbol(&self, position: usize, symbol: char) -> Result<Layout> {
+        let key_index = self
+            .non_fixed_key_indices()
+            .nth(position)
+            .ok_or(PlacementError::InvalidPosition(position, symbol))?;
+        let mut key_chars = self.key_chars();
+        match key_chars[key_index].first_mut() {
+            Some(c) if *c == EMPTY_SYMBOL => *c = symbol,
+            _ => return Err(PlacementError::OccupiedPosition(position, symbol).into()),
+        }
+
+        Layout::new(
+            key_chars,
+            self.fixed_keys(),
+            self.keyboard.clone(),
+            self.modifiers.clone(),
+        )
+    }
+
+    /// The positions (indices in [`Layout::as_text`]) of the empty non-fixed keys of a
+    /// partial layout.
+    pub fn empty_positions(&self) -> Vec<usize> {
+        self.non_fixed_key_indices()
+            .enumerate()
+            .filter(|(_, key_index)| {
+                self.key_layers[*key_index]
+                    .first()
+                    .map(|lk| self.get_layerkey(lk).symbol == EMPTY_SYMBOL)
+                    .unwrap_or(false)
+            })
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    /// The symbols of each key's layers.
+    fn key_chars(&self) -> Vec<Vec<char>> {
+        self.key_layers
+            .iter()
+            .map(|layerkeys| {
+                layerkeys
+                    .iter()
+                    .map(|lk| self.get_layerkey(lk).symbol)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Whether each key is fixed.
+    fn fixed_keys(&self) -> Vec<bool> {
+        self.key_layers
+            .iter()
+            .map(|layerkeys| {
+                layerkeys
+                    .first()
+                    .map(|lk| self.get_layerkey(lk).is_fixed)
+                    .unwrap_or(true)
+            })
+            .collect()
+    }
+
+    /// The indices of the non-fixed keys (in the order of [`Layout::as_text`]).
+    fn non_fixed_key_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.key_layers
+            .iter()
+            .enumerate()
+            .filter(move |(_, layerkeys)| {
+                layerkeys
+                    .first()
+                    .map(|lk| !self.get_layerkey(lk).is_fixed)
+                    .unwrap_or(false)
+            })
+            .map(|(key_index, _)| key_index)
+    }
+
     fn gen_key_map(layerkeys: &[LayerKey]) -> Map<char, LayerKeyIndex> {
         let mut m = Map::default();
         layerkeys
@@ -438,6 +585,17 @@ impl Layout {
         self.key_layers[key_index][0]
     }
 
+    /// Get the index of the symbol on the given layer of the key corresponding to a given [`LayerKeyIndex`]
+    #[inline(always)]
+    pub fn get_layerkey_index_on_layer(
+        &self,
+        layerkey_index: &LayerKeyIndex,
+        layer: u8,
+    ) -> Option<LayerKeyIndex> {
+        let key_index: usize = self.layerkey_to_key_index[*layerkey_index as usize] as usize;
+        self.key_layers[key_index].get(layer as usize).cloned()
+    }
+
     /// Get a list of modifiers required to generate a given [`LayerKey`] as a Vec of [`LayerKey`]s
     #[inline(always)]
     pub fn resolve_modifiers(&self, k: &LayerKeyIndex) -> (LayerKeyIndex, LayerModifiers) {
@@ -469,24 +627,51 @@ impl Layout {
 
     /// Plot a graphical representation of a layer
     pub fn plot_layer(&self, layer: usize) -> String {
-        let fmt_char = |c: char| -> char {
-            match c {
-                ' ' => '␣',
-                '\n' => '\u{23ce}',
-                '\t' => '\u{21e5}',
-                '' => '\u{2327}',
-                normal_char => {
-                    if normal_char.is_alphabetic() || c == '"' || c == '\'' || c == ',' || c == '.'
-                    {
-                        normal_char
-                    } else {
-                        '_'
-                    }
-                }
+        self.keyboard
+            .plot(&self.layer_labels(layer, &PlotOptions::default()))
+    }
+
+    /// Plot a graphical representation of a layer with given plot options
+    pub fn plot_layer_with_options(&self, layer: usize, options: &PlotOptions) -> String {
+        self.keyboard
+            .plot_with_options(&self.layer_labels(layer, options), options)
+    }
+
+    /// Labels of all keys for a given layer (as used in plots)
+    fn layer_labels(&self, layer: usize, options: &PlotOptions) -> Vec<String> {
+        // the first layer activated by each modifier (modifiers are separate layerkeys that
+        // share the key and symbol of the layerkey shown in the plot)
+        let mut modifier_layers: AHashMap<(MatrixPosition, char), u8> = AHashMap::default();
+        if options.label_layer_modifiers {
+            self.layerkeys.iter().for_each(|lk| {
+                lk.modifiers.layerkey_indices().iter().for_each(|i| {
+                    let m = self.get_layerkey(i);
+                    let l = modifier_layers
+                        .entry((m.key.matrix_position, m.symbol))
+                        .or_insert(lk.layer);
+                    *l = (*l).min(lk.layer);
+                })
+            });
+        }
+
+        let fmt_symbol = |k: &LayerKey| -> String {
+            let c = k.symbol;
+            if c == EMPTY_SYMBOL {
+                return " ".to_string();
+            }
+            if let Some(l) = modifier_layers.get(&(k.key.matrix_position, c)) {
+                return format!("L{}", l + 1);
+            }
+            if let Some(label) = options.label(c) {
+                return label;
+            }
+            if c.is_alphabetic() || c == '"' || c == '\'' || c == ',' || c == '.' {
+                c.to_string()
+            } else {
+                "_".to_string()
             }
         };
-        let key_chars: Vec<String> = self
-            .key_layers
+        self.key_layers
             .iter()
             .map(|layers| {
                 if layers.is_empty() {
@@ -500,16 +685,14 @@ impl Layout {
                     " ".to_string()
                 } else {
                     // if no symbol is in layers, show last layers value if it is fixed
-                    let mut s = fmt_char(k.symbol).to_string();
+                    let mut s = fmt_symbol(k);
                     if !k.is_fixed {
                         s = s.yellow().bold().to_string();
                     }
                     s
                 }
             })
-            .collect();
-
-        self.keyboard.plot(&key_chars)
+            .collect()
     }
 
     /// Plot a graphical representation of the base (first) layer
@@ -517,6 +700,11 @@ impl Layout {
         self.plot_layer(0)
     }
 
+    /// Plot a graphical representation of the base (first) layer with given plot options
+    pub fn plot_with_options(&self, options: &PlotOptions) -> String {
+        self.plot_layer_with_options(0, options)
+    }
+
     /// Plot a compact graphical representation (without borders and only non-fixed keys) of the base (first) layer
     pub fn plot_compact(&self) -> String {
         let key_chars: Vec<String> = self
@@ -524,18 +712,113 @@ impl Layout {
             .iter()
             .filter_map(|layerkeys| layerkeys.first().map(|lk| self.get_layerkey(lk)))
             .filter(|k| !k.is_fixed)
-            .map(|k| k.symbol.to_string())
+            .map(|k| match k.symbol {
+                EMPTY_SYMBOL => " ".to_string(),
+                c => c.to_string(),
+            })
             .collect();
         self.keyboard.plot_compact(&key_chars)
     }
 
     /// Concatenate all non-fixed keys into a string without any whitespace
+    /// (except for empty positions of partial layouts)
     pub fn as_text(&self) -> String {
         self.key_layers
             .iter()
             .filter_map(|layerkeys| layerkeys.first().map(|lk| self.get_layerkey(lk)))
             .filter(|k| !k.is_fixed)
-            .map(|k| k.symbol.to_string())
+            .map(|k| match k.symbol {
+                EMPTY_SYMBOL => ' ',
+                c => c,
+            })
             .collect()
     }
+
+    /// List the keys (matrix position and base layer symbol) assigned to each finger of each hand
+    pub fn keys_by_finger(&self) -> AHashMap<(Hand, Finger), Vec<(MatrixPosition, char)>> {
+        let mut keys_by_finger: AHashMap<(Hand, Finger), Vec<(MatrixPosition, char)>> =
+            AHashMap::default();
+        self.key_layers
+            .iter()
+            .filter_map(|layerkeys| layerkeys.first().map(|lk| self.get_layerkey(lk)))
+            .for_each(|k| {
+                keys_by_finger
+                    .entry((k.key.hand, k.key.finger))
+                    .or_default()
+                    .push((k.key.matrix_position, k.symbol))
+            });
+
+        keys_by_finger
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::LayoutConfig, keyboard::Keyboard, layout_generator::LayoutGenerator,
+        neo_layout_generator::NeoLayoutGenerator,
+    };
+
+    use std::{str::FromStr, sync::Arc};
+
+    #[test]
+    fn keys_by_finger_contains_each_permutable_key_once() {
+        let cfg_str = include_str!("../../config/keyboard/standard.yml");
+        let cfg = LayoutConfig::from_str(cfg_str).unwrap();
+        let keyboard = Arc::new(Keyboard::from_yaml_object(cfg.keyboard));
+        let layout_generator = NeoLayoutGenerator::from_object(cfg.base_layout, keyboard);
+
+        let layout = layout_generator
+            .generate("xvlcwkhgfqyßuiaeosnrtdüöäpzbm,.j")
+            .unwrap();
+
+        let symbols: Vec<char> = layout
+            .keys_by_finger()
+            .values()
+            .flatten()
+            .map(|(_, symbol)| *symbol)
+            .collect();
+
+        for c in layout.as_text().chars() {
+            assert_eq!(symbols.iter().filter(|s| **s == c).count(), 1);
+        }
+    }
+
+    #[test]
+    fn mirror_moves_symbols_to_the_other_hand() {
+        let cfg_str = include_str!("../../config/keyboard/sval.yml");
+        let mut cfg = LayoutConfig::from_str(cfg_str).unwrap();
+        // fix the only permutable thumb key, whose symmetrical key (space) is fixed
+        cfg.base_layout.fixed_keys[1][4] = true;
+        let keyboard = Arc::new(Keyboard::from_yaml_object(cfg.keyboard));
+        let layout_generator = NeoLayoutGenerator::from_object(cfg.base_layout, keyboard);
+
+        let layout = layout_generator
+            .generate("q0a1zw2sbxe3dtcr4fgvuhj5miyk67onl89p={}(")
+            .unwrap();
+        let mirrored = layout.mirror().unwrap();
+
+        for c in layout.as_text().chars() {
+            let key = &layout.get_layerkey_for_symbol(&c).unwrap().key;
+            let mirrored_key = &mirrored.get_layerkey_for_symbol(&c).unwrap().key;
+            assert_eq!(mirrored_key.hand, key.hand.other());
+            assert_eq!(mirrored_key.finger, key.finger);
+            assert_eq!(mirrored_key.matrix_position.1, key.matrix_position.1);
+        }
+        assert_eq!(mirrored.mirror().unwrap().as_text(), layout.as_text());
+    }
+
+    #[test]
+    fn mirror_fails_without_symmetrical_keys() {
+        let cfg_str = include_str!("../../config/keyboard/standard.yml");
+        let cfg = LayoutConfig::from_str(cfg_str).unwrap();
+        let keyboard = Arc::new(Keyboard::from_yaml_object(cfg.keyboard));
+        let layout_generator = NeoLayoutGenerator::from_object(cfg.base_layout, keyboard);
+
+        let layout = layout_generator
+            .generate("xvlcwkhgfqyßuiaeosnrtdüöäpzbm,.j")
+            .unwrap();
+
+        assert!(layout.mirror().is_err());
+    }
 }
@@ -4,10 +4,19 @@
 use core::fmt;
 
 use crate::layout::Layout;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 pub trait LayoutGenerator: Send + Sync + LayoutGeneratorClone + fmt::Debug {
     fn generate(&self, layout_keys: &str) -> Result<Layout>;
+
+    /// Generate a partial layout in which the `empty` char marks empty positions (if the
+    /// generator supports it, see [`NeoLayoutGenerator`](crate::neo_layout_generator::NeoLayoutGenerator)).
+    fn generate_partial(&self, layout_keys: &str, _empty: char) -> Result<Layout> {
+        Err(anyhow!(
+            "Partial layouts are not supported by this layout generator: '{}'",
+            layout_keys
+        ))
+    }
 }
 
 impl Clone for Box<dyn LayoutGenerator> {
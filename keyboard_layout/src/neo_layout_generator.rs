@@ -1,11 +1,11 @@
 use crate::key::Hand;
 use crate::keyboard::Keyboard;
-use crate::layout::{LayerModifierLocations, Layout};
+use crate::layout::{LayerModifierLocations, Layout, EMPTY_SYMBOL};
 use crate::layout_generator::LayoutGenerator;
 
 use ahash::{AHashMap, AHashSet};
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{fs::File, iter::FromIterator, sync::Arc};
 use thiserror::Error;
 
@@ -23,11 +23,28 @@ pub enum LayoutError {
     WrongKeyNumber(usize, usize),
 }
 
+/// A change applied by [`NeoLayoutGenerator::repair_layout_string`]. Positions are char
+/// indices in the given layout string.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RepairAction {
+    /// A duplicate or unsupported char was replaced with a missing one.
+    Replaced {
+        position: usize,
+        removed: char,
+        inserted: char,
+    },
+    /// A duplicate or unsupported char was removed (without a missing char to take its place).
+    Removed { position: usize, removed: char },
+    /// A missing char was appended to the end of the layout string.
+    Appended { inserted: char },
+}
+
 /// A collection of data (configuration) regarding the Neo layout (and its family)
 /// required to generate Neo layout variants.
 ///
 /// Corresponds to (parts of) a YAML configuration file.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct BaseLayoutYAML {
     pub keys: Vec<Vec<Vec<String>>>,
     pub fixed_keys: Vec<Vec<bool>>,
@@ -36,6 +53,21 @@ pub struct BaseLayoutYAML {
     pub grouped_layers: u8,
 }
 
+/// The symbols of a key's layers in the base layout. Layers are positional, so that empty
+/// layers (given as empty strings) hold the [`EMPTY_SYMBOL`] and the symbols of the following
+/// layers keep their layer. Trailing empty layers are dropped.
+pub(crate) fn base_layout_key_symbols(layers: &[String]) -> Vec<char> {
+    let mut symbols: Vec<char> = layers
+        .iter()
+        .map(|l| l.chars().next().unwrap_or(EMPTY_SYMBOL))
+        .collect();
+    while symbols.last() == Some(&EMPTY_SYMBOL) {
+        symbols.pop();
+    }
+
+    symbols
+}
+
 impl BaseLayoutYAML {
     /// Checks the [`KeyboardYAML`] for common errors.
     pub fn validate(&self) -> Result<()> {
@@ -70,7 +102,7 @@ impl NeoLayoutGenerator {
             .keys
             .iter()
             .flatten()
-            .map(|layers| layers.iter().filter_map(|l| l.chars().next()).collect())
+            .map(|layers| base_layout_key_symbols(layers))
             .collect();
         let fixed_keys: Vec<bool> = base.fixed_keys.iter().flatten().cloned().collect();
 
@@ -81,8 +113,8 @@ impl NeoLayoutGenerator {
             .enumerate()
             .filter(|(_i, (_key_layers, fixed))| !*fixed)
             .for_each(|(i, (key_layers, _fixed))| {
-                if !key_layers.is_empty() {
-                    permutable_key_map.entry(key_layers[0]).or_insert(i as u8);
+                if let Some(c) = key_layers.first().filter(|c| **c != EMPTY_SYMBOL) {
+                    permutable_key_map.entry(*c).or_insert(i as u8);
                 }
             });
 
@@ -113,6 +145,53 @@ impl NeoLayoutGenerator {
     /// Does not check whether the given string is valid (sufficient, correct and unique charactors).
     /// This is useful for plotting unfinished or invalid layouts.
     pub fn generate_unchecked(&self, layout_keys: &str) -> Result<Layout> {
+        self.assemble(layout_keys, None)
+    }
+
+    /// Check the given chars (of `layout_keys`) for duplicates, unsupported chars, and
+    /// (if `require_all`) missing chars.
+    fn check_chars(&self, layout_keys: &str, chars: &[char], require_all: bool) -> Result<()> {
+        let char_set: AHashSet<char> = AHashSet::from_iter(chars.iter().cloned());
+        let layout_set: AHashSet<char> =
+            AHashSet::from_iter(self.permutable_key_map.keys().cloned());
+
+        // Check for duplicate chars
+        if char_set.len() != chars.len() {
+            let mut duplicates = AHashSet::default();
+            let mut seen_chars = AHashSet::default();
+            for char in chars.iter() {
+                if seen_chars.contains(char) {
+                    duplicates.insert(*char);
+                } else {
+                    seen_chars.insert(*char);
+                }
+            }
+            return Err(LayoutError::DuplicateChars(
+                layout_keys.to_string(),
+                duplicates.iter().cloned().collect::<String>(),
+            )
+            .into());
+        }
+
+        let mut unsupported_chars: Vec<char> = char_set.difference(&layout_set).cloned().collect();
+        let mut missing_chars: Vec<char> = layout_set.difference(&char_set).cloned().collect();
+
+        unsupported_chars.sort_unstable();
+        missing_chars.sort_unstable();
+
+        if !unsupported_chars.is_empty() {
+            return Err(LayoutError::UnsupportedChars(unsupported_chars.iter().collect()).into());
+        }
+        if require_all && !missing_chars.is_empty() {
+            return Err(LayoutError::MissingChars(missing_chars.iter().collect()).into());
+        }
+
+        Ok(())
+    }
+
+    /// Assemble the [`Layout`] for the given layout string. If an `empty` char is given,
+    /// it marks empty positions, and the positions exceeding the layout string are empty.
+    fn assemble(&self, layout_keys: &str, empty: Option<char>) -> Result<Layout> {
         let chars: Vec<char> = layout_keys.chars().collect();
 
         // assemble a Vec<Vec<char>> representation of the layer for the given layout string
@@ -124,29 +203,43 @@ impl NeoLayoutGenerator {
                 key_chars.push(key_layers.clone());
             } else {
                 let given_char = given_chars.next();
-                if given_char.is_none() {
+                if given_char.is_none() && empty.is_none() {
                     // number of given layout keys are insufficient
                     log::warn!("Number of given symbols in layout string is smaller than number of non-fixed keys");
                     break;
                 }
-                let given_char = given_char.unwrap();
-
-                let key_idx = self
-                    .permutable_key_map
-                    .get(given_char)
-                    .ok_or_else(|| LayoutError::UnsupportedChars(given_char.to_string()))?;
-                let given_key_layers = &self.base_layout_symbols[*key_idx as usize];
-                let new_key_layers = given_key_layers
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(layer_id, c)| {
-                        if !self.fixed_layers.contains(&(layer_id as u8)) {
-                            Some(*c)
-                        } else {
-                            key_layers.get(layer_id).cloned()
-                        }
-                    })
-                    .collect();
+
+                let new_key_layers = match given_char {
+                    Some(c) if Some(*c) != empty => {
+                        let key_idx = self
+                            .permutable_key_map
+                            .get(c)
+                            .ok_or_else(|| LayoutError::UnsupportedChars(c.to_string()))?;
+                        let given_key_layers = &self.base_layout_symbols[*key_idx as usize];
+                        given_key_layers
+                            .iter()
+                            .enumerate()
+                            .filter_map(|(layer_id, c)| {
+                                if !self.fixed_layers.contains(&(layer_id as u8)) {
+                                    Some(*c)
+                                } else {
+                                    key_layers.get(layer_id).cloned()
+                                }
+                            })
+                            .collect()
+                    }
+                    // an empty position (only keeps the symbols of its fixed layers)
+                    _ => key_layers
+                        .iter()
+                        .enumerate()
+                        .map(
+                            |(layer_id, c)| match self.fixed_layers.contains(&(layer_id as u8)) {
+                                true => *c,
+                                false => EMPTY_SYMBOL,
+                            },
+                        )
+                        .collect(),
+                };
                 key_chars.push(new_key_layers);
             }
         }
@@ -159,6 +252,58 @@ impl NeoLayoutGenerator {
         )
     }
 
+    /// Repair a layout string containing duplicate, unsupported, or missing chars with as few
+    /// changes as possible: The first occurrence of each supported char is kept, the other
+    /// positions are filled with the missing chars (in the order of the base layout). Positions
+    /// left over are removed, and missing chars left over are appended. The result is
+    /// deterministic and valid for [`LayoutGenerator::generate`]; valid layout strings are
+    /// returned unchanged.
+    pub fn repair_layout_string(&self, layout_keys: &str) -> (String, Vec<RepairAction>) {
+        let mut seen_chars = AHashSet::default();
+        let kept: Vec<Option<char>> = layout_keys
+            .chars()
+            .map(|c| (self.permutable_key_map.contains_key(&c) && seen_chars.insert(c)).then(|| c))
+            .collect();
+
+        let mut missing_chars: Vec<(u8, char)> = self
+            .permutable_key_map
+            .iter()
+            .filter(|(c, _)| !seen_chars.contains(*c))
+            .map(|(c, key_idx)| (*key_idx, *c))
+            .collect();
+        missing_chars.sort_unstable();
+        let mut missing_chars = missing_chars.into_iter().map(|(_, c)| c);
+
+        let mut actions = Vec::new();
+        let mut repaired = String::with_capacity(layout_keys.len());
+        for (position, (c, kept_c)) in layout_keys.chars().zip(kept).enumerate() {
+            if let Some(c) = kept_c {
+                repaired.push(c);
+                continue;
+            }
+            match missing_chars.next() {
+                Some(inserted) => {
+                    repaired.push(inserted);
+                    actions.push(RepairAction::Replaced {
+                        position,
+                        removed: c,
+                        inserted,
+                    });
+                }
+                None => actions.push(RepairAction::Removed {
+                    position,
+                    removed: c,
+                }),
+            }
+        }
+        for inserted in missing_chars {
+            repaired.push(inserted);
+            actions.push(RepairAction::Appended { inserted });
+        }
+
+        (repaired, actions)
+    }
+
     /// Get the list of permutable symbols
     pub fn permutable_keys(&self) -> Vec<char> {
         self.permutable_key_map.keys().cloned().collect()
@@ -169,42 +314,107 @@ impl LayoutGenerator for NeoLayoutGenerator {
     /// Generate a Neo variant [`Layout`] from a given string representation of its base layer (only non-fixed keys)
     fn generate(&self, layout_keys: &str) -> Result<Layout> {
         let chars: Vec<char> = layout_keys.chars().collect();
+        self.check_chars(layout_keys, &chars, true)?;
 
-        let char_set: AHashSet<char> = AHashSet::from_iter(chars.clone());
-        let layout_set: AHashSet<char> =
-            AHashSet::from_iter(self.permutable_key_map.keys().cloned());
+        self.generate_unchecked(layout_keys)
+    }
 
-        // Check for duplicate chars
-        if char_set.len() != chars.len() {
-            let mut duplicates = AHashSet::default();
-            let mut seen_chars = AHashSet::default();
-            for char in chars.iter() {
-                if seen_chars.contains(char) {
-                    duplicates.insert(*char);
-                } else {
-                    seen_chars.insert(*char);
-                }
-            }
-            return Err(LayoutError::DuplicateChars(
-                layout_keys.to_string(),
-                duplicates.iter().cloned().collect::<String>(),
-            )
-            .into());
-        }
+    /// Generate a partial Neo variant [`Layout`] in which some positions are empty, e.g. for
+    /// evaluating an unfinished layout. The `empty` char marks empty positions in the layout
+    /// string; if the string is shorter than the number of non-fixed keys, the remaining
+    /// positions are empty as well. The non-fixed layers of empty positions hold the
+    /// [`EMPTY_SYMBOL`], so ngrams containing characters that are not placed can not be mapped
+    /// (they count as not found in an evaluation).
+    ///
+    /// Missing characters are allowed, duplicate and unsupported ones are not.
+    fn generate_partial(&self, layout_keys: &str, empty: char) -> Result<Layout> {
+        let chars: Vec<char> = layout_keys.chars().filter(|c| *c != empty).collect();
+        self.check_chars(layout_keys, &chars, false)?;
 
-        let mut unsupported_chars: Vec<char> = char_set.difference(&layout_set).cloned().collect();
-        let mut missing_chars: Vec<char> = layout_set.difference(&char_set).cloned().collect();
+        self.assemble(layout_keys, Some(empty))
+    }
+}
 
-        unsupported_chars.sort_unstable();
-        missing_chars.sort_unstable();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LayoutConfig;
 
-        if !unsupported_chars.is_empty() {
-            return Err(LayoutError::UnsupportedChars(unsupported_chars.iter().collect()).into());
-        }
-        if !missing_chars.is_empty() {
-            return Err(LayoutError::MissingChars(missing_chars.iter().collect()).into());
-        }
+    use std::str::FromStr;
 
-        self.generate_unchecked(layout_keys)
+    fn layout_generator() -> NeoLayoutGenerator {
+        let cfg_str = include_str!("../../config/keyboard/standard.yml");
+        let cfg = LayoutConfig::from_str(cfg_str).unwrap();
+        let keyboard = Arc::new(Keyboard::from_yaml_object(cfg.keyboard));
+        NeoLayoutGenerator::from_object(cfg.base_layout, keyboard)
+    }
+
+    #[test]
+    fn all_six_layers_are_positional_and_round_trip() {
+        let layout_generator = layout_generator();
+        let layout = layout_generator
+            .generate("vxlcwkhgfqyßuiaeosnrtdüöäpzbm,.j")
+            .unwrap();
+
+        // the (non-fixed) layers of "v" moved to the position of "x", including the symbol on
+        // the sixth layer that follows an empty fifth layer; the fixed third layer stays
+        let v = layout.get_layerkey_for_symbol(&'v').unwrap();
+        let sqrt = layout.get_layerkey_for_symbol(&'√').unwrap();
+        let ellipsis = layout.get_layerkey_for_symbol(&'…').unwrap();
+        assert_eq!(sqrt.layer, 5);
+        assert_eq!(sqrt.key.matrix_position, v.key.matrix_position);
+        assert_eq!(ellipsis.layer, 2);
+        assert_eq!(ellipsis.key.matrix_position, v.key.matrix_position);
+        assert!(layout.plot_layer(5).contains('Ξ'));
+
+        let symbols = |layout: &Layout| -> Vec<_> {
+            layout
+                .layerkeys
+                .iter()
+                .map(|k| (k.key.matrix_position, k.layer, k.symbol))
+                .collect()
+        };
+        let regenerated = layout_generator.generate(&layout.as_text()).unwrap();
+        assert_eq!(symbols(&regenerated), symbols(&layout));
+    }
+
+    #[test]
+    fn repair_keeps_valid_layout_strings() {
+        let layout_generator = layout_generator();
+        let layout_str = "xvlcwkhgfqyßuiaeosnrtdüöäpzbm,.j";
+
+        assert_eq!(
+            layout_generator.repair_layout_string(layout_str),
+            (layout_str.to_string(), Vec::new())
+        );
+    }
+
+    #[test]
+    fn repair_fills_missing_chars_into_duplicates() {
+        let layout_generator = layout_generator();
+        let (repaired, actions) =
+            layout_generator.repair_layout_string("xvlcwkhgfqyßuiaeosnrtdüöäpzbm,.x");
+
+        assert_eq!(repaired, "xvlcwkhgfqyßuiaeosnrtdüöäpzbm,.j");
+        assert_eq!(
+            actions,
+            vec![RepairAction::Replaced {
+                position: 31,
+                removed: 'x',
+                inserted: 'j'
+            }]
+        );
+        assert!(layout_generator.generate(&repaired).is_ok());
+
+        let (repaired, actions) =
+            layout_generator.repair_layout_string("xvlcwkhgfqyßuiaeosnrtdüöäpzbm,.j#");
+        assert_eq!(repaired, "xvlcwkhgfqyßuiaeosnrtdüöäpzbm,.j");
+        assert_eq!(
+            actions,
+            vec![RepairAction::Removed {
+                position: 32,
+                removed: '#'
+            }]
+        );
     }
 }
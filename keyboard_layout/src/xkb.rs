@@ -0,0 +1,196 @@
+//! The `xkb` module exports a [`Layout`] as an xkb symbols snippet that can be used to install
+//! the layout on Linux (e.g. in `/usr/share/X11/xkb/symbols/`).
+//!
+//! The mapping from physical keys to xkb keycode names (e.g. `AD01`) is keyboard-dependent and
+//! therefore provided separately as [`XkbKeycodes`]. The symbols of each key are exported in the
+//! order of the layout's layers, which become the key's shift levels. Keys with more than four
+//! layers use the `EIGHT_LEVEL` key type; the modifiers activating each level need to be defined
+//! by the including xkb configuration (e.g. by including `level3` and `level5` snippets).
+
+use crate::{
+    key::MatrixPosition,
+    layout::{Layout, EMPTY_SYMBOL},
+};
+
+use ahash::AHashMap;
+use anyhow::Result;
+use serde::Deserialize;
+use std::fs::File;
+
+/// Configurable xkb keycode names for the keys of a keyboard.
+#[derive(Deserialize, Debug, Clone)]
+pub struct XkbKeycodes {
+    /// Keycode names (without angle brackets) of each key, in the same order as the keyboard's
+    /// `matrix_positions`. An empty name excludes the key from the export.
+    pub keycodes: Vec<Vec<String>>,
+}
+
+impl XkbKeycodes {
+    pub fn from_yaml(filename: &str) -> Result<Self> {
+        let f = File::open(filename)?;
+        Ok(serde_yaml::from_reader(f)?)
+    }
+}
+
+/// An xkb symbols snippet together with warnings about symbols that could not be exported.
+#[derive(Debug, Clone)]
+pub struct XkbExport {
+    pub symbols: String,
+    pub warnings: Vec<String>,
+}
+
+/// The xkb keysym name for a symbol, if it can be generated by a regular key press.
+fn keysym(c: char) -> Option<String> {
+    let name = match c {
+        ' ' => "space",
+        '!' => "exclam",
+        '"' => "quotedbl",
+        '#' => "numbersign",
+        '$' => "dollar",
+        '%' => "percent",
+        '&' => "ampersand",
+        '\'' => "apostrophe",
+        '(' => "parenleft",
+        ')' => "parenright",
+        '*' => "asterisk",
+        '+' => "plus",
+        ',' => "comma",
+        '-' => "minus",
+        '.' => "period",
+        '/' => "slash",
+        ':' => "colon",
+        ';' => "semicolon",
+        '<' => "less",
+        '=' => "equal",
+        '>' => "greater",
+        '?' => "question",
+        '@' => "at",
+        '[' => "bracketleft",
+        '\\' => "backslash",
+        ']' => "bracketright",
+        '^' => "asciicircum",
+        '_' => "underscore",
+        '`' => "grave",
+        '{' => "braceleft",
+        '|' => "bar",
+        '}' => "braceright",
+        '~' => "asciitilde",
+        '\n' => "Return",
+        '\t' | '⇥' => "Tab",
+        '⌫' => "BackSpace",
+        '⌦' => "Delete",
+        '⎀' => "Insert",
+        '⇞' => "Prior",
+        '⇟' => "Next",
+        '⇱' => "Home",
+        '⇲' => "End",
+        '⇡' => "Up",
+        '⇣' => "Down",
+        '⇠' => "Left",
+        '⇢' => "Right",
+        '↶' => "Undo",
+        c if c.is_ascii_alphanumeric() => return Some(c.to_string()),
+        c if c.is_control() => return None,
+        c => return Some(format!("U{:04X}", c as u32)),
+    };
+
+    Some(name.to_string())
+}
+
+/// Export the layout as an xkb symbols snippet named `name`, using the given keycode names.
+///
+/// Symbols that can not be exported (modifiers, control characters, and symbols of keys
+/// without a keycode name) are listed in the warnings.
+pub fn xkb_symbols(layout: &Layout, keycodes: &XkbKeycodes, name: &str) -> XkbExport {
+    let keycode_names: Vec<&String> = keycodes.keycodes.iter().flatten().collect();
+    let keycode_map: AHashMap<MatrixPosition, &String> = layout
+        .keyboard
+        .keys
+        .iter()
+        .zip(keycode_names.iter())
+        .filter(|(_, name)| !name.is_empty())
+        .map(|(key, name)| (key.matrix_position, *name))
+        .collect();
+
+    let mut warnings = Vec::new();
+    if keycode_names.len() != layout.keyboard.keys.len() {
+        warnings.push(format!(
+            "Number of keycodes ({}) does not match the number of keys ({})",
+            keycode_names.len(),
+            layout.keyboard.keys.len()
+        ));
+    }
+
+    // collect the exportable symbols of each key by layer
+    let mut key_symbols: AHashMap<MatrixPosition, Vec<Option<String>>> = AHashMap::default();
+    for layerkey in layout.layerkeys.iter() {
+        let symbol = layerkey.symbol.escape_debug();
+        let pos = layerkey.key.matrix_position;
+        if layerkey.symbol == EMPTY_SYMBOL {
+            continue;
+        }
+        if layerkey.is_modifier.is_some() {
+            warnings.push(format!(
+                "Modifier '{}' at {:?} is not exported",
+                symbol, pos
+            ));
+            continue;
+        }
+        if !keycode_map.contains_key(&pos) {
+            warnings.push(format!(
+                "Symbol '{}' at {:?} is not exported (no keycode)",
+                symbol, pos
+            ));
+            continue;
+        }
+        match keysym(layerkey.symbol) {
+            Some(keysym) => {
+                let levels = key_symbols.entry(pos).or_default();
+                let layer = layerkey.layer as usize;
+                if levels.len() <= layer {
+                    levels.resize(layer + 1, None);
+                }
+                levels[layer] = Some(keysym);
+            }
+            None => warnings.push(format!(
+                "Symbol '{}' at {:?} has no keysym and is not exported",
+                symbol, pos
+            )),
+        }
+    }
+
+    let mut lines = vec![
+        "default partial alphanumeric_keys".to_string(),
+        format!("xkb_symbols \"{}\" {{", name),
+        format!("    name[Group1] = \"{}\";", name),
+    ];
+    for key in layout.keyboard.keys.iter() {
+        let (keycode, levels) = match (
+            keycode_map.get(&key.matrix_position),
+            key_symbols.get(&key.matrix_position),
+        ) {
+            (Some(keycode), Some(levels)) => (keycode, levels),
+            _ => continue,
+        };
+        let levels: Vec<&str> = levels
+            .iter()
+            .map(|l| l.as_deref().unwrap_or("NoSymbol"))
+            .collect();
+        let key_type = match levels.len() > 4 {
+            true => "type[Group1] = \"EIGHT_LEVEL\", ",
+            false => "",
+        };
+        lines.push(format!(
+            "    key <{}> {{ {}[ {} ] }};",
+            keycode,
+            key_type,
+            levels.join(", ")
+        ));
+    }
+    lines.push("};".to_string());
+
+    XkbExport {
+        symbols: lines.join("\n"),
+        warnings,
+    }
+}
@@ -52,6 +52,23 @@ struct Options {
     #[clap(long)]
     only_total_costs: bool,
 
+    /// Only output the number of presses of each key as JSON to stdout (e.g. for heatmaps)
+    #[clap(long)]
+    key_presses: bool,
+
+    /// Attribute the costs to the layout's characters (shown in the results and included in the JSON output)
+    #[clap(long)]
+    char_costs: bool,
+
+    /// Report the costs relative to a theoretical lower bound (assigning the most frequent characters to the cheapest keys)
+    #[clap(long)]
+    lower_bound: bool,
+
+    /// Evaluate partial layouts in which this character marks empty positions. Missing
+    /// characters are allowed, and ngrams containing characters that are not placed are skipped
+    #[clap(long)]
+    partial: Option<char>,
+
     /// Sort results by total costs
     #[clap(long)]
     sort: bool,
@@ -60,8 +77,8 @@ struct Options {
 fn main() {
     dotenv::dotenv().ok();
     let options = Options::parse();
-    if !options.json {
-        // if the "json" option is set, we do not want any other log messages
+    if !options.json && !options.key_presses {
+        // if a JSON option is set, we do not want any other log messages
         env_logger::init();
     }
 
@@ -91,15 +108,31 @@ fn main() {
                 .chars()
                 .filter(|c| options.do_not_remove_whitespace || !c.is_whitespace())
                 .collect();
-            let layout = match layout_generator.generate(&layout_str) {
+            let layout = match options.partial {
+                Some(empty) => layout_generator.generate_partial(&layout_str, empty),
+                None => layout_generator.generate(&layout_str),
+            };
+            let layout = match layout {
                 Ok(layout) => layout,
                 Err(e) => {
                     log::error!("Error in generating layout: {:?}", e);
                     panic!("{:?}", e);
                 }
             };
-            let evaluation_result =
-                result_cache.get_or_insert_with(&layout_str, || evaluator.evaluate_layout(&layout));
+            let evaluation_result = result_cache.get_or_insert_with(&layout_str, || {
+                let res = match options.char_costs {
+                    true => evaluator.evaluate_layout_with_char_costs(&layout),
+                    false => evaluator.evaluate_layout(&layout),
+                };
+                let res = match options.lower_bound {
+                    true => res.with_lower_bound(evaluator.theoretical_lower_bound(&layout)),
+                    false => res,
+                };
+                match options.partial {
+                    Some(_) => res.with_coverage(evaluator.coverage(&layout)),
+                    None => res,
+                }
+            });
             (layout_str, layout, evaluation_result)
         })
         .collect();
@@ -112,7 +145,18 @@ fn main() {
     }
 
     // print results
-    if options.json {
+    if options.key_presses {
+        let key_presses: Vec<serde_json::Value> = results
+            .iter()
+            .map(|(layout_str, layout, _)| {
+                serde_json::json!({
+                    "layout": layout_str,
+                    "key_presses": evaluator.key_presses(layout),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string(&key_presses).unwrap());
+    } else if options.json {
         let results: Vec<LayoutEvaluation> =
             results.into_iter().map(|(_, _, res)| res.into()).collect();
         println!("{}", serde_json::to_string(&results).unwrap());
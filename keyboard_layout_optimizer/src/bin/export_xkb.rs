@@ -0,0 +1,62 @@
+use clap::Parser;
+
+use keyboard_layout::xkb::{xkb_symbols, XkbKeycodes};
+use keyboard_layout_optimizer::common;
+
+#[derive(Parser, Debug)]
+#[clap(name = "Keyboard layout xkb export")]
+/// Export a layout as an xkb symbols snippet (printed to stdout).
+struct Options {
+    /// Layout keys from left to right, top to bottom
+    layout_str: String,
+
+    /// Filename of layout configuration file to use
+    #[clap(short, long, default_value = "config/keyboard/standard.yml")]
+    layout_config: String,
+
+    /// Filename of the xkb keycode names of the keyboard's keys
+    #[clap(short, long, default_value = "config/xkb/standard.yml")]
+    keycodes: String,
+
+    /// Name of the resulting xkb symbols
+    #[clap(short, long, default_value = "optimized")]
+    name: String,
+
+    /// Interpred given layout string using the "grouped" logic
+    #[clap(long)]
+    pub grouped_layout_generator: bool,
+}
+
+fn main() {
+    dotenv::dotenv().ok();
+    env_logger::init();
+    let options = Options::parse();
+
+    let layout_str: String = options
+        .layout_str
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let layout_generator =
+        common::init_layout_generator(&options.layout_config, options.grouped_layout_generator);
+
+    let layout = match layout_generator.generate(&layout_str) {
+        Ok(layout) => layout,
+        Err(e) => {
+            log::error!("{:?}", e);
+            panic!("{:?}", e);
+        }
+    };
+    let keycodes = XkbKeycodes::from_yaml(&options.keycodes).unwrap_or_else(|e| {
+        panic!(
+            "Could not read xkb keycodes file {}: {:?}",
+            options.keycodes, e
+        )
+    });
+
+    let export = xkb_symbols(&layout, &keycodes, &options.name);
+    for warning in export.warnings.iter() {
+        log::warn!("{}", warning);
+    }
+    println!("{}", export.symbols);
+}
@@ -29,6 +29,10 @@ struct Options {
 
     /// Pairs of weight and ngram frequency directory in the form path:weight
     components: Vec<WeightedComponent>,
+
+    /// Additionally save the ngrams in a binary format that loads faster
+    #[clap(long)]
+    binary: bool,
 }
 
 fn add<T: Clone + Eq + Hash>(weight: f64, res: &mut AHashMap<T, f64>, ngrams: &AHashMap<T, f64>) {
@@ -101,17 +105,20 @@ fn main() {
 
     log::info!("Writing result to {}...", options.out);
     let out = Path::new(&options.out);
-    Unigrams {
+    let unigrams = Unigrams {
         grams: res_unigrams,
-    }
-    .save_frequencies(out.join("1-grams.txt"))
-    .unwrap();
-    Bigrams { grams: res_bigrams }
-        .save_frequencies(out.join("2-grams.txt"))
-        .unwrap();
-    Trigrams {
+    };
+    unigrams.save_frequencies(out.join("1-grams.txt")).unwrap();
+    let bigrams = Bigrams { grams: res_bigrams };
+    bigrams.save_frequencies(out.join("2-grams.txt")).unwrap();
+    let trigrams = Trigrams {
         grams: res_trigrams,
+    };
+    trigrams.save_frequencies(out.join("3-grams.txt")).unwrap();
+
+    if options.binary {
+        unigrams.save_binary(out.join("1-grams.bin")).unwrap();
+        bigrams.save_binary(out.join("2-grams.bin")).unwrap();
+        trigrams.save_binary(out.join("3-grams.bin")).unwrap();
     }
-    .save_frequencies(out.join("3-grams.txt"))
-    .unwrap();
 }
@@ -1,7 +1,10 @@
 use clap::Parser;
 use std::{fs, path::Path};
 
-use layout_evaluation::ngrams::{Bigrams, Trigrams, Unigrams};
+use layout_evaluation::{
+    ngram_mapper::normalization::UnicodeNormalization,
+    ngrams::{Bigrams, Trigrams, Unigrams, Words},
+};
 
 #[derive(Parser, Debug)]
 #[clap(name = "Ngram frequency generator")]
@@ -12,6 +15,14 @@ struct Options {
 
     /// Name for resulting ngram frequencies (a directory at that path will be generated)
     out: String,
+
+    /// Normalize the text to Unicode NFC (canonical composition) before counting ngrams
+    #[clap(long)]
+    nfc: bool,
+
+    /// Additionally save the ngrams in a binary format that loads faster
+    #[clap(long)]
+    binary: bool,
 }
 
 fn main() {
@@ -21,18 +32,43 @@ fn main() {
 
     let text = fs::read_to_string(&options.filename)
         .unwrap_or_else(|_| panic!("Could not read corpus file from {}.", options.filename));
+    let normalization = match options.nfc {
+        true => UnicodeNormalization::Nfc,
+        false => UnicodeNormalization::None,
+    };
+    let text = normalization.normalize_text(&text);
 
     let d = Path::new(&options.out);
 
     let unigrams = Unigrams::from_text(&text).expect("Could not generate unigrams from text.");
     let p = d.join("1-grams.txt");
     unigrams.save_frequencies(p).unwrap();
+    if options.binary {
+        unigrams.save_binary(d.join("1-grams.bin")).unwrap();
+    }
 
     let bigrams = Bigrams::from_text(&text).expect("Could not generate bigrams from text.");
+    let (intra_word, cross_word) = bigrams.cross_word_weights();
+    log::info!(
+        "Bigrams: {:.2}% intra-word, {:.2}% cross-word",
+        100.0 * intra_word / (intra_word + cross_word),
+        100.0 * cross_word / (intra_word + cross_word)
+    );
     let p = d.join("2-grams.txt");
     bigrams.save_frequencies(p).unwrap();
+    if options.binary {
+        bigrams.save_binary(d.join("2-grams.bin")).unwrap();
+    }
 
     let trigrams = Trigrams::from_text(&text).expect("Could not generate trigrams from text.");
     let p = d.join("3-grams.txt");
     trigrams.save_frequencies(p).unwrap();
+    if options.binary {
+        trigrams.save_binary(d.join("3-grams.bin")).unwrap();
+    }
+
+    // words are required by word-level metrics (e.g. `one_hand_words`)
+    let words = Words::from_text(&text).expect("Could not generate words from text.");
+    let p = d.join("words.txt");
+    words.save_frequencies(p).unwrap();
 }
@@ -3,7 +3,13 @@ use layout_evaluation::cache::Cache;
 use layout_optimization_genetic::optimization;
 
 use clap::Parser;
-use std::{env, process};
+use std::{
+    env, process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 #[derive(Parser, Debug)]
 #[clap(name = "Keyboard layout optimization - Genetic Algorithm")]
@@ -24,6 +30,11 @@ struct Options {
     #[clap(short, long, default_value = "config/optimization/genetic.yml")]
     optimization_parameters: String,
 
+    /// Use this parameter preset (quick, balanced, or thorough) instead of the optimization
+    /// configuration file
+    #[clap(long)]
+    preset: Option<String>,
+
     /// Start optimization from this layout (keys from left to right, top to bottom)
     #[clap(short, long)]
     start_layout: Option<String>,
@@ -55,7 +66,7 @@ struct Options {
 
 fn main() {
     dotenv::dotenv().ok();
-    env_logger::init();
+    common::init_logger(&[]);
 
     // Disable storing worst ngrams for speed boost
     if env::var("SHOW_WORST").is_err() {
@@ -64,9 +75,15 @@ fn main() {
 
     let final_results: Cache<f64> = Cache::new();
 
-    // Handle Ctrl+C
+    // Handle Ctrl+C: The first one stops the optimization gracefully, a second one exits
+    let stop = Arc::new(AtomicBool::new(false));
+    let cloned_stop = stop.clone();
     let cloned_final_results = final_results.clone();
     ctrlc::set_handler(move || {
+        if !cloned_stop.swap(true, Ordering::Relaxed) {
+            log::info!("Stopping optimization (press Ctrl+C again to exit immediately)");
+            return;
+        }
         // Display a summary of the optimization.
         println!("\n\n{}\n", cloned_final_results);
         // Stop execution
@@ -90,15 +107,17 @@ fn main() {
 
     let (layout_generator, evaluator) = common::init(&options.evaluation_parameters);
 
-    let mut optimization_params = optimization::Parameters::from_yaml(
-        &options.optimization_parameters,
-    )
-    .unwrap_or_else(|_| {
-        panic!(
-            "Could not read optimization parameters from {}.",
-            &options.optimization_parameters
-        )
-    });
+    let mut optimization_params = match &options.preset {
+        Some(preset) => optimization::Parameters::preset(preset)
+            .unwrap_or_else(|e| panic!("Could not use optimization preset: {}", e)),
+        None => optimization::Parameters::from_yaml(&options.optimization_parameters)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Could not read optimization parameters from {}: {:?}",
+                    &options.optimization_parameters, e
+                )
+            }),
+    };
 
     if let Some(generation_limit) = options.generation_limit {
         optimization_params.generation_limit = generation_limit
@@ -115,7 +134,9 @@ fn main() {
             &options.fix.clone().unwrap_or_default(),
             start_layout.is_some(),
             !options.no_cache_results,
-        );
+            Some(stop.clone()),
+        )
+        .unwrap_or_else(|e| panic!("Could not run the optimization: {}", e));
         let evaluation_result = evaluator.evaluate_layout(&layout);
         let cost = evaluation_result.total_cost();
         let _ = final_results.get_or_insert_with(&layout_str, || cost);
@@ -142,6 +163,11 @@ fn main() {
             );
         }
 
+        if stop.load(Ordering::Relaxed) {
+            // Display a summary of the optimization.
+            println!("\n\n{}\n", final_results);
+            break;
+        }
         if !options.run_forever {
             break;
         }
@@ -5,7 +5,13 @@ use layout_optimization_sa::optimization;
 use clap::Parser;
 use colored::Colorize;
 use rayon::iter::{ParallelBridge, ParallelIterator};
-use std::{env, process};
+use std::{
+    env, process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 #[derive(Parser, Debug)]
 #[clap(name = "Keyboard layout optimization - Simulated Annealing")]
@@ -26,6 +32,11 @@ struct Options {
     #[clap(short, long, default_value = "config/optimization/sa.yml")]
     optimization_parameters: String,
 
+    /// Use this parameter preset (quick, balanced, or thorough) instead of the optimization
+    /// configuration file
+    #[clap(long)]
+    preset: Option<String>,
+
     /// Start optimization from this layout (keys from left to right, top to bottom)
     #[clap(short, long)]
     start_layouts: Vec<String>,
@@ -46,7 +57,9 @@ struct Options {
     #[clap(short, long)]
     greedy: bool,
 
-    /// If used, log every single iteration instead of every 100th.
+    /// Log every single iteration instead of every 100th (sets the log level of the optimizer
+    /// to `trace`). Alternatively, use `RUST_LOG=layout_optimization_sa=debug` (every 100th
+    /// iteration) or `=trace` (every iteration).
     #[clap(long)]
     log_everything: bool,
 
@@ -108,7 +121,12 @@ impl Iterator for LayoutIterator {
 
 fn main() {
     dotenv::dotenv().ok();
-    env_logger::init();
+    let options = Options::parse();
+    let trace_modules: &[&str] = match options.log_everything {
+        true => &["layout_optimization_sa"],
+        false => &[],
+    };
+    common::init_logger(trace_modules);
 
     // Disable storing worst ngrams for speed boost
     if env::var("SHOW_WORST").is_err() {
@@ -117,9 +135,15 @@ fn main() {
 
     let final_results: Cache<f64> = Cache::new();
 
-    // Handle Ctrl+C
+    // Handle Ctrl+C: The first one stops the optimization gracefully, a second one exits
+    let stop = Arc::new(AtomicBool::new(false));
+    let cloned_stop = stop.clone();
     let cloned_final_results = final_results.clone();
     ctrlc::set_handler(move || {
+        if !cloned_stop.swap(true, Ordering::Relaxed) {
+            log::info!("Stopping optimization (press Ctrl+C again to exit immediately)");
+            return;
+        }
         // Display a summary of the optimization.
         println!("\n\n{}\n", cloned_final_results);
         // Stop execution
@@ -127,8 +151,6 @@ fn main() {
     })
     .expect("Error setting Ctrl-C handler");
 
-    let options = Options::parse();
-
     let fix_from: String = options
         .fix_from
         .chars()
@@ -147,15 +169,17 @@ fn main() {
 
     let (layout_generator, evaluator) = common::init(&options.evaluation_parameters);
 
-    let mut optimization_params = optimization::Parameters::from_yaml(
-        &options.optimization_parameters,
-    )
-    .unwrap_or_else(|_| {
-        panic!(
-            "Could not read optimization parameters from {}.",
-            &options.optimization_parameters
-        )
-    });
+    let mut optimization_params = match &options.preset {
+        Some(preset) => optimization::Parameters::preset(preset)
+            .unwrap_or_else(|e| panic!("Could not use optimization preset: {}", e)),
+        None => optimization::Parameters::from_yaml(&options.optimization_parameters)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Could not read optimization parameters from {}: {:?}",
+                    &options.optimization_parameters, e
+                )
+            }),
+    };
     if options.greedy {
         optimization_params.init_temp = Some(f64::MIN_POSITIVE);
     } else if options.init_temp.is_some() {
@@ -176,6 +200,7 @@ fn main() {
     };
 
     layout_iterator
+        .take_while(|_| !stop.load(Ordering::Relaxed))
         .enumerate()
         .par_bridge()
         .for_each(|(i, fix_from)| {
@@ -205,7 +230,9 @@ fn main() {
                 options.log_everything,
                 cache.clone(),
                 None,
-            );
+                Some(stop.clone()),
+            )
+            .unwrap_or_else(|e| panic!("Could not run the optimization: {}", e));
             let evaluation_result = evaluator.evaluate_layout(&layout);
             let cost = evaluation_result.total_cost();
             let _ = final_results.get_or_insert_with(&layout_str, || cost);
@@ -238,4 +265,9 @@ fn main() {
                 );
             }
         });
+
+    if stop.load(Ordering::Relaxed) {
+        // Display a summary of the optimization.
+        println!("\n\n{}\n", final_results);
+    }
 }
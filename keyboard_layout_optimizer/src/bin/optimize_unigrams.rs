@@ -0,0 +1,60 @@
+use keyboard_layout_optimizer::common;
+use layout_optimization_common::optimal_unigram_assignment;
+
+use clap::Parser;
+use colored::Colorize;
+
+#[derive(Parser, Debug)]
+#[clap(name = "Keyboard layout optimization - Unigram assignment")]
+/// Assign the layout's characters to the keys optimally with respect to unigram frequencies
+/// and key costs only (ignoring bigrams and trigrams). The result is a fast baseline and an
+/// eligible start layout for the other optimizers.
+struct Options {
+    /// Evaluation parameters
+    #[clap(flatten)]
+    evaluation_parameters: common::Options,
+
+    /// Layout whose characters are assigned (keys from left to right, top to bottom)
+    #[clap(default_value = "xvlcwkhgfqßuiaeosnrtdyüöäpzbm,.j")]
+    layout_str: String,
+
+    /// Do not move those keys
+    #[clap(short, long)]
+    fix: Option<String>,
+
+    /// Do not remove whitespace from the layout string
+    #[clap(long)]
+    do_not_remove_whitespace: bool,
+}
+
+fn main() {
+    dotenv::dotenv().ok();
+    env_logger::init();
+    let options = Options::parse();
+
+    let layout_str: String = options
+        .layout_str
+        .chars()
+        .filter(|c| options.do_not_remove_whitespace || !c.is_whitespace())
+        .collect();
+
+    let (layout_generator, evaluator) = common::init(&options.evaluation_parameters);
+
+    let (layout_str, layout) = optimal_unigram_assignment(
+        &evaluator,
+        layout_generator.as_ref(),
+        &layout_str,
+        &options.fix.unwrap_or_default(),
+    )
+    .unwrap_or_else(|e| panic!("Could not assign the layout's characters: {:?}", e));
+    let evaluation_result = evaluator.evaluate_layout(&layout);
+
+    println!(
+        "{}\n\n{}\n\n{}\n{}\n{}",
+        "Optimal unigram assignment:".green().bold(),
+        layout_str,
+        layout.plot_compact(),
+        layout.plot(),
+        evaluation_result,
+    );
+}
@@ -1,5 +1,6 @@
 use clap::Parser;
 
+use keyboard_layout::keyboard::PlotOptions;
 use keyboard_layout_optimizer::common;
 
 #[derive(Parser, Debug)]
@@ -19,6 +20,26 @@ struct Options {
     /// Interpred given layout string using the "grouped" logic
     #[clap(long)]
     pub grouped_layout_generator: bool,
+
+    /// Width of each key cell in the plot (defaults to the widest label)
+    #[clap(long)]
+    cell_width: Option<usize>,
+
+    /// Do not draw borders around the keys
+    #[clap(long)]
+    no_borders: bool,
+
+    /// Plot each row of keys on a single line
+    #[clap(long)]
+    compact_rows: bool,
+
+    /// Display label of a symbol in the form "<symbol>=<label>" (may be given multiple times)
+    #[clap(long)]
+    label: Vec<String>,
+
+    /// Label layer modifier keys with the layer they activate (e.g. "L2")
+    #[clap(long)]
+    label_layer_modifiers: bool,
 }
 
 fn main() {
@@ -41,13 +62,31 @@ fn main() {
             panic!("{:?}", e);
         }
     };
+    let labels = options
+        .label
+        .iter()
+        .map(|l| {
+            let mut chars = l.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), Some('=')) => (c, chars.collect()),
+                _ => panic!("Invalid label '{}', expected \"<symbol>=<label>\"", l),
+            }
+        })
+        .collect();
+    let plot_options = PlotOptions {
+        cell_width: options.cell_width,
+        borders: !options.no_borders,
+        compact: options.compact_rows,
+        labels,
+        label_layer_modifiers: options.label_layer_modifiers,
+    };
     let max_layer = layout.layerkeys.iter().map(|k| k.layer).max().unwrap_or(0);
     for layer in 0..max_layer + 1 {
         println!(
             "Layout '{}' (layer {}):\n{}",
             layout_str,
             layer + 1,
-            layout.plot_layer(layer as usize)
+            layout.plot_layer_with_options(layer as usize, &plot_options)
         );
     }
     println!("Layout compact: \n{}", layout.plot_compact());
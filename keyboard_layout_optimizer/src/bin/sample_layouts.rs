@@ -0,0 +1,106 @@
+use keyboard_layout_optimizer::common;
+use layout_evaluation::cache::Cache;
+use layout_optimization_sa::sampling;
+
+use clap::Parser;
+use std::{fs::File, io::Write};
+
+#[derive(Parser, Debug)]
+#[clap(name = "Keyboard layout sampling - Metropolis chain")]
+/// Sample layouts proportionally to exp(-cost / temperature) with a Metropolis chain, e.g. for
+/// analyzing the distribution of good layouts. Prints the sampled layouts with their costs
+/// (tab separated) and the acceptance rate of the chain.
+struct Options {
+    /// Evaluation parameters
+    #[clap(flatten)]
+    evaluation_parameters: common::Options,
+
+    /// Start the chain from this layout (keys from left to right, top to bottom)
+    #[clap(default_value = "xvlcwkhgfqßuiaeosnrtdyüöäpzbm,.j")]
+    layout_str: String,
+
+    /// Do not move those keys
+    #[clap(short, long)]
+    fix: Option<String>,
+
+    /// Filename of sampling configuration file
+    #[clap(short, long, default_value = "config/optimization/sampling.yml")]
+    sampling_parameters: String,
+
+    /// Override the temperature of the configuration file
+    #[clap(long)]
+    temperature: Option<f64>,
+
+    /// Override the number of samples of the configuration file
+    #[clap(long)]
+    n_samples: Option<usize>,
+
+    /// Write the samples and statistics as JSON to this file instead of printing them
+    #[clap(long)]
+    output: Option<String>,
+
+    /// Do not remove whitespace from the layout string
+    #[clap(long)]
+    do_not_remove_whitespace: bool,
+
+    /// Do not cache intermediate results
+    #[clap(long)]
+    no_cache_results: bool,
+}
+
+fn main() {
+    dotenv::dotenv().ok();
+    env_logger::init();
+    let options = Options::parse();
+
+    let layout_str: String = options
+        .layout_str
+        .chars()
+        .filter(|c| options.do_not_remove_whitespace || !c.is_whitespace())
+        .collect();
+
+    let mut params = sampling::Parameters::from_yaml(&options.sampling_parameters)
+        .unwrap_or_else(|e| panic!("Could not read sampling parameters: {:?}", e));
+    if let Some(temperature) = options.temperature {
+        params.temperature = temperature;
+    }
+    if let Some(n_samples) = options.n_samples {
+        params.n_samples = n_samples;
+    }
+
+    let (layout_generator, evaluator) = common::init(&options.evaluation_parameters);
+    let cache = match options.no_cache_results {
+        true => None,
+        false => Some(Cache::new()),
+    };
+
+    let samples = sampling::sample(
+        &params,
+        &layout_str,
+        &options.fix.unwrap_or_default(),
+        layout_generator.as_ref(),
+        &evaluator,
+        cache,
+    )
+    .unwrap_or_else(|e| panic!("Could not sample layouts: {:?}", e));
+
+    match options.output {
+        Some(filename) => {
+            let mut f = File::create(&filename)
+                .unwrap_or_else(|e| panic!("Could not create {}: {:?}", filename, e));
+            serde_json::to_writer_pretty(&f, &samples).unwrap();
+            writeln!(f).unwrap();
+        }
+        None => samples
+            .samples
+            .iter()
+            .for_each(|s| println!("{}\t{:.4}", s.layout, s.cost)),
+    }
+
+    eprintln!(
+        "Acceptance rate: {:.1}% ({} of {} proposals)",
+        100.0 * samples.acceptance_rate,
+        samples.accepted,
+        samples.proposals,
+    );
+}
@@ -1,12 +1,11 @@
+use crate::pipeline::{EvaluatorBuilder, NgramFilter, NgramSource};
 use keyboard_layout::{
     config::LayoutConfig, grouped_layout_generator::GroupedLayoutGenerator, keyboard::Keyboard,
     layout_generator::LayoutGenerator, neo_layout_generator::NeoLayoutGenerator,
 };
+
 use layout_evaluation::{
-    config::EvaluationParameters,
-    evaluation::Evaluator,
-    ngram_mapper::on_demand_ngram_mapper::OnDemandNgramMapper,
-    ngrams::{Bigrams, Trigrams, Unigrams},
+    config::EvaluationParameters, evaluation::Evaluator, weight_presets::apply_weight_preset,
 };
 
 use ahash::AHashMap;
@@ -15,7 +14,8 @@ use itertools::Itertools;
 use std::{
     fs::{self, OpenOptions},
     io::prelude::*,
-    path::Path,
+    path::PathBuf,
+    str::FromStr,
     sync::Arc,
 };
 
@@ -30,6 +30,11 @@ pub struct Options {
     #[clap(short, long, default_value = "config/evaluation/default.yml")]
     pub eval_parameters: String,
 
+    /// Apply the metric weight preset with this name ("effort" or "speed") to the evaluation
+    /// configuration
+    #[clap(long)]
+    pub weight_preset: Option<String>,
+
     /// Filename of layout configuration file to use
     #[clap(short, long, default_value = "config/keyboard/standard.yml")]
     pub layout_config: String,
@@ -61,6 +66,10 @@ pub struct Options {
     /// Interpred given layout string using the "grouped" logic
     #[clap(long)]
     pub grouped_layout_generator: bool,
+
+    /// Record (and print) the time spent in each metric
+    #[clap(long)]
+    pub profile_metrics: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -84,6 +93,17 @@ pub struct PublishingOptions {
     pub publish_to: String,
 }
 
+/// Initialize the logger from the `RUST_LOG` environment variable. Without it, messages up
+/// to the `Info` level are logged. The given modules additionally log at the `Trace` level.
+pub fn init_logger(trace_modules: &[&str]) {
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+    for module in trace_modules {
+        builder.filter_module(module, log::LevelFilter::Trace);
+    }
+    builder.init();
+}
+
 pub fn init(options: &Options) -> (Box<dyn LayoutGenerator>, Evaluator) {
     (
         init_layout_generator(&options.layout_config, options.grouped_layout_generator),
@@ -130,13 +150,27 @@ pub fn init_layout_generator(
 }
 
 pub fn init_evaluator(options: &Options) -> Evaluator {
-    let eval_params =
-        EvaluationParameters::from_yaml(&options.eval_parameters).unwrap_or_else(|e| {
+    let mut eval_params = match &options.weight_preset {
+        Some(preset) => {
+            let eval_params_str =
+                fs::read_to_string(&options.eval_parameters).unwrap_or_else(|e| {
+                    panic!(
+                        "Could not read evaluation yaml file {}: {:?}",
+                        options.eval_parameters, e
+                    )
+                });
+            let eval_params_str = apply_weight_preset(&eval_params_str, preset)
+                .unwrap_or_else(|e| panic!("Could not apply weight preset: {}", e));
+            EvaluationParameters::from_str(&eval_params_str)
+                .unwrap_or_else(|e| panic!("Could not read evaluation parameters: {:?}", e))
+        }
+        None => EvaluationParameters::from_yaml(&options.eval_parameters).unwrap_or_else(|e| {
             panic!(
                 "Could not read evaluation yaml file {}: {:?}",
                 options.eval_parameters, e
             )
-        });
+        }),
+    };
 
     let text = options.text.as_ref().cloned().or_else(|| {
         options.corpus.as_ref().map(|corpus_file| {
@@ -144,69 +178,27 @@ pub fn init_evaluator(options: &Options) -> Evaluator {
                 .unwrap_or_else(|_| panic!("Could not read corpus file from {}.", corpus_file))
         })
     });
+    let ngrams = match text {
+        Some(text) => NgramSource::Text(text),
+        None => NgramSource::NgramsDir(PathBuf::from(&options.ngrams)),
+    };
 
-    let mut ngram_mapper_config = eval_params.ngram_mapper.clone();
     if options.no_split_modifiers {
-        ngram_mapper_config.split_modifiers.enabled = false;
+        eval_params.ngram_mapper.split_modifiers.enabled = false;
     }
-
-    let mut ngrams_config = eval_params.ngrams.clone();
     if options.no_increase_common_ngrams {
-        ngrams_config.increase_common_ngrams.enabled = false;
+        eval_params.ngrams.increase_common_ngrams.enabled = false;
     }
 
-    let (mut unigrams, mut bigrams, mut trigrams) = match text {
-        Some(txt) => {
-            let unigrams =
-                Unigrams::from_text(&txt).expect("Could not generate unigrams from text.");
-            let bigrams = Bigrams::from_text(&txt).expect("Could not generate bigrams from text.");
-            let trigrams =
-                Trigrams::from_text(&txt).expect("Could not generate trigrams from text.");
-
-            (unigrams, bigrams, trigrams)
-        }
-        None => {
-            let p = Path::new(&options.ngrams).join("1-grams.txt");
-            log::info!("Reading unigram file: '{:?}'", p);
-            let unigrams = Unigrams::from_file(p.to_str().unwrap())
-                .unwrap_or_else(|_| panic!("Could not read 1-gramme file from '{:?}'.", &p));
-            let p = Path::new(&options.ngrams).join("2-grams.txt");
-            log::info!("Reading bigram file: '{:?}'", p);
-            let bigrams = Bigrams::from_file(p.to_str().unwrap())
-                .unwrap_or_else(|_| panic!("Could not read 2-gramme file from '{:?}'.", &p));
-            let p = Path::new(&options.ngrams).join("3-grams.txt");
-            log::info!("Reading trigram file: '{:?}'", p);
-            let trigrams = Trigrams::from_file(p.to_str().unwrap())
-                .unwrap_or_else(|_| panic!("Could not read 3-gramme file from '{:?}'.", &p));
-
-            (unigrams, bigrams, trigrams)
-        }
+    let filter = NgramFilter {
+        exclude_chars: options.exclude_chars.clone().unwrap_or_default(),
+        tops: options.tops,
     };
 
-    if let Some(exclude_chars) = &options.exclude_chars {
-        for exclude_char in exclude_chars.chars() {
-            unigrams = unigrams.exclude_char(&exclude_char);
-            bigrams = bigrams.exclude_char(&exclude_char);
-            trigrams = trigrams.exclude_char(&exclude_char);
-        }
-    }
-
-    if ngrams_config.increase_common_ngrams.enabled {
-        unigrams = unigrams.increase_common(&ngrams_config.increase_common_ngrams);
-        bigrams = bigrams.increase_common(&ngrams_config.increase_common_ngrams);
-        trigrams = trigrams.increase_common(&ngrams_config.increase_common_ngrams);
-    }
-
-    if let Some(tops) = options.tops {
-        unigrams = unigrams.tops(tops);
-        bigrams = bigrams.tops(tops);
-        trigrams = trigrams.tops(tops);
-    }
-
-    let ngram_provider =
-        OnDemandNgramMapper::with_ngrams(unigrams, bigrams, trigrams, ngram_mapper_config);
-
-    Evaluator::default(Box::new(ngram_provider)).default_metrics(&eval_params.metrics)
+    EvaluatorBuilder::new()
+        .build_filtered(&eval_params, &ngrams, &filter)
+        .unwrap_or_else(|e| panic!("Could not initialize the evaluator: {:?}", e))
+        .with_profiling(options.profile_metrics)
 }
 
 /// Appends a layout-string to a file.
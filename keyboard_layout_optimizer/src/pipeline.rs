@@ -0,0 +1,357 @@
+//! The `pipeline` module provides a high-level entrypoint that runs a complete optimization
+//! (loading ngrams, building the evaluator, optimizing, and evaluating the result) without
+//! having to wire up the individual building blocks.
+
+use keyboard_layout::{
+    config::LayoutConfig, keyboard::Keyboard, layout_generator::LayoutGenerator,
+    neo_layout_generator::NeoLayoutGenerator,
+};
+use layout_evaluation::{
+    cache::Cache,
+    config::EvaluationParameters,
+    evaluation::Evaluator,
+    ngram_mapper::{
+        normalization::UnicodeNormalization, on_demand_ngram_mapper::OnDemandNgramMapper,
+    },
+    ngrams::{Bigrams, Trigrams, Unigrams},
+    results::EvaluationResult,
+};
+use layout_optimization_genetic::optimization as genetic_optimization;
+use layout_optimization_sa::optimization as sa_optimization;
+
+use ahash::AHashMap;
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Source of the ngram data used for the evaluation.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum NgramSource {
+    /// Collect ngrams from the given text.
+    Text(String),
+    /// Read ngram frequencies from a directory containing `1-grams.txt`, `2-grams.txt`, and `3-grams.txt`
+    /// (or their binary versions, see [`read_ngrams`]).
+    NgramsDir(PathBuf),
+}
+
+/// The optimization algorithm to use with its parameters.
+#[derive(Clone, Debug)]
+pub enum Backend {
+    SimulatedAnnealing(sa_optimization::Parameters),
+    Genetic(genetic_optimization::Parameters),
+}
+
+/// Options regarding the layout to optimize.
+#[derive(Clone, Debug, Default)]
+pub struct PipelineOptions {
+    /// Layout to start from (and whose fixed characters are kept).
+    pub start_layout: String,
+    /// Characters that shall not be permutated.
+    pub fixed_characters: String,
+    /// Whether to start from `start_layout` instead of a random permutation of it.
+    pub start_with_layout: bool,
+}
+
+/// Result of a pipeline run.
+#[derive(Clone, Debug)]
+pub struct PipelineResult {
+    /// The best layout found.
+    pub layout: String,
+    /// The evaluation of the best layout.
+    pub evaluation: EvaluationResult,
+    /// A plot of the best layout.
+    pub plot: String,
+    /// The evaluation of the start layout (for comparison).
+    pub start_evaluation: EvaluationResult,
+    /// Wall-clock time spent in the optimization.
+    pub duration: Duration,
+}
+
+/// Additional preprocessing of the ngrams of an evaluator, on top of the `ngrams` settings of
+/// the evaluation parameters.
+#[derive(Clone, Debug, Default)]
+pub struct NgramFilter {
+    /// Remove the ngrams that contain any of these characters.
+    pub exclude_chars: String,
+    /// Only keep the top ngrams up to the given fraction.
+    pub tops: Option<f64>,
+}
+
+/// The (unprocessed) ngrams loaded from an [`NgramSource`]. Each order is loaded once it is
+/// required by an evaluator.
+#[derive(Clone, Default, Debug)]
+struct LoadedNgrams {
+    unigrams: Option<Arc<Unigrams>>,
+    bigrams: Option<Arc<Bigrams>>,
+    trigrams: Option<Arc<Trigrams>>,
+}
+
+/// Builds [`Evaluator`]s that share the ngrams loaded from each [`NgramSource`].
+///
+/// Loading the ngrams (reading ngram files or collecting ngrams from a text) is the most
+/// expensive part of building an evaluator. The builder keeps the loaded ngrams keyed by
+/// their source (and the Unicode normalization applied to texts), so that evaluators with
+/// different metrics or ngram settings can be built repeatedly (e.g. in a service) without
+/// loading the same corpus again. Clones of the builder share the loaded ngrams.
+#[derive(Clone, Default, Debug)]
+pub struct EvaluatorBuilder {
+    ngrams: Arc<Mutex<AHashMap<(NgramSource, UnicodeNormalization), LoadedNgrams>>>,
+}
+
+impl EvaluatorBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build an [`Evaluator`] from evaluation parameters and an ngram source.
+    ///
+    /// Only the ngram orders that are used by active metrics are loaded (if they have not
+    /// been loaded from the source before).
+    pub fn build(
+        &self,
+        eval_params: &EvaluationParameters,
+        ngrams: &NgramSource,
+    ) -> Result<Evaluator> {
+        self.build_filtered(eval_params, ngrams, &NgramFilter::default())
+    }
+
+    /// Like [`EvaluatorBuilder::build`], but additionally applies the given [`NgramFilter`].
+    pub fn build_filtered(
+        &self,
+        eval_params: &EvaluationParameters,
+        ngrams: &NgramSource,
+        filter: &NgramFilter,
+    ) -> Result<Evaluator> {
+        let empty_ngram_provider = OnDemandNgramMapper::with_ngrams(
+            Unigrams::default(),
+            Bigrams::default(),
+            Trigrams::default(),
+            eval_params.ngram_mapper.clone(),
+        );
+        let mut evaluator = Evaluator::default(Box::new(empty_ngram_provider))
+            .default_metrics(&eval_params.metrics)
+            .with_typing_speed_weighting(&eval_params.typing_speed_weighting)?
+            .with_digit_handling(&eval_params.digits)
+            .with_calibration(eval_params.load_calibration()?);
+
+        let used = (
+            evaluator.uses_unigrams(),
+            evaluator.uses_bigrams(),
+            evaluator.uses_trigrams(),
+        );
+        for (used, name) in [
+            (used.0, "unigrams"),
+            (used.1, "bigrams"),
+            (used.2, "trigrams"),
+        ] {
+            if !used {
+                log::info!("Skipping {}: no active metric uses them", name);
+            }
+        }
+
+        let loaded = self.load(ngrams, eval_params.ngram_mapper.unicode_normalization, used)?;
+        let mut unigrams = loaded.unigrams.map(|n| (*n).clone()).unwrap_or_default();
+        let mut bigrams = loaded.bigrams.map(|n| (*n).clone()).unwrap_or_default();
+        let mut trigrams = loaded.trigrams.map(|n| (*n).clone()).unwrap_or_default();
+
+        for exclude_char in filter.exclude_chars.chars() {
+            unigrams = unigrams.exclude_char(&exclude_char);
+            bigrams = bigrams.exclude_char(&exclude_char);
+            trigrams = trigrams.exclude_char(&exclude_char);
+        }
+
+        let ngrams_config = &eval_params.ngrams;
+        if ngrams_config.increase_common_ngrams.enabled {
+            unigrams = unigrams.increase_common(&ngrams_config.increase_common_ngrams);
+            bigrams = bigrams.increase_common(&ngrams_config.increase_common_ngrams);
+            trigrams = trigrams.increase_common(&ngrams_config.increase_common_ngrams);
+        }
+        if let Some(fraction) = ngrams_config.trigram_fraction {
+            trigrams = trigrams.tops_scaled(fraction);
+        }
+        if let Some(tops) = filter.tops {
+            unigrams = unigrams.tops(tops);
+            bigrams = bigrams.tops(tops);
+            trigrams = trigrams.tops(tops);
+        }
+
+        let ngram_provider = OnDemandNgramMapper::with_ngrams(
+            unigrams,
+            bigrams,
+            trigrams,
+            eval_params.ngram_mapper.clone(),
+        );
+        evaluator.set_ngram_mapper(Box::new(ngram_provider));
+
+        Ok(evaluator)
+    }
+
+    /// The number of distinct ngram sources currently held by the builder.
+    pub fn nr_sources(&self) -> usize {
+        self.ngrams.lock().unwrap().len()
+    }
+
+    /// Drop all loaded ngrams.
+    pub fn clear(&self) {
+        self.ngrams.lock().unwrap().clear();
+    }
+
+    /// Get the ngrams of the given orders from the source, loading the ones that are missing.
+    fn load(
+        &self,
+        ngrams: &NgramSource,
+        normalization: UnicodeNormalization,
+        (use_unigrams, use_bigrams, use_trigrams): (bool, bool, bool),
+    ) -> Result<LoadedNgrams> {
+        // texts are normalized before their ngrams are collected, ngram files are not
+        let normalization = match ngrams {
+            NgramSource::Text(_) => normalization,
+            NgramSource::NgramsDir(_) => UnicodeNormalization::None,
+        };
+
+        let mut cache = self.ngrams.lock().unwrap();
+        let loaded = cache.entry((ngrams.clone(), normalization)).or_default();
+
+        match ngrams {
+            NgramSource::Text(text) => {
+                let text = normalization.normalize_text(text);
+                if use_unigrams && loaded.unigrams.is_none() {
+                    loaded.unigrams = Some(Arc::new(Unigrams::from_text(&text)?));
+                }
+                if use_bigrams && loaded.bigrams.is_none() {
+                    loaded.bigrams = Some(Arc::new(Bigrams::from_text(&text)?));
+                }
+                if use_trigrams && loaded.trigrams.is_none() {
+                    loaded.trigrams = Some(Arc::new(Trigrams::from_text(&text)?));
+                }
+            }
+            NgramSource::NgramsDir(dir) => {
+                if use_unigrams && loaded.unigrams.is_none() {
+                    let unigrams = read_ngrams(dir, 1, Unigrams::from_file, Unigrams::load_binary)?;
+                    loaded.unigrams = Some(Arc::new(unigrams));
+                }
+                if use_bigrams && loaded.bigrams.is_none() {
+                    let bigrams = read_ngrams(dir, 2, Bigrams::from_file, Bigrams::load_binary)?;
+                    loaded.bigrams = Some(Arc::new(bigrams));
+                }
+                if use_trigrams && loaded.trigrams.is_none() {
+                    let trigrams = read_ngrams(dir, 3, Trigrams::from_file, Trigrams::load_binary)?;
+                    loaded.trigrams = Some(Arc::new(trigrams));
+                }
+            }
+        }
+
+        // only hand out the orders that are used
+        Ok(LoadedNgrams {
+            unigrams: loaded.unigrams.clone().filter(|_| use_unigrams),
+            bigrams: loaded.bigrams.clone().filter(|_| use_bigrams),
+            trigrams: loaded.trigrams.clone().filter(|_| use_trigrams),
+        })
+    }
+}
+
+/// Read the ngrams of order `n` from the directory. A binary file `<n>-grams.bin` (see
+/// `ngrams --binary`) is preferred over the frequencies file `<n>-grams.txt` unless it is older.
+fn read_ngrams<T>(
+    dir: &Path,
+    n: usize,
+    from_file: fn(&str) -> Result<T>,
+    load_binary: fn(PathBuf) -> Result<T>,
+) -> Result<T> {
+    let p = dir.join(format!("{}-grams.txt", n));
+    let p_bin = p.with_extension("bin");
+
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+    let binary_is_current = match (modified(&p_bin), modified(&p)) {
+        (Some(bin_time), Some(txt_time)) => bin_time >= txt_time,
+        (Some(_), None) => true,
+        _ => false,
+    };
+
+    if binary_is_current {
+        log::info!("Reading binary {}-gram file: '{:?}'", n, p_bin);
+        load_binary(p_bin.clone())
+            .with_context(|| format!("Could not read {}-gram file from '{:?}'", n, p_bin))
+    } else {
+        log::info!("Reading {}-gram file: '{:?}'", n, p);
+        from_file(&p.to_string_lossy())
+            .with_context(|| format!("Could not read {}-gram file from '{:?}'", n, p))
+    }
+}
+
+/// Build an [`Evaluator`] from evaluation parameters and an ngram source.
+///
+/// Only the ngram orders that are used by active metrics are loaded. Use an
+/// [`EvaluatorBuilder`] to share the loaded ngrams between multiple evaluators.
+pub fn init_evaluator(
+    eval_params: &EvaluationParameters,
+    ngrams: &NgramSource,
+) -> Result<Evaluator> {
+    EvaluatorBuilder::new().build(eval_params, ngrams)
+}
+
+/// Run a complete optimization: Load the ngrams, build the evaluator, optimize the layout
+/// with the given backend, and evaluate the result.
+pub fn run_pipeline(
+    layout_config: LayoutConfig,
+    eval_params: &EvaluationParameters,
+    ngrams: &NgramSource,
+    backend: &Backend,
+    options: &PipelineOptions,
+) -> Result<PipelineResult> {
+    let keyboard = Arc::new(Keyboard::from_yaml_object(layout_config.keyboard));
+    let layout_generator: Box<dyn LayoutGenerator> = Box::new(NeoLayoutGenerator::from_object(
+        layout_config.base_layout,
+        keyboard,
+    ));
+    let evaluator = init_evaluator(eval_params, ngrams)?;
+
+    let start_layout = layout_generator
+        .generate(&options.start_layout)
+        .context("Invalid start layout")?;
+    let start_evaluation = evaluator.evaluate_layout(&start_layout);
+
+    let start = Instant::now();
+    let (layout_str, layout) = match backend {
+        Backend::SimulatedAnnealing(params) => {
+            let mut params = params.clone();
+            params.correct_init_temp();
+            sa_optimization::optimize(
+                "Pipeline",
+                &params,
+                &options.start_layout,
+                &options.fixed_characters,
+                &layout_generator,
+                options.start_with_layout,
+                &evaluator,
+                false,
+                Some(Cache::new()),
+                None,
+                None,
+            )
+        }
+        Backend::Genetic(params) => genetic_optimization::optimize(
+            params,
+            &evaluator,
+            &options.start_layout,
+            &layout_generator,
+            &options.fixed_characters,
+            options.start_with_layout,
+            true,
+            None,
+        ),
+    }?;
+    let duration = start.elapsed();
+
+    Ok(PipelineResult {
+        evaluation: evaluator.evaluate_layout(&layout),
+        plot: layout.plot(),
+        layout: layout_str,
+        start_evaluation,
+        duration,
+    })
+}
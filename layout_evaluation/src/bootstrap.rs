@@ -0,0 +1,246 @@
+//! The `bootstrap` module estimates whether the cost difference of two layouts is genuine or
+//! within the noise of the corpus.
+//!
+//! The ngrams are resampled many times, both layouts are evaluated with each resample, and the
+//! distribution of the cost differences is summarized.
+//!
+//! Resampling scheme: A corpus of N ngram occurrences is resampled by drawing N occurrences
+//! with replacement. For large corpora, the resampled count of each ngram is (approximately)
+//! Poisson distributed with its original count as mean and independent of all other ngrams
+//! ("Poisson bootstrap"), which is how the counts are drawn here. Unigrams, bigrams, and
+//! trigrams are resampled independently. The ngram weights are taken as counts unless the
+//! size of the corpus is given (see [`Bootstrap::with_corpus_size`]), e.g. for ngrams with
+//! relative frequencies.
+
+use crate::{
+    evaluation::Evaluator,
+    ngram_mapper::on_demand_ngram_mapper::{NgramMapperConfig, OnDemandNgramMapper},
+    ngrams::{Bigrams, Trigrams, Unigrams},
+};
+
+use keyboard_layout::layout::Layout;
+
+use ahash::AHashMap;
+use rand::Rng;
+use serde::Serialize;
+use std::{f64::consts::PI, hash::Hash};
+
+/// The distribution of the cost difference of two layouts A and B (cost of A minus cost of B)
+/// over resampled ngrams.
+#[derive(Clone, Debug, Serialize)]
+pub struct BootstrapSummary {
+    /// Cost difference with the original ngrams
+    pub difference: f64,
+    /// Cost differences with each resample (ascending)
+    pub differences: Vec<f64>,
+    /// Mean of the resampled cost differences
+    pub mean: f64,
+    /// Standard deviation of the resampled cost differences
+    pub std_dev: f64,
+    /// Lower and upper bound of the 95% percentile interval of the resampled cost differences
+    pub interval_95: (f64, f64),
+    /// Fraction of the resamples in which layout A is cheaper than layout B
+    pub confidence_a_better: f64,
+}
+
+/// Resamples the ngrams of a corpus for comparing layouts (see the module documentation).
+#[derive(Clone, Debug)]
+pub struct Bootstrap {
+    unigrams: Unigrams,
+    bigrams: Bigrams,
+    trigrams: Trigrams,
+    config: NgramMapperConfig,
+    corpus_size: Option<f64>,
+}
+
+impl Bootstrap {
+    /// Generate a [`Bootstrap`] for the given (unprocessed) char-based ngrams, which are mapped
+    /// with the given configuration.
+    pub fn new(
+        unigrams: Unigrams,
+        bigrams: Bigrams,
+        trigrams: Trigrams,
+        config: NgramMapperConfig,
+    ) -> Self {
+        Self {
+            unigrams,
+            bigrams,
+            trigrams,
+            config,
+            corpus_size: None,
+        }
+    }
+
+    /// Set the number of ngram occurrences in the corpus the ngrams stem from. The weights of
+    /// each ngram order are scaled to this size before resampling (and scaled back afterwards).
+    pub fn with_corpus_size(mut self, corpus_size: f64) -> Self {
+        self.corpus_size = Some(corpus_size);
+        self
+    }
+
+    /// Evaluate both layouts with the metrics of the `evaluator` on `samples` resamples of the
+    /// ngrams and summarize the distribution of their cost difference (A minus B).
+    pub fn compare<R: Rng>(
+        &self,
+        evaluator: &Evaluator,
+        layout_a: &Layout,
+        layout_b: &Layout,
+        samples: usize,
+        rng: &mut R,
+    ) -> BootstrapSummary {
+        let mut evaluator = evaluator.clone();
+        let mut difference = |unigrams, bigrams, trigrams| {
+            evaluator.set_ngram_mapper(Box::new(OnDemandNgramMapper::with_ngrams(
+                unigrams,
+                bigrams,
+                trigrams,
+                self.config.clone(),
+            )));
+            evaluator.evaluate_layout(layout_a).total_cost()
+                - evaluator.evaluate_layout(layout_b).total_cost()
+        };
+
+        let original = difference(
+            self.unigrams.clone(),
+            self.bigrams.clone(),
+            self.trigrams.clone(),
+        );
+        let mut differences: Vec<f64> = (0..samples)
+            .map(|_| {
+                difference(
+                    Unigrams {
+                        grams: self.resample(&self.unigrams.grams, rng),
+                    },
+                    Bigrams {
+                        grams: self.resample(&self.bigrams.grams, rng),
+                    },
+                    Trigrams {
+                        grams: self.resample(&self.trigrams.grams, rng),
+                    },
+                )
+            })
+            .collect();
+        differences.sort_by(|d1, d2| d1.partial_cmp(d2).unwrap());
+
+        let n = differences.len().max(1) as f64;
+        let mean = differences.iter().sum::<f64>() / n;
+        let variance = differences.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / n;
+        let percentile = |p: f64| match differences.len() {
+            0 => original,
+            len => differences[((p * (len - 1) as f64).round() as usize).min(len - 1)],
+        };
+
+        BootstrapSummary {
+            difference: original,
+            mean,
+            std_dev: variance.sqrt(),
+            interval_95: (percentile(0.025), percentile(0.975)),
+            confidence_a_better: differences.iter().filter(|d| **d < 0.0).count() as f64 / n,
+            differences,
+        }
+    }
+
+    /// Draw Poisson distributed counts for all ngrams (in a fixed order, so that results are
+    /// reproducible with a seeded random number generator).
+    fn resample<T, R>(&self, grams: &AHashMap<T, f64>, rng: &mut R) -> AHashMap<T, f64>
+    where
+        T: Copy + Ord + Hash,
+        R: Rng,
+    {
+        let total_weight: f64 = grams.values().sum();
+        let scale = match self.corpus_size {
+            Some(corpus_size) if total_weight > 0.0 => corpus_size / total_weight,
+            _ => 1.0,
+        };
+
+        let mut sorted: Vec<(&T, &f64)> = grams.iter().collect();
+        sorted.sort_by_key(|(gram, _)| **gram);
+        sorted
+            .into_iter()
+            .filter_map(|(gram, weight)| {
+                let count = sample_poisson(weight * scale, rng);
+                if count > 0.0 {
+                    Some((*gram, count / scale))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Draw a Poisson distributed number with the given mean (using a normal approximation for
+/// large means).
+fn sample_poisson<R: Rng>(mean: f64, rng: &mut R) -> f64 {
+    if mean <= 0.0 {
+        return 0.0;
+    }
+
+    if mean < 30.0 {
+        let limit = (-mean).exp();
+        let mut product: f64 = rng.gen();
+        let mut count = 0.0;
+        while product > limit {
+            product *= rng.gen::<f64>();
+            count += 1.0;
+        }
+        return count;
+    }
+
+    // Box-Muller transform
+    let (u1, u2): (f64, f64) = (rng.gen(), rng.gen());
+    let z = (-2.0 * (1.0 - u1).ln()).sqrt() * (2.0 * PI * u2).cos();
+
+    (mean + mean.sqrt() * z).round().max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bootstrap;
+    use crate::{
+        ngrams::{Bigrams, Trigrams, Unigrams},
+        test_utils::{eval_params, evaluator, layout_generator, LAYOUT, TEXT},
+    };
+
+    use keyboard_layout::layout_generator::LayoutGenerator;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn bootstrap_summarizes_the_resampled_cost_differences() {
+        let layout_generator = layout_generator();
+
+        let eval_params = eval_params();
+        let evaluator = evaluator(TEXT, &eval_params);
+        let bootstrap = Bootstrap::new(
+            Unigrams::from_text(TEXT).unwrap(),
+            Bigrams::from_text(TEXT).unwrap(),
+            Trigrams::from_text(TEXT).unwrap(),
+            eval_params.ngram_mapper,
+        );
+
+        let a = layout_generator.generate(LAYOUT).unwrap();
+        let b = layout_generator
+            .generate("q0a1zw2sbxt3decr4fgvuoj5miyk67hnl89p={}([")
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+
+        // identical layouts never differ
+        let summary = bootstrap.compare(&evaluator, &a, &a, 10, &mut rng);
+        assert_eq!(summary.differences, vec![0.0; 10]);
+        assert_eq!(summary.confidence_a_better, 0.0);
+
+        let summary = bootstrap.compare(&evaluator, &a, &b, 20, &mut rng);
+        assert_eq!(summary.differences.len(), 20);
+        assert!(summary.interval_95.0 <= summary.interval_95.1);
+        assert!((0.0..=1.0).contains(&summary.confidence_a_better));
+
+        // the noise vanishes for huge corpora
+        let summary = bootstrap
+            .with_corpus_size(1e15)
+            .compare(&evaluator, &a, &b, 5, &mut rng);
+        for d in summary.differences.iter() {
+            assert!((d - summary.difference).abs() < 1e-3 * summary.difference.abs().max(1.0));
+        }
+    }
+}
@@ -1,11 +1,21 @@
 use ahash::AHashMap;
 use colored::Colorize;
 use parking_lot::Mutex;
-use std::{fmt, sync::Arc};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
+/// A thread-safe map from (canonical) layout strings to results. Clones share their entries
+/// and hit statistics.
 #[derive(Clone, Debug)]
 pub struct Cache<T: Clone> {
     cache: Arc<Mutex<AHashMap<String, T>>>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
 }
 
 impl<T: Clone> Default for Cache<T> {
@@ -18,6 +28,28 @@ impl<T: Clone> Cache<T> {
     pub fn new() -> Self {
         Self {
             cache: Arc::new(Mutex::new(AHashMap::default())),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Number of lookups that were answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of lookups that required computing the result.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of lookups that were answered from the cache (`None` if there were none yet).
+    pub fn hit_rate(&self) -> Option<f64> {
+        let hits = self.hits();
+        let total = hits + self.misses();
+        match total {
+            0 => None,
+            _ => Some(hits as f64 / total as f64),
         }
     }
 
@@ -27,7 +59,11 @@ impl<T: Clone> Cache<T> {
             let cache = self.cache.lock();
             cache_val = cache.get(elem).cloned();
         }
+        if cache_val.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
         cache_val.unwrap_or_else(|| {
+            self.misses.fetch_add(1, Ordering::Relaxed);
             let res = f();
             {
                 let mut cache = self.cache.lock();
@@ -0,0 +1,151 @@
+//! The `calibration` module provides a [`Calibration`] that converts the (dimensionless)
+//! costs of an evaluation into a unit of choice, e.g. milliseconds of typing time, so that
+//! results of different metric configurations can be compared and interpreted.
+//!
+//! The calibration only affects how results are reported. Each metric's contribution to the
+//! total cost (its cost after weighting and normalization, as printed in the results) is
+//! multiplied with `units_per_cost` and the metric's factor in `metric_factors` (1 by default):
+//!
+//! `calibrated_cost = units_per_cost * metric_factor * weight * cost / normalization`
+//!
+//! where `cost / normalization` is the metric's raw output divided by its normalization (e.g.
+//! the found ngram weight for `weight_found`). A typical calibration first chooses
+//! `units_per_cost` such that a reference, e.g. the total cost of typing a standard keystroke,
+//! maps to its measured value in the unit, and then corrects individual metrics whose scale
+//! deviates from that reference with their factors. The calibrated total cost is the sum of
+//! the calibrated metric costs.
+
+use crate::metric_info::available_metrics;
+
+use ahash::AHashMap;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{fs::File, str::FromStr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CalibrationError {
+    #[error("Unknown metric '{0}' in calibration")]
+    UnknownMetric(String),
+    #[error("Invalid calibration factor {1} for '{0}' (needs to be finite and positive)")]
+    InvalidFactor(String, f64),
+}
+
+/// Conversion of evaluation costs into a unit (see the module documentation).
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Calibration {
+    /// Name of the unit, e.g. "ms"
+    pub unit: String,
+    /// Amount of the unit corresponding to one unit of (weighted and normalized) cost
+    pub units_per_cost: f64,
+    /// Additional factors for individual metrics, by their ids in the evaluation parameters
+    /// (e.g. `key_costs`)
+    #[serde(default)]
+    pub metric_factors: AHashMap<String, f64>,
+}
+
+impl Calibration {
+    pub fn from_yaml(filename: &str) -> Result<Self> {
+        let f = File::open(filename)?;
+        let calibration: Calibration = serde_yaml::from_reader(f)?;
+        calibration.validate()?;
+
+        Ok(calibration)
+    }
+
+    /// Check that the factors are positive and refer to known metrics.
+    pub fn validate(&self) -> Result<()> {
+        let valid = |f: f64| f.is_finite() && f > 0.0;
+        if !valid(self.units_per_cost) {
+            return Err(CalibrationError::InvalidFactor(
+                "units_per_cost".to_string(),
+                self.units_per_cost,
+            )
+            .into());
+        }
+
+        let metrics = available_metrics();
+        for (id, factor) in self.metric_factors.iter() {
+            if !metrics.iter().any(|m| m.id == id) {
+                return Err(CalibrationError::UnknownMetric(id.clone()).into());
+            }
+            if !valid(*factor) {
+                return Err(CalibrationError::InvalidFactor(id.clone(), *factor).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The factor converting a contribution of the metric with the given name (as used in
+    /// evaluation results) into the unit.
+    pub fn factor(&self, metric_name: &str) -> f64 {
+        let metric_factor = available_metrics()
+            .into_iter()
+            .find(|m| m.name == metric_name)
+            .and_then(|m| self.metric_factors.get(m.id))
+            .unwrap_or(&1.0);
+
+        self.units_per_cost * metric_factor
+    }
+}
+
+impl FromStr for Calibration {
+    type Err = anyhow::Error;
+    fn from_str(calibration_str: &str) -> Result<Self, Self::Err> {
+        let calibration: Calibration = serde_yaml::from_str(calibration_str)?;
+        calibration.validate()?;
+
+        Ok(calibration)
+    }
+}
+
+/// A calibration in the evaluation parameters, given either as the path of a calibration file
+/// or inline.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum CalibrationConfig {
+    File(String),
+    Inline(Calibration),
+}
+
+impl CalibrationConfig {
+    /// Load the calibration (reading the file, if given as a path).
+    pub fn load(&self) -> Result<Calibration> {
+        match self {
+            CalibrationConfig::File(filename) => Calibration::from_yaml(filename),
+            CalibrationConfig::Inline(calibration) => {
+                calibration.validate()?;
+                Ok(calibration.clone())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factors_apply_by_metric_name() {
+        let calibration: Calibration =
+            "unit: ms\nunits_per_cost: 0.5\nmetric_factors:\n  key_costs: 2.0\n"
+                .parse()
+                .unwrap();
+
+        assert_eq!(calibration.factor("Key Costs"), 1.0);
+        assert_eq!(calibration.factor("Finger Repeats"), 0.5);
+    }
+
+    #[test]
+    fn rejects_unknown_metrics_and_invalid_factors() {
+        assert!(
+            "unit: ms\nunits_per_cost: 1.0\nmetric_factors:\n  no_such_metric: 1.0\n"
+                .parse::<Calibration>()
+                .is_err()
+        );
+        assert!("unit: ms\nunits_per_cost: 0.0\n"
+            .parse::<Calibration>()
+            .is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::{
-    evaluation::MetricParameters, ngram_mapper::on_demand_ngram_mapper::NgramMapperConfig,
+    calibration::{Calibration, CalibrationConfig},
+    evaluation::{DigitHandlingConfig, MetricParameters, TypingSpeedWeightingConfig},
+    ngram_mapper::on_demand_ngram_mapper::NgramMapperConfig,
     ngrams::NgramsConfig,
 };
 
@@ -12,6 +14,14 @@ pub struct EvaluationParameters {
     pub metrics: MetricParameters,
     pub ngrams: NgramsConfig,
     pub ngram_mapper: NgramMapperConfig,
+    #[serde(default)]
+    pub typing_speed_weighting: TypingSpeedWeightingConfig,
+    /// Separate handling of ngrams involving digits
+    #[serde(default)]
+    pub digits: DigitHandlingConfig,
+    /// Conversion of the costs into a unit (only affects how results are reported)
+    #[serde(default)]
+    pub calibration: Option<CalibrationConfig>,
 }
 
 impl EvaluationParameters {
@@ -21,6 +31,11 @@ impl EvaluationParameters {
 
         Ok(k)
     }
+
+    /// Load the configured calibration, if any.
+    pub fn load_calibration(&self) -> Result<Option<Calibration>> {
+        self.calibration.as_ref().map(|c| c.load()).transpose()
+    }
 }
 
 impl FromStr for EvaluationParameters {
@@ -7,17 +7,62 @@
 //! The ngram mapper is responsible for mapping char-based ngrams (as read from input data)
 //! to singles, pairs, and triplets of [`LayerKey`]s that can then be analysed by the individual metrics.
 
+use crate::calibration::Calibration;
 use crate::results::{
-    EvaluationResult, MetricResult, MetricResults, MetricType, NormalizationType,
+    BigramCostMatrix, CharCosts, ContributionBounds, Coverage, EvaluationResult, KeyPresses,
+    MetricResult, MetricResults, MetricType, NormalizationType, RankedLayout,
 };
 use crate::{
-    metrics::{bigram_metrics::*, layout_metrics::*, trigram_metrics::*, unigram_metrics::*},
-    ngram_mapper::NgramMapper,
+    metric_info::available_metrics,
+    metrics::{
+        bigram_metrics::*, layout_metrics::*, trigram_metrics::*, unigram_metrics::*, Metric,
+    },
+    ngram_mapper::{
+        on_demand_ngram_mapper::OnDemandNgramMapper, MappedBigrams, MappedTrigrams, MappedUnigrams,
+        NgramMapper,
+    },
 };
 
-use keyboard_layout::layout::{LayerKey, Layout};
+use keyboard_layout::{
+    key::MatrixPosition,
+    layout::{LayerKey, Layout, EMPTY_SYMBOL},
+    layout_generator::LayoutGenerator,
+};
 
+use ahash::{AHashMap, AHashSet};
+use anyhow::Result;
+use instant::Instant;
+use ordered_float::OrderedFloat;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::Deserialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use thiserror::Error;
+
+/// A callback reporting the progress of a batch operation with the number of finished items and
+/// the total number of items. With the `parallel` feature, it may be called from multiple threads.
+#[cfg(feature = "parallel")]
+pub type ProgressCallback<'a> = dyn Fn(usize, usize) + Sync + 'a;
+/// A callback reporting the progress of a batch operation with the number of finished items and
+/// the total number of items. With the `parallel` feature, it may be called from multiple threads.
+#[cfg(not(feature = "parallel"))]
+pub type ProgressCallback<'a> = dyn Fn(usize, usize) + 'a;
+
+#[derive(Error, Debug)]
+pub enum EvaluationError {
+    #[error("Unknown metric '{0}'")]
+    UnknownMetric(String),
+    #[error("Metric '{0}' is not active in the evaluator")]
+    InactiveMetric(String),
+    #[error("Can not place '{0}': It is already placed in the layout")]
+    AlreadyPlaced(char),
+    #[error("Can not place '{0}': The layout has no empty position")]
+    NoEmptyPosition(char),
+    #[error("Invalid corpus mixture: {0}")]
+    InvalidCorpusMixture(String),
+    #[error("Invalid typing-speed weighting strength {0}: It must not be negative")]
+    InvalidTypingSpeedStrength(f64),
+}
 
 /// A wrapper around individuals metric's parameters (`T`) specifying
 /// additional generic attributes. This mostly facilitates configuration of
@@ -30,10 +75,164 @@ pub struct WeightedParams<T> {
     pub weight: f64,
     /// The normalization strategy to use.
     pub normalization: NormalizationType,
+    /// Lower bound of the metric's contribution to the total cost (after weighting and
+    /// normalization), e.g. to cap the reward of a metric with negative costs.
+    #[serde(default)]
+    pub min_contribution: Option<f64>,
+    /// Upper bound of the metric's contribution to the total cost (after weighting and
+    /// normalization).
+    #[serde(default)]
+    pub max_contribution: Option<f64>,
     /// The metric's individual parameters.
     pub params: T,
 }
 
+impl<T> WeightedParams<T> {
+    /// The bounds of the metric's contribution to the total cost.
+    pub fn contribution_bounds(&self) -> ContributionBounds {
+        ContributionBounds {
+            min: self.min_contribution,
+            max: self.max_contribution,
+        }
+    }
+}
+
+/// Configuration of the (optional) typing-speed weighting of ngrams.
+///
+/// Awkward sequences are not only uncomfortable, but also take longer to type, which
+/// compounds their impact on fatigue. If enabled, each ngram's weight is multiplied with an
+/// estimated typing-time factor before the metrics are evaluated. The factor is derived from
+/// the ngram's "difficulty", i.e. the sum of the individual costs that all (positively weighted)
+/// metrics of the same ngram order assign to it, multiplied with the metrics' weights:
+///
+/// `factor = (1 + strength * difficulty / mean_difficulty) / (1 + strength)`
+///
+/// where `mean_difficulty` is the frequency-weighted mean difficulty of all ngrams. The
+/// denominator keeps the total weight of the ngrams unchanged, so that difficult ngrams gain
+/// weight at the expense of easy ones. Metrics that do not provide individual costs do not
+/// contribute to the difficulty. Layout metrics are unaffected.
+#[derive(Clone, Deserialize, Debug)]
+pub struct TypingSpeedWeightingConfig {
+    /// Whether to apply the typing-speed weighting.
+    pub enabled: bool,
+    /// How strongly the difficulty of an ngram influences its weight (must not be negative).
+    pub strength: f64,
+}
+
+impl Default for TypingSpeedWeightingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strength: 1.0,
+        }
+    }
+}
+
+/// Configuration of the (optional) separate handling of digits.
+///
+/// Numbers are typed in bursts and many users care less about their placement than about the
+/// letters'. If enabled, ngrams involving a digit key (a key whose symbol is a digit, e.g.
+/// also for shifted number-row symbols when modifiers are split) form a separate category:
+/// Their weight is multiplied with `weight` before the unigram, bigram, and trigram metrics
+/// are evaluated, and the metrics listed in `excluded_metrics` do not see them at all.
+/// Excluded metrics are normalized with the same found weight as the other metrics of their
+/// ngram order. Layout metrics are unaffected.
+#[derive(Clone, Deserialize, Debug)]
+pub struct DigitHandlingConfig {
+    /// Whether to handle digits separately.
+    pub enabled: bool,
+    /// Multiply the weight of ngrams involving digit keys with this factor.
+    pub weight: f64,
+    /// Ids of the metrics (as in the evaluation parameters, e.g. `key_costs`) that ignore
+    /// ngrams involving digit keys.
+    #[serde(default)]
+    pub excluded_metrics: Vec<String>,
+}
+
+impl Default for DigitHandlingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            weight: 1.0,
+            excluded_metrics: Vec::new(),
+        }
+    }
+}
+
+/// The resolved [`DigitHandlingConfig`] of an [`Evaluator`].
+#[derive(Clone, Debug)]
+struct DigitHandling {
+    weight: f64,
+    /// Names of the metrics that ignore ngrams involving digit keys
+    excluded_metrics: Vec<String>,
+}
+
+/// Whether a key generates a digit (see [`DigitHandlingConfig`]).
+fn is_digit_key(k: &LayerKey) -> bool {
+    k.is_modifier.is_none() && k.symbol.is_ascii_digit()
+}
+
+/// Multiply the weight of the ngrams involving digit keys with a factor. Returns the total
+/// weight by which the ngrams were reduced.
+fn scale_digit_grams<T>(
+    grams: &mut [(T, f64)],
+    factor: f64,
+    has_digit: impl Fn(&T) -> bool,
+) -> f64 {
+    grams
+        .iter_mut()
+        .filter(|(gram, _)| has_digit(gram))
+        .map(|(_, w)| {
+            let reduction = (1.0 - factor) * *w;
+            *w *= factor;
+            reduction
+        })
+        .sum()
+}
+
+/// Remove the ngrams involving a key outside of the zone (see [`Evaluator::with_zone`]).
+/// Returns the total weight of the removed ngrams.
+fn retain_zone_grams<T>(grams: &mut Vec<(T, f64)>, in_zone: impl Fn(&T) -> bool) -> f64 {
+    let mut removed_weight = 0.0;
+    grams.retain(|(gram, w)| {
+        let keep = in_zone(gram);
+        if !keep {
+            removed_weight += w;
+        }
+        keep
+    });
+
+    removed_weight
+}
+
+/// Multiply each ngram's weight with its typing-time factor (see [`TypingSpeedWeightingConfig`]).
+fn apply_typing_speed_weighting<T>(
+    grams: &mut [(T, f64)],
+    strength: f64,
+    difficulty: impl Fn(&T, f64) -> f64,
+) {
+    let total_weight: f64 = grams.iter().map(|(_, w)| w).sum();
+    let difficulties: Vec<f64> = grams
+        .iter()
+        .map(|(gram, _)| difficulty(gram, total_weight).max(0.0))
+        .collect();
+    let mean_difficulty = grams
+        .iter()
+        .zip(difficulties.iter())
+        .map(|((_, w), d)| w * d)
+        .sum::<f64>()
+        / total_weight;
+
+    if mean_difficulty.is_nan() || mean_difficulty <= 0.0 {
+        return;
+    }
+
+    grams
+        .iter_mut()
+        .zip(difficulties)
+        .for_each(|((_, w), d)| *w *= (1.0 + strength * d / mean_difficulty) / (1.0 + strength));
+}
+
 /// Compiles configuration parameters for all "default" metrics available.
 /// This is usually read from a config file.
 #[derive(Clone, Deserialize, Debug)]
@@ -41,25 +240,43 @@ pub struct MetricParameters {
     pub shortcut_keys: Option<WeightedParams<shortcut_keys::Parameters>>,
     pub similar_letters: Option<WeightedParams<similar_letters::Parameters>>,
     pub similar_letter_groups: Option<WeightedParams<similar_letter_groups::Parameters>>,
+    pub one_hand_words: Option<WeightedParams<one_hand_words::Parameters>>,
 
     pub finger_balance: Option<WeightedParams<finger_balance::Parameters>>,
     pub hand_disbalance: Option<WeightedParams<hand_disbalance::Parameters>>,
+    pub hand_symmetry: Option<WeightedParams<hand_symmetry::Parameters>>,
     pub row_loads: Option<WeightedParams<row_loads::Parameters>>,
+    pub pinky_off_home: Option<WeightedParams<pinky_off_home::Parameters>>,
+    pub bottom_row_load: Option<WeightedParams<bottom_row_load::Parameters>>,
+    pub thumb_load: Option<WeightedParams<thumb_load::Parameters>>,
     pub key_costs: Option<WeightedParams<key_costs::Parameters>>,
     pub modifier_usage: Option<WeightedParams<modifier_usage::Parameters>>,
+    pub same_hand_modifiers: Option<WeightedParams<same_hand_modifiers::Parameters>>,
 
     pub symmetric_handswitches: Option<WeightedParams<symmetric_handswitches::Parameters>>,
+    pub same_row_bigrams: Option<WeightedParams<same_row_bigrams::Parameters>>,
+    pub same_hand_after_space: Option<WeightedParams<same_hand_after_space::Parameters>>,
     pub finger_repeats: Option<WeightedParams<finger_repeats::Parameters>>,
+    pub finger_gap: Option<WeightedParams<finger_gap::Parameters>>,
+    pub lateral_stretch: Option<WeightedParams<lateral_stretch::Parameters>>,
+    pub sticky_finger: Option<WeightedParams<sticky_finger::Parameters>>,
     pub scissoring: Option<WeightedParams<scissoring::Parameters>>,
     pub manual_bigram_penalty: Option<WeightedParams<manual_bigram_penalty::Parameters>>,
+    pub alternating_column_penalty: Option<WeightedParams<alternating_column_penalty::Parameters>>,
     pub movement_pattern: Option<WeightedParams<movement_pattern::Parameters>>,
     pub no_handswitch_after_unbalancing_key:
         Option<WeightedParams<no_handswitch_after_unbalancing_key::Parameters>>,
+    pub worst_bigrams: Option<WeightedParams<worst_bigrams::Parameters>>,
 
     pub irregularity: Option<WeightedParams<irregularity::Parameters>>,
     pub no_handswitch_in_trigram: Option<WeightedParams<no_handswitch_in_trigram::Parameters>>,
     pub secondary_bigrams: Option<WeightedParams<secondary_bigrams::Parameters>>,
     pub trigram_finger_repeats: Option<WeightedParams<trigram_finger_repeats::Parameters>>,
+    pub trigram_same_finger: Option<WeightedParams<trigram_same_finger::Parameters>>,
+    pub finger_reuse_gaps: Option<WeightedParams<finger_reuse_gaps::Parameters>>,
+    pub handswitch_then_same_finger:
+        Option<WeightedParams<handswitch_then_same_finger::Parameters>>,
+    pub key_repeats: Option<WeightedParams<key_repeats::Parameters>>,
     pub trigram_rolls: Option<WeightedParams<trigram_rolls::Parameters>>,
 
     pub kla_home_key_words: Option<WeightedParams<kla_home_key_words::Parameters>>,
@@ -92,6 +309,14 @@ pub struct Evaluator {
     bigram_metrics: Vec<(f64, NormalizationType, Box<dyn BigramMetric>)>,
     trigram_metrics: Vec<(f64, NormalizationType, Box<dyn TrigramMetric>)>,
     ngram_mapper: Box<dyn NgramMapper>,
+    corpus_blend: Option<(Box<dyn NgramMapper>, f64)>,
+    profiling: bool,
+    typing_speed_strength: Option<f64>,
+    calibration: Option<Calibration>,
+    contribution_bounds: AHashMap<String, ContributionBounds>,
+    digit_handling: Option<DigitHandling>,
+    zone: Option<AHashSet<MatrixPosition>>,
+    key_cost: Option<key_costs::KeyCost>,
 }
 
 impl Evaluator {
@@ -103,34 +328,304 @@ impl Evaluator {
             bigram_metrics: Vec::new(),
             trigram_metrics: Vec::new(),
             ngram_mapper,
+            corpus_blend: None,
+            profiling: false,
+            typing_speed_strength: None,
+            calibration: None,
+            contribution_bounds: AHashMap::default(),
+            digit_handling: None,
+            zone: None,
+            key_cost: None,
+        }
+    }
+
+    /// Generate an "empty" [`Evaluator`] object without any metric that evaluates a weighted
+    /// mixture of several corpora (e.g. in different languages). The weights give the shares
+    /// of the corpora independently of their sizes.
+    ///
+    /// The ngrams of all corpora are merged into a single ngram mapper in advance (see
+    /// [`OnDemandNgramMapper::mixture`]), so an evaluation is as fast as with a single corpus.
+    /// Changing the weights requires constructing a new evaluator, though (see
+    /// [`Evaluator::with_corpus_blend`] for an adjustable alternative with two corpora).
+    pub fn from_corpus_mixture(corpora: &[(OnDemandNgramMapper, f64)]) -> Result<Self> {
+        if let Some((_, weight)) = corpora.iter().find(|(_, w)| !w.is_finite() || *w < 0.0) {
+            return Err(EvaluationError::InvalidCorpusMixture(format!(
+                "Invalid weight {}",
+                weight
+            ))
+            .into());
+        }
+        let ngram_mapper = OnDemandNgramMapper::mixture(corpora).ok_or_else(|| {
+            EvaluationError::InvalidCorpusMixture("No corpus with a positive weight".to_string())
+        })?;
+
+        Ok(Self::default(Box::new(ngram_mapper)))
+    }
+
+    /// Enable or disable recording the time spent in each metric (disabled by default).
+    pub fn with_profiling(mut self, profiling: bool) -> Self {
+        self.set_profiling(profiling);
+        self
+    }
+
+    /// Enable or disable recording the time spent in each metric (disabled by default).
+    pub fn set_profiling(&mut self, profiling: bool) {
+        self.profiling = profiling;
+    }
+
+    /// Configure the typing-speed weighting of ngrams (disabled by default). A negative
+    /// strength is rejected.
+    pub fn with_typing_speed_weighting(
+        mut self,
+        config: &TypingSpeedWeightingConfig,
+    ) -> Result<Self> {
+        if config.enabled && config.strength < 0.0 {
+            return Err(EvaluationError::InvalidTypingSpeedStrength(config.strength).into());
+        }
+
+        self.typing_speed_strength = config.enabled.then(|| config.strength);
+        Ok(self)
+    }
+
+    /// Configure the separate handling of digits (disabled by default). Unknown metric ids in
+    /// `excluded_metrics` are ignored with a warning.
+    pub fn with_digit_handling(mut self, config: &DigitHandlingConfig) -> Self {
+        self.digit_handling = config.enabled.then(|| {
+            let metrics = available_metrics();
+            let excluded_metrics = config
+                .excluded_metrics
+                .iter()
+                .filter_map(|id| match metrics.iter().find(|m| m.id == id) {
+                    Some(m) => Some(m.name.to_string()),
+                    None => {
+                        log::warn!("Ignoring unknown metric '{}' in the digit handling", id);
+                        None
+                    }
+                })
+                .collect();
+
+            DigitHandling {
+                weight: config.weight,
+                excluded_metrics,
+            }
+        });
+        self
+    }
+
+    /// Restrict the unigram, bigram, and trigram metrics to a zone of the keyboard given by the
+    /// matrix positions of its keys (e.g. the alpha block). Ngrams involving a key outside of
+    /// the zone (e.g. also a modifier when modifiers are split) are ignored completely, i.e.
+    /// their weight is not counted as found ngram weight either. Layout metrics are
+    /// unaffected. `None` (the default) evaluates the whole keyboard.
+    pub fn with_zone(mut self, zone: Option<&[MatrixPosition]>) -> Self {
+        self.set_zone(zone);
+        self
+    }
+
+    /// Change the zone of the evaluation (see [`Evaluator::with_zone`]).
+    pub fn set_zone(&mut self, zone: Option<&[MatrixPosition]>) {
+        self.zone = zone.map(|positions| positions.iter().copied().collect());
+    }
+
+    /// Whether a key lies within the zone of the evaluation (if any).
+    #[inline(always)]
+    fn in_zone(&self, k: &LayerKey) -> bool {
+        self.zone
+            .as_ref()
+            .map_or(true, |zone| zone.contains(&k.key.matrix_position))
+    }
+
+    /// Whether the metric with the given name ignores ngrams involving digit keys.
+    fn excludes_digits(&self, metric_name: &str) -> bool {
+        self.digit_handling.as_ref().map_or(false, |d| {
+            d.excluded_metrics.iter().any(|m| m == metric_name)
+        })
+    }
+
+    /// The ngrams without those involving digit keys, if any metric ignores them.
+    fn without_digit_grams<T: Copy>(
+        &self,
+        grams: &[(T, f64)],
+        has_digit: impl Fn(&T) -> bool,
+    ) -> Option<Vec<(T, f64)>> {
+        self.digit_handling
+            .as_ref()
+            .filter(|d| !d.excluded_metrics.is_empty())
+            .map(|_| {
+                grams
+                    .iter()
+                    .filter(|(gram, _)| !has_digit(gram))
+                    .copied()
+                    .collect()
+            })
+    }
+
+    /// Attach a calibration converting the costs into a unit to all evaluation results (see
+    /// [`Calibration`]). The costs themselves, e.g. as optimized, are unaffected.
+    pub fn with_calibration(mut self, calibration: Option<Calibration>) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Bound the contribution of the metric with the given name to the total cost (after
+    /// weighting and normalization). Results of clamped metrics are flagged.
+    pub fn set_contribution_bounds(&mut self, metric_name: &str, bounds: ContributionBounds) {
+        match bounds.is_unbounded() {
+            true => self.contribution_bounds.remove(metric_name),
+            false => self
+                .contribution_bounds
+                .insert(metric_name.to_string(), bounds),
+        };
+    }
+
+    /// Replace the evaluator's ngram mapper, e.g. with one containing updated ngram data.
+    ///
+    /// Evaluations that are running concurrently on clones of the evaluator are unaffected.
+    pub fn set_ngram_mapper(&mut self, ngram_mapper: Box<dyn NgramMapper>) {
+        self.ngram_mapper = ngram_mapper;
+    }
+
+    /// Generate a copy of the evaluator that only considers the most common ngrams up to a
+    /// given combined fraction of their total weight, e.g. for a faster, coarse optimization.
+    pub fn with_ngram_tops(&self, fraction: f64) -> Self {
+        let mut evaluator = self.clone();
+        evaluator.ngram_mapper = self.ngram_mapper.tops(fraction);
+        if let Some((ngram_mapper, _)) = evaluator.corpus_blend.as_mut() {
+            *ngram_mapper = ngram_mapper.tops(fraction);
+        }
+        evaluator
+    }
+
+    /// Blend the evaluator's ngrams with those of a second corpus. The `ratio` (between 0 and 1)
+    /// gives the share of the second corpus and can be changed with
+    /// [`Evaluator::set_corpus_blend`] without rebuilding any ngrams.
+    ///
+    /// The ngrams of both corpora are mapped to each evaluated layout and combined on the fly.
+    /// As the mapping is the most expensive step of an evaluation, this roughly doubles the
+    /// evaluation time for ratios strictly between 0 and 1 (plus the cost of merging the mapped
+    /// ngrams). For a ratio of 0 or 1, only the respective corpus is mapped.
+    pub fn with_corpus_blend(mut self, ngram_mapper: Box<dyn NgramMapper>, ratio: f64) -> Self {
+        self.corpus_blend = Some((ngram_mapper, ratio.clamp(0.0, 1.0)));
+        self
+    }
+
+    /// Change the share (between 0 and 1) of the second corpus of a corpus blend (see
+    /// [`Evaluator::with_corpus_blend`]). Has no effect if no second corpus is configured.
+    pub fn set_corpus_blend(&mut self, ratio: f64) {
+        if let Some((_, r)) = self.corpus_blend.as_mut() {
+            *r = ratio.clamp(0.0, 1.0);
+        }
+    }
+
+    /// The share of the second corpus, if a corpus blend is configured.
+    pub fn corpus_blend(&self) -> Option<f64> {
+        self.corpus_blend.as_ref().map(|(_, r)| *r)
+    }
+
+    /// Map the evaluator's unigrams (with their frequencies) to the given layout.
+    pub fn map_unigrams<'s>(&self, layout: &'s Layout) -> MappedUnigrams<'s> {
+        match &self.corpus_blend {
+            Some((ngram_mapper, r)) if *r >= 1.0 => ngram_mapper.map_unigrams(layout),
+            Some((ngram_mapper, r)) if *r > 0.0 => self
+                .ngram_mapper
+                .map_unigrams(layout)
+                .blend(ngram_mapper.map_unigrams(layout), *r),
+            _ => self.ngram_mapper.map_unigrams(layout),
+        }
+    }
+
+    /// Map the evaluator's bigrams (with their frequencies) to the given layout.
+    fn map_bigrams<'s>(&self, layout: &'s Layout) -> MappedBigrams<'s> {
+        match &self.corpus_blend {
+            Some((ngram_mapper, r)) if *r >= 1.0 => ngram_mapper.map_bigrams(layout),
+            Some((ngram_mapper, r)) if *r > 0.0 => self
+                .ngram_mapper
+                .map_bigrams(layout)
+                .blend(ngram_mapper.map_bigrams(layout), *r),
+            _ => self.ngram_mapper.map_bigrams(layout),
+        }
+    }
+
+    /// Map the evaluator's trigrams (with their frequencies) to the given layout.
+    fn map_trigrams<'s>(&self, layout: &'s Layout) -> MappedTrigrams<'s> {
+        match &self.corpus_blend {
+            Some((ngram_mapper, r)) if *r >= 1.0 => ngram_mapper.map_trigrams(layout),
+            Some((ngram_mapper, r)) if *r > 0.0 => self
+                .ngram_mapper
+                .map_trigrams(layout)
+                .blend(ngram_mapper.map_trigrams(layout), *r),
+            _ => self.ngram_mapper.map_trigrams(layout),
         }
     }
 
+    /// The number of presses of each key of the layout (in the keyboard's order of keys), i.e.
+    /// the raw unigram frequencies mapped to the layout. If the ngram mapper splits modifiers,
+    /// presses of modifiers are counted for the modifier keys. In contrast to the metrics, no
+    /// costs or typing speed weights are applied.
+    pub fn key_presses(&self, layout: &Layout) -> Vec<KeyPresses> {
+        let mut presses: AHashMap<MatrixPosition, f64> = AHashMap::default();
+        self.map_unigrams(layout)
+            .grams
+            .iter()
+            .for_each(|(k, weight)| *presses.entry(k.key.matrix_position).or_default() += weight);
+
+        let mut symbols: AHashMap<MatrixPosition, char> = AHashMap::default();
+        layout
+            .layerkeys
+            .iter()
+            .filter(|k| k.layer == 0 && k.symbol != EMPTY_SYMBOL)
+            .for_each(|k| {
+                symbols.entry(k.key.matrix_position).or_insert(k.symbol);
+            });
+
+        layout
+            .keyboard
+            .keys
+            .iter()
+            .map(|key| KeyPresses {
+                matrix_position: key.matrix_position,
+                symbol: symbols.get(&key.matrix_position).copied(),
+                presses: presses.get(&key.matrix_position).copied().unwrap_or(0.0),
+            })
+            .collect()
+    }
+
+    /// Whether any active metric consumes unigrams.
+    pub fn uses_unigrams(&self) -> bool {
+        !self.unigram_metrics.is_empty()
+    }
+
+    /// Whether any active metric consumes bigrams.
+    pub fn uses_bigrams(&self) -> bool {
+        !self.bigram_metrics.is_empty()
+    }
+
+    /// Whether any active metric consumes trigrams.
+    pub fn uses_trigrams(&self) -> bool {
+        !self.trigram_metrics.is_empty()
+    }
+
     /// Add all "default" metrics to the evaluator.
     pub fn default_metrics(mut self, params: &MetricParameters) -> Self {
         macro_rules! add_metric {
             ($metric_type:ident, $metric_name:ident, $metric_struct:ident) => {
                 if let Some(p) = &params.$metric_name {
                     if p.enabled {
-                        self.$metric_type(
-                            Box::new($metric_name::$metric_struct::new(&p.params)),
-                            p.weight,
-                            p.normalization.clone(),
-                        );
+                        let metric = Box::new($metric_name::$metric_struct::new(&p.params));
+                        self.set_contribution_bounds(metric.name(), p.contribution_bounds());
+                        self.$metric_type(metric, p.weight, p.normalization.clone());
                     }
                 }
             };
             ($metric_type:ident, $metric_name:ident, $metric_struct:ident, "add_bigram_metrics") => {
                 if let Some(p) = &params.$metric_name {
                     if p.enabled {
-                        self.$metric_type(
-                            Box::new($metric_name::$metric_struct::new(
-                                self.bigram_metrics.clone(),
-                                &p.params,
-                            )),
-                            p.weight,
-                            p.normalization.clone(),
-                        );
+                        let metric = Box::new($metric_name::$metric_struct::new(
+                            self.bigram_metrics.clone(),
+                            &p.params,
+                        ));
+                        self.set_contribution_bounds(metric.name(), p.contribution_bounds());
+                        self.$metric_type(metric, p.weight, p.normalization.clone());
                     }
                 }
             };
@@ -140,18 +635,37 @@ impl Evaluator {
         add_metric!(layout_metric, shortcut_keys, ShortcutKeys);
         add_metric!(layout_metric, similar_letters, SimilarLetters);
         add_metric!(layout_metric, similar_letter_groups, SimilarLetterGroups);
+        add_metric!(layout_metric, one_hand_words, OneHandWords);
 
         // unigram metrics
         add_metric!(unigram_metric, finger_balance, FingerBalance);
         add_metric!(unigram_metric, hand_disbalance, HandDisbalance);
+        add_metric!(unigram_metric, hand_symmetry, HandSymmetry);
         add_metric!(unigram_metric, row_loads, RowLoads);
+        add_metric!(unigram_metric, pinky_off_home, PinkyOffHome);
+        add_metric!(unigram_metric, bottom_row_load, BottomRowLoad);
+        add_metric!(unigram_metric, thumb_load, ThumbLoad);
         add_metric!(unigram_metric, modifier_usage, ModifierUsage);
+        add_metric!(unigram_metric, same_hand_modifiers, SameHandModifiers);
         add_metric!(unigram_metric, key_costs, KeyCost);
+        self.key_cost = params
+            .key_costs
+            .as_ref()
+            .filter(|p| p.enabled)
+            .map(|p| key_costs::KeyCost::new(&p.params));
 
         // bigram metrics
         add_metric!(bigram_metric, finger_repeats, FingerRepeats);
+        add_metric!(bigram_metric, finger_gap, FingerGap);
+        add_metric!(bigram_metric, lateral_stretch, LateralStretch);
+        add_metric!(bigram_metric, sticky_finger, StickyFinger);
         add_metric!(bigram_metric, scissoring, Scissoring);
         add_metric!(bigram_metric, manual_bigram_penalty, ManualBigramPenalty);
+        add_metric!(
+            bigram_metric,
+            alternating_column_penalty,
+            AlternatingColumnPenalty
+        );
         add_metric!(bigram_metric, movement_pattern, MovementPattern);
         add_metric!(
             bigram_metric,
@@ -159,6 +673,14 @@ impl Evaluator {
             NoHandSwitchAfterUnbalancingKey
         );
         add_metric!(bigram_metric, symmetric_handswitches, SymmetricHandswitches);
+        add_metric!(bigram_metric, same_row_bigrams, SameRowBigrams);
+        add_metric!(bigram_metric, same_hand_after_space, SameHandAfterSpace);
+        add_metric!(
+            bigram_metric,
+            worst_bigrams,
+            WorstBigrams,
+            "add_bigram_metrics"
+        );
 
         // trigram_metrics
         add_metric!(
@@ -167,6 +689,14 @@ impl Evaluator {
             NoHandswitchInTrigram
         );
         add_metric!(trigram_metric, trigram_finger_repeats, TrigramFingerRepeats);
+        add_metric!(trigram_metric, trigram_same_finger, TrigramSameFinger);
+        add_metric!(trigram_metric, finger_reuse_gaps, FingerReuseGaps);
+        add_metric!(
+            trigram_metric,
+            handswitch_then_same_finger,
+            HandSwitchThenSameFinger
+        );
+        add_metric!(trigram_metric, key_repeats, KeyRepeats);
         add_metric!(trigram_metric, trigram_rolls, TrigramRolls);
         add_metric!(
             trigram_metric,
@@ -205,6 +735,21 @@ impl Evaluator {
         self
     }
 
+    /// Add a metric of any type, e.g. a custom metric implemented outside of this crate.
+    pub fn add_metric(
+        &mut self,
+        metric: impl Into<Metric>,
+        weight: f64,
+        normalization: NormalizationType,
+    ) {
+        match metric.into() {
+            Metric::Layout(m) => self.layout_metric(m, weight, normalization),
+            Metric::Unigram(m) => self.unigram_metric(m, weight, normalization),
+            Metric::Bigram(m) => self.bigram_metric(m, weight, normalization),
+            Metric::Trigram(m) => self.trigram_metric(m, weight, normalization),
+        }
+    }
+
     /// Add a metric that operates only on the layout itself ("layout metric").
     pub fn layout_metric(
         &mut self,
@@ -245,8 +790,8 @@ impl Evaluator {
         self.trigram_metrics.push((weight, normalization, metric));
     }
 
-    /// Evaluate all layout metrics for a layout.
-    fn evaluate_layout_metrics(&self, layout: &Layout) -> Vec<MetricResult> {
+    /// Evaluate all layout metrics (or `only` the one with the given name) for a layout.
+    fn evaluate_layout_metrics(&self, layout: &Layout, only: Option<&str>) -> Vec<MetricResult> {
         if self.layout_metrics.is_empty() {
             return Vec::new();
         }
@@ -254,7 +799,9 @@ impl Evaluator {
         let metric_costs: Vec<MetricResult> = self
             .layout_metrics
             .iter()
+            .filter(|(_, _, metric)| only.map_or(true, |name| metric.name() == name))
             .map(|(weight, normalization, metric)| {
+                let start = self.profiling.then(Instant::now);
                 let (cost, message) = metric.total_cost(layout);
                 MetricResult {
                     name: metric.name().to_string(),
@@ -262,6 +809,8 @@ impl Evaluator {
                     weight: *weight,
                     normalization: normalization.clone(),
                     message,
+                    duration: start.map(|s| s.elapsed().as_secs_f64()),
+                    bounds: self.contribution_bounds.get(metric.name()).cloned(),
                 }
             })
             .collect();
@@ -269,28 +818,40 @@ impl Evaluator {
         metric_costs
     }
 
-    /// Evaluate all unigram metrics for a layout.
+    /// Evaluate all unigram metrics (or `only` the one with the given name) for a layout.
     fn evaluate_unigram_metrics(
         &self,
         layout: &Layout,
         keys: &[(&LayerKey, f64)],
+        only: Option<&str>,
     ) -> Vec<MetricResult> {
         if self.unigram_metrics.is_empty() {
             return Vec::new();
         }
 
         let total_weight = keys.iter().map(|(_, w)| w).sum();
+        let without_digits = self.without_digit_grams(keys, |k: &&LayerKey| is_digit_key(k));
         let metric_costs: Vec<MetricResult> = self
             .unigram_metrics
             .iter()
+            .filter(|(_, _, metric)| only.map_or(true, |name| metric.name() == name))
             .map(|(weight, normalization, metric)| {
-                let (cost, message) = metric.total_cost(keys, Some(total_weight), layout);
+                let start = self.profiling.then(Instant::now);
+                let (cost, message) = match &without_digits {
+                    Some(without_digits) if self.excludes_digits(metric.name()) => {
+                        let total_weight = without_digits.iter().map(|(_, w)| w).sum();
+                        metric.total_cost(without_digits, Some(total_weight), layout)
+                    }
+                    _ => metric.total_cost(keys, Some(total_weight), layout),
+                };
                 MetricResult {
                     name: metric.name().to_string(),
                     cost,
                     weight: *weight,
                     normalization: normalization.clone(),
                     message,
+                    duration: start.map(|s| s.elapsed().as_secs_f64()),
+                    bounds: self.contribution_bounds.get(metric.name()).cloned(),
                 }
             })
             .collect();
@@ -298,28 +859,42 @@ impl Evaluator {
         metric_costs
     }
 
-    /// Evaluate all bigram metrics for a layout.
+    /// Evaluate all bigram metrics (or `only` the one with the given name) for a layout.
     fn evaluate_bigram_metrics(
         &self,
         layout: &Layout,
         keys: &[((&LayerKey, &LayerKey), f64)],
+        only: Option<&str>,
     ) -> Vec<MetricResult> {
         if self.bigram_metrics.is_empty() {
             return Vec::new();
         }
 
         let total_weight = keys.iter().map(|(_, w)| w).sum();
+        let without_digits = self.without_digit_grams(keys, |(k1, k2): &(&LayerKey, &LayerKey)| {
+            is_digit_key(k1) || is_digit_key(k2)
+        });
         let metric_costs: Vec<MetricResult> = self
             .bigram_metrics
             .iter()
+            .filter(|(_, _, metric)| only.map_or(true, |name| metric.name() == name))
             .map(|(weight, normalization, metric)| {
-                let (cost, message) = metric.total_cost(keys, Some(total_weight), layout);
+                let start = self.profiling.then(Instant::now);
+                let (cost, message) = match &without_digits {
+                    Some(without_digits) if self.excludes_digits(metric.name()) => {
+                        let total_weight = without_digits.iter().map(|(_, w)| w).sum();
+                        metric.total_cost(without_digits, Some(total_weight), layout)
+                    }
+                    _ => metric.total_cost(keys, Some(total_weight), layout),
+                };
                 MetricResult {
                     name: metric.name().to_string(),
                     cost,
                     weight: *weight,
                     normalization: normalization.clone(),
                     message,
+                    duration: start.map(|s| s.elapsed().as_secs_f64()),
+                    bounds: self.contribution_bounds.get(metric.name()).cloned(),
                 }
             })
             .collect();
@@ -327,28 +902,43 @@ impl Evaluator {
         metric_costs
     }
 
-    /// Evaluate all trigram metrics for a layout.
+    /// Evaluate all trigram metrics (or `only` the one with the given name) for a layout.
     fn evaluate_trigram_metrics(
         &self,
         layout: &Layout,
         keys: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
+        only: Option<&str>,
     ) -> Vec<MetricResult> {
         if self.trigram_metrics.is_empty() {
             return Vec::new();
         }
 
         let total_weight = keys.iter().map(|(_, w)| w).sum();
+        let without_digits =
+            self.without_digit_grams(keys, |(k1, k2, k3): &(&LayerKey, &LayerKey, &LayerKey)| {
+                is_digit_key(k1) || is_digit_key(k2) || is_digit_key(k3)
+            });
         let metric_costs: Vec<MetricResult> = self
             .trigram_metrics
             .iter()
+            .filter(|(_, _, metric)| only.map_or(true, |name| metric.name() == name))
             .map(|(weight, normalization, metric)| {
-                let (cost, message) = metric.total_cost(keys, Some(total_weight), layout);
+                let start = self.profiling.then(Instant::now);
+                let (cost, message) = match &without_digits {
+                    Some(without_digits) if self.excludes_digits(metric.name()) => {
+                        let total_weight = without_digits.iter().map(|(_, w)| w).sum();
+                        metric.total_cost(without_digits, Some(total_weight), layout)
+                    }
+                    _ => metric.total_cost(keys, Some(total_weight), layout),
+                };
                 MetricResult {
                     name: metric.name().to_string(),
                     cost,
                     weight: *weight,
                     normalization: normalization.clone(),
                     message,
+                    duration: start.map(|s| s.elapsed().as_secs_f64()),
+                    bounds: self.contribution_bounds.get(metric.name()).cloned(),
                 }
             })
             .collect();
@@ -356,13 +946,164 @@ impl Evaluator {
         metric_costs
     }
 
+    /// Map the unigrams to the layout (applying the zone, the digit handling, and the
+    /// typing-speed weighting, if configured).
+    fn mapped_unigrams<'s>(&self, layout: &'s Layout) -> MappedUnigrams<'s> {
+        let mut mapped_unigrams = self.map_unigrams(layout);
+        if self.zone.is_some() {
+            let removed_weight = retain_zone_grams(&mut mapped_unigrams.grams, |k| self.in_zone(k));
+            mapped_unigrams.weight_found -= removed_weight;
+        }
+        if let Some(digit_handling) = &self.digit_handling {
+            let reduction =
+                scale_digit_grams(&mut mapped_unigrams.grams, digit_handling.weight, |k| {
+                    is_digit_key(k)
+                });
+            mapped_unigrams.weight_found -= reduction;
+        }
+        if let Some(strength) = self.typing_speed_strength {
+            apply_typing_speed_weighting(&mut mapped_unigrams.grams, strength, |k, tw| {
+                self.unigram_metrics
+                    .iter()
+                    .filter(|(weight, _, _)| *weight > 0.0)
+                    .filter_map(|(weight, _, m)| {
+                        m.individual_cost(k, 1.0, tw, layout).map(|c| weight * c)
+                    })
+                    .sum()
+            });
+        }
+
+        mapped_unigrams
+    }
+
+    /// Map the bigrams to the layout (applying the zone, the digit handling, and the
+    /// typing-speed weighting, if configured).
+    fn mapped_bigrams<'s>(&self, layout: &'s Layout) -> MappedBigrams<'s> {
+        let mut mapped_bigrams = self.map_bigrams(layout);
+        if self.zone.is_some() {
+            let removed_weight = retain_zone_grams(&mut mapped_bigrams.grams, |(k1, k2)| {
+                self.in_zone(k1) && self.in_zone(k2)
+            });
+            mapped_bigrams.weight_found -= removed_weight;
+        }
+        if let Some(digit_handling) = &self.digit_handling {
+            let reduction = scale_digit_grams(
+                &mut mapped_bigrams.grams,
+                digit_handling.weight,
+                |(k1, k2)| is_digit_key(k1) || is_digit_key(k2),
+            );
+            mapped_bigrams.weight_found -= reduction;
+        }
+        if let Some(strength) = self.typing_speed_strength {
+            apply_typing_speed_weighting(&mut mapped_bigrams.grams, strength, |(k1, k2), tw| {
+                self.bigram_metrics
+                    .iter()
+                    .filter(|(weight, _, _)| *weight > 0.0)
+                    .filter_map(|(weight, _, m)| {
+                        m.individual_cost(k1, k2, 1.0, tw, layout)
+                            .map(|c| weight * c)
+                    })
+                    .sum()
+            });
+        }
+
+        mapped_bigrams
+    }
+
+    /// Map the trigrams to the layout (applying the zone, the digit handling, and the
+    /// typing-speed weighting, if configured).
+    fn mapped_trigrams<'s>(&self, layout: &'s Layout) -> MappedTrigrams<'s> {
+        let mut mapped_trigrams = self.map_trigrams(layout);
+        if self.zone.is_some() {
+            let removed_weight = retain_zone_grams(&mut mapped_trigrams.grams, |(k1, k2, k3)| {
+                self.in_zone(k1) && self.in_zone(k2) && self.in_zone(k3)
+            });
+            mapped_trigrams.weight_found -= removed_weight;
+        }
+        if let Some(digit_handling) = &self.digit_handling {
+            let reduction = scale_digit_grams(
+                &mut mapped_trigrams.grams,
+                digit_handling.weight,
+                |(k1, k2, k3)| is_digit_key(k1) || is_digit_key(k2) || is_digit_key(k3),
+            );
+            mapped_trigrams.weight_found -= reduction;
+        }
+        if let Some(strength) = self.typing_speed_strength {
+            apply_typing_speed_weighting(
+                &mut mapped_trigrams.grams,
+                strength,
+                |(k1, k2, k3), tw| {
+                    self.trigram_metrics
+                        .iter()
+                        .filter(|(weight, _, _)| *weight > 0.0)
+                        .filter_map(|(weight, _, m)| {
+                            m.individual_cost(k1, k2, k3, 1.0, tw, layout)
+                                .map(|c| weight * c)
+                        })
+                        .sum()
+                },
+            );
+        }
+
+        mapped_trigrams
+    }
+
+    /// Evaluate a single metric for a layout, identified by its id in the evaluation parameters
+    /// (e.g. `key_costs`). Only the ngrams the metric operates on are mapped.
+    ///
+    /// Returns an error if the id is unknown or the metric is not active in the evaluator.
+    pub fn evaluate_metric(&self, layout: &Layout, metric_id: &str) -> Result<MetricResult> {
+        let info = available_metrics()
+            .into_iter()
+            .find(|m| m.id == metric_id)
+            .ok_or_else(|| EvaluationError::UnknownMetric(metric_id.to_string()))?;
+
+        let name = info.name;
+        let is_active = match info.metric_type {
+            MetricType::Layout => self.layout_metrics.iter().any(|(_, _, m)| m.name() == name),
+            MetricType::Unigram => self
+                .unigram_metrics
+                .iter()
+                .any(|(_, _, m)| m.name() == name),
+            MetricType::Bigram => self.bigram_metrics.iter().any(|(_, _, m)| m.name() == name),
+            MetricType::Trigram => self
+                .trigram_metrics
+                .iter()
+                .any(|(_, _, m)| m.name() == name),
+        };
+        if !is_active {
+            return Err(EvaluationError::InactiveMetric(metric_id.to_string()).into());
+        }
+
+        let results = match info.metric_type {
+            MetricType::Layout => self.evaluate_layout_metrics(layout, Some(name)),
+            MetricType::Unigram => {
+                let mapped_unigrams = self.mapped_unigrams(layout);
+                self.evaluate_unigram_metrics(layout, &mapped_unigrams.grams, Some(name))
+            }
+            MetricType::Bigram => {
+                let mapped_bigrams = self.mapped_bigrams(layout);
+                self.evaluate_bigram_metrics(layout, &mapped_bigrams.grams, Some(name))
+            }
+            MetricType::Trigram => {
+                let mapped_trigrams = self.mapped_trigrams(layout);
+                self.evaluate_trigram_metrics(layout, &mapped_trigrams.grams, Some(name))
+            }
+        };
+
+        results
+            .into_iter()
+            .next()
+            .ok_or_else(|| EvaluationError::InactiveMetric(metric_id.to_string()).into())
+    }
+
     /// Evaluate all metrics for a layout.
     pub fn evaluate_layout(&self, layout: &Layout) -> EvaluationResult {
         let mut results: Vec<MetricResults> = Vec::new();
 
         // Layout metrics
         if !self.layout_metrics.is_empty() {
-            let metric_costs = self.evaluate_layout_metrics(layout);
+            let metric_costs = self.evaluate_layout_metrics(layout, None);
             let mut layout_costs = MetricResults::new(MetricType::Layout, 1.0, 0.0);
             metric_costs
                 .into_iter()
@@ -371,9 +1112,9 @@ impl Evaluator {
         }
 
         // Unigram metrics
-        if !self.unigram_metrics.is_empty() {
-            let mapped_unigrams = self.ngram_mapper.map_unigrams(layout);
-            let metric_costs = self.evaluate_unigram_metrics(layout, &mapped_unigrams.grams);
+        if self.uses_unigrams() {
+            let mapped_unigrams = self.mapped_unigrams(layout);
+            let metric_costs = self.evaluate_unigram_metrics(layout, &mapped_unigrams.grams, None);
             let mut unigram_costs = MetricResults::new(
                 MetricType::Unigram,
                 mapped_unigrams.weight_found,
@@ -387,14 +1128,15 @@ impl Evaluator {
         }
 
         // Bigram metrics
-        if !self.bigram_metrics.is_empty() {
-            let mapped_bigrams = self.ngram_mapper.map_bigrams(layout);
-            let metric_costs = self.evaluate_bigram_metrics(layout, &mapped_bigrams.grams);
+        if self.uses_bigrams() {
+            let mapped_bigrams = self.mapped_bigrams(layout);
+            let metric_costs = self.evaluate_bigram_metrics(layout, &mapped_bigrams.grams, None);
             let mut bigram_costs = MetricResults::new(
                 MetricType::Bigram,
                 mapped_bigrams.weight_found,
                 mapped_bigrams.weight_not_found,
-            );
+            )
+            .with_cross_word_weight(mapped_bigrams.weight_cross_word);
             metric_costs
                 .into_iter()
                 .for_each(|mc| bigram_costs.add_result(mc));
@@ -403,9 +1145,9 @@ impl Evaluator {
         }
 
         // Trigram metrics
-        if !self.trigram_metrics.is_empty() {
-            let mapped_trigrams = self.ngram_mapper.map_trigrams(layout);
-            let metric_costs = self.evaluate_trigram_metrics(layout, &mapped_trigrams.grams);
+        if self.uses_trigrams() {
+            let mapped_trigrams = self.mapped_trigrams(layout);
+            let metric_costs = self.evaluate_trigram_metrics(layout, &mapped_trigrams.grams, None);
             let mut trigram_costs = MetricResults::new(
                 MetricType::Trigram,
                 mapped_trigrams.weight_found,
@@ -418,6 +1160,818 @@ impl Evaluator {
             results.push(trigram_costs);
         }
 
-        EvaluationResult::new(layout.as_text(), results)
+        let result = EvaluationResult::new(layout.as_text(), results);
+        match &self.calibration {
+            Some(calibration) => result.with_calibration(calibration.clone()),
+            None => result,
+        }
+    }
+
+    /// Evaluate all metrics for a partial layout (see
+    /// [`LayoutGenerator::generate_partial`](keyboard_layout::layout_generator::LayoutGenerator::generate_partial))
+    /// and report its coverage of the ngrams.
+    ///
+    /// The metrics only consider ngrams whose symbols are all placed, so ngrams containing
+    /// any of the reported unplaced symbols are skipped (their weight is reported as not found).
+    /// With a corpus blend, the unplaced symbols refer to the primary corpus.
+    pub fn evaluate_partial_layout(&self, layout: &Layout) -> EvaluationResult {
+        self.evaluate_layout(layout)
+            .with_coverage(self.coverage(layout))
+    }
+
+    /// Find the best position for placing a character into a partial layout (see
+    /// [`Layout::place_symbol`]) and the resulting change of the total cost. Only the empty
+    /// non-fixed positions are candidates, and only ngrams whose symbols are all placed are
+    /// considered. The position is the index in [`Layout::as_text`]; of equally good positions,
+    /// the first one is chosen.
+    ///
+    /// Fails if the character is already placed or the layout has no empty position.
+    pub fn best_placement(&self, partial: &Layout, ch: char) -> Result<(usize, f64)> {
+        if partial.get_layerkey_index_for_symbol(&ch).is_some() {
+            return Err(EvaluationError::AlreadyPlaced(ch).into());
+        }
+
+        let cost = self.evaluate_layout(partial).total_cost();
+        let mut best: Option<(usize, f64)> = None;
+        for position in partial.empty_positions() {
+            let placed = partial.place_symbol(position, ch)?;
+            let delta = self.evaluate_layout(&placed).total_cost() - cost;
+            if best.map_or(true, |(_, best_delta)| delta < best_delta) {
+                best = Some((position, delta));
+            }
+        }
+
+        best.ok_or_else(|| EvaluationError::NoEmptyPosition(ch).into())
+    }
+
+    /// Evaluate a layout and its mirror image (see [`Layout::mirror`]) and return the cheaper of
+    /// both together with its evaluation result. Fails if the layout can not be mirrored.
+    pub fn evaluate_with_mirror(&self, layout: &Layout) -> Result<(Layout, EvaluationResult)> {
+        let mirrored = layout.mirror()?;
+        let result = self.evaluate_layout(layout);
+        let mirrored_result = self.evaluate_layout(&mirrored);
+
+        Ok(match mirrored_result.total_cost() < result.total_cost() {
+            true => (mirrored, mirrored_result),
+            false => (layout.clone(), result),
+        })
+    }
+
+    /// Evaluate the candidate layouts and rank them by their total costs (cheapest first).
+    /// Candidates that can not be generated are not dropped, but listed after the ranked ones
+    /// together with their error. With the `parallel` feature, the candidates are evaluated in
+    /// parallel.
+    pub fn rank_layouts(
+        &self,
+        layouts: &[&str],
+        layout_generator: &dyn LayoutGenerator,
+    ) -> Vec<RankedLayout> {
+        self.rank_layouts_with_progress(layouts, layout_generator, None)
+    }
+
+    /// Like [`Evaluator::rank_layouts`], but calls `progress` after each evaluated candidate.
+    /// With the `parallel` feature, the calls may arrive out of order.
+    pub fn rank_layouts_with_progress(
+        &self,
+        layouts: &[&str],
+        layout_generator: &dyn LayoutGenerator,
+        progress: Option<&ProgressCallback>,
+    ) -> Vec<RankedLayout> {
+        let done = AtomicUsize::new(0);
+        let report = || {
+            if let Some(progress) = progress {
+                progress(done.fetch_add(1, Ordering::Relaxed) + 1, layouts.len());
+            }
+        };
+        let rank = |layout_str: &&str| -> RankedLayout {
+            let ranked = self.rank_layout(layout_str, layout_generator);
+            report();
+            ranked
+        };
+
+        #[cfg(feature = "parallel")]
+        let mut ranked: Vec<RankedLayout> = layouts.par_iter().map(rank).collect();
+        #[cfg(not(feature = "parallel"))]
+        let mut ranked: Vec<RankedLayout> = layouts.iter().map(rank).collect();
+
+        // invalid candidates (without cost) go last, the order of equal costs is kept
+        ranked.sort_by_key(|r| (r.cost.is_none(), r.cost.map(OrderedFloat)));
+        ranked
+            .iter_mut()
+            .filter(|r| r.cost.is_some())
+            .enumerate()
+            .for_each(|(i, r)| r.rank = Some(i + 1));
+
+        ranked
+    }
+
+    /// Evaluate a single candidate of [`Evaluator::rank_layouts`] (without its rank).
+    fn rank_layout(
+        &self,
+        layout_str: &str,
+        layout_generator: &dyn LayoutGenerator,
+    ) -> RankedLayout {
+        match layout_generator.generate(layout_str) {
+            Ok(layout) => {
+                let result = self.evaluate_layout(&layout);
+                RankedLayout {
+                    layout: layout_str.to_string(),
+                    rank: None,
+                    cost: Some(result.total_cost()),
+                    breakdown: result.contributions(),
+                    error: None,
+                }
+            }
+            Err(e) => RankedLayout {
+                layout: layout_str.to_string(),
+                rank: None,
+                cost: None,
+                breakdown: Vec::new(),
+                error: Some(format!("{:#}", e)),
+            },
+        }
+    }
+
+    /// The coverage of the ngrams by a (partial) layout, i.e. the symbols it does not place.
+    pub fn coverage(&self, layout: &Layout) -> Coverage {
+        Coverage {
+            unplaced_symbols: self.ngram_mapper.unmapped_symbols(layout),
+        }
+    }
+
+    /// Evaluate all metrics for a layout and attribute the cost to the layout's characters.
+    ///
+    /// For each unigram, bigram, and trigram metric, the individual cost of each mapped ngram
+    /// is split evenly among the ngram's symbols, e.g. a bigram "th" attributes half of its
+    /// cost to "t" and half to "h" (and all of it to "e" for "ee"). Symbols of modifier keys
+    /// that the ngram mapper inserts (e.g. shift) receive their share like any other symbol.
+    /// The resulting shares are then scaled such that they sum up to the metric's (weighted and
+    /// normalized) contribution to the total cost. This way, metrics whose total cost is not
+    /// the sum of their individual costs (e.g. the square root in "Irregularity") are
+    /// attributed in proportion to their individual costs.
+    ///
+    /// Layout metrics and metrics that do not provide individual ngram costs (or whose
+    /// individual costs sum up to zero) can not be attributed; their cost is reported as
+    /// unattributed.
+    pub fn evaluate_layout_with_char_costs(&self, layout: &Layout) -> EvaluationResult {
+        let result = self.evaluate_layout(layout);
+        let metric_cost = |metric_type: MetricType, name: &str| {
+            result
+                .iter()
+                .filter(|mr| mr.metric_type == metric_type)
+                .flat_map(|mr| mr.metric_costs.iter())
+                .find(|mc| mc.core.name == name)
+                .map(|mc| mc.weighted_cost)
+                .unwrap_or(0.0)
+        };
+
+        let mut costs: AHashMap<char, f64> = AHashMap::default();
+
+        if self.uses_unigrams() {
+            let mapped = self.mapped_unigrams(layout);
+            let total_weight = mapped.grams.iter().map(|(_, w)| w).sum();
+            for (_, _, metric) in self.unigram_metrics.iter() {
+                let mut metric_costs = AHashMap::default();
+                for (k, w) in mapped.grams.iter() {
+                    if let Some(c) = metric.individual_cost(k, *w, total_weight, layout) {
+                        add_shares(&mut metric_costs, &[k.symbol], c);
+                    }
+                }
+                let cost = metric_cost(MetricType::Unigram, metric.name());
+                merge_scaled(&mut costs, metric_costs, cost);
+            }
+        }
+
+        if self.uses_bigrams() {
+            let mapped = self.mapped_bigrams(layout);
+            let total_weight = mapped.grams.iter().map(|(_, w)| w).sum();
+            for (_, _, metric) in self.bigram_metrics.iter() {
+                let mut metric_costs = AHashMap::default();
+                for ((k1, k2), w) in mapped.grams.iter() {
+                    if let Some(c) = metric.individual_cost(k1, k2, *w, total_weight, layout) {
+                        add_shares(&mut metric_costs, &[k1.symbol, k2.symbol], c);
+                    }
+                }
+                let cost = metric_cost(MetricType::Bigram, metric.name());
+                merge_scaled(&mut costs, metric_costs, cost);
+            }
+        }
+
+        if self.uses_trigrams() {
+            let mapped = self.mapped_trigrams(layout);
+            let total_weight = mapped.grams.iter().map(|(_, w)| w).sum();
+            for (_, _, metric) in self.trigram_metrics.iter() {
+                let mut metric_costs = AHashMap::default();
+                for ((k1, k2, k3), w) in mapped.grams.iter() {
+                    if let Some(c) = metric.individual_cost(k1, k2, k3, *w, total_weight, layout) {
+                        add_shares(&mut metric_costs, &[k1.symbol, k2.symbol, k3.symbol], c);
+                    }
+                }
+                let cost = metric_cost(MetricType::Trigram, metric.name());
+                merge_scaled(&mut costs, metric_costs, cost);
+            }
+        }
+
+        let unattributed = result.total_cost() - costs.values().sum::<f64>();
+        result.with_char_costs(CharCosts {
+            costs,
+            unattributed,
+        })
+    }
+
+    /// The cost of each bigram of keys of the layout according to the active bigram metrics.
+    ///
+    /// The matrix is computed once from the individual bigram costs of the metrics (using the
+    /// keys' base layer symbols of the layout) and is indexed by the keyboard's keys (see
+    /// [`BigramCostMatrix`]). Each entry is the sum of the weighted individual costs of a bigram
+    /// with relative frequency one, divided by the metrics' normalization values. Multiplying
+    /// the entries with the relative frequencies of the bigrams and summing them up thus yields
+    /// the (unclamped) total cost of the bigram metrics, as long as their costs are linear in the
+    /// bigram weights. Metrics that do not provide individual costs are not included.
+    pub fn bigram_cost_matrix(&self, layout: &Layout) -> BigramCostMatrix {
+        let base_keys: AHashMap<MatrixPosition, &LayerKey> = layout
+            .layerkeys
+            .iter()
+            .filter(|k| k.layer == 0)
+            .map(|k| (k.key.matrix_position, k))
+            .collect();
+        let keys: Vec<Option<&LayerKey>> = layout
+            .keyboard
+            .keys
+            .iter()
+            .map(|key| base_keys.get(&key.matrix_position).copied())
+            .collect();
+
+        let costs = keys
+            .iter()
+            .map(|k1| {
+                keys.iter()
+                    .map(|k2| match (k1, k2) {
+                        (Some(k1), Some(k2)) => self
+                            .bigram_metrics
+                            .iter()
+                            .filter_map(|(weight, normalization, metric)| {
+                                let factor = match normalization {
+                                    NormalizationType::Fixed(t)
+                                    | NormalizationType::WeightFound(t)
+                                    | NormalizationType::WeightAll(t) => t,
+                                };
+                                metric
+                                    .individual_cost(k1, k2, 1.0, 1.0, layout)
+                                    .map(|cost| weight * cost / factor)
+                            })
+                            .sum(),
+                        _ => 0.0,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        BigramCostMatrix {
+            matrix_positions: layout
+                .keyboard
+                .keys
+                .iter()
+                .map(|key| key.matrix_position)
+                .collect(),
+            costs,
+        }
+    }
+}
+
+impl Evaluator {
+    /// The cost of the key itself as seen by the "Key Costs" metric (taking the key's width
+    /// into account), if the metric has been added with [`Evaluator::default_metrics`].
+    pub fn key_cost(&self, key: &LayerKey) -> Option<f64> {
+        self.key_cost.as_ref().map(|m| m.key_cost(key))
+    }
+
+    /// A cheap (and usually loose) lower bound of the total cost of any layout that permutes
+    /// the non-fixed keys of `layout`.
+    ///
+    /// Only the "Key Costs" metric is bounded: the unigram weights pressed on the non-fixed
+    /// keys of the layout are assigned to the non-fixed keys in order of the keys' costs (the
+    /// most frequent to the lowest-cost key), ignoring all interactions between keys. Costs of
+    /// fixed keys and modifiers stay as they are. All other metrics are assumed to be
+    /// non-negative and contribute nothing to the bound.
+    pub fn theoretical_lower_bound(&self, layout: &Layout) -> f64 {
+        let key_costs_name = match available_metrics()
+            .into_iter()
+            .find(|m| m.id == "key_costs")
+        {
+            Some(info) => info.name,
+            None => return 0.0,
+        };
+        let (weight, normalization, metric) = match self
+            .unigram_metrics
+            .iter()
+            .find(|(_, _, m)| m.name() == key_costs_name)
+        {
+            Some(m) => m,
+            None => return 0.0,
+        };
+
+        let mapped = self.mapped_unigrams(layout);
+        let total_weight = mapped.grams.iter().map(|(_, w)| w).sum();
+        let (cost, _) = metric.total_cost(&mapped.grams, Some(total_weight), layout);
+
+        // current cost of the presses of the non-fixed keys
+        let mut key_weights: AHashMap<MatrixPosition, f64> = AHashMap::default();
+        mapped
+            .grams
+            .iter()
+            .filter(|(k, _)| !k.is_fixed)
+            .for_each(|(k, w)| *key_weights.entry(k.key.matrix_position).or_default() += w);
+        let mut key_costs: Vec<(MatrixPosition, f64)> = layout
+            .layerkeys
+            .iter()
+            .filter(|k| k.layer == 0 && !k.is_fixed)
+            .map(|k| {
+                let cost = self.key_cost(k).unwrap_or(k.key.cost);
+                (k.key.matrix_position, cost)
+            })
+            .collect();
+        let current: f64 = key_costs
+            .iter()
+            .map(|(pos, cost)| key_weights.get(pos).copied().unwrap_or(0.0) * cost)
+            .sum();
+
+        // most frequent keys on the cheapest positions
+        let mut weights: Vec<f64> = key_costs
+            .iter()
+            .map(|(pos, _)| key_weights.get(pos).copied().unwrap_or(0.0))
+            .collect();
+        weights.sort_by(|w1, w2| w2.partial_cmp(w1).unwrap());
+        key_costs.sort_by(|(_, c1), (_, c2)| c1.partial_cmp(c2).unwrap());
+        let minimum: f64 = weights
+            .iter()
+            .zip(key_costs.iter())
+            .map(|(w, (_, c))| w * c)
+            .sum();
+
+        let results = MetricResults::new(
+            MetricType::Unigram,
+            mapped.weight_found,
+            mapped.weight_not_found,
+        );
+        results.normalize_value(weight * (cost - current + minimum), normalization)
+    }
+}
+
+/// Split `cost` evenly among the `symbols` of an ngram.
+fn add_shares(costs: &mut AHashMap<char, f64>, symbols: &[char], cost: f64) {
+    let share = cost / symbols.len() as f64;
+    symbols
+        .iter()
+        .for_each(|c| *costs.entry(*c).or_default() += share);
+}
+
+/// Add the `metric_costs` of the characters to `costs`, scaled such that they sum up to
+/// `total`. Nothing is added if the metric costs sum up to zero.
+fn merge_scaled(costs: &mut AHashMap<char, f64>, metric_costs: AHashMap<char, f64>, total: f64) {
+    let sum: f64 = metric_costs.values().sum();
+    if sum == 0.0 {
+        return;
+    }
+
+    let scale = total / sum;
+    metric_costs
+        .into_iter()
+        .for_each(|(c, cost)| *costs.entry(c).or_default() += scale * cost);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_typing_speed_weighting, Evaluator, TypingSpeedWeightingConfig};
+    use crate::{
+        ngram_mapper::on_demand_ngram_mapper::OnDemandNgramMapper,
+        ngrams::{Bigrams, Trigrams, Unigrams},
+        results::{DeadMetricReason, EvaluationResult, MetricType},
+        test_utils::{
+            eval_params, evaluator, generator_and_evaluator, layout_generator, LAYOUT, TEXT,
+        },
+    };
+
+    use keyboard_layout::{key::MatrixPosition, layout::Layout, layout_generator::LayoutGenerator};
+
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    /// The costs of all metrics as bits, i.e. for an exact comparison.
+    fn cost_bits(result: &EvaluationResult) -> Vec<(String, u64)> {
+        result
+            .iter()
+            .flat_map(|mr| mr.metric_costs.iter())
+            .map(|mc| (mc.core.name.clone(), mc.weighted_cost.to_bits()))
+            .chain(std::iter::once((
+                "total".to_string(),
+                result.total_cost().to_bits(),
+            )))
+            .collect()
+    }
+
+    #[test]
+    fn parallel_evaluation_is_bit_identical_to_serial_evaluation() {
+        let (layout_generator, evaluator) = generator_and_evaluator();
+        let evaluator = Arc::new(evaluator);
+
+        let layouts: Vec<Arc<Layout>> = [LAYOUT, "q0a1zw2sbxt3decr4fgvuoj5miyk67hnl89p={}(["]
+            .iter()
+            .map(|l| Arc::new(layout_generator.generate(l).unwrap()))
+            .collect();
+
+        let serial: Vec<Vec<(String, u64)>> = layouts
+            .iter()
+            .map(|layout| cost_bits(&evaluator.evaluate_layout(layout)))
+            .collect();
+
+        let handles: Vec<_> = layouts
+            .iter()
+            .cycle()
+            .take(4 * layouts.len())
+            .enumerate()
+            .map(|(i, layout)| {
+                let evaluator = evaluator.clone();
+                let layout = layout.clone();
+                thread::spawn(move || (i, cost_bits(&evaluator.evaluate_layout(&layout))))
+            })
+            .collect();
+
+        for handle in handles {
+            let (i, parallel) = handle.join().unwrap();
+            assert_eq!(parallel, serial[i % layouts.len()]);
+        }
+    }
+
+    #[test]
+    fn dead_metrics_distinguish_missing_ngrams_from_zero_costs() {
+        let layout_generator = layout_generator();
+        let eval_params = eval_params();
+        let ngram_mapper = OnDemandNgramMapper::with_ngrams(
+            Unigrams::from_text(TEXT).unwrap(),
+            Bigrams::from_text(TEXT).unwrap(),
+            Trigrams::from_text("").unwrap(),
+            eval_params.ngram_mapper,
+        );
+        let evaluator =
+            Evaluator::default(Box::new(ngram_mapper)).default_metrics(&eval_params.metrics);
+
+        let layout = layout_generator.generate(LAYOUT).unwrap();
+        let dead_metrics = evaluator.evaluate_layout(&layout).dead_metrics();
+
+        assert!(dead_metrics
+            .iter()
+            .filter(|m| m.metric_type == MetricType::Trigram)
+            .all(|m| m.reason == DeadMetricReason::NoNgrams));
+        assert!(dead_metrics
+            .iter()
+            .any(|m| m.metric_type == MetricType::Trigram));
+        // the layout has no higher layers reached with modifiers
+        assert!(dead_metrics
+            .iter()
+            .any(|m| m.name == "Modifier Usage" && m.reason == DeadMetricReason::ZeroCost));
+    }
+
+    #[test]
+    fn rank_layouts_sorts_by_cost_and_keeps_invalid_candidates() {
+        let (layout_generator, evaluator) = generator_and_evaluator();
+
+        let candidates = [
+            LAYOUT,
+            "not a layout",
+            "q0a1zw2sbxt3decr4fgvuoj5miyk67hnl89p={}([",
+        ];
+        let ranked = evaluator.rank_layouts(&candidates, &layout_generator);
+
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0].rank, Some(1));
+        assert_eq!(ranked[1].rank, Some(2));
+        assert!(ranked[0].cost.unwrap() <= ranked[1].cost.unwrap());
+        assert!(!ranked[0].breakdown.is_empty());
+
+        assert_eq!(ranked[2].layout, "not a layout");
+        assert_eq!(ranked[2].rank, None);
+        assert!(ranked[2].error.is_some());
+    }
+
+    #[test]
+    fn rank_layouts_reports_progress_for_each_candidate() {
+        let (layout_generator, evaluator) = generator_and_evaluator();
+
+        let candidates = [
+            LAYOUT,
+            "not a layout",
+            "q0a1zw2sbxt3decr4fgvuoj5miyk67hnl89p={}([",
+        ];
+        let reports = Mutex::new(Vec::new());
+        let progress = |done: usize, total: usize| reports.lock().unwrap().push((done, total));
+        evaluator.rank_layouts_with_progress(&candidates, &layout_generator, Some(&progress));
+
+        let mut reports = reports.into_inner().unwrap();
+        reports.sort();
+        assert_eq!(reports, vec![(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn corpus_mixture_shares_are_independent_of_corpus_sizes() {
+        let layout = layout_generator().generate(LAYOUT).unwrap();
+
+        let eval_params = eval_params();
+        // a corpus consisting of `times` copies of the text (without ngrams across copies)
+        let mapper = |times: usize| {
+            let (mut unigrams, mut bigrams, mut trigrams) =
+                (Unigrams::default(), Bigrams::default(), Trigrams::default());
+            for _ in 0..times {
+                unigrams.merge(&Unigrams::from_text(TEXT).unwrap());
+                bigrams.merge(&Bigrams::from_text(TEXT).unwrap());
+                trigrams.merge(&Trigrams::from_text(TEXT).unwrap());
+            }
+            OnDemandNgramMapper::with_ngrams(
+                unigrams,
+                bigrams,
+                trigrams,
+                eval_params.ngram_mapper.clone(),
+            )
+        };
+        let cost = |corpora: &[(OnDemandNgramMapper, f64)]| {
+            Evaluator::from_corpus_mixture(corpora)
+                .unwrap()
+                .default_metrics(&eval_params.metrics)
+                .evaluate_layout(&layout)
+                .total_cost()
+        };
+
+        let single = cost(&[(mapper(1), 1.0)]);
+        let mixed = cost(&[(mapper(1), 2.0), (mapper(3), 2.0)]);
+        assert!((single - mixed).abs() < 1e-9 * single.abs().max(1.0));
+
+        assert!(Evaluator::from_corpus_mixture(&[]).is_err());
+        assert!(Evaluator::from_corpus_mixture(&[(mapper(1), 0.0)]).is_err());
+        assert!(Evaluator::from_corpus_mixture(&[(mapper(1), -1.0)]).is_err());
+    }
+
+    #[test]
+    fn contribution_bounds_clamp_and_flag_metrics() {
+        let layout_generator = layout_generator();
+
+        let mut eval_params = eval_params();
+        eval_params
+            .metrics
+            .key_costs
+            .as_mut()
+            .unwrap()
+            .max_contribution = Some(1.0);
+        let evaluator = evaluator(TEXT, &eval_params);
+
+        let layout = layout_generator.generate(LAYOUT).unwrap();
+        let result = evaluator.evaluate_layout(&layout);
+
+        let metric_costs: Vec<_> = result
+            .iter()
+            .flat_map(|mr| mr.metric_costs.iter())
+            .collect();
+        let key_costs = metric_costs
+            .iter()
+            .find(|mc| mc.core.name == "Key Costs")
+            .unwrap();
+        assert_eq!(key_costs.weighted_cost, 1.0);
+        assert!(key_costs.clamped);
+        assert!(metric_costs
+            .iter()
+            .filter(|mc| mc.core.name != "Key Costs")
+            .all(|mc| !mc.clamped));
+
+        let sum: f64 = metric_costs.iter().map(|mc| mc.weighted_cost).sum();
+        assert!((result.total_cost() - sum).abs() < 1e-9);
+    }
+
+    #[test]
+    fn best_placement_picks_the_cheapest_empty_position() {
+        let (layout_generator, evaluator) = generator_and_evaluator();
+
+        let partial = layout_generator
+            .generate_partial("q0a1zw2sbx_3dtcr4fgvuhj5m_yk67onl89p={}([", '_')
+            .unwrap();
+        let (position, delta) = evaluator.best_placement(&partial, 'e').unwrap();
+
+        assert!(partial.empty_positions().contains(&position));
+        let placed = partial.place_symbol(position, 'e').unwrap();
+        let cost = evaluator.evaluate_layout(&placed).total_cost()
+            - evaluator.evaluate_layout(&partial).total_cost();
+        assert!((cost - delta).abs() < 1e-9);
+        for other in partial.empty_positions() {
+            let other_placed = partial.place_symbol(other, 'e').unwrap();
+            assert!(
+                evaluator.evaluate_layout(&other_placed).total_cost()
+                    >= evaluator.evaluate_layout(&placed).total_cost()
+            );
+        }
+
+        assert!(evaluator.best_placement(&partial, 'q').is_err());
+        assert!(partial.place_symbol(0, 'e').is_err());
+    }
+
+    #[test]
+    fn bigram_cost_matrix_is_indexed_by_the_keyboard_keys() {
+        let (layout_generator, evaluator) = generator_and_evaluator();
+
+        let layout = layout_generator.generate(LAYOUT).unwrap();
+        let matrix = evaluator.bigram_cost_matrix(&layout);
+
+        let keyboard = &layout.keyboard;
+        let n = keyboard.keys.len();
+        assert_eq!(matrix.matrix_positions.len(), n);
+        assert_eq!(matrix.costs.len(), n);
+        assert!(matrix.costs.iter().all(|row| row.len() == n));
+        assert!(matrix.costs.iter().flatten().any(|c| *c > 0.0));
+        for (i, from) in keyboard.keys.iter().enumerate() {
+            assert_eq!(matrix.index_of(&from.matrix_position), Some(i));
+            for (j, to) in keyboard.keys.iter().enumerate() {
+                assert_eq!(
+                    matrix.cost(&from.matrix_position, &to.matrix_position),
+                    Some(matrix.costs[i][j])
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn digit_handling_ignores_the_placement_of_digits() {
+        let layout_generator = layout_generator();
+
+        let mut eval_params = eval_params();
+        eval_params.digits.enabled = true;
+        eval_params.digits.weight = 0.0;
+        eval_params.digits.excluded_metrics = vec!["key_costs".to_string()];
+        let evaluator = evaluator(TEXT, &eval_params).with_digit_handling(&eval_params.digits);
+
+        // the digits "2" and "4" of the text swap their positions
+        let costs = |layout_str: &str| -> Vec<(String, f64)> {
+            let layout = layout_generator.generate(layout_str).unwrap();
+            evaluator
+                .evaluate_layout(&layout)
+                .iter()
+                .filter(|mr| mr.metric_type != MetricType::Layout)
+                .flat_map(|mr| mr.metric_costs.iter())
+                .map(|mc| (mc.core.name.clone(), mc.weighted_cost))
+                .collect()
+        };
+        let costs1 = costs(LAYOUT);
+        let costs2 = costs("q0a1zw4sbxe3dtcr2fgvuhj5miyk67onl89p={}([");
+
+        assert!(!costs1.is_empty());
+        for ((name1, cost1), (name2, cost2)) in costs1.iter().zip(costs2.iter()) {
+            assert_eq!(name1, name2);
+            assert!(
+                (cost1 - cost2).abs() < 1e-9,
+                "{}: {} != {}",
+                name1,
+                cost1,
+                cost2
+            );
+        }
+    }
+
+    #[test]
+    fn wide_keys_lower_key_costs_and_lower_bound_consistently() {
+        let layout_generator = layout_generator();
+
+        let mut eval_params = eval_params();
+        eval_params
+            .metrics
+            .key_costs
+            .as_mut()
+            .unwrap()
+            .params
+            .width_factor = 1.0;
+        let evaluator = evaluator(TEXT, &eval_params);
+
+        let layout = layout_generator.generate(LAYOUT).unwrap();
+        let position = layout
+            .get_layerkey_for_symbol(&'q')
+            .unwrap()
+            .key
+            .matrix_position;
+
+        // a very wide key at the position of the rare "q" (of the frequent "e" after a swap)
+        let widen = |layout_str: &str| -> Layout {
+            let mut layout = layout_generator.generate(layout_str).unwrap();
+            layout
+                .layerkeys
+                .iter_mut()
+                .filter(|k| k.key.matrix_position == position)
+                .for_each(|k| k.key.width = 100.0);
+            layout
+        };
+        let key_costs = |layout: &Layout| -> f64 {
+            evaluator
+                .evaluate_layout(layout)
+                .iter()
+                .flat_map(|mr| mr.metric_costs.iter())
+                .find(|mc| mc.core.name == "Key Costs")
+                .unwrap()
+                .weighted_cost
+        };
+
+        let wide = widen(LAYOUT);
+        let wide_swapped = widen("e0a1zw2sbxq3dtcr4fgvuhj5miyk67onl89p={}([");
+
+        let q = wide.get_layerkey_for_symbol(&'q').unwrap();
+        assert_eq!(evaluator.key_cost(q), Some(q.key.cost / 100.0));
+        assert!(key_costs(&wide) < key_costs(&layout));
+
+        // the bound covers all permutations of the keys, so it must not depend on which of
+        // them is the current one
+        let lower_bound = evaluator.theoretical_lower_bound(&wide);
+        assert!((lower_bound - evaluator.theoretical_lower_bound(&wide_swapped)).abs() < 1e-9);
+        assert!(lower_bound <= key_costs(&wide));
+        assert!(lower_bound <= key_costs(&wide_swapped));
+    }
+
+    #[test]
+    fn zone_ignores_ngrams_outside_of_the_zone() {
+        let (layout_generator, evaluator) = generator_and_evaluator();
+
+        let layout1 = layout_generator.generate(LAYOUT).unwrap();
+        let zone: Vec<MatrixPosition> = "abcdefghijklmnopqrstuvwxyz"
+            .chars()
+            .map(|c| {
+                layout1
+                    .get_layerkey_for_symbol(&c)
+                    .unwrap()
+                    .key
+                    .matrix_position
+            })
+            .collect();
+        let zoned_evaluator = evaluator.clone().with_zone(Some(&zone));
+
+        // the digits "2" and "4" of the text swap their positions outside of the zone
+        let layout2 = layout_generator
+            .generate("q0a1zw4sbxe3dtcr2fgvuhj5miyk67onl89p={}([")
+            .unwrap();
+        let ngram_costs = |evaluator: &Evaluator, layout: &Layout| -> Vec<(f64, f64)> {
+            evaluator
+                .evaluate_layout(layout)
+                .iter()
+                .filter(|mr| mr.metric_type != MetricType::Layout)
+                .map(|mr| (mr.found_weight, mr.total_cost()))
+                .collect()
+        };
+
+        let (zoned1, zoned2) = (
+            ngram_costs(&zoned_evaluator, &layout1),
+            ngram_costs(&zoned_evaluator, &layout2),
+        );
+        assert!(!zoned1.is_empty());
+        for ((weight1, cost1), (weight2, cost2)) in zoned1.iter().zip(zoned2.iter()) {
+            assert_eq!(weight1, weight2);
+            assert!((cost1 - cost2).abs() < 1e-9);
+        }
+
+        // the ngrams outside of the zone do not count as found
+        for ((zoned_weight, _), (weight, _)) in
+            zoned1.iter().zip(ngram_costs(&evaluator, &layout1).iter())
+        {
+            assert!(zoned_weight < weight);
+        }
+    }
+
+    #[test]
+    fn typing_speed_weighting_shifts_weight_to_difficult_ngrams() {
+        let mut grams = vec![('a', 3.0), ('b', 2.0), ('c', 1.0)];
+        let difficulty = |c: &char, _: f64| match c {
+            'a' => 0.0,
+            'b' => 1.0,
+            _ => 4.0,
+        };
+
+        apply_typing_speed_weighting(&mut grams, 2.0, difficulty);
+
+        let total_weight: f64 = grams.iter().map(|(_, w)| w).sum();
+        assert!((total_weight - 6.0).abs() < 1e-9);
+        // the mean difficulty is 1.0, so ngrams with a higher difficulty gain weight
+        assert!(grams[0].1 < 3.0);
+        assert!((grams[1].1 - 2.0).abs() < 1e-9);
+        assert!(grams[2].1 > 1.0);
+    }
+
+    #[test]
+    fn typing_speed_weighting_rejects_a_negative_strength() {
+        let (_, evaluator) = generator_and_evaluator();
+        let config = |enabled, strength| TypingSpeedWeightingConfig { enabled, strength };
+
+        assert!(evaluator
+            .clone()
+            .with_typing_speed_weighting(&config(true, 0.0))
+            .is_ok());
+        assert!(evaluator
+            .clone()
+            .with_typing_speed_weighting(&config(false, -1.0))
+            .is_ok());
+        assert!(evaluator
+            .with_typing_speed_weighting(&config(true, -1.0))
+            .is_err());
     }
 }
@@ -1,11 +1,19 @@
+pub mod bootstrap;
 pub mod cache;
+pub mod calibration;
 pub mod config;
 pub mod evaluation;
+pub mod metric_influence;
+pub mod metric_info;
 pub mod metrics;
+pub mod multi_corpus;
 pub mod ngram_mapper;
 pub mod ngrams;
 pub mod results;
 pub mod sval;
+#[cfg(test)]
+pub(crate) mod test_utils;
+pub mod weight_presets;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,231 @@
+//! The `metric_influence` module analyzes which metrics drive the ranking of a set of layouts,
+//! e.g. for pruning metrics that do not matter for a corpus or for focusing the tuning of the
+//! metric weights.
+//!
+//! It works on the per-metric breakdowns of a ranking (see
+//! [`Evaluator::rank_layouts`](crate::evaluation::Evaluator::rank_layouts)), so the layouts do
+//! not need to be evaluated again. For each metric, two values are reported:
+//! - the (Spearman) rank correlation between the metric's contribution and the total cost
+//!   across the layouts, and
+//! - a sensitivity score: the fraction of layout pairs whose order in the ranking flips if the
+//!   metric's weight is scaled by `1 ± perturbation` (the larger of both fractions).
+
+use crate::results::{MetricType, RankedLayout};
+
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, fmt};
+
+/// The influence of a metric on the ranking of a set of layouts.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MetricInfluence {
+    /// Name of the metric.
+    pub label: String,
+    /// Type of the metric.
+    pub metric_type: MetricType,
+    /// Average contribution of the metric to the total cost.
+    pub mean_contribution: f64,
+    /// Spearman rank correlation between the metric's contribution and the total cost.
+    pub rank_correlation: f64,
+    /// Fraction of layout pairs whose order changes if the metric's weight is perturbed.
+    pub sensitivity: f64,
+}
+
+impl fmt::Display for MetricInfluence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>7.2}% {:>+6.2} {:>10.4} {}",
+            100.0 * self.sensitivity,
+            self.rank_correlation,
+            self.mean_contribution,
+            self.label,
+        )
+    }
+}
+
+/// Compute the influence of each metric on the ranking of the valid layouts (the ones with a
+/// cost) for a relative weight `perturbation` (e.g. 0.1 for ±10%). The metrics are sorted by
+/// their sensitivity (descending) and then by the magnitude of their rank correlation.
+pub fn metric_influence(ranked: &[RankedLayout], perturbation: f64) -> Vec<MetricInfluence> {
+    let ranked: Vec<&RankedLayout> = ranked.iter().filter(|r| r.cost.is_some()).collect();
+    let totals: Vec<f64> = ranked.iter().map(|r| r.cost.unwrap()).collect();
+
+    let mut metrics: Vec<(String, MetricType)> = Vec::new();
+    ranked
+        .iter()
+        .flat_map(|r| r.breakdown.iter())
+        .for_each(|c| {
+            if !metrics
+                .iter()
+                .any(|(label, t)| label == &c.label && t == &c.metric_type)
+            {
+                metrics.push((c.label.clone(), c.metric_type.clone()));
+            }
+        });
+
+    let mut influences: Vec<MetricInfluence> = metrics
+        .into_iter()
+        .map(|(label, metric_type)| {
+            // a metric that is missing in a breakdown does not contribute to its total
+            let contributions: Vec<f64> = ranked
+                .iter()
+                .map(|r| {
+                    r.breakdown
+                        .iter()
+                        .find(|c| c.label == label && c.metric_type == metric_type)
+                        .map_or(0.0, |c| c.weighted_value)
+                })
+                .collect();
+
+            let flipped_fraction = |factor: f64| {
+                let perturbed: Vec<f64> = totals
+                    .iter()
+                    .zip(contributions.iter())
+                    .map(|(total, contribution)| total + (factor - 1.0) * contribution)
+                    .collect();
+                discordant_fraction(&totals, &perturbed)
+            };
+
+            MetricInfluence {
+                mean_contribution: contributions.iter().sum::<f64>()
+                    / contributions.len().max(1) as f64,
+                rank_correlation: spearman_correlation(&contributions, &totals),
+                sensitivity: flipped_fraction(1.0 + perturbation)
+                    .max(flipped_fraction(1.0 - perturbation)),
+                label,
+                metric_type,
+            }
+        })
+        .collect();
+
+    influences.sort_by(|i1, i2| {
+        i2.sensitivity
+            .partial_cmp(&i1.sensitivity)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| {
+                i2.rank_correlation
+                    .abs()
+                    .partial_cmp(&i1.rank_correlation.abs())
+                    .unwrap_or(Ordering::Equal)
+            })
+    });
+
+    influences
+}
+
+/// The fraction of pairs whose (strict) order differs between both lists of values.
+fn discordant_fraction(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len();
+    if n < 2 {
+        return 0.0;
+    }
+
+    let mut discordant = 0;
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if a[i].partial_cmp(&a[j]) != b[i].partial_cmp(&b[j]) {
+                discordant += 1;
+            }
+        }
+    }
+
+    discordant as f64 / (n * (n - 1) / 2) as f64
+}
+
+/// The ranks of the values (starting with 1), with tied values getting their average rank.
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|i, j| {
+        values[*i]
+            .partial_cmp(&values[*j])
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        order[start..end].iter().for_each(|i| ranks[*i] = rank);
+        start = end;
+    }
+
+    ranks
+}
+
+/// The Spearman rank correlation of both lists of values (zero if either is constant).
+fn spearman_correlation(a: &[f64], b: &[f64]) -> f64 {
+    let (ranks_a, ranks_b) = (ranks(a), ranks(b));
+    let n = ranks_a.len().max(1) as f64;
+    let mean_a = ranks_a.iter().sum::<f64>() / n;
+    let mean_b = ranks_b.iter().sum::<f64>() / n;
+
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    ranks_a.iter().zip(ranks_b.iter()).for_each(|(ra, rb)| {
+        cov += (ra - mean_a) * (rb - mean_b);
+        var_a += (ra - mean_a).powi(2);
+        var_b += (rb - mean_b).powi(2);
+    });
+
+    match var_a > 0.0 && var_b > 0.0 {
+        true => cov / (var_a * var_b).sqrt(),
+        false => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::metric_influence;
+    use crate::results::{MetricContribution, MetricType, RankedLayout};
+
+    fn ranked_layout(contributions: &[(&str, f64)]) -> RankedLayout {
+        RankedLayout {
+            layout: String::new(),
+            rank: None,
+            cost: Some(contributions.iter().map(|(_, c)| c).sum()),
+            breakdown: contributions
+                .iter()
+                .map(|(label, c)| MetricContribution {
+                    label: label.to_string(),
+                    metric_type: MetricType::Bigram,
+                    weighted_value: *c,
+                    raw_value: *c,
+                    weight: 1.0,
+                })
+                .collect(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn metrics_are_sorted_by_their_influence_on_the_ranking() {
+        let ranked = vec![
+            ranked_layout(&[("Driver", 10.0), ("Tiebreaker", 6.0), ("Constant", 5.0)]),
+            ranked_layout(&[("Driver", 15.7), ("Tiebreaker", 0.0), ("Constant", 5.0)]),
+            ranked_layout(&[("Driver", 30.0), ("Tiebreaker", 0.0), ("Constant", 5.0)]),
+            RankedLayout {
+                cost: None,
+                error: Some("invalid".to_string()),
+                ..ranked_layout(&[])
+            },
+        ];
+
+        let influences = metric_influence(&ranked, 0.1);
+        let labels: Vec<&str> = influences.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, vec!["Driver", "Tiebreaker", "Constant"]);
+
+        // scaling either the driver up or the tiebreaker down swaps the first two layouts
+        let (driver, tiebreaker, constant) = (&influences[0], &influences[1], &influences[2]);
+        assert!((driver.sensitivity - 1.0 / 3.0).abs() < 1e-9);
+        assert!((driver.rank_correlation - 0.5).abs() < 1e-9);
+        assert!((tiebreaker.sensitivity - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(tiebreaker.rank_correlation, 0.0);
+
+        assert_eq!(constant.sensitivity, 0.0);
+        assert_eq!(constant.rank_correlation, 0.0);
+        assert_eq!(constant.mean_contribution, 5.0);
+    }
+}
@@ -0,0 +1,523 @@
+//! The `metric_info` module provides metadata (identifier, display name, description,
+//! parameters and defaults) about all metrics that can be configured in the
+//! evaluation parameters. This allows front-ends to build their settings dynamically
+//! instead of hardcoding the available metrics.
+
+use crate::results::{MetricType, NormalizationType};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_yaml::Value;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MetricConfigError {
+    #[error("Evaluation parameters do not contain a 'metrics' section")]
+    MissingMetricsSection,
+    #[error("Missing or invalid '{0}'")]
+    InvalidField(&'static str),
+    #[error("Invalid config for metric '{0}': {1}")]
+    InvalidMetricConfig(String, String),
+}
+
+/// Static description of a metric.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricInfo {
+    /// Identifier of the metric as used in the `metrics` section of the evaluation parameters.
+    pub id: &'static str,
+    /// Name of the metric as shown in evaluation results.
+    pub name: &'static str,
+    /// Which data the metric operates on.
+    pub metric_type: MetricType,
+    /// Short description of what the metric evaluates.
+    pub description: &'static str,
+}
+
+/// Description of a single parameter of a metric, derived from its default value.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParameterInfo {
+    /// Name of the parameter.
+    pub name: String,
+    /// Kind of the value ("null", "bool", "number", "string", "list", or "map").
+    pub kind: &'static str,
+    /// Default value of the parameter. Map keys that are not strings are converted
+    /// to their inline YAML representation (e.g. `[[3, 1], [2, 3]]`).
+    pub default: Value,
+}
+
+/// Default configuration of a metric as found in a set of evaluation parameters.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricDefaults {
+    pub enabled: bool,
+    pub weight: f64,
+    pub normalization: NormalizationType,
+    /// The parameters of the metric (empty if the metric does not take any).
+    pub params: Vec<ParameterInfo>,
+}
+
+/// A metric's static description together with its default configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricDescription {
+    #[serde(flatten)]
+    pub info: MetricInfo,
+    /// `None` if the metric is not present in the given evaluation parameters.
+    pub defaults: Option<MetricDefaults>,
+}
+
+macro_rules! metric_info {
+    ($id:literal, $name:literal, $metric_type:ident, $description:literal) => {
+        MetricInfo {
+            id: $id,
+            name: $name,
+            metric_type: MetricType::$metric_type,
+            description: $description,
+        }
+    };
+}
+
+/// Metadata of all metrics that can be configured in the evaluation parameters.
+pub fn available_metrics() -> Vec<MetricInfo> {
+    vec![
+        // layout metrics
+        metric_info!(
+            "shortcut_keys",
+            "Badly Positioned Shortcut Keys",
+            Layout,
+            "Penalizes configurable letters (usually \"cvxz\") if they are not within the first five columns of the left hand."
+        ),
+        metric_info!(
+            "similar_letters",
+            "Similar Letters",
+            Layout,
+            "Checks configurable pairs of letters (e.g. \"aä\" or \"mn\") for sensible, symmetric placement."
+        ),
+        metric_info!(
+            "similar_letter_groups",
+            "Similar Letter-Groups",
+            Layout,
+            "Compares the relative locations of configurable groups of similar letters."
+        ),
+        metric_info!(
+            "one_hand_words",
+            "One-Hand Words",
+            Layout,
+            "Penalizes the fraction of a corpus' words (by weight) that are typed entirely with one hand."
+        ),
+        metric_info!(
+            "kla_same_finger_words",
+            "Same Finger Words",
+            Layout,
+            "Counts words of a word list that can be typed with a single finger."
+        ),
+        metric_info!(
+            "kla_home_key_words",
+            "Home Key Words",
+            Layout,
+            "Counts words of a word list that can be typed using only home row keys."
+        ),
+        // unigram metrics
+        metric_info!(
+            "finger_balance",
+            "Finger Balance",
+            Unigram,
+            "Compares the load of each finger with configurable intended finger loads, optionally penalizing the most overworked finger."
+        ),
+        metric_info!(
+            "hand_disbalance",
+            "Hand Disbalance",
+            Unigram,
+            "Penalizes the deviation of each hand's load (excluding thumbs) from one half."
+        ),
+        metric_info!(
+            "hand_symmetry",
+            "Hand Symmetry",
+            Unigram,
+            "Penalizes the load differences between mirrored fingers of both hands (e.g. left and right pinky)."
+        ),
+        metric_info!(
+            "row_loads",
+            "Row Loads",
+            Unigram,
+            "Informational metric (without cost) reporting the fraction of keystrokes typed on each row."
+        ),
+        metric_info!(
+            "pinky_off_home",
+            "Pinky Off-Home",
+            Unigram,
+            "Penalizes pinky presses away from the home key, with separate weights for top row, bottom row, and outer columns."
+        ),
+        metric_info!(
+            "bottom_row_load",
+            "Bottom Row Load",
+            Unigram,
+            "Penalizes the fraction of keystrokes (excluding thumbs) typed on the bottom row."
+        ),
+        metric_info!(
+            "thumb_load",
+            "Thumb Load",
+            Unigram,
+            "Penalizes a fraction of keystrokes typed with the thumbs outside of a target range."
+        ),
+        metric_info!(
+            "key_costs",
+            "Key Costs",
+            Unigram,
+            "Multiplies each unigram's weight with the cost of its key and layer."
+        ),
+        metric_info!(
+            "modifier_usage",
+            "Modifier Usage",
+            Unigram,
+            "Sums the weights of all modifier keystrokes required for higher layers."
+        ),
+        metric_info!(
+            "same_hand_modifiers",
+            "Same-Hand Modifiers",
+            Unigram,
+            "Penalizes higher-layer symbols whose modifiers are pressed with the same hand as the symbol."
+        ),
+        // bigram metrics
+        metric_info!(
+            "finger_repeats",
+            "Finger Repeats",
+            Bigram,
+            "Penalizes bigrams that use the same finger for different keys (thumbs excluded)."
+        ),
+        metric_info!(
+            "finger_gap",
+            "Finger Gap",
+            Bigram,
+            "Penalizes same-hand bigrams by the number of fingers between the two involved fingers."
+        ),
+        metric_info!(
+            "lateral_stretch",
+            "Lateral Stretch",
+            Bigram,
+            "Penalizes bigrams on adjacent fingers whose keys are horizontally further apart than the fingers' natural spacing."
+        ),
+        metric_info!(
+            "sticky_finger",
+            "Sticky Finger",
+            Bigram,
+            "Penalizes same-finger bigrams whose keys are in different columns by their horizontal shift."
+        ),
+        metric_info!(
+            "scissoring",
+            "Scissoring",
+            Bigram,
+            "Penalizes bigrams whose keys are pressed in opposite directions (e.g. north and south), especially involving the pinky."
+        ),
+        metric_info!(
+            "manual_bigram_penalty",
+            "Manual Bigram Penalty",
+            Bigram,
+            "Penalizes bigrams mapped to one of a list of configurable \"bad\" key pairs."
+        ),
+        metric_info!(
+            "alternating_column_penalty",
+            "Alternating Column Penalty",
+            Bigram,
+            "Penalizes hand-alternating bigrams on one of a list of configurable \"awkward\" column pairs."
+        ),
+        metric_info!(
+            "movement_pattern",
+            "Movement Pattern",
+            Bigram,
+            "Penalizes bigrams on (almost) neighboring fingers with configurable costs per finger combination."
+        ),
+        metric_info!(
+            "no_handswitch_after_unbalancing_key",
+            "No Handswitch After Unbalancing Key",
+            Bigram,
+            "Penalizes bigrams that start with an unbalancing key and stay on the same hand."
+        ),
+        metric_info!(
+            "symmetric_handswitches",
+            "Symmetric Handswitches",
+            Bigram,
+            "Rewards bigrams whose keys sit at symmetric positions on both hands (thumbs excluded)."
+        ),
+        metric_info!(
+            "same_row_bigrams",
+            "Same Row Bigrams",
+            Bigram,
+            "Counts bigrams whose keys lie in the same row (penalty or reward depending on the weight's sign)."
+        ),
+        metric_info!(
+            "same_hand_after_space",
+            "Same Hand After Space",
+            Bigram,
+            "Penalizes bigrams continuing with the hand whose thumb pressed the preceding space."
+        ),
+        metric_info!(
+            "worst_bigrams",
+            "Worst Bigrams",
+            Bigram,
+            "Sums the costs (according to all bigram metrics) of the most expensive frequent bigrams."
+        ),
+        metric_info!(
+            "kla_distance",
+            "Distance",
+            Bigram,
+            "Measures the distance the fingers travel from their resting positions."
+        ),
+        metric_info!(
+            "kla_finger_usage",
+            "Finger Usage",
+            Bigram,
+            "Weighs each keystroke with configurable finger and hand scores."
+        ),
+        metric_info!(
+            "kla_same_finger",
+            "Same Finger",
+            Bigram,
+            "Counts bigrams typed with the same finger."
+        ),
+        metric_info!(
+            "kla_same_hand",
+            "Same Hand",
+            Bigram,
+            "Counts bigrams typed with the same hand."
+        ),
+        metric_info!(
+            "oxey_sfbs",
+            "Sfbs",
+            Bigram,
+            "Counts same-finger bigrams on different keys."
+        ),
+        metric_info!(
+            "oxey_lsbs",
+            "Lsbs",
+            Bigram,
+            "Counts lateral stretch bigrams (same hand, neighboring fingers, large horizontal distance)."
+        ),
+        // trigram metrics
+        metric_info!(
+            "no_handswitch_in_trigram",
+            "No Handswitch in Trigram",
+            Trigram,
+            "Penalizes trigrams typed with a single hand, especially if they change direction."
+        ),
+        metric_info!(
+            "trigram_finger_repeats",
+            "Trigram Finger Repeats",
+            Trigram,
+            "Penalizes trigrams that use the same finger for all three keys (thumbs excluded)."
+        ),
+        metric_info!(
+            "trigram_same_finger",
+            "Trigram Same Finger",
+            Trigram,
+            "Penalizes trigrams with at least two keys on the same finger, weighting the all-three case separately (thumbs excluded)."
+        ),
+        metric_info!(
+            "finger_reuse_gaps",
+            "Finger Reuse Gaps",
+            Trigram,
+            "Penalizes reusing a finger by the number of keystrokes since its last use, as far as visible in trigrams (thumbs excluded)."
+        ),
+        metric_info!(
+            "handswitch_then_same_finger",
+            "Hand Switch Then Same Finger",
+            Trigram,
+            "Penalizes trigrams with a hand switch followed by a same-finger bigram (thumbs excluded)."
+        ),
+        metric_info!(
+            "key_repeats",
+            "Key Repeats",
+            Trigram,
+            "Penalizes trigrams that press the same key three times in a row."
+        ),
+        metric_info!(
+            "trigram_rolls",
+            "Trigram Rolls",
+            Trigram,
+            "Rewards trigrams that roll inwards or outwards on one hand."
+        ),
+        metric_info!(
+            "irregularity",
+            "Irregularity",
+            Trigram,
+            "Evaluates both bigrams of a trigram with all bigram metrics and multiplies their costs."
+        ),
+        metric_info!(
+            "secondary_bigrams",
+            "Secondary Bigrams",
+            Trigram,
+            "Evaluates the first and last symbol of a trigram with all bigram metrics."
+        ),
+        metric_info!(
+            "oxey_combined_trigram",
+            "Combined",
+            Trigram,
+            "Combines all oxey trigram statistics into a single cost."
+        ),
+        metric_info!(
+            "oxey_dsfbs",
+            "Dsfbs",
+            Trigram,
+            "Counts disjointed same-finger bigrams (first and last key of a trigram)."
+        ),
+        metric_info!(
+            "oxey_inward_rolls",
+            "Inward Rolls",
+            Trigram,
+            "Counts trigrams with an inward roll on one hand and a handswitch."
+        ),
+        metric_info!(
+            "oxey_outward_rolls",
+            "Outward Rolls",
+            Trigram,
+            "Counts trigrams with an outward roll on one hand and a handswitch."
+        ),
+        metric_info!(
+            "oxey_onehands",
+            "Onehands",
+            Trigram,
+            "Counts trigrams rolling in a single direction on one hand."
+        ),
+        metric_info!(
+            "oxey_alternates",
+            "Alternates",
+            Trigram,
+            "Counts trigrams alternating between both hands."
+        ),
+        metric_info!(
+            "oxey_alternates_sfs",
+            "Alternates (sfs)",
+            Trigram,
+            "Counts alternating trigrams whose first and last key use the same finger."
+        ),
+        metric_info!(
+            "oxey_redirects",
+            "Redirects",
+            Trigram,
+            "Counts one-handed trigrams that change direction."
+        ),
+        metric_info!(
+            "oxey_bad_redirects",
+            "Bad Redirects",
+            Trigram,
+            "Counts one-handed trigrams that change direction without using the index finger."
+        ),
+    ]
+}
+
+fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Sequence(_) => "list",
+        Value::Mapping(_) => "map",
+        Value::Tagged(tagged) => value_kind(&tagged.value),
+    }
+}
+
+/// Inline (flow style) YAML representation of a value.
+fn inline_yaml(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.to_string(),
+        Value::Sequence(seq) => format!(
+            "[{}]",
+            seq.iter().map(inline_yaml).collect::<Vec<_>>().join(", ")
+        ),
+        Value::Mapping(mapping) => format!(
+            "{{{}}}",
+            mapping
+                .iter()
+                .map(|(k, v)| format!("{}: {}", inline_yaml(k), inline_yaml(v)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Tagged(tagged) => inline_yaml(&tagged.value),
+    }
+}
+
+/// Convert all map keys that are not strings to strings, such that the value can be
+/// represented in formats like JSON.
+fn with_string_keys(value: &Value) -> Value {
+    match value {
+        Value::Sequence(seq) => Value::Sequence(seq.iter().map(with_string_keys).collect()),
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .iter()
+                .map(|(k, v)| {
+                    let key = match k {
+                        Value::String(_) => k.clone(),
+                        _ => Value::String(inline_yaml(k)),
+                    };
+                    (key, with_string_keys(v))
+                })
+                .collect(),
+        ),
+        Value::Tagged(tagged) => with_string_keys(&tagged.value),
+        _ => value.clone(),
+    }
+}
+
+fn metric_defaults(config: &Value) -> Result<MetricDefaults> {
+    let enabled = config
+        .get("enabled")
+        .and_then(Value::as_bool)
+        .ok_or(MetricConfigError::InvalidField("enabled"))?;
+    let weight = config
+        .get("weight")
+        .and_then(Value::as_f64)
+        .ok_or(MetricConfigError::InvalidField("weight"))?;
+    let normalization: NormalizationType = serde_yaml::from_value(
+        config
+            .get("normalization")
+            .cloned()
+            .ok_or(MetricConfigError::InvalidField("normalization"))?,
+    )?;
+
+    // metrics without parameters are configured with `params: {null: null}`
+    let params = match config.get("params") {
+        Some(Value::Mapping(mapping)) => mapping
+            .iter()
+            .filter(|(name, _)| !name.is_null())
+            .map(|(name, default)| ParameterInfo {
+                name: inline_yaml(name),
+                kind: value_kind(default),
+                default: with_string_keys(default),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(MetricDefaults {
+        enabled,
+        weight,
+        normalization,
+        params,
+    })
+}
+
+/// Describe all available metrics, taking their defaults from the given evaluation
+/// parameters (in YAML format, as in the evaluation config files).
+pub fn describe_metrics(eval_params_str: &str) -> Result<Vec<MetricDescription>> {
+    let eval_params: Value = serde_yaml::from_str(eval_params_str)?;
+    let metrics = eval_params
+        .get("metrics")
+        .ok_or(MetricConfigError::MissingMetricsSection)?;
+
+    available_metrics()
+        .into_iter()
+        .map(|info| {
+            let defaults = match metrics.get(info.id) {
+                Some(config) if !config.is_null() => {
+                    Some(metric_defaults(config).map_err(|e| {
+                        MetricConfigError::InvalidMetricConfig(info.id.to_string(), e.to_string())
+                    })?)
+                }
+                _ => None,
+            };
+            Ok(MetricDescription { info, defaults })
+        })
+        .collect()
+}
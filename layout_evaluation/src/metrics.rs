@@ -1,6 +1,62 @@
 //! The `metrics` module provides traits for layout, unigram, bigram, and trigram metrics.
+//!
+//! Custom metrics (e.g. from other crates) can be implemented against these traits and
+//! registered with an [`Evaluator`](crate::evaluation::Evaluator) using
+//! [`Evaluator::add_metric`](crate::evaluation::Evaluator::add_metric). Each metric receives
+//! the data corresponding to its type:
+//!
+//! - [`LayoutMetric`]: only the layout itself.
+//! - [`UnigramMetric`], [`BigramMetric`], [`TrigramMetric`]: the layout and the weighted ngrams
+//!   mapped to the layout's [`LayerKey`](keyboard_layout::layout::LayerKey)s. Higher-layer symbols
+//!   are already split into base-layer keys and modifiers and ngrams that can not be generated by
+//!   the layout are omitted. The weights are absolute frequencies as in the ngram data.
+//!
+//! A metric returns a cost (and optionally a message) that gets weighted and normalized by
+//! the evaluator. In the [`EvaluationResult`](crate::results::EvaluationResult), the metric is
+//! listed under the name returned by its `name` method.
+//!
+//! Ngram metrics either provide `individual_cost` (the default `total_cost` sums these up and
+//! reports the worst ngrams) or override `total_cost` for costs that depend on aggregated data.
 
 pub mod bigram_metrics;
 pub mod layout_metrics;
 pub mod trigram_metrics;
 pub mod unigram_metrics;
+
+use bigram_metrics::BigramMetric;
+use layout_metrics::LayoutMetric;
+use trigram_metrics::TrigramMetric;
+use unigram_metrics::UnigramMetric;
+
+/// A metric of any of the supported types.
+#[derive(Clone, Debug)]
+pub enum Metric {
+    Layout(Box<dyn LayoutMetric>),
+    Unigram(Box<dyn UnigramMetric>),
+    Bigram(Box<dyn BigramMetric>),
+    Trigram(Box<dyn TrigramMetric>),
+}
+
+impl From<Box<dyn LayoutMetric>> for Metric {
+    fn from(metric: Box<dyn LayoutMetric>) -> Self {
+        Self::Layout(metric)
+    }
+}
+
+impl From<Box<dyn UnigramMetric>> for Metric {
+    fn from(metric: Box<dyn UnigramMetric>) -> Self {
+        Self::Unigram(metric)
+    }
+}
+
+impl From<Box<dyn BigramMetric>> for Metric {
+    fn from(metric: Box<dyn BigramMetric>) -> Self {
+        Self::Bigram(metric)
+    }
+}
+
+impl From<Box<dyn TrigramMetric>> for Metric {
+    fn from(metric: Box<dyn TrigramMetric>) -> Self {
+        Self::Trigram(metric)
+    }
+}
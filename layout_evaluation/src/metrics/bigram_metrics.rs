@@ -5,18 +5,25 @@ use ordered_float::{Float, OrderedFloat};
 use priority_queue::DoublePriorityQueue;
 use std::{env, fmt};
 
+pub mod alternating_column_penalty;
+pub mod finger_gap;
 pub mod finger_repeats;
 pub mod kla_distance;
 pub mod kla_finger_usage;
 pub mod kla_same_finger;
 pub mod kla_same_hand;
+pub mod lateral_stretch;
 pub mod manual_bigram_penalty;
 pub mod movement_pattern;
 pub mod no_handswitch_after_unbalancing_key;
 pub mod oxey_lsbs;
 pub mod oxey_sfbs;
+pub mod same_hand_after_space;
+pub mod same_row_bigrams;
 pub mod scissoring;
+pub mod sticky_finger;
 pub mod symmetric_handswitches;
+pub mod worst_bigrams;
 
 /// BigramMetric is a trait for metrics that iterates over weighted bigrams.
 pub trait BigramMetric: Send + Sync + BigramMetricClone + fmt::Debug {
@@ -0,0 +1,70 @@
+//! The bigram metric [`AlternatingColumnPenalty`] incurrs costs if a bigram alternates hands and
+//! its keys lie in one of a list of configurable "awkward" column pairs.
+//!
+//! This is a user-supplied correction table for idiosyncratic combinations that are not
+//! captured by other metrics. Bigrams typed with a single hand are ignored.
+
+use super::BigramMetric;
+
+use keyboard_layout::layout::{LayerKey, Layout};
+
+use ahash::AHashMap;
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// If the reversed column pairs shall be added automatically
+    pub add_mirrored: bool,
+    /// Costs of (from_column, to_column) pairs in terms of matrix columns
+    pub column_pairs: AHashMap<(u8, u8), f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AlternatingColumnPenalty {
+    column_pairs: AHashMap<(u8, u8), f64>,
+}
+
+impl AlternatingColumnPenalty {
+    pub fn new(params: &Parameters) -> Self {
+        let mut column_pairs = params.column_pairs.clone();
+
+        // add the reversed pairs as well, if configured
+        if params.add_mirrored {
+            column_pairs.extend(
+                params
+                    .column_pairs
+                    .iter()
+                    .map(|((c1, c2), w)| ((*c2, *c1), *w)),
+            );
+        }
+
+        Self { column_pairs }
+    }
+}
+
+impl BigramMetric for AlternatingColumnPenalty {
+    fn name(&self) -> &str {
+        "Alternating Column Penalty"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        if k1.key.hand == k2.key.hand {
+            return Some(0.0);
+        }
+
+        let columns = (k1.key.matrix_position.0, k2.key.matrix_position.0);
+        if let Some(val) = self.column_pairs.get(&columns) {
+            return Some(weight * *val);
+        }
+
+        Some(0.0)
+    }
+}
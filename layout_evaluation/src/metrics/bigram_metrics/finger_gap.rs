@@ -0,0 +1,89 @@
+//! The bigram metric [`FingerGap`] assigns a cost to each same-hand bigram depending on the
+//! number of fingers between the two involved fingers. Adjacent fingers (e.g. index and middle)
+//! are comfortable, whereas wider spans (e.g. index and pinky) stress the hand laterally.
+//!
+//! Bigrams involving thumbs or using the same finger twice are excluded (the latter are
+//! covered by the `finger_repeats` metric).
+
+use super::BigramMetric;
+
+use keyboard_layout::{
+    key::Finger,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Cost of bigrams on adjacent fingers
+    pub adjacent_cost: f64,
+    /// Cost of bigrams skipping one finger
+    pub skip_one_cost: f64,
+    /// Cost of bigrams skipping two or more fingers
+    pub skip_two_cost: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct FingerGap {
+    adjacent_cost: f64,
+    skip_one_cost: f64,
+    skip_two_cost: f64,
+}
+
+impl FingerGap {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            adjacent_cost: params.adjacent_cost,
+            skip_one_cost: params.skip_one_cost,
+            skip_two_cost: params.skip_two_cost,
+        }
+    }
+
+    /// The finger gap of a bigram, if it is relevant for this metric.
+    #[inline(always)]
+    fn finger_gap(k1: &LayerKey, k2: &LayerKey) -> Option<u8> {
+        if k1.key.hand != k2.key.hand
+            || k1.key.finger == Finger::Thumb
+            || k2.key.finger == Finger::Thumb
+        {
+            return None;
+        }
+
+        match k1.key.finger.distance(&k2.key.finger) {
+            0 => None,
+            gap => Some(gap),
+        }
+    }
+
+    #[inline(always)]
+    fn gap_cost(&self, gap: u8) -> f64 {
+        match gap {
+            1 => self.adjacent_cost,
+            2 => self.skip_one_cost,
+            _ => self.skip_two_cost,
+        }
+    }
+}
+
+impl BigramMetric for FingerGap {
+    fn name(&self) -> &str {
+        "Finger Gap"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        let cost = Self::finger_gap(k1, k2)
+            .map(|gap| self.gap_cost(gap))
+            .unwrap_or(0.0);
+
+        Some(weight * cost)
+    }
+}
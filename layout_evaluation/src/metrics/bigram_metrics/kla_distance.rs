@@ -17,6 +17,10 @@ pub struct Parameters {
     pub keydown_distance: f64,
     pub dscoring: AHashMap<Hand, AHashMap<Finger, f64>>,
     pub hscoring: AHashMap<Hand, f64>,
+    /// Width of a standard key in the units of the keys' positions. If positive, wide keys are
+    /// reached at the closest point of their center line instead of their center.
+    #[serde(default)]
+    pub key_unit: f64,
 }
 
 #[derive(Clone, Debug)]
@@ -26,6 +30,7 @@ pub struct KLADistance {
     keydown_distance: f64,
     dscoring: HandFingerMap<f64>,
     hscoring: HandMap<f64>,
+    key_unit: f64,
 }
 
 impl KLADistance {
@@ -36,6 +41,7 @@ impl KLADistance {
             keydown_distance: params.keydown_distance,
             dscoring: HandFingerMap::with_hashmap(&params.dscoring, 1.0),
             hscoring: HandMap::with_hashmap(&params.hscoring, 1.0),
+            key_unit: params.key_unit,
         }
     }
 }
@@ -116,7 +122,7 @@ impl BigramMetric for KLADistance {
 
                         // move previously idle finger to key press it
                         (KeyUsage::Idle(prev_pos), KeyUsage::Used(curr_key)) => {
-                            let dist = prev_pos.distance(&curr_key.key.position)
+                            let dist = curr_key.key.reach_distance(prev_pos, self.key_unit)
                                 + self.keydown_distance
                                 + self.keyup_distance;
                             *finger_values.get_mut(&curr_key.key.hand, &curr_key.key.finger) +=
@@ -125,7 +131,7 @@ impl BigramMetric for KLADistance {
 
                         // return finger from previous key press to home row
                         (KeyUsage::Used(prev_key), KeyUsage::Idle(curr_pos)) => {
-                            let dist = prev_key.key.position.distance(curr_pos);
+                            let dist = prev_key.key.reach_distance(curr_pos, self.key_unit);
                             *finger_values.get_mut(&prev_key.key.hand, &prev_key.key.finger) +=
                                 dist * weight;
                         }
@@ -134,7 +140,9 @@ impl BigramMetric for KLADistance {
                         (KeyUsage::Used(prev_key), KeyUsage::Used(curr_key)) => {
                             // if both keys are identical and are mods it is a hold -> no cost
                             if !(prev_key == curr_key && curr_key.is_modifier.is_some()) {
-                                let dist = curr_key.key.position.distance(&prev_key.key.position)
+                                let dist = curr_key
+                                    .key
+                                    .reach_distance(&prev_key.key.position, self.key_unit)
                                     + self.keydown_distance
                                     + self.keyup_distance;
                                 *finger_values.get_mut(&curr_key.key.hand, &curr_key.key.finger) +=
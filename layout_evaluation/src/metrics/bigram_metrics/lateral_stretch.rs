@@ -0,0 +1,163 @@
+//! The bigram metric [`LateralStretch`] penalizes same-hand bigrams on adjacent fingers whose
+//! keys are horizontally further apart than the fingers' natural spacing (e.g. middle finger
+//! on its home key followed by the index finger on the inner column). In contrast to row
+//! jumps, this is about the horizontal reach between the two keys.
+//!
+//! The natural spacing is the horizontal distance between the centers (mean positions) of the
+//! keys assigned to each of the two fingers. If the keyboard's positions do not distinguish the
+//! two fingers horizontally, the keys' matrix columns are compared instead (with a natural
+//! spacing of one column).
+//!
+//! Thumbs are excluded.
+
+use super::BigramMetric;
+
+use keyboard_layout::{
+    key::{Finger, HandFingerMap},
+    keyboard::Keyboard,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Fraction of the natural finger spacing by which the keys may be further apart
+    /// without a cost
+    pub tolerance: f64,
+    /// Exponent applied to the excess stretch (relative to the natural spacing)
+    pub exponent: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct LateralStretch {
+    tolerance: f64,
+    exponent: f64,
+}
+
+impl LateralStretch {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            tolerance: params.tolerance,
+            exponent: params.exponent,
+        }
+    }
+
+    /// The horizontal center of the keys assigned to each finger.
+    fn finger_centers(keyboard: &Keyboard) -> HandFingerMap<f64> {
+        let mut sums: HandFingerMap<(f64, f64)> = HandFingerMap::with_default((0.0, 0.0));
+        keyboard.keys.iter().for_each(|k| {
+            let (sum, count) = sums.get_mut(&k.hand, &k.finger);
+            *sum += k.position.0;
+            *count += 1.0;
+        });
+
+        let mut centers = HandFingerMap::with_default(0.0);
+        centers.each_mut(|hand, finger, center| {
+            let (sum, count) = sums.get(hand, finger);
+            if *count > 0.0 {
+                *center = sum / count;
+            }
+        });
+
+        centers
+    }
+
+    /// The horizontal offset of the bigram's keys relative to the natural spacing of the
+    /// fingers, if the bigram is relevant for this metric.
+    #[inline(always)]
+    fn relative_offset(
+        k1: &LayerKey,
+        k2: &LayerKey,
+        finger_centers: &HandFingerMap<f64>,
+    ) -> Option<f64> {
+        if k1.key.hand != k2.key.hand
+            || k1.key.finger == Finger::Thumb
+            || k2.key.finger == Finger::Thumb
+            || k1.key.finger.distance(&k2.key.finger) != 1
+        {
+            return None;
+        }
+
+        let center1 = finger_centers.get(&k1.key.hand, &k1.key.finger);
+        let center2 = finger_centers.get(&k2.key.hand, &k2.key.finger);
+        let spacing = (center1 - center2).abs();
+
+        let offset = if spacing > 0.0 {
+            (k1.key.position.0 - k2.key.position.0).abs() / spacing
+        } else {
+            (k1.key.matrix_position.0 as f64 - k2.key.matrix_position.0 as f64).abs()
+        };
+
+        Some(offset)
+    }
+
+    #[inline(always)]
+    fn stretch_cost(&self, relative_offset: f64) -> f64 {
+        let excess = relative_offset - 1.0 - self.tolerance;
+        match excess > 0.0 {
+            true => excess.powf(self.exponent),
+            false => 0.0,
+        }
+    }
+}
+
+impl BigramMetric for LateralStretch {
+    fn name(&self) -> &str {
+        "Lateral Stretch"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        layout: &Layout,
+    ) -> Option<f64> {
+        let finger_centers = Self::finger_centers(&layout.keyboard);
+        let cost = Self::relative_offset(k1, k2, &finger_centers)
+            .map(|offset| self.stretch_cost(offset))
+            .unwrap_or(0.0);
+
+        Some(weight * cost)
+    }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+
+        let finger_centers = Self::finger_centers(&layout.keyboard);
+        let mut cost = 0.0;
+        let mut stretch_sum = 0.0;
+        let mut stretched_weight = 0.0;
+
+        bigrams.iter().for_each(|((k1, k2), weight)| {
+            if let Some(offset) = Self::relative_offset(k1, k2, &finger_centers) {
+                let stretch_cost = self.stretch_cost(offset);
+                if stretch_cost > 0.0 {
+                    cost += stretch_cost * weight;
+                    stretch_sum += (offset - 1.0) * weight;
+                    stretched_weight += weight;
+                }
+            }
+        });
+
+        let message = if stretched_weight > 0.0 {
+            format!(
+                "Stretched bigrams: {:.2}%, average lateral stretch: {:.2} (of the natural finger spacing)",
+                100.0 * stretched_weight / total_weight,
+                stretch_sum / stretched_weight
+            )
+        } else {
+            "No laterally stretched bigrams".to_string()
+        };
+
+        (cost, Some(message))
+    }
+}
@@ -0,0 +1,56 @@
+//! The bigram metric [`SameHandAfterSpace`] penalizes bigrams that start with a space and
+//! continue with a key of the same hand as the thumb that pressed the space key.
+//!
+//! The hand owning the space key is taken from the key that generates the space symbol
+//! in the layout. The cost can be configured per hand.
+
+use super::BigramMetric;
+
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::{Hand, HandMap},
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Cost factor for each hand (of the space thumb)
+    pub hand_factors: AHashMap<Hand, f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SameHandAfterSpace {
+    hand_factors: HandMap<f64>,
+}
+
+impl SameHandAfterSpace {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            hand_factors: HandMap::with_hashmap(&params.hand_factors, 1.0),
+        }
+    }
+}
+
+impl BigramMetric for SameHandAfterSpace {
+    fn name(&self) -> &str {
+        "Same Hand After Space"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        if k1.symbol == ' ' && k2.symbol != ' ' && k1.key.hand == k2.key.hand {
+            Some(weight * self.hand_factors.get(&k1.key.hand))
+        } else {
+            Some(0.0)
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! The bigram metric [`SameRowBigrams`] counts bigrams whose two keys lie in the same
+//! (matrix) row. Repeats of the same key are excluded.
+//!
+//! Depending on the sign of the metric's weight, staying on the same row can either be
+//! penalized or rewarded.
+
+use super::BigramMetric;
+
+use keyboard_layout::layout::{LayerKey, Layout};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Only count bigrams whose keys are typed with the same hand
+    pub same_hand_only: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct SameRowBigrams {
+    same_hand_only: bool,
+}
+
+impl SameRowBigrams {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            same_hand_only: params.same_hand_only,
+        }
+    }
+
+    #[inline(always)]
+    fn is_same_row(&self, k1: &LayerKey, k2: &LayerKey) -> bool {
+        let pos1 = k1.key.matrix_position;
+        let pos2 = k2.key.matrix_position;
+
+        pos1.1 == pos2.1 && pos1 != pos2 && (!self.same_hand_only || k1.key.hand == k2.key.hand)
+    }
+}
+
+impl BigramMetric for SameRowBigrams {
+    fn name(&self) -> &str {
+        "Same Row Bigrams"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        if self.is_same_row(k1, k2) {
+            Some(weight)
+        } else {
+            Some(0.0)
+        }
+    }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+
+        let cost: f64 = bigrams
+            .iter()
+            .filter_map(|((k1, k2), weight)| {
+                self.individual_cost(k1, k2, *weight, total_weight, layout)
+            })
+            .sum();
+
+        let message = format!("Same-row bigrams: {:.2}%", 100.0 * cost / total_weight);
+
+        (cost, Some(message))
+    }
+}
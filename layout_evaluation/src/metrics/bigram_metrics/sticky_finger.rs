@@ -0,0 +1,128 @@
+//! The bigram metric [`StickyFinger`] penalizes same-finger bigrams whose keys lie in
+//! different columns, i.e. where the finger has to slide sideways (e.g. on wide keyboards
+//! where a finger owns multiple columns). In contrast to row jumps, only the horizontal shift
+//! between the two keys is considered.
+//!
+//! The cost of such a bigram scales with the horizontal distance between the keys (in key
+//! units). If the keyboard's positions do not distinguish the two keys horizontally, the
+//! difference of their matrix columns is used instead.
+
+use super::BigramMetric;
+
+use keyboard_layout::{
+    key::Finger,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Only consider bigrams whose keys are in the same matrix row
+    pub same_row_only: bool,
+    /// Exclude bigrams typed with a thumb
+    pub exclude_thumbs: bool,
+    /// Exponent applied to the horizontal shift
+    pub exponent: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct StickyFinger {
+    same_row_only: bool,
+    exclude_thumbs: bool,
+    exponent: f64,
+}
+
+impl StickyFinger {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            same_row_only: params.same_row_only,
+            exclude_thumbs: params.exclude_thumbs,
+            exponent: params.exponent,
+        }
+    }
+
+    /// The horizontal shift between the bigram's keys, if the bigram is relevant for this
+    /// metric.
+    #[inline(always)]
+    fn column_shift(&self, k1: &LayerKey, k2: &LayerKey) -> Option<f64> {
+        let (pos1, pos2) = (&k1.key.matrix_position, &k2.key.matrix_position);
+        if k1.key.hand != k2.key.hand
+            || k1.key.finger != k2.key.finger
+            || pos1.0 == pos2.0
+            || (self.same_row_only && pos1.1 != pos2.1)
+            || (self.exclude_thumbs && k1.key.finger == Finger::Thumb)
+        {
+            return None;
+        }
+
+        let shift = (k1.key.position.0 - k2.key.position.0).abs();
+        match shift > 0.0 {
+            true => Some(shift),
+            false => Some((pos1.0 as f64 - pos2.0 as f64).abs()),
+        }
+    }
+}
+
+impl BigramMetric for StickyFinger {
+    fn name(&self) -> &str {
+        "Sticky Finger"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        let cost = self
+            .column_shift(k1, k2)
+            .map(|shift| shift.powf(self.exponent))
+            .unwrap_or(0.0);
+
+        Some(weight * cost)
+    }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+
+        let mut cost = 0.0;
+        let mut shift_sum = 0.0;
+        let mut sticky_weight = 0.0;
+        let mut worst: Option<(&LayerKey, &LayerKey, f64)> = None;
+
+        bigrams.iter().for_each(|((k1, k2), weight)| {
+            if let Some(shift) = self.column_shift(k1, k2) {
+                let bigram_cost = shift.powf(self.exponent) * weight;
+                cost += bigram_cost;
+                shift_sum += shift * weight;
+                sticky_weight += weight;
+                if worst.map_or(true, |(_, _, c)| bigram_cost > c) {
+                    worst = Some((k1, k2, bigram_cost));
+                }
+            }
+        });
+
+        let message = match worst {
+            Some((k1, k2, worst_cost)) => format!(
+                "Sticky bigrams: {:.2}%, average shift: {:.2}, worst: {}{} ({:.1}%)",
+                100.0 * sticky_weight / total_weight,
+                shift_sum / sticky_weight,
+                k1,
+                k2,
+                100.0 * worst_cost / cost,
+            ),
+            None => "No sticky bigrams".to_string(),
+        };
+
+        (cost, Some(message))
+    }
+}
@@ -0,0 +1,112 @@
+//! The bigram metric [`WorstBigrams`] evaluates the single worst bigrams instead of the sum over
+//! all bigrams. Each bigram is evaluated with all configured bigram metrics that can assign costs
+//! to individual bigrams (like in the irregularity metric), yielding the cost of typing it once.
+//! Among the bigrams exceeding a frequency threshold, the costs of the most expensive ones are
+//! summed up, so that no common bigram is terrible even if the average is good.
+//!
+//! As the resulting cost does not scale with the bigram frequencies, a `fixed` normalization
+//! is appropriate.
+
+use super::BigramMetric;
+use crate::results::NormalizationType;
+
+use keyboard_layout::layout::{LayerKey, Layout};
+
+use ordered_float::OrderedFloat;
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Only bigrams with at least this relative frequency (fraction of all bigrams) are considered
+    pub min_frequency: f64,
+    /// Number of the most expensive bigrams whose costs are summed up (1 yields the maximum)
+    pub n_penalized: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct WorstBigrams {
+    bigram_metrics: Vec<(f64, NormalizationType, Box<dyn BigramMetric>)>,
+    min_frequency: f64,
+    n_penalized: usize,
+}
+
+impl WorstBigrams {
+    pub fn new(
+        bigram_metrics: Vec<(f64, NormalizationType, Box<dyn BigramMetric>)>,
+        params: &Parameters,
+    ) -> Self {
+        Self {
+            bigram_metrics,
+            min_frequency: params.min_frequency,
+            n_penalized: params.n_penalized,
+        }
+    }
+
+    /// The weighted cost of typing the bigram once, summed over all bigram metrics.
+    fn bigram_cost(&self, k1: &LayerKey, k2: &LayerKey, total_weight: f64, layout: &Layout) -> f64 {
+        self.bigram_metrics
+            .iter()
+            .map(|(metric_weight, _, metric)| {
+                metric_weight
+                    * metric
+                        .individual_cost(k1, k2, 1.0, total_weight, layout)
+                        .unwrap_or(0.0)
+            })
+            .sum()
+    }
+}
+
+impl BigramMetric for WorstBigrams {
+    fn name(&self) -> &str {
+        "Worst Bigrams"
+    }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+
+        let mut costs: Vec<(usize, f64)> = bigrams
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, weight))| *weight >= self.min_frequency * total_weight)
+            .map(|(i, ((k1, k2), _))| (i, self.bigram_cost(k1, k2, total_weight, layout)))
+            .filter(|(_, cost)| *cost > 0.0)
+            .collect();
+        costs.sort_by_key(|(_, cost)| std::cmp::Reverse(OrderedFloat(*cost)));
+        costs.truncate(self.n_penalized);
+
+        let cost: f64 = costs.iter().map(|(_, cost)| cost).sum();
+
+        let message = if costs.is_empty() {
+            format!(
+                "No costly bigrams above {:.2}% frequency",
+                100.0 * self.min_frequency
+            )
+        } else {
+            let worst_msgs: Vec<String> = costs
+                .iter()
+                .map(|(i, cost)| {
+                    let ((k1, k2), weight) = bigrams[*i];
+                    format!(
+                        "{}{} ({:.2}, {:.2}%)",
+                        k1,
+                        k2,
+                        cost,
+                        100.0 * weight / total_weight
+                    )
+                })
+                .collect();
+            format!(
+                "Worst bigrams above {:.2}% frequency (cost, frequency): {}",
+                100.0 * self.min_frequency,
+                worst_msgs.join(", ")
+            )
+        };
+
+        (cost, Some(message))
+    }
+}
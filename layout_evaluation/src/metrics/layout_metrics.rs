@@ -3,17 +3,20 @@ use keyboard_layout::layout::Layout;
 
 use std::fmt;
 
+pub mod home_row_pins;
 pub mod kla_home_key_words;
 pub mod kla_same_finger_words;
+pub mod one_hand_words;
 pub mod shortcut_keys;
 pub mod similar_letter_groups;
 pub mod similar_letters;
+pub mod soft_pins;
 
 /// LayoutMetric is a trait for metrics that depends only on the layout.
 pub trait LayoutMetric: Send + Sync + LayoutMetricClone + fmt::Debug {
-    /// Return the name of the metric
+    /// Return the name of the metric (as shown in the evaluation results)
     fn name(&self) -> &str;
-    /// Compute the total cost for the metric
+    /// Compute the total cost for the metric and an optional message shown alongside it
     fn total_cost(&self, layout: &Layout) -> (f64, Option<String>);
 }
 
@@ -0,0 +1,92 @@
+//! The layout metric [`HomeRowPins`] penalizes configurable characters that are not located
+//! on the home row. The cost to add for each such a character is configurable.
+//!
+//! For each hand, the home row is the (matrix) row of the index finger's home key (see
+//! `Keyboard::home_key`). Characters on thumb keys never count as being on the home row.
+
+use super::LayoutMetric;
+
+use keyboard_layout::{
+    key::{Finger, Hand, HandMap},
+    layout::Layout,
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Characters that should preferably be located on the home row.
+    pub chars: String,
+    /// Cost for each of these characters that is not on the home row.
+    pub cost: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct HomeRowPins {
+    chars: Vec<char>,
+    cost: f64,
+}
+
+impl HomeRowPins {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            chars: params.chars.chars().collect(),
+            cost: params.cost,
+        }
+    }
+}
+
+/// Find the matrix row index of each hand's home row.
+fn home_rows(layout: &Layout) -> HandMap<Option<u8>> {
+    let mut rows: HandMap<Option<u8>> = HandMap::default();
+
+    for hand in [Hand::Left, Hand::Right] {
+        let home_row = layout
+            .keyboard
+            .home_key(&hand, &Finger::Index)
+            .map(|k| k.matrix_position.1);
+
+        rows.set(&hand, home_row);
+    }
+
+    rows
+}
+
+impl LayoutMetric for HomeRowPins {
+    fn name(&self) -> &str {
+        "Home Row Pins"
+    }
+
+    fn total_cost(&self, layout: &Layout) -> (f64, Option<String>) {
+        let home_rows = home_rows(layout);
+
+        let mut off_home_row: Vec<char> = self
+            .chars
+            .iter()
+            .filter(|c| {
+                layout
+                    .get_layerkey_for_symbol(c)
+                    .map(|k| {
+                        k.key.finger == Finger::Thumb
+                            || *home_rows.get(&k.key.hand) != Some(k.key.matrix_position.1)
+                    })
+                    .unwrap_or(true)
+            })
+            .cloned()
+            .collect();
+        off_home_row.sort_unstable();
+
+        let cost = self.cost * off_home_row.len() as f64;
+
+        let message = if !off_home_row.is_empty() {
+            Some(format!(
+                "Off the home row: {}",
+                off_home_row.iter().collect::<String>()
+            ))
+        } else {
+            None
+        };
+
+        (cost, message)
+    }
+}
@@ -0,0 +1,81 @@
+//! The layout metric [`OneHandWords`] computes the fraction of the words of a corpus (by
+//! weight) that are typed entirely with one hand. Such words are slow and awkward as they
+//! provide no opportunity for hand alternation.
+//!
+//! In contrast to the trigram-level hand-alternation metrics, this metric requires the word
+//! structure of the corpus, i.e. a words file as generated alongside the ngram files by the
+//! `ngrams` binary. Only the base keys of the words' symbols are considered (modifiers are
+//! ignored). Words containing symbols that are not part of the layout are skipped.
+
+use super::LayoutMetric;
+
+use crate::ngrams::Words;
+
+use keyboard_layout::layout::Layout;
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// File with word frequencies (lines with weight and word, e.g. `words.txt` generated by
+    /// the `ngrams` binary)
+    pub words_filename: String,
+    /// Words with fewer symbols are not considered
+    pub min_word_length: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct OneHandWords {
+    words: Vec<(Vec<char>, f64)>,
+}
+
+impl OneHandWords {
+    pub fn new(params: &Parameters) -> Self {
+        let words = Words::from_file(&params.words_filename)
+            .unwrap_or_else(|_| panic!("Could not read words file {}", params.words_filename));
+
+        let mut words: Vec<(Vec<char>, f64)> = words
+            .words
+            .into_iter()
+            .map(|(word, weight)| (word.chars().collect::<Vec<char>>(), weight))
+            .filter(|(chars, _)| chars.len() >= params.min_word_length)
+            .collect();
+        // sort for a deterministic order of summation
+        words.sort_by(|(c1, _), (c2, _)| c1.cmp(c2));
+
+        Self { words }
+    }
+}
+
+impl LayoutMetric for OneHandWords {
+    fn name(&self) -> &str {
+        "One-Hand Words"
+    }
+
+    fn total_cost(&self, layout: &Layout) -> (f64, Option<String>) {
+        let mut one_hand_weight = 0.0;
+        let mut found_weight = 0.0;
+
+        self.words.iter().for_each(|(word, weight)| {
+            let hands: Option<Vec<_>> = word
+                .iter()
+                .map(|c| layout.get_layerkey_for_symbol(c).map(|k| k.key.hand))
+                .collect();
+
+            if let Some(hands) = hands {
+                found_weight += weight;
+                if hands.iter().all(|h| *h == hands[0]) {
+                    one_hand_weight += weight;
+                }
+            }
+        });
+
+        let fraction = match found_weight > 0.0 {
+            true => one_hand_weight / found_weight,
+            false => 0.0,
+        };
+        let message = format!("One-handed words: {:.2}%", 100.0 * fraction);
+
+        (fraction, Some(message))
+    }
+}
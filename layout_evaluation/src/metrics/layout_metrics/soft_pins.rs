@@ -0,0 +1,121 @@
+//! The layout metric [`SoftPins`] penalizes configurable characters ("soft pins") that are
+//! not located at the same key as in a reference layout (usually the starting layout of an
+//! optimization). The cost to add for each such a character is configurable.
+//!
+//! In contrast to fixed characters, soft-pinned characters may still be moved by an
+//! optimizer if the gain outweighs the cost.
+
+use super::LayoutMetric;
+
+use keyboard_layout::{key::MatrixPosition, layout::Layout};
+
+use ahash::AHashMap;
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Characters that should preferably stay at their position in the reference layout.
+    pub chars: String,
+    /// Cost for each of these characters that is not at its reference position.
+    pub cost: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct SoftPins {
+    positions: AHashMap<char, (MatrixPosition, u8)>,
+    cost: f64,
+}
+
+impl SoftPins {
+    /// Take the positions of the soft-pinned characters from the `reference` layout.
+    /// Characters that are not contained in the reference layout are ignored.
+    pub fn new(params: &Parameters, reference: &Layout) -> Self {
+        let positions = params
+            .chars
+            .chars()
+            .filter_map(|c| {
+                reference
+                    .get_layerkey_for_symbol(&c)
+                    .map(|k| (c, (k.key.matrix_position, k.layer)))
+            })
+            .collect();
+
+        Self {
+            positions,
+            cost: params.cost,
+        }
+    }
+}
+
+impl LayoutMetric for SoftPins {
+    fn name(&self) -> &str {
+        "Soft Pins"
+    }
+
+    fn total_cost(&self, layout: &Layout) -> (f64, Option<String>) {
+        let mut moved: Vec<char> = self
+            .positions
+            .iter()
+            .filter(|(c, pos)| {
+                layout
+                    .get_layerkey_for_symbol(c)
+                    .map(|k| (k.key.matrix_position, k.layer) != **pos)
+                    .unwrap_or(true)
+            })
+            .map(|(c, _)| *c)
+            .collect();
+        moved.sort_unstable();
+
+        let cost = self.cost * moved.len() as f64;
+
+        let message = if !moved.is_empty() {
+            Some(format!(
+                "Moved soft pins: {}",
+                moved.iter().collect::<String>()
+            ))
+        } else {
+            None
+        };
+
+        (cost, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Parameters, SoftPins};
+    use crate::{
+        metrics::layout_metrics::LayoutMetric,
+        test_utils::{layout_generator, LAYOUT},
+    };
+
+    use keyboard_layout::layout_generator::LayoutGenerator;
+
+    #[test]
+    fn soft_pins_cost_each_moved_character() {
+        let layout_generator = layout_generator();
+        let reference = layout_generator.generate(LAYOUT).unwrap();
+        // characters missing in the reference layout are ignored
+        let params = Parameters {
+            chars: "eq€".to_string(),
+            cost: 2.0,
+        };
+        let metric = SoftPins::new(&params, &reference);
+
+        assert_eq!(metric.total_cost(&reference), (0.0, None));
+
+        let layout = layout_generator
+            .generate("q0a1zw2sbxt3decr4fgvuhj5miyk67onl89p={}([")
+            .unwrap();
+        let (cost, message) = metric.total_cost(&layout);
+        assert_eq!(cost, 2.0);
+        assert_eq!(message.unwrap(), "Moved soft pins: e");
+
+        let layout = layout_generator
+            .generate("e0a1zw2sbxq3dtcr4fgvuhj5miyk67onl89p={}([")
+            .unwrap();
+        let (cost, message) = metric.total_cost(&layout);
+        assert_eq!(cost, 4.0);
+        assert_eq!(message.unwrap(), "Moved soft pins: eq");
+    }
+}
@@ -5,7 +5,10 @@ use ordered_float::OrderedFloat;
 use priority_queue::DoublePriorityQueue;
 use std::{env, fmt};
 
+pub mod finger_reuse_gaps;
+pub mod handswitch_then_same_finger;
 pub mod irregularity;
+pub mod key_repeats;
 pub mod no_handswitch_in_trigram;
 pub mod oxey_alternates;
 pub mod oxey_alternates_sfs;
@@ -19,6 +22,7 @@ pub mod oxey_redirects;
 pub mod secondary_bigrams;
 pub mod trigram_finger_repeats;
 pub mod trigram_rolls;
+pub mod trigram_same_finger;
 
 /// TrigramMetric is a trait for metrics that iterates over weighted trigrams.
 pub trait TrigramMetric: Send + Sync + TrigramMetricClone + fmt::Debug {
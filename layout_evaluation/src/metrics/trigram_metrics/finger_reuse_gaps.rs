@@ -0,0 +1,125 @@
+//! The trigram metric [`FingerReuseGaps`] approximates the time since the finger of a keystroke
+//! was last used. For the last key of each trigram, the gap is the number of keystrokes since
+//! the same finger pressed a (different) key before: 0 if it was the preceding key, 1 if one
+//! keystroke of another finger lies in between. Longer gaps can not be seen in trigrams and
+//! count as no reuse. Each gap gets its own cost, so that closely spaced reuses can be penalized
+//! more than spread out ones.
+//!
+//! As every keystroke is the last key of exactly one trigram, the metric reports the
+//! distribution of the reuse gaps over all keystrokes.
+//!
+//! Thumbs are excluded.
+
+use super::TrigramMetric;
+
+use keyboard_layout::{
+    key::Finger,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Costs of finger reuses by gap (index 0: directly consecutive, index 1: one keystroke in
+    /// between). Gaps without entry cost nothing.
+    pub gap_costs: Vec<f64>,
+    /// Do not consider pressing the same key again as a finger reuse
+    pub exclude_key_repeats: bool,
+}
+
+/// Largest gap that can be observed in trigrams.
+const MAX_GAP: usize = 1;
+
+#[derive(Clone, Debug)]
+pub struct FingerReuseGaps {
+    gap_costs: Vec<f64>,
+    exclude_key_repeats: bool,
+}
+
+impl FingerReuseGaps {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            gap_costs: params.gap_costs.clone(),
+            exclude_key_repeats: params.exclude_key_repeats,
+        }
+    }
+
+    #[inline(always)]
+    fn is_reuse(&self, earlier: &LayerKey, key: &LayerKey) -> bool {
+        earlier.key.hand == key.key.hand
+            && earlier.key.finger == key.key.finger
+            && !(self.exclude_key_repeats && earlier.key.matrix_position == key.key.matrix_position)
+    }
+
+    /// The gap since the finger of the trigram's last key was used before (if visible).
+    #[inline(always)]
+    fn reuse_gap(&self, k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> Option<usize> {
+        if k3.key.finger == Finger::Thumb {
+            return None;
+        }
+
+        [k2, k1]
+            .iter()
+            .position(|earlier| self.is_reuse(earlier, k3))
+    }
+
+    #[inline(always)]
+    fn gap_cost(&self, gap: Option<usize>) -> f64 {
+        gap.and_then(|gap| self.gap_costs.get(gap))
+            .cloned()
+            .unwrap_or(0.0)
+    }
+}
+
+impl TrigramMetric for FingerReuseGaps {
+    fn name(&self) -> &str {
+        "Finger Reuse Gaps"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        k3: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        Some(self.gap_cost(self.reuse_gap(k1, k2, k3)) * weight)
+    }
+
+    fn total_cost(
+        &self,
+        trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| trigrams.iter().map(|(_, w)| w).sum());
+
+        let mut gap_weights = [0.0; MAX_GAP + 1];
+        let mut cost = 0.0;
+        trigrams.iter().for_each(|((k1, k2, k3), weight)| {
+            let gap = self.reuse_gap(k1, k2, k3);
+            if let Some(gap) = gap {
+                gap_weights[gap] += weight;
+            }
+            cost += self.gap_cost(gap) * weight;
+        });
+
+        let reused_weight: f64 = gap_weights.iter().sum();
+        let gap_msgs: Vec<String> = gap_weights
+            .iter()
+            .enumerate()
+            .map(|(gap, weight)| format!("{}: {:.2}%", gap, 100.0 * weight / total_weight))
+            .collect();
+        let message = format!(
+            "Reuse gaps: {}, longer or none: {:.2}%",
+            gap_msgs.join(", "),
+            100.0 * (total_weight - reused_weight) / total_weight,
+        );
+
+        (cost, Some(message))
+    }
+}
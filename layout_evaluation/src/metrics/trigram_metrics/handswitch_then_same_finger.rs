@@ -0,0 +1,96 @@
+//! The trigram metric [`HandSwitchThenSameFinger`] counts trigrams that switch hands between
+//! the first and second symbol and then use the same finger for the second and third symbol
+//! (i.e. a same-finger bigram right after a hand switch). Optionally, the reverse pattern
+//! (a same-finger bigram followed by a hand switch) is counted as well.
+//!
+//! Thumbs are excluded.
+
+use super::TrigramMetric;
+
+use keyboard_layout::{
+    key::Finger,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Also count same-finger bigrams between the first and second symbol that are followed by a hand switch
+    pub include_reverse: bool,
+    /// Do not count same-finger bigrams that repeat the same key
+    pub exclude_key_repeats: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct HandSwitchThenSameFinger {
+    include_reverse: bool,
+    exclude_key_repeats: bool,
+}
+
+impl HandSwitchThenSameFinger {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            include_reverse: params.include_reverse,
+            exclude_key_repeats: params.exclude_key_repeats,
+        }
+    }
+
+    #[inline(always)]
+    fn is_same_finger(&self, k1: &LayerKey, k2: &LayerKey) -> bool {
+        k1.key.hand == k2.key.hand
+            && k1.key.finger == k2.key.finger
+            && k1.key.finger != Finger::Thumb
+            && !(self.exclude_key_repeats && k1.key.matrix_position == k2.key.matrix_position)
+    }
+
+    #[inline(always)]
+    fn is_affected(&self, k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> bool {
+        (k1.key.hand != k2.key.hand && self.is_same_finger(k2, k3))
+            || (self.include_reverse && self.is_same_finger(k1, k2) && k2.key.hand != k3.key.hand)
+    }
+}
+
+impl TrigramMetric for HandSwitchThenSameFinger {
+    fn name(&self) -> &str {
+        "Hand Switch Then Same Finger"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        k3: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        match self.is_affected(k1, k2, k3) {
+            true => Some(weight),
+            false => Some(0.0),
+        }
+    }
+
+    fn total_cost(
+        &self,
+        trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| trigrams.iter().map(|(_, w)| w).sum());
+
+        let cost: f64 = trigrams
+            .iter()
+            .filter(|((k1, k2, k3), _)| self.is_affected(k1, k2, k3))
+            .map(|(_, weight)| weight)
+            .sum();
+
+        let message = format!(
+            "Hand switch then same finger: {:.2}%",
+            100.0 * cost / total_weight
+        );
+
+        (cost, Some(message))
+    }
+}
@@ -0,0 +1,88 @@
+//! The trigram metric [`KeyRepeats`] counts trigrams that press the same key three times in a
+//! row (e.g. "eee"). Such sustained repetitions of a single finger strain the finger even
+//! without any movement, so this metric is independent from the movement-based metrics.
+
+use super::TrigramMetric;
+
+use keyboard_layout::{
+    key::Finger,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Cost of each repetition of the same key
+    pub repeat_cost: f64,
+    /// Do not count repetitions of thumb keys
+    pub exclude_thumbs: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyRepeats {
+    repeat_cost: f64,
+    exclude_thumbs: bool,
+}
+
+impl KeyRepeats {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            repeat_cost: params.repeat_cost,
+            exclude_thumbs: params.exclude_thumbs,
+        }
+    }
+
+    #[inline(always)]
+    fn is_repeat(&self, k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> bool {
+        let pos = k1.key.matrix_position;
+
+        k2.key.matrix_position == pos
+            && k3.key.matrix_position == pos
+            && !(self.exclude_thumbs && k1.key.finger == Finger::Thumb)
+    }
+}
+
+impl TrigramMetric for KeyRepeats {
+    fn name(&self) -> &str {
+        "Key Repeats"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        k3: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        match self.is_repeat(k1, k2, k3) {
+            true => Some(self.repeat_cost * weight),
+            false => Some(0.0),
+        }
+    }
+
+    fn total_cost(
+        &self,
+        trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| trigrams.iter().map(|(_, w)| w).sum());
+
+        let repeat_weight: f64 = trigrams
+            .iter()
+            .filter(|((k1, k2, k3), _)| self.is_repeat(k1, k2, k3))
+            .map(|(_, weight)| weight)
+            .sum();
+
+        let message = format!(
+            "Key repeat load: {:.3}%",
+            100.0 * repeat_weight / total_weight
+        );
+
+        (self.repeat_cost * repeat_weight, Some(message))
+    }
+}
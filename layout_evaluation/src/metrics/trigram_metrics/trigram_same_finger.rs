@@ -0,0 +1,145 @@
+//! The trigram metric [`TrigramSameFinger`] considers trigrams in which at least two of the
+//! three keys are pressed by the same finger, including the first and last key. Trigrams using
+//! the same finger for all three keys are weighted separately (usually much more heavily),
+//! since they are more straining than the sum of their same-finger bigrams. This complements
+//! the bigram metrics, which only ever see one pair of keys at a time.
+//!
+//! Thumbs are excluded.
+
+use super::TrigramMetric;
+
+use keyboard_layout::{
+    key::Finger,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Cost of trigrams using the same finger for all three keys
+    pub all_three_cost: f64,
+    /// Cost of trigrams using the same finger for two consecutive keys only
+    pub adjacent_pair_cost: f64,
+    /// Cost of trigrams using the same finger for the first and last key only
+    pub outer_pair_cost: f64,
+    /// Do not consider keys pressed twice in a row as using the same finger
+    pub exclude_key_repeats: bool,
+}
+
+/// How many keys of a trigram share a finger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SameFinger {
+    None,
+    AdjacentPair,
+    OuterPair,
+    AllThree,
+}
+
+#[derive(Clone, Debug)]
+pub struct TrigramSameFinger {
+    all_three_cost: f64,
+    adjacent_pair_cost: f64,
+    outer_pair_cost: f64,
+    exclude_key_repeats: bool,
+}
+
+impl TrigramSameFinger {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            all_three_cost: params.all_three_cost,
+            adjacent_pair_cost: params.adjacent_pair_cost,
+            outer_pair_cost: params.outer_pair_cost,
+            exclude_key_repeats: params.exclude_key_repeats,
+        }
+    }
+
+    #[inline(always)]
+    fn is_same_finger(&self, k1: &LayerKey, k2: &LayerKey, consecutive: bool) -> bool {
+        k1.key.hand == k2.key.hand
+            && k1.key.finger == k2.key.finger
+            && k1.key.finger != Finger::Thumb
+            && !(consecutive
+                && self.exclude_key_repeats
+                && k1.key.matrix_position == k2.key.matrix_position)
+    }
+
+    #[inline(always)]
+    fn same_finger(&self, k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> SameFinger {
+        let first = self.is_same_finger(k1, k2, true);
+        let second = self.is_same_finger(k2, k3, true);
+
+        if first && second {
+            SameFinger::AllThree
+        } else if first || second {
+            SameFinger::AdjacentPair
+        } else if self.is_same_finger(k1, k3, false) {
+            SameFinger::OuterPair
+        } else {
+            SameFinger::None
+        }
+    }
+
+    #[inline(always)]
+    fn cost(&self, same_finger: SameFinger) -> f64 {
+        match same_finger {
+            SameFinger::None => 0.0,
+            SameFinger::AdjacentPair => self.adjacent_pair_cost,
+            SameFinger::OuterPair => self.outer_pair_cost,
+            SameFinger::AllThree => self.all_three_cost,
+        }
+    }
+}
+
+impl TrigramMetric for TrigramSameFinger {
+    fn name(&self) -> &str {
+        "Trigram Same Finger"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        k3: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        Some(self.cost(self.same_finger(k1, k2, k3)) * weight)
+    }
+
+    fn total_cost(
+        &self,
+        trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| trigrams.iter().map(|(_, w)| w).sum());
+
+        let mut all_three_weight = 0.0;
+        let mut adjacent_pair_weight = 0.0;
+        let mut outer_pair_weight = 0.0;
+        trigrams.iter().for_each(
+            |((k1, k2, k3), weight)| match self.same_finger(k1, k2, k3) {
+                SameFinger::None => {}
+                SameFinger::AdjacentPair => adjacent_pair_weight += weight,
+                SameFinger::OuterPair => outer_pair_weight += weight,
+                SameFinger::AllThree => all_three_weight += weight,
+            },
+        );
+
+        let cost = self.all_three_cost * all_three_weight
+            + self.adjacent_pair_cost * adjacent_pair_weight
+            + self.outer_pair_cost * outer_pair_weight;
+
+        let message = format!(
+            "All three same finger: {:.3}%, adjacent pair: {:.2}%, outer pair: {:.2}%",
+            100.0 * all_three_weight / total_weight,
+            100.0 * adjacent_pair_weight / total_weight,
+            100.0 * outer_pair_weight / total_weight,
+        );
+
+        (cost, Some(message))
+    }
+}
@@ -5,11 +5,16 @@ use priority_queue::DoublePriorityQueue;
 
 use std::{env, fmt};
 
+pub mod bottom_row_load;
 pub mod finger_balance;
 pub mod hand_disbalance;
+pub mod hand_symmetry;
 pub mod key_costs;
 pub mod modifier_usage;
+pub mod pinky_off_home;
 pub mod row_loads;
+pub mod same_hand_modifiers;
+pub mod thumb_load;
 
 /// UnigramMetric is a trait for metrics that iterate over weighted unigrams.
 pub trait UnigramMetric: Send + Sync + UnigramMetricClone + fmt::Debug {
@@ -36,70 +41,81 @@ pub trait UnigramMetric: Send + Sync + UnigramMetricClone + fmt::Debug {
         total_weight: Option<f64>,
         layout: &Layout,
     ) -> (f64, Option<String>) {
-        let show_worst: bool = env::var("SHOW_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(true);
-        let n_worst: usize = env::var("N_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(15);
-
-        let total_weight = total_weight.unwrap_or_else(|| unigrams.iter().map(|(_, w)| w).sum());
-        let cost_iter = unigrams
-            .iter()
-            .enumerate()
-            .filter_map(|(i, (unigram, weight))| {
-                let cost_option = self.individual_cost(unigram, *weight, total_weight, layout);
-
-                cost_option.map(|cost| (i, unigram, cost))
-            });
-
-        let (total_cost, msg) = if show_worst {
-            let (total_cost, worst) = cost_iter.fold(
-                (0.0, DoublePriorityQueue::new()),
-                |(mut total_cost, mut worst), (i, _, cost)| {
-                    total_cost += cost;
-                    worst.push(i, OrderedFloat(cost.abs()));
-                    if worst.len() > n_worst {
-                        worst.pop_min();
-                    }
-
-                    (total_cost, worst)
-                },
-            );
-
-            let mut msgs = Vec::new();
-
-            let worst_msgs: Vec<String> = worst
-                .into_sorted_iter()
-                .rev()
-                .filter(|(_, cost)| cost.into_inner() > 0.0)
-                .map(|(i, cost)| {
-                    let (gram, _) = unigrams[i];
-                    format!(
-                        "{} ({:>5.2}%)",
-                        gram,
-                        100.0 * cost.into_inner() / total_cost,
-                    )
-                })
-                .collect();
-
-            if !worst_msgs.is_empty() {
-                msgs.push(format!("Worst unigrams: {}", worst_msgs.join(", ")))
-            }
-
-            let msg = Some(msgs.join(";  "));
-
-            (total_cost, msg)
-        } else {
-            let total_cost: f64 = cost_iter.map(|(_, _, c)| c).sum();
-
-            (total_cost, None)
-        };
+        sum_individual_costs(self, unigrams, total_weight, layout)
+    }
+}
+
+/// The default [`UnigramMetric::total_cost`]: Sums up the individual costs of the unigrams and
+/// reports the worst unigrams (unless disabled with the `SHOW_WORST` environment variable).
+pub fn sum_individual_costs<M: UnigramMetric + ?Sized>(
+    metric: &M,
+    unigrams: &[(&LayerKey, f64)],
+    total_weight: Option<f64>,
+    layout: &Layout,
+) -> (f64, Option<String>) {
+    let show_worst: bool = env::var("SHOW_WORST")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(true);
+    let n_worst: usize = env::var("N_WORST")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(15);
+
+    let total_weight = total_weight.unwrap_or_else(|| unigrams.iter().map(|(_, w)| w).sum());
+    let cost_iter = unigrams
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (unigram, weight))| {
+            let cost_option = metric.individual_cost(unigram, *weight, total_weight, layout);
+
+            cost_option.map(|cost| (i, unigram, cost))
+        });
+
+    let (total_cost, msg) = if show_worst {
+        let (total_cost, worst) = cost_iter.fold(
+            (0.0, DoublePriorityQueue::new()),
+            |(mut total_cost, mut worst), (i, _, cost)| {
+                total_cost += cost;
+                worst.push(i, OrderedFloat(cost.abs()));
+                if worst.len() > n_worst {
+                    worst.pop_min();
+                }
+
+                (total_cost, worst)
+            },
+        );
+
+        let mut msgs = Vec::new();
+
+        let worst_msgs: Vec<String> = worst
+            .into_sorted_iter()
+            .rev()
+            .filter(|(_, cost)| cost.into_inner() > 0.0)
+            .map(|(i, cost)| {
+                let (gram, _) = unigrams[i];
+                format!(
+                    "{} ({:>5.2}%)",
+                    gram,
+                    100.0 * cost.into_inner() / total_cost,
+                )
+            })
+            .collect();
+
+        if !worst_msgs.is_empty() {
+            msgs.push(format!("Worst unigrams: {}", worst_msgs.join(", ")))
+        }
+
+        let msg = Some(msgs.join(";  "));
 
         (total_cost, msg)
-    }
+    } else {
+        let total_cost: f64 = cost_iter.map(|(_, _, c)| c).sum();
+
+        (total_cost, None)
+    };
+
+    (total_cost, msg)
 }
 
 impl Clone for Box<dyn UnigramMetric> {
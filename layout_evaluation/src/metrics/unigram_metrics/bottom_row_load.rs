@@ -0,0 +1,94 @@
+//! The unigram metric [`BottomRowLoad`] penalizes the usage of keys on the bottom row.
+//!
+//! For each hand, the bottom row is the lowest (matrix) row containing non-thumb keys,
+//! given that it lies below the home row (the row of the index finger's home key, see
+//! `Keyboard::home_key`). Thumb keys are excluded. The resulting cost is the fraction of all
+//! unigrams typed on the bottom row.
+
+use super::UnigramMetric;
+
+use keyboard_layout::{
+    key::{Finger, Hand, HandMap},
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {}
+
+#[derive(Clone, Debug)]
+pub struct BottomRowLoad {}
+
+impl BottomRowLoad {
+    pub fn new(_params: &Parameters) -> Self {
+        Self {}
+    }
+}
+
+/// Find the matrix row index of each hand's bottom row (if there is a row below the home row).
+fn bottom_rows(layout: &Layout) -> HandMap<Option<u8>> {
+    let keyboard = &layout.keyboard;
+    let mut rows: HandMap<Option<u8>> = HandMap::default();
+
+    for hand in [Hand::Left, Hand::Right] {
+        let hand_keys = || {
+            keyboard
+                .keys
+                .iter()
+                .filter(move |k| k.hand == hand && k.finger != Finger::Thumb)
+        };
+
+        let home_row = keyboard
+            .home_key(&hand, &Finger::Index)
+            .map(|k| k.matrix_position.1);
+        let lowest_row = hand_keys().map(|k| k.matrix_position.1).max();
+
+        let bottom_row = match (home_row, lowest_row) {
+            (Some(home_row), Some(lowest_row)) if lowest_row > home_row => Some(lowest_row),
+            _ => None,
+        };
+
+        rows.set(&hand, bottom_row);
+    }
+
+    rows
+}
+
+impl UnigramMetric for BottomRowLoad {
+    fn name(&self) -> &str {
+        "Bottom Row Load"
+    }
+
+    fn total_cost(
+        &self,
+        unigrams: &[(&LayerKey, f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| unigrams.iter().map(|(_, w)| w).sum());
+        let bottom_rows = bottom_rows(layout);
+
+        let mut loads: HandMap<f64> = HandMap::default();
+        unigrams
+            .iter()
+            .filter(|(key, _weight)| key.key.finger != Finger::Thumb)
+            .for_each(|(key, weight)| {
+                let hand = &key.key.hand;
+                if *bottom_rows.get(hand) == Some(key.key.matrix_position.1) {
+                    *loads.get_mut(hand) += *weight;
+                }
+            });
+
+        let load = (loads.get(&Hand::Left) + loads.get(&Hand::Right)) / total_weight;
+
+        let message = format!(
+            "Bottom row load: {:.2}% (left: {:.2}%, right: {:.2}%)",
+            100.0 * load,
+            100.0 * loads.get(&Hand::Left) / total_weight,
+            100.0 * loads.get(&Hand::Right) / total_weight,
+        );
+
+        (load, Some(message))
+    }
+}
@@ -2,6 +2,10 @@
 //! per finger with configurable intended finger loads. The metric costs come from
 //! discrepancies which are computed based on a standard deviation computation.
 //!
+//! Optionally, the load of the most overworked finger (relative to its intended load)
+//! can be added to the cost with a configurable weight. This is a minimax objective
+//! that specifically flattens the single worst finger.
+//!
 //! *Note:* In contrast to ArneBab's version of the metric, thumb keys are excluded
 //! from the discrepancy computation.
 
@@ -18,11 +22,23 @@ use serde::Deserialize;
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
     pub intended_loads: AHashMap<(Hand, Finger), f64>,
+    /// Weight of the deviation from the intended loads (standard deviation).
+    #[serde(default = "default_deviation_weight")]
+    pub deviation_weight: f64,
+    /// Weight of the highest load relative to its intended load (the "worst finger").
+    #[serde(default)]
+    pub worst_finger_weight: f64,
+}
+
+fn default_deviation_weight() -> f64 {
+    1.0
 }
 
 #[derive(Clone, Debug)]
 pub struct FingerBalance {
     intended_loads: AHashMap<(Hand, Finger), f64>,
+    deviation_weight: f64,
+    worst_finger_weight: f64,
 }
 
 impl FingerBalance {
@@ -37,7 +53,11 @@ impl FingerBalance {
         intended_loads.values_mut().for_each(|l| {
             *l /= total_intended;
         });
-        Self { intended_loads }
+        Self {
+            intended_loads,
+            deviation_weight: params.deviation_weight,
+            worst_finger_weight: params.worst_finger_weight,
+        }
     }
 }
 
@@ -68,11 +88,12 @@ impl UnigramMetric for FingerBalance {
         // dbg!(&total_weight);
 
         // A version more similar to ArneBab's solution using the standard deviation
-        let fractions: Vec<f64> = self
+        let fractions: Vec<((Hand, Finger), f64)> = self
             .intended_loads
             .iter()
             .filter(|((_hand, finger), _intended_load)| *finger != Finger::Thumb)
-            .map(|((hand, finger), intended_load)| {
+            .map(|(hand_finger, intended_load)| {
+                let (hand, finger) = hand_finger;
                 let load = finger_loads.get(hand, finger) / total_weight;
                 log::trace!(
                     "Finger: {:>13}, Intended: {:>5.2}, Load: {:>5.2}, Fraction: {:>.4}",
@@ -81,18 +102,20 @@ impl UnigramMetric for FingerBalance {
                     100.0 * load,
                     load / intended_load,
                 );
-                load / intended_load
+                (*hand_finger, load / intended_load)
             })
             .collect();
 
-        let mean: f64 = fractions.iter().sum::<f64>() / fractions.len() as f64;
+        let mean: f64 = fractions.iter().map(|(_, f)| f).sum::<f64>() / fractions.len() as f64;
         let var = fractions
             .iter()
-            .map(|f| (f - mean) * (f - mean))
+            .map(|(_, f)| (f - mean) * (f - mean))
             .sum::<f64>()
             / (fractions.len() - 1) as f64;
 
-        let message = format!(
+        let mut cost = self.deviation_weight * var.sqrt();
+
+        let mut message = format!(
             "Finger loads % (no thumb): {:.1} {:.1} {:.1} {:.1} - {:.1} {:.1} {:.1} {:.1}",
             100.0 * finger_loads.get(&Hand::Left, &Finger::Pinky) / total_weight,
             100.0 * finger_loads.get(&Hand::Left, &Finger::Ring) / total_weight,
@@ -104,7 +127,22 @@ impl UnigramMetric for FingerBalance {
             100.0 * finger_loads.get(&Hand::Right, &Finger::Pinky) / total_weight,
         );
 
-        (var.sqrt(), Some(message))
+        if self.worst_finger_weight != 0.0 {
+            // without any load (or an intended load of zero) the fractions are not finite
+            if let Some(((hand, finger), worst)) = fractions
+                .iter()
+                .filter(|(_, f)| f.is_finite())
+                .max_by(|(_, f1), (_, f2)| f1.partial_cmp(f2).unwrap())
+            {
+                cost += self.worst_finger_weight * worst;
+                message.push_str(&format!(
+                    "; Worst finger: {:?} {:?} ({:.2}x intended load)",
+                    hand, finger, worst
+                ));
+            }
+        }
+
+        (cost, Some(message))
 
         // A version using the total variation distance instead of standard deviation
         // This is a more uniform approach, i.e. half distance on two fingers equals full distance on one finger
@@ -128,3 +166,64 @@ impl UnigramMetric for FingerBalance {
         // (0.5 * diff, None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{FingerBalance, Parameters};
+    use crate::metrics::unigram_metrics::UnigramMetric;
+
+    use keyboard_layout::{
+        config::LayoutConfig,
+        key::{Finger, Hand},
+        keyboard::Keyboard,
+        layout::LayerKey,
+        layout_generator::LayoutGenerator,
+        neo_layout_generator::NeoLayoutGenerator,
+    };
+
+    use std::{str::FromStr, sync::Arc};
+
+    #[test]
+    fn worst_finger_skips_fingers_without_finite_load_fraction() {
+        let cfg =
+            LayoutConfig::from_str(include_str!("../../../../config/keyboard/sval.yml")).unwrap();
+        let keyboard = Arc::new(Keyboard::from_yaml_object(cfg.keyboard));
+        let layout = NeoLayoutGenerator::from_object(cfg.base_layout, keyboard)
+            .generate("q0a1zw2sbxe3dtcr4fgvuhj5miyk67onl89p={}([")
+            .unwrap();
+
+        // the left pinky is not intended to be used at all
+        let fingers = [Finger::Pinky, Finger::Ring, Finger::Middle, Finger::Index];
+        let intended_loads = [Hand::Left, Hand::Right]
+            .iter()
+            .flat_map(|h| fingers.iter().map(move |f| (*h, *f)))
+            .map(|(h, f)| match (h, f) {
+                (Hand::Left, Finger::Pinky) => ((h, f), 0.0),
+                _ => ((h, f), 1.0),
+            })
+            .collect();
+        let metric = FingerBalance::new(&Parameters {
+            intended_loads,
+            deviation_weight: 0.0,
+            worst_finger_weight: 1.0,
+        });
+
+        // without any unigrams, no finger has a finite load fraction
+        let (_, message) = metric.total_cost(&[], None, &layout);
+        assert!(!message.unwrap().contains("Worst finger"));
+
+        let key_of = |hand: Hand, finger: Finger| -> &LayerKey {
+            layout
+                .layerkeys
+                .iter()
+                .find(|k| k.layer == 0 && k.key.hand == hand && k.key.finger == finger)
+                .unwrap()
+        };
+        let unigrams = [
+            (key_of(Hand::Left, Finger::Pinky), 1.0),
+            (key_of(Hand::Left, Finger::Index), 1.0),
+        ];
+        let (_, message) = metric.total_cost(&unigrams, None, &layout);
+        assert!(message.unwrap().contains("Worst finger: Left Index"));
+    }
+}
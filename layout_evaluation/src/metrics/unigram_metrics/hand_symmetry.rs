@@ -0,0 +1,85 @@
+//! The unigram metric [`HandSymmetry`] compares the load of each finger with the load of the
+//! corresponding finger of the other hand (e.g. left pinky vs. right pinky). The resulting cost
+//! is the sum of the absolute load differences of all mirrored finger pairs.
+//!
+//! In contrast to [`super::hand_disbalance::HandDisbalance`], which only compares the total
+//! loads of both hands, this metric also penalizes a balanced layout that loads different
+//! fingers on both hands.
+
+use super::UnigramMetric;
+
+use keyboard_layout::{
+    key::{Finger, Hand, HandFingerMap},
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Also compare the loads of both thumbs
+    #[serde(default)]
+    pub include_thumbs: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct HandSymmetry {
+    include_thumbs: bool,
+}
+
+impl HandSymmetry {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            include_thumbs: params.include_thumbs,
+        }
+    }
+}
+
+impl UnigramMetric for HandSymmetry {
+    fn name(&self) -> &str {
+        "Hand Symmetry"
+    }
+
+    fn total_cost(
+        &self,
+        unigrams: &[(&LayerKey, f64)],
+        _total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let mut finger_loads: HandFingerMap<f64> = HandFingerMap::with_default(0.0);
+        unigrams
+            .iter()
+            .filter(|(key, _weight)| self.include_thumbs || key.key.finger != Finger::Thumb)
+            .for_each(|(key, weight)| {
+                *finger_loads.get_mut(&key.key.hand, &key.key.finger) += *weight;
+            });
+        let total_weight: f64 = finger_loads.iter().sum();
+
+        let mut fingers = vec![Finger::Pinky, Finger::Ring, Finger::Middle, Finger::Index];
+        if self.include_thumbs {
+            fingers.push(Finger::Thumb);
+        }
+
+        let differences: Vec<(Finger, f64)> = fingers
+            .into_iter()
+            .map(|finger| {
+                let left = finger_loads.get(&Hand::Left, &finger);
+                let right = finger_loads.get(&Hand::Right, &finger);
+                (finger, (left - right).abs() / total_weight)
+            })
+            .collect();
+
+        let cost: f64 = differences.iter().map(|(_, d)| d).sum();
+
+        let message = format!(
+            "Mirrored finger load differences %: {}",
+            differences
+                .iter()
+                .map(|(finger, d)| format!("{:?} {:.1}", finger, 100.0 * d))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+
+        (cost, Some(message))
+    }
+}
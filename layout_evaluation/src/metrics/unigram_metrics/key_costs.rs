@@ -1,22 +1,74 @@
 //! The unigram metric [`KeyCost`] multiplies each unigram's weight with the key cost
 //! of the corresponding key (as configured for the [`Keyboard`]) and the associated
 //! layer cost (as configured for the [`Layout`]).
+//!
+//! Accessing a higher layer costs the key costs of the layer's modifiers (e.g. holding AltGr)
+//! plus an optional configurable access cost of the layer itself. If modifiers are split in
+//! the ngram mapping, the modifiers are separate unigrams with their own key costs and the
+//! symbols are mapped to the base layer, so that the per-layer access costs do not apply.
+//! The metric reports which fraction of its cost is caused by accessing higher layers.
+//!
+//! Wide keys (e.g. a 2u thumb key) are easier to hit. With a positive `width_factor`, the key
+//! cost is divided by the key's width (in units of a standard key) to the power of the factor,
+//! e.g. halved for a 2u key with a factor of one. Since this scales the configured key cost,
+//! wide keys that are far to reach still cost more than close ones.
 
-use super::UnigramMetric;
+use super::{sum_individual_costs, UnigramMetric};
 
-use keyboard_layout::layout::{LayerKey, Layout};
+use keyboard_layout::layout::{LayerKey, LayerModifierType, Layout};
 
 use serde::Deserialize;
 
 #[derive(Clone, Deserialize, Debug)]
-pub struct Parameters {}
+pub struct Parameters {
+    /// Additional cost of accessing each layer (by layer index, starting with the base layer),
+    /// added to the key costs of the layer's modifiers. Layers without entry cost nothing extra.
+    #[serde(default)]
+    pub layer_costs: Vec<f64>,
+    /// Exponent of the key width by which the key costs are divided (zero ignores the widths)
+    #[serde(default)]
+    pub width_factor: f64,
+}
 
 #[derive(Clone, Debug)]
-pub struct KeyCost {}
+pub struct KeyCost {
+    layer_costs: Vec<f64>,
+    width_factor: f64,
+}
 
 impl KeyCost {
-    pub fn new(_params: &Parameters) -> Self {
-        Self {}
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            layer_costs: params.layer_costs.clone(),
+            width_factor: params.width_factor,
+        }
+    }
+
+    /// The cost of the key itself, taking its width into account.
+    #[inline(always)]
+    pub fn key_cost(&self, key: &LayerKey) -> f64 {
+        if self.width_factor == 0.0 {
+            return key.key.cost;
+        }
+
+        key.key.cost / key.key.width.powf(self.width_factor)
+    }
+
+    /// The cost of accessing the key's layer (its modifiers and the layer's own cost).
+    fn layer_access_cost(&self, key: &LayerKey, layout: &Layout) -> f64 {
+        let modifier_cost: f64 = key
+            .modifiers
+            .layerkey_indices()
+            .iter()
+            .map(|i| layout.get_layerkey(i).key.cost)
+            .sum();
+        let layer_cost = self
+            .layer_costs
+            .get(key.layer as usize)
+            .cloned()
+            .unwrap_or(0.0);
+
+        modifier_cost + layer_cost
     }
 }
 
@@ -33,13 +85,7 @@ impl UnigramMetric for KeyCost {
         total_weight: f64,
         layout: &Layout,
     ) -> Option<f64> {
-        let modifier_cost: f64 = key
-            .modifiers
-            .layerkey_indices()
-            .iter()
-            .map(|i| layout.get_layerkey(i).key.cost)
-            .sum();
-        let cost = key.key.cost + modifier_cost;
+        let cost = self.key_cost(key) + self.layer_access_cost(key, layout);
 
         // log the top scorers (with weight > 1%)
         if weight > 0.01 * total_weight {
@@ -49,4 +95,36 @@ impl UnigramMetric for KeyCost {
 
         Some(weight * cost)
     }
+
+    fn total_cost(
+        &self,
+        unigrams: &[(&LayerKey, f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let (cost, message) = sum_individual_costs(self, unigrams, total_weight, layout);
+
+        // modifiers split off from higher-layer symbols count completely as layer access
+        let layer_access_cost: f64 = unigrams
+            .iter()
+            .map(|(key, weight)| match key.is_modifier {
+                LayerModifierType::None => weight * self.layer_access_cost(key, layout),
+                _ => weight * (self.key_cost(key) + self.layer_access_cost(key, layout)),
+            })
+            .sum();
+        if layer_access_cost <= 0.0 || cost <= 0.0 {
+            return (cost, message);
+        }
+
+        let layer_access_msg = format!(
+            "Layer access: {:.1}% of the key costs",
+            100.0 * layer_access_cost / cost
+        );
+        let message = match message {
+            Some(message) if !message.is_empty() => format!("{};  {}", layer_access_msg, message),
+            _ => layer_access_msg,
+        };
+
+        (cost, Some(message))
+    }
 }
@@ -0,0 +1,121 @@
+//! The unigram metric [`PinkyOffHome`] counts how often the pinkies leave their home keys.
+//!
+//! The home key of each pinky is the explicitly configured home key or, by default, the pinky
+//! key closest to the finger's resting position on the keyboard. Presses on other pinky keys are weighted depending on whether they lie
+//! above or below the home row and/or in an outer column. The resulting cost is the
+//! weighted fraction of all unigrams.
+
+use super::UnigramMetric;
+
+use keyboard_layout::{
+    key::{Finger, Hand, HandMap, MatrixPosition},
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Weight of pinky presses above the home row
+    pub top_row_weight: f64,
+    /// Weight of pinky presses below the home row
+    pub bottom_row_weight: f64,
+    /// Additional weight of pinky presses in a column outside of the home key's column
+    pub outer_column_weight: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct PinkyOffHome {
+    top_row_weight: f64,
+    bottom_row_weight: f64,
+    outer_column_weight: f64,
+}
+
+impl PinkyOffHome {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            top_row_weight: params.top_row_weight,
+            bottom_row_weight: params.bottom_row_weight,
+            outer_column_weight: params.outer_column_weight,
+        }
+    }
+
+    fn off_home_weight(&self, hand: &Hand, pos: &MatrixPosition, home: &MatrixPosition) -> f64 {
+        let mut weight = 0.0;
+
+        if pos.1 < home.1 {
+            weight += self.top_row_weight;
+        } else if pos.1 > home.1 {
+            weight += self.bottom_row_weight;
+        }
+
+        let outer = match hand {
+            Hand::Left => pos.0 < home.0,
+            Hand::Right => pos.0 > home.0,
+        };
+        if outer {
+            weight += self.outer_column_weight;
+        }
+
+        weight
+    }
+}
+
+/// Find the matrix position of each pinky's home key.
+fn pinky_home_positions(layout: &Layout) -> HandMap<Option<MatrixPosition>> {
+    let mut homes: HandMap<Option<MatrixPosition>> = HandMap::default();
+
+    for hand in [Hand::Left, Hand::Right] {
+        let home = layout
+            .keyboard
+            .home_key(&hand, &Finger::Pinky)
+            .map(|k| k.matrix_position);
+
+        homes.set(&hand, home);
+    }
+
+    homes
+}
+
+impl UnigramMetric for PinkyOffHome {
+    fn name(&self) -> &str {
+        "Pinky Off-Home"
+    }
+
+    fn total_cost(
+        &self,
+        unigrams: &[(&LayerKey, f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| unigrams.iter().map(|(_, w)| w).sum());
+        let homes = pinky_home_positions(layout);
+
+        let mut loads: HandMap<f64> = HandMap::default();
+        let mut costs: HandMap<f64> = HandMap::default();
+        unigrams
+            .iter()
+            .filter(|(key, _weight)| key.key.finger == Finger::Pinky)
+            .for_each(|(key, weight)| {
+                let hand = &key.key.hand;
+                if let Some(home) = homes.get(hand) {
+                    let off_home_weight =
+                        self.off_home_weight(hand, &key.key.matrix_position, home);
+                    if off_home_weight > 0.0 {
+                        *loads.get_mut(hand) += *weight;
+                        *costs.get_mut(hand) += off_home_weight * *weight;
+                    }
+                }
+            });
+
+        let message = format!(
+            "Pinky off-home loads %: left {:.2}, right {:.2}",
+            100.0 * loads.get(&Hand::Left) / total_weight,
+            100.0 * loads.get(&Hand::Right) / total_weight,
+        );
+
+        let cost = (costs.get(&Hand::Left) + costs.get(&Hand::Right)) / total_weight;
+
+        (cost, Some(message))
+    }
+}
@@ -0,0 +1,98 @@
+//! The unigram metric [`SameHandModifiers`] penalizes symbols of higher layers whose modifiers
+//! (e.g. shift) need to be pressed with the same hand as the symbol's key. Typing a symbol
+//! with a modifier of the other hand is usually faster and less straining.
+//!
+//! Which modifier is used for a symbol is configured per hand in the layout configuration
+//! (usually the one of the other hand). The metric charges `same_hand_cost` for each
+//! modifier on the symbol's hand and reports the fraction of modified symbols ("same-hand
+//! modifier load") that require at least one such modifier.
+//!
+//! If modifiers are split in the ngram mapping, the symbols are mapped to the base layer and
+//! this metric does not apply. The resulting bigrams of modifier and key are then covered by
+//! the bigram metrics.
+
+use super::{sum_individual_costs, UnigramMetric};
+
+use keyboard_layout::layout::{LayerKey, Layout};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Cost of each modifier that is pressed with the same hand as the symbol's key
+    pub same_hand_cost: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct SameHandModifiers {
+    same_hand_cost: f64,
+}
+
+impl SameHandModifiers {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            same_hand_cost: params.same_hand_cost,
+        }
+    }
+
+    /// The number of the key's modifiers that are on the same hand as the key itself.
+    fn n_same_hand_modifiers(key: &LayerKey, layout: &Layout) -> usize {
+        key.modifiers
+            .layerkey_indices()
+            .iter()
+            .filter(|i| layout.get_layerkey(i).key.hand == key.key.hand)
+            .count()
+    }
+}
+
+impl UnigramMetric for SameHandModifiers {
+    fn name(&self) -> &str {
+        "Same-Hand Modifiers"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        key: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        layout: &Layout,
+    ) -> Option<f64> {
+        let n_same_hand = Self::n_same_hand_modifiers(key, layout);
+
+        Some(weight * self.same_hand_cost * n_same_hand as f64)
+    }
+
+    fn total_cost(
+        &self,
+        unigrams: &[(&LayerKey, f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let (cost, message) = sum_individual_costs(self, unigrams, total_weight, layout);
+
+        let (modified_weight, same_hand_weight) = unigrams
+            .iter()
+            .filter(|(key, _)| !key.modifiers.layerkey_indices().is_empty())
+            .fold((0.0, 0.0), |(modified, same_hand), (key, weight)| {
+                match Self::n_same_hand_modifiers(key, layout) {
+                    0 => (modified + weight, same_hand),
+                    _ => (modified + weight, same_hand + weight),
+                }
+            });
+        if modified_weight <= 0.0 {
+            return (cost, message);
+        }
+
+        let load_msg = format!(
+            "Same-hand modifier load: {:.1}% of the modified symbols",
+            100.0 * same_hand_weight / modified_weight
+        );
+        let message = match message {
+            Some(message) if !message.is_empty() => format!("{};  {}", load_msg, message),
+            _ => load_msg,
+        };
+
+        (cost, Some(message))
+    }
+}
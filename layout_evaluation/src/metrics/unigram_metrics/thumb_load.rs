@@ -0,0 +1,79 @@
+//! The unigram metric [`ThumbLoad`] penalizes thumb loads outside of a target range.
+//!
+//! The thumb load is the fraction of all unigrams (including modifier keystrokes) typed with
+//! either thumb. Thumbs can take more load than other fingers, but putting too much (or too
+//! little) on them is still suboptimal, in particular on boards with thumb clusters. The
+//! resulting cost is the distance of the thumb load to the target range (zero within it).
+
+use super::UnigramMetric;
+
+use keyboard_layout::{
+    key::{Finger, Hand, HandMap},
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Lower bound of the target range of the thumb load (fraction of all keystrokes)
+    pub min_load: f64,
+    /// Upper bound of the target range of the thumb load (fraction of all keystrokes)
+    pub max_load: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct ThumbLoad {
+    min_load: f64,
+    max_load: f64,
+}
+
+impl ThumbLoad {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            min_load: params.min_load,
+            max_load: params.max_load,
+        }
+    }
+}
+
+impl UnigramMetric for ThumbLoad {
+    fn name(&self) -> &str {
+        "Thumb Load"
+    }
+
+    fn total_cost(
+        &self,
+        unigrams: &[(&LayerKey, f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| unigrams.iter().map(|(_, w)| w).sum());
+
+        let mut loads: HandMap<f64> = HandMap::default();
+        unigrams
+            .iter()
+            .filter(|(key, _weight)| key.key.finger == Finger::Thumb)
+            .for_each(|(key, weight)| *loads.get_mut(&key.key.hand) += *weight);
+
+        let load = (loads.get(&Hand::Left) + loads.get(&Hand::Right)) / total_weight;
+        let cost = if load < self.min_load {
+            self.min_load - load
+        } else if load > self.max_load {
+            load - self.max_load
+        } else {
+            0.0
+        };
+
+        let message = format!(
+            "Thumb load: {:.2}% (left: {:.2}%, right: {:.2}%), target: {:.0}%-{:.0}%",
+            100.0 * load,
+            100.0 * loads.get(&Hand::Left) / total_weight,
+            100.0 * loads.get(&Hand::Right) / total_weight,
+            100.0 * self.min_load,
+            100.0 * self.max_load,
+        );
+
+        (cost, Some(message))
+    }
+}
@@ -0,0 +1,99 @@
+//! The `multi_corpus` module provides the [`MultiCorpusEvaluator`], which evaluates a layout on
+//! several named corpora (e.g. one per language) at once, e.g. for comparing the performance of
+//! a layout across languages. In contrast to a corpus blend (see
+//! [`Evaluator::with_corpus_blend`]), the results are reported separately for each corpus.
+
+use crate::{evaluation::Evaluator, ngram_mapper::NgramMapper, results::EvaluationResult};
+
+use keyboard_layout::layout::Layout;
+
+use std::collections::BTreeMap;
+
+/// Evaluates layouts with the same metrics on several named corpora.
+#[derive(Clone, Debug)]
+pub struct MultiCorpusEvaluator {
+    evaluator: Evaluator,
+    corpora: Vec<(String, Evaluator)>,
+}
+
+impl MultiCorpusEvaluator {
+    /// Generate a [`MultiCorpusEvaluator`] without any corpus that evaluates the metrics (and
+    /// uses the remaining configuration) of the given `evaluator`. Its own ngrams are ignored.
+    pub fn new(evaluator: Evaluator) -> Self {
+        Self {
+            evaluator,
+            corpora: Vec::new(),
+        }
+    }
+
+    /// Add a corpus with the given name (see [`MultiCorpusEvaluator::add_corpus`]).
+    pub fn with_corpus(mut self, name: &str, ngram_mapper: Box<dyn NgramMapper>) -> Self {
+        self.add_corpus(name, ngram_mapper);
+        self
+    }
+
+    /// Add a corpus with the given name. A previously added corpus of the same name is replaced.
+    pub fn add_corpus(&mut self, name: &str, ngram_mapper: Box<dyn NgramMapper>) {
+        let mut evaluator = self.evaluator.clone();
+        evaluator.set_ngram_mapper(ngram_mapper);
+
+        match self.corpora.iter_mut().find(|(n, _)| n == name) {
+            Some((_, e)) => *e = evaluator,
+            None => self.corpora.push((name.to_string(), evaluator)),
+        }
+    }
+
+    /// The names of the corpora (in the order of their addition).
+    pub fn corpus_names(&self) -> Vec<&str> {
+        self.corpora.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Evaluate the layout on each corpus. The layout (and thus the mapping of its symbols to
+    /// keys) is shared by all evaluations.
+    pub fn evaluate_layout(&self, layout: &Layout) -> BTreeMap<String, EvaluationResult> {
+        self.corpora
+            .iter()
+            .map(|(name, evaluator)| (name.clone(), evaluator.evaluate_layout(layout)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultiCorpusEvaluator;
+    use crate::test_utils::{eval_params, evaluator, layout_generator, ngram_mapper, LAYOUT};
+
+    use keyboard_layout::layout_generator::LayoutGenerator;
+
+    const ENGLISH: &str = "The quick brown fox jumps over the lazy dog. Pack my box with five \
+        dozen liquor jugs! How vexingly quick daft zebras jump.";
+    const GERMAN: &str = "Franz jagt im komplett verwahrlosten Taxi quer durch Bayern. Zwölf \
+        Boxkämpfer jagen Viktor quer über den großen Sylter Deich.";
+
+    #[test]
+    fn results_equal_those_of_separate_evaluators() {
+        let layout = layout_generator().generate(LAYOUT).unwrap();
+
+        let eval_params = eval_params();
+        let mapper = |text: &str| Box::new(ngram_mapper(text, &eval_params));
+        let evaluator = |text: &str| evaluator(text, &eval_params);
+
+        let multi_corpus = MultiCorpusEvaluator::new(evaluator(ENGLISH))
+            .with_corpus("german", mapper(ENGLISH))
+            .with_corpus("english", mapper(ENGLISH))
+            .with_corpus("german", mapper(GERMAN));
+        assert_eq!(multi_corpus.corpus_names(), vec!["german", "english"]);
+
+        let results = multi_corpus.evaluate_layout(&layout);
+        assert_eq!(results.len(), 2);
+        for (name, text) in [("english", ENGLISH), ("german", GERMAN)] {
+            assert_eq!(
+                results[name].total_cost().to_bits(),
+                evaluator(text)
+                    .evaluate_layout(&layout)
+                    .total_cost()
+                    .to_bits()
+            );
+        }
+    }
+}
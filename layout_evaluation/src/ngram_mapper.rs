@@ -33,6 +33,7 @@
 
 pub mod bigram_mapper;
 pub mod common;
+pub mod normalization;
 pub mod trigram_mapper;
 pub mod unigram_mapper;
 
@@ -62,6 +63,9 @@ pub struct MappedBigrams<'s> {
     pub weight_not_found: f64,
     /// Total weight (frequencies) of unigrams that can be generated by the layout
     pub weight_found: f64,
+    /// Part of the found weight stemming from bigrams across word boundaries (only if the
+    /// mapper distinguishes them)
+    pub weight_cross_word: Option<f64>,
 }
 
 /// Trigrams in terms of a [`Layout`]'s [`LayerKey`]s and statistics about ngrams that
@@ -75,11 +79,124 @@ pub struct MappedTrigrams<'s> {
     pub weight_found: f64,
 }
 
+/// Blend the mapped ngrams of two corpora. The weights of each corpus are taken relative to its
+/// total weight (found and not found), combined with `ratio` (0 for only the first corpus, 1 for
+/// only the second), and scaled to the correspondingly blended total weight. Ngrams present in
+/// both corpora are merged. The result is ordered by the ngrams' `key`.
+fn blend_grams<T: Copy, K: Ord>(
+    (grams, total): (Vec<(T, f64)>, f64),
+    (other_grams, other_total): (Vec<(T, f64)>, f64),
+    ratio: f64,
+    key: impl Fn(&T) -> K,
+) -> (Vec<(T, f64)>, f64, f64) {
+    let blended_total = (1.0 - ratio) * total + ratio * other_total;
+    let factor = |corpus_ratio: f64, corpus_total: f64| match corpus_total > 0.0 {
+        true => corpus_ratio * blended_total / corpus_total,
+        false => 0.0,
+    };
+    let (factor, other_factor) = (factor(1.0 - ratio, total), factor(ratio, other_total));
+
+    let mut blended: Vec<(T, f64)> = grams
+        .into_iter()
+        .map(|(gram, w)| (gram, factor * w))
+        .chain(
+            other_grams
+                .into_iter()
+                .map(|(gram, w)| (gram, other_factor * w)),
+        )
+        .collect();
+    blended.sort_by_key(|(gram, _)| key(gram));
+
+    let mut merged: Vec<(T, f64)> = Vec::with_capacity(blended.len());
+    for (gram, w) in blended {
+        match merged.last_mut() {
+            Some((last, last_w)) if key(last) == key(&gram) => *last_w += w,
+            _ => merged.push((gram, w)),
+        }
+    }
+
+    (merged, factor, other_factor)
+}
+
+/// The address of a [`LayerKey`] (for comparing mapped ngrams of the same layout).
+fn address(k: &LayerKey) -> usize {
+    k as *const LayerKey as usize
+}
+
+impl<'s> MappedUnigrams<'s> {
+    /// Blend the unigrams with those of another corpus (see [`blend_grams`]).
+    pub fn blend(self, other: Self, ratio: f64) -> Self {
+        let (grams, f1, f2) = blend_grams(
+            (self.grams, self.weight_found + self.weight_not_found),
+            (other.grams, other.weight_found + other.weight_not_found),
+            ratio,
+            |k| address(k),
+        );
+
+        Self {
+            grams,
+            weight_not_found: f1 * self.weight_not_found + f2 * other.weight_not_found,
+            weight_found: f1 * self.weight_found + f2 * other.weight_found,
+        }
+    }
+}
+
+impl<'s> MappedBigrams<'s> {
+    /// Blend the bigrams with those of another corpus (see [`blend_grams`]).
+    pub fn blend(self, other: Self, ratio: f64) -> Self {
+        let (grams, f1, f2) = blend_grams(
+            (self.grams, self.weight_found + self.weight_not_found),
+            (other.grams, other.weight_found + other.weight_not_found),
+            ratio,
+            |(k1, k2)| (address(k1), address(k2)),
+        );
+
+        let weight_cross_word = match (self.weight_cross_word, other.weight_cross_word) {
+            (None, None) => None,
+            (w1, w2) => Some(f1 * w1.unwrap_or(0.0) + f2 * w2.unwrap_or(0.0)),
+        };
+
+        Self {
+            grams,
+            weight_not_found: f1 * self.weight_not_found + f2 * other.weight_not_found,
+            weight_found: f1 * self.weight_found + f2 * other.weight_found,
+            weight_cross_word,
+        }
+    }
+}
+
+impl<'s> MappedTrigrams<'s> {
+    /// Blend the trigrams with those of another corpus (see [`blend_grams`]).
+    pub fn blend(self, other: Self, ratio: f64) -> Self {
+        let (grams, f1, f2) = blend_grams(
+            (self.grams, self.weight_found + self.weight_not_found),
+            (other.grams, other.weight_found + other.weight_not_found),
+            ratio,
+            |(k1, k2, k3)| (address(k1), address(k2), address(k3)),
+        );
+
+        Self {
+            grams,
+            weight_not_found: f1 * self.weight_not_found + f2 * other.weight_not_found,
+            weight_found: f1 * self.weight_found + f2 * other.weight_found,
+        }
+    }
+}
+
 /// Provides ngrams in terms of a [`Layout`]'s [`LayerKey`]s.
 pub trait NgramMapper: Send + Sync + NgramMapperClone + fmt::Debug {
     fn map_unigrams<'s>(&self, layout: &'s Layout) -> MappedUnigrams<'s>;
     fn map_bigrams<'s>(&self, layout: &'s Layout) -> MappedBigrams<'s>;
     fn map_trigrams<'s>(&self, layout: &'s Layout) -> MappedTrigrams<'s>;
+
+    /// The symbols of the unigrams that the layout can not generate with their share of the
+    /// total unigram weight, sorted by weight (descending). All ngrams containing one of them
+    /// are not found.
+    fn unmapped_symbols(&self, layout: &Layout) -> Vec<(char, f64)>;
+
+    /// Generate a copy of the mapper providing only the most common ngrams of each order
+    /// up to a given combined fraction of their total weight.
+    fn tops(&self, fraction: f64) -> Box<dyn NgramMapper>;
 }
 
 // in order to implement clone for Box<dyn LayoutMetric>, the following trick is necessary
@@ -89,7 +89,9 @@ impl OnDemandBigramMapper {
         let bigram_keys = if self.split_modifiers.enabled && layout.has_hold_layers() {
             self.process_hold_modifiers(bigram_keys_vec, layout)
         } else {
-            bigram_keys_vec.into_iter().collect()
+            let mut bigram_keys = fixed_hasher_map(bigram_keys_vec.len());
+            bigram_keys.extend(bigram_keys_vec);
+            bigram_keys
         };
 
         // bigram_keys
@@ -136,7 +138,7 @@ impl OnDemandBigramMapper {
     /// Each bigram of higher-layer symbols will transform into a series of bigrams with permutations of
     /// the involved base-keys and modifers. However, the base-key will always be after its modifier.
     fn process_hold_modifiers(&self, bigrams: BigramIndicesVec, layout: &Layout) -> BigramIndices {
-        let mut bigram_w_map = AHashMap::with_capacity(bigrams.len() / 3);
+        let mut bigram_w_map = fixed_hasher_map(bigrams.len() / 3);
 
         bigrams.into_iter().for_each(|((k1, k2), w)| {
             let (base1, mods1) = layout.resolve_modifiers(&k1);
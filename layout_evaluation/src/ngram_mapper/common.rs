@@ -1,9 +1,17 @@
 /// The `common` module provides utility functions for resolving modifiers in ngrams.
 use keyboard_layout::layout::LayerKeyIndex;
 
-use ahash::AHashMap;
+use ahash::{AHashMap, RandomState};
 use std::{cmp::Eq, hash::Hash, slice};
 
+/// An empty [`AHashMap`] whose hasher uses fixed seeds. In contrast to maps with a random
+/// hasher state, such maps iterate in the same order whenever they are filled in the same order.
+/// Metric costs are thus accumulated in the same order (and are bit-identical) for each
+/// evaluation of a layout.
+pub fn fixed_hasher_map<K, V>(capacity: usize) -> AHashMap<K, V> {
+    AHashMap::with_capacity_and_hasher(capacity, RandomState::with_seeds(1, 2, 3, 4))
+}
+
 /// Iterator over unigrams of the base-layer key and each modifier.
 #[derive(Clone, Debug)]
 pub struct TakeOneLayerKey<'a> {
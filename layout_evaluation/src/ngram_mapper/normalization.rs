@@ -0,0 +1,97 @@
+//! This module provides an optional Unicode normalization of corpus texts, char-based ngrams,
+//! and layout symbols.
+//!
+//! Equivalent symbols may be encoded differently, e.g. a precomposed "é" versus an "e"
+//! followed by a combining acute accent. Without normalization, ngrams of a corpus using one
+//! form silently fail to map to a layout using the other. With [`UnicodeNormalization::Nfc`],
+//! corpus texts are composed before their ngrams are counted, the symbols of char-based ngrams
+//! are normalized individually, and normalized symbols are matched to layout symbols by their
+//! normalized form as well.
+//!
+//! Only normalizations of a single symbol into a single symbol can be applied to ngrams and
+//! layouts. Composing a symbol with a following combining mark requires normalizing the
+//! corpus text before its ngrams are counted.
+
+use ahash::AHashMap;
+use serde::Deserialize;
+use std::{borrow::Cow, hash::Hash};
+use unicode_normalization::{is_nfc, UnicodeNormalization as _};
+
+use super::common::NgramMap;
+
+/// The Unicode normalization form applied to corpus texts, ngrams, and layout symbols.
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum UnicodeNormalization {
+    /// Leave all symbols unchanged.
+    None,
+    /// Canonical composition (NFC).
+    Nfc,
+}
+
+impl Default for UnicodeNormalization {
+    fn default() -> Self {
+        UnicodeNormalization::None
+    }
+}
+
+impl UnicodeNormalization {
+    /// Normalize a corpus text (before counting its ngrams).
+    pub fn normalize_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            UnicodeNormalization::Nfc if !is_nfc(text) => Cow::Owned(text.nfc().collect()),
+            _ => Cow::Borrowed(text),
+        }
+    }
+
+    /// Normalize a stream of corpus chars (like [`UnicodeNormalization::normalize_text`],
+    /// but without requiring the complete text).
+    pub fn normalize_chars<'a, I>(&self, chars: I) -> Box<dyn Iterator<Item = char> + 'a>
+    where
+        I: Iterator<Item = char> + 'a,
+    {
+        match self {
+            UnicodeNormalization::None => Box::new(chars),
+            UnicodeNormalization::Nfc => Box::new(chars.nfc()),
+        }
+    }
+
+    /// Normalize a single symbol. Symbols whose normalized form consists of multiple chars
+    /// are returned unchanged.
+    pub fn normalize_char(&self, c: char) -> char {
+        match self {
+            UnicodeNormalization::None => c,
+            UnicodeNormalization::Nfc => {
+                let mut normalized = std::iter::once(c).nfc();
+                match (normalized.next(), normalized.next()) {
+                    (Some(n), None) => n,
+                    _ => c,
+                }
+            }
+        }
+    }
+
+    /// Normalize the symbols of char-based ngrams with the given function (usually based on
+    /// [`UnicodeNormalization::normalize_char`]), merging the weights of ngrams that become
+    /// identical. Returns the normalized ngrams and the number of ngrams that were changed.
+    pub fn normalize_grams<T, F>(
+        grams: &AHashMap<T, f64>,
+        normalize: F,
+    ) -> (AHashMap<T, f64>, usize)
+    where
+        T: Copy + Eq + Hash,
+        F: Fn(&T) -> T,
+    {
+        let mut nr_changed = 0;
+        let mut normalized = AHashMap::with_capacity(grams.len());
+        grams.iter().for_each(|(gram, weight)| {
+            let normalized_gram = normalize(gram);
+            if normalized_gram != *gram {
+                nr_changed += 1;
+            }
+            normalized.insert_or_add_weight(normalized_gram, *weight);
+        });
+
+        (normalized, nr_changed)
+    }
+}
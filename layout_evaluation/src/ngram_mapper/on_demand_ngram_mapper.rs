@@ -1,15 +1,34 @@
 //! This module provides an implementation of the [`NgramMapper`] trait.
+//!
+//! Besides the regular char-based ngrams, the [`OnDemandNgramMapper`] can hold separate
+//! "shifted" ngrams (see [`OnDemandNgramMapper::with_shifted_ngrams`]). Their symbols denote
+//! base-layer symbols that were typed with shift held. For each layout, they are translated
+//! into the symbols on the shift layer (layer 1) of the corresponding keys and then mapped
+//! like any other higher-layer symbol: The shift key is resolved as a modifier of the base key
+//! (if `split_modifiers` is enabled), so shift presses enter the unigram, bigram, and trigram
+//! data just like those of regular uppercase letters and are costed by the same metrics.
+//! Symbols whose key has no shift layer count as "not found".
 
 use super::bigram_mapper::OnDemandBigramMapper;
+use super::normalization::UnicodeNormalization;
 use super::trigram_mapper::OnDemandTrigramMapper;
 use super::unigram_mapper::OnDemandUnigramMapper;
-use super::{MappedBigrams, MappedTrigrams, MappedUnigrams, NgramMapper};
+use super::{
+    common::{fixed_hasher_map, NgramMap},
+    MappedBigrams, MappedTrigrams, MappedUnigrams, NgramMapper,
+};
 
-use crate::ngrams::{Bigrams, Trigrams, Unigrams};
+use crate::ngrams::{ngrams_from_chars, Bigrams, ReaderChars, Trigrams, Unigrams};
 
+use ahash::AHashMap;
 use keyboard_layout::layout::Layout;
 
+use anyhow::Result;
 use serde::Deserialize;
+use std::{
+    io::BufRead,
+    sync::{Arc, Mutex},
+};
 
 /// Configuration parameters for the modifier splitting process.
 #[derive(Clone, Deserialize, Debug)]
@@ -20,6 +39,26 @@ pub struct SplitModifiersConfig {
     pub same_key_mod_factor: f64,
 }
 
+/// Configuration parameters for weighting bigrams across word boundaries (see
+/// [`crate::ngrams::is_cross_word_bigram`]) differently from those within words.
+#[derive(Clone, Deserialize, Debug)]
+pub struct CrossWordBigramsConfig {
+    /// Whether to distinguish cross-word bigrams from intra-word bigrams.
+    pub enabled: bool,
+    /// Multiply the weight of cross-word bigrams with this factor (usually below one to
+    /// down-weight them).
+    pub factor: f64,
+}
+
+impl Default for CrossWordBigramsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            factor: 1.0,
+        }
+    }
+}
+
 /// Configuration parameters for the [`OnDemandNgramMapper`].
 #[derive(Clone, Deserialize, Debug)]
 pub struct NgramMapperConfig {
@@ -27,46 +66,501 @@ pub struct NgramMapperConfig {
     pub split_modifiers: SplitModifiersConfig,
     /// Exclude ngrams that contain a line break, followed by a non-line-break character
     pub exclude_line_breaks: bool,
+    /// Unicode normalization applied to corpus texts, ngrams, and layout symbols before comparing them
+    #[serde(default)]
+    pub unicode_normalization: UnicodeNormalization,
+    /// Weighting of bigrams across word boundaries
+    #[serde(default)]
+    pub cross_word_bigrams: CrossWordBigramsConfig,
 }
 
 /// Implements the [`NgramMapper`] trait for generating ngrams in terms of [`LayerKey`]s for a given [`Layout`].
+///
+/// The ngram data can be updated in place using [`OnDemandNgramMapper::add_ngrams`] or
+/// [`OnDemandNgramMapper::add_text`]. These require exclusive (`&mut`) access, so a mapper shared
+/// between threads (e.g. inside an [`Evaluator`](crate::evaluation::Evaluator)) can not be
+/// updated while it is in use. Instead, update a clone and swap it in using
+/// [`Evaluator::set_ngram_mapper`](crate::evaluation::Evaluator::set_ngram_mapper).
 #[derive(Clone, Debug)]
 pub struct OnDemandNgramMapper {
     unigrams: Unigrams,
     bigrams: Bigrams,
     trigrams: Trigrams,
+    shifted_unigrams: Unigrams,
+    shifted_bigrams: Bigrams,
+    shifted_trigrams: Trigrams,
     unigram_mapper: OnDemandUnigramMapper,
     bigram_mapper: OnDemandBigramMapper,
     trigram_mapper: OnDemandTrigramMapper,
     config: NgramMapperConfig,
+    nr_normalized_ngrams: usize,
+    cross_word_split: Option<Arc<CrossWordSplit>>,
+    translation_cache: TranslationCache,
+}
+
+/// The bigrams split into intra-word and cross-word bigrams (see
+/// [`Bigrams::split_cross_word`]). Only computed if [`CrossWordBigramsConfig::enabled`].
+#[derive(Debug)]
+struct CrossWordSplit {
+    intra_word: Bigrams,
+    cross_word: Bigrams,
+}
+
+impl CrossWordSplit {
+    fn new(config: &CrossWordBigramsConfig, bigrams: &Bigrams) -> Option<Self> {
+        config.enabled.then(|| {
+            let (intra_word, cross_word) = bigrams.split_cross_word();
+            Self {
+                intra_word,
+                cross_word,
+            }
+        })
+    }
+}
+
+/// The ngrams translated to the symbols of a layout (see [`layout_symbol_translation`]).
+#[derive(Debug)]
+struct TranslatedNgrams {
+    translation: AHashMap<char, char>,
+    unigrams: Unigrams,
+    bigrams: Bigrams,
+    trigrams: Trigrams,
+    cross_word_split: Option<CrossWordSplit>,
+}
+
+impl TranslatedNgrams {
+    /// The layout symbol that generates a (normalized) ngram symbol.
+    fn symbol(translated: Option<&Self>, c: &char) -> char {
+        translated
+            .and_then(|t| t.translation.get(c))
+            .copied()
+            .unwrap_or(*c)
+    }
+}
+
+/// Cache of the translated ngrams for the symbol set of the most recently mapped layout
+/// (`None` if the ngrams need no translation). As layouts generated during an optimization
+/// only permute the same symbols, the translation is computed only once. Clones of the
+/// mapper start with an empty cache, as their ngrams may differ.
+#[derive(Default, Debug)]
+struct TranslationCache(Mutex<Option<CachedTranslation>>);
+
+/// The sorted symbols of a layout and the ngrams translated to them.
+type CachedTranslation = (Vec<char>, Option<Arc<TranslatedNgrams>>);
+
+impl Clone for TranslationCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
 }
 
 impl OnDemandNgramMapper {
     /// Generate a [`OnDemandNgramMapper`] with given char-based ngrams.
+    ///
+    /// Ngram orders that are not used by any metric may be passed as empty ngrams
+    /// (e.g. `Trigrams::default()`) to save on loading time and memory.
     pub fn with_ngrams(
         unigrams: Unigrams,
         bigrams: Bigrams,
         trigrams: Trigrams,
         config: NgramMapperConfig,
     ) -> Self {
+        let (unigrams, bigrams, trigrams, nr_normalized_ngrams) =
+            normalize_ngrams(config.unicode_normalization, unigrams, bigrams, trigrams);
+        if nr_normalized_ngrams > 0 {
+            log::info!(
+                "Unicode normalization changed the symbols of {} ngrams",
+                nr_normalized_ngrams
+            );
+        }
+
+        let cross_word_split = CrossWordSplit::new(&config.cross_word_bigrams, &bigrams);
         Self {
             unigrams,
             bigrams,
             trigrams,
+            shifted_unigrams: Unigrams::default(),
+            shifted_bigrams: Bigrams::default(),
+            shifted_trigrams: Trigrams::default(),
             unigram_mapper: OnDemandUnigramMapper::new(config.split_modifiers.clone()),
             bigram_mapper: OnDemandBigramMapper::new(config.split_modifiers.clone()),
             trigram_mapper: OnDemandTrigramMapper::new(config.split_modifiers.clone()),
+            cross_word_split: cross_word_split.map(Arc::new),
             config,
+            nr_normalized_ngrams,
+            translation_cache: TranslationCache::default(),
+        }
+    }
+
+    /// Generate a [`OnDemandNgramMapper`] with the ngrams of a corpus text provided by a reader.
+    ///
+    /// The text is streamed (see [`ngrams_from_reader`](crate::ngrams::ngrams_from_reader))
+    /// instead of being loaded as a whole. The resulting ngrams are identical to those of the
+    /// complete text, but corpora larger than the available memory can be used.
+    pub fn with_corpus_reader<R: BufRead>(reader: R, config: NgramMapperConfig) -> Result<Self> {
+        let mut chars = ReaderChars::new(reader);
+        let (unigrams, bigrams, trigrams) =
+            ngrams_from_chars(config.unicode_normalization.normalize_chars(&mut chars));
+        chars.finish()?;
+
+        Ok(Self::with_ngrams(unigrams, bigrams, trigrams, config))
+    }
+
+    /// Generate a [`OnDemandNgramMapper`] holding a weighted mixture of the ngrams of several
+    /// corpora (e.g. in different languages), using the configuration of the first corpus.
+    /// Returns `None` if the weights do not sum up to a positive value.
+    ///
+    /// The mixture is merged in advance, so evaluating it costs the same as evaluating a single
+    /// corpus. For each ngram order, the (regular and shifted) ngrams of each corpus are scaled
+    /// to a combined weight equal to the corpus's share of the total weight before they are
+    /// added up. The weights thus give the shares of the corpora independently of their sizes.
+    pub fn mixture(corpora: &[(OnDemandNgramMapper, f64)]) -> Option<Self> {
+        let total_weight: f64 = corpora.iter().map(|(_, weight)| weight).sum();
+        let (first, _) = corpora.first()?;
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        let mut mixture = Self::with_ngrams(
+            Unigrams::default(),
+            Bigrams::default(),
+            Trigrams::default(),
+            first.config.clone(),
+        );
+        for (mapper, weight) in corpora.iter().filter(|(_, weight)| *weight > 0.0) {
+            let share = weight / total_weight;
+            add_scaled_grams(
+                &mut mixture.unigrams.grams,
+                &mut mixture.shifted_unigrams.grams,
+                &mapper.unigrams.grams,
+                &mapper.shifted_unigrams.grams,
+                share,
+            );
+            add_scaled_grams(
+                &mut mixture.bigrams.grams,
+                &mut mixture.shifted_bigrams.grams,
+                &mapper.bigrams.grams,
+                &mapper.shifted_bigrams.grams,
+                share,
+            );
+            add_scaled_grams(
+                &mut mixture.trigrams.grams,
+                &mut mixture.shifted_trigrams.grams,
+                &mapper.trigrams.grams,
+                &mapper.shifted_trigrams.grams,
+                share,
+            );
+            mixture.nr_normalized_ngrams += mapper.nr_normalized_ngrams;
         }
+        mixture.update_cross_word_split();
+
+        Some(mixture)
+    }
+
+    /// Number of char-based ngrams whose symbols were changed by the Unicode normalization
+    /// (see [`NgramMapperConfig::unicode_normalization`]).
+    pub fn nr_normalized_ngrams(&self) -> usize {
+        self.nr_normalized_ngrams
+    }
+
+    /// Add separate ngrams of base-layer symbols that were typed with shift held.
+    pub fn with_shifted_ngrams(
+        mut self,
+        unigrams: Unigrams,
+        bigrams: Bigrams,
+        trigrams: Trigrams,
+    ) -> Self {
+        self.set_shifted_ngrams(unigrams, bigrams, trigrams);
+        self
+    }
+
+    /// Set separate ngrams of base-layer symbols that were typed with shift held.
+    pub fn set_shifted_ngrams(&mut self, unigrams: Unigrams, bigrams: Bigrams, trigrams: Trigrams) {
+        let (unigrams, bigrams, trigrams, nr_normalized) = normalize_ngrams(
+            self.config.unicode_normalization,
+            unigrams,
+            bigrams,
+            trigrams,
+        );
+        self.nr_normalized_ngrams += nr_normalized;
+        self.translation_cache = TranslationCache::default();
+        self.shifted_unigrams = unigrams;
+        self.shifted_bigrams = bigrams;
+        self.shifted_trigrams = trigrams;
+    }
+
+    /// Estimated memory consumption (in bytes) of the char-based ngrams held by the mapper.
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.unigrams.estimated_memory_bytes()
+            + self.bigrams.estimated_memory_bytes()
+            + self.trigrams.estimated_memory_bytes()
+            + self.shifted_unigrams.estimated_memory_bytes()
+            + self.shifted_bigrams.estimated_memory_bytes()
+            + self.shifted_trigrams.estimated_memory_bytes()
+            + self.cross_word_split.as_ref().map_or(0, |split| {
+                split.intra_word.estimated_memory_bytes()
+                    + split.cross_word.estimated_memory_bytes()
+            })
+    }
+
+    /// Fold additional char-based ngrams into the existing ones, updating their weights in place.
+    ///
+    /// This only touches the additional ngrams and is therefore much cheaper than rebuilding the
+    /// mapper from the combined corpus. No preprocessing (e.g. `tops` or `increase_common`) is
+    /// applied to the additional data.
+    pub fn add_ngrams(&mut self, unigrams: &Unigrams, bigrams: &Bigrams, trigrams: &Trigrams) {
+        self.translation_cache = TranslationCache::default();
+        if self.config.unicode_normalization != UnicodeNormalization::None {
+            let (unigrams, bigrams, trigrams, nr_normalized) = normalize_ngrams(
+                self.config.unicode_normalization,
+                unigrams.clone(),
+                bigrams.clone(),
+                trigrams.clone(),
+            );
+            self.nr_normalized_ngrams += nr_normalized;
+            self.unigrams.merge(&unigrams);
+            self.bigrams.merge(&bigrams);
+            self.trigrams.merge(&trigrams);
+        } else {
+            self.unigrams.merge(unigrams);
+            self.bigrams.merge(bigrams);
+            self.trigrams.merge(trigrams);
+        }
+        self.update_cross_word_split();
+    }
+
+    /// Recompute the cross-word split after the (regular) bigrams changed.
+    fn update_cross_word_split(&mut self) {
+        self.cross_word_split =
+            CrossWordSplit::new(&self.config.cross_word_bigrams, &self.bigrams).map(Arc::new);
+    }
+
+    /// Fold the ngrams of an additional corpus text into the existing ones.
+    ///
+    /// Ngrams spanning the boundary between previously added text and `text` are not counted.
+    pub fn add_text(&mut self, text: &str) -> Result<()> {
+        let text = self.config.unicode_normalization.normalize_text(text);
+        let text = text.as_ref();
+        self.add_ngrams(
+            &Unigrams::from_text(text)?,
+            &Bigrams::from_text(text)?,
+            &Trigrams::from_text(text)?,
+        );
+
+        Ok(())
+    }
+
+    /// The ngrams translated to the symbols of the layout, if any of its symbols differ from
+    /// their normalized form (see [`NgramMapperConfig::unicode_normalization`]).
+    fn translated_ngrams(&self, layout: &Layout) -> Option<Arc<TranslatedNgrams>> {
+        if self.config.unicode_normalization == UnicodeNormalization::None {
+            return None;
+        }
+
+        let mut symbols: Vec<char> = layout.layerkeys.iter().map(|k| k.symbol).collect();
+        symbols.sort_unstable();
+
+        let mut cache = self.translation_cache.0.lock().unwrap();
+        if let Some((cached_symbols, translated)) = cache.as_ref() {
+            if *cached_symbols == symbols {
+                return translated.clone();
+            }
+        }
+
+        let translated = layout_symbol_translation(self.config.unicode_normalization, layout).map(
+            |translation| {
+                let tr = |c: &char| translation.get(c).copied().unwrap_or(*c);
+                let bigrams = Bigrams {
+                    grams: translate_grams(&self.bigrams.grams, |(c1, c2)| (tr(c1), tr(c2))),
+                };
+                Arc::new(TranslatedNgrams {
+                    unigrams: Unigrams {
+                        grams: translate_grams(&self.unigrams.grams, tr),
+                    },
+                    cross_word_split: CrossWordSplit::new(
+                        &self.config.cross_word_bigrams,
+                        &bigrams,
+                    ),
+                    bigrams,
+                    trigrams: Trigrams {
+                        grams: translate_grams(&self.trigrams.grams, |(c1, c2, c3)| {
+                            (tr(c1), tr(c2), tr(c3))
+                        }),
+                    },
+                    translation,
+                })
+            },
+        );
+        *cache = Some((symbols, translated.clone()));
+
+        translated
     }
 }
 
+/// Normalize the symbols of char-based ngrams. Also returns the number of changed ngrams.
+fn normalize_ngrams(
+    normalization: UnicodeNormalization,
+    unigrams: Unigrams,
+    bigrams: Bigrams,
+    trigrams: Trigrams,
+) -> (Unigrams, Bigrams, Trigrams, usize) {
+    if normalization == UnicodeNormalization::None {
+        return (unigrams, bigrams, trigrams, 0);
+    }
+
+    let n = |c: &char| normalization.normalize_char(*c);
+    let (unigram_grams, nr_unigrams) = UnicodeNormalization::normalize_grams(&unigrams.grams, n);
+    let (bigram_grams, nr_bigrams) =
+        UnicodeNormalization::normalize_grams(&bigrams.grams, |(c1, c2)| (n(c1), n(c2)));
+    let (trigram_grams, nr_trigrams) =
+        UnicodeNormalization::normalize_grams(&trigrams.grams, |(c1, c2, c3)| {
+            (n(c1), n(c2), n(c3))
+        });
+
+    (
+        Unigrams {
+            grams: unigram_grams,
+        },
+        Bigrams {
+            grams: bigram_grams,
+        },
+        Trigrams {
+            grams: trigram_grams,
+        },
+        nr_unigrams + nr_bigrams + nr_trigrams,
+    )
+}
+
+/// Add the regular and shifted ngrams of one corpus (of the same order) to those of a mixture,
+/// scaled such that their combined weight equals `share`.
+fn add_scaled_grams<T>(
+    mixture: &mut AHashMap<T, f64>,
+    shifted_mixture: &mut AHashMap<T, f64>,
+    grams: &AHashMap<T, f64>,
+    shifted_grams: &AHashMap<T, f64>,
+    share: f64,
+) where
+    T: Copy + Eq + std::hash::Hash,
+{
+    let total_weight: f64 = grams.values().chain(shifted_grams.values()).sum();
+    if total_weight <= 0.0 {
+        return;
+    }
+
+    let factor = share / total_weight;
+    grams
+        .iter()
+        .for_each(|(gram, weight)| mixture.insert_or_add_weight(*gram, factor * weight));
+    shifted_grams
+        .iter()
+        .for_each(|(gram, weight)| shifted_mixture.insert_or_add_weight(*gram, factor * weight));
+}
+
+/// Map normalized symbols to the (differently encoded) symbols of the layout that generate
+/// them. `None` if no normalization is configured or all layout symbols are normalized.
+fn layout_symbol_translation(
+    normalization: UnicodeNormalization,
+    layout: &Layout,
+) -> Option<AHashMap<char, char>> {
+    if normalization == UnicodeNormalization::None {
+        return None;
+    }
+
+    let translation: AHashMap<char, char> = layout
+        .layerkeys
+        .iter()
+        .filter_map(|k| {
+            let normalized = normalization.normalize_char(k.symbol);
+            (normalized != k.symbol && layout.get_layerkey_for_symbol(&normalized).is_none())
+                .then(|| (normalized, k.symbol))
+        })
+        .collect();
+
+    (!translation.is_empty()).then(|| translation)
+}
+
+/// Translate the symbols of char-based ngrams with the given mapping (see
+/// [`layout_symbol_translation`]).
+fn translate_grams<T, F>(grams: &AHashMap<T, f64>, translate: F) -> AHashMap<T, f64>
+where
+    T: Copy + Eq + std::hash::Hash,
+    F: Fn(&T) -> T,
+{
+    let mut translated = fixed_hasher_map(grams.len());
+    grams
+        .iter()
+        .for_each(|(gram, weight)| translated.insert_or_add_weight(translate(gram), *weight));
+
+    translated
+}
+
+/// The symbol on the shift layer (layer 1) of the key generating the given symbol.
+fn shifted_symbol(c: &char, layout: &Layout) -> Option<char> {
+    let idx = layout.get_layerkey_index_for_symbol(c)?;
+    let shifted_idx = layout.get_layerkey_index_on_layer(&idx, 1)?;
+    Some(layout.get_layerkey(&shifted_idx).symbol)
+}
+
+/// Translate shifted char-based ngrams into regular ones using the given symbol mapping.
+/// Returns the translated ngrams and the weight of those that could not be translated.
+fn shift_ngrams<T, F>(grams: &AHashMap<T, f64>, shift: F) -> (AHashMap<T, f64>, f64)
+where
+    T: Copy + Eq + std::hash::Hash,
+    F: Fn(&T) -> Option<T>,
+{
+    let mut not_found_weight = 0.0;
+    let mut shifted = fixed_hasher_map(grams.len());
+    grams.iter().for_each(|(gram, weight)| match shift(gram) {
+        Some(shifted_gram) => shifted.insert_or_add_weight(shifted_gram, *weight),
+        None => not_found_weight += *weight,
+    });
+
+    (shifted, not_found_weight)
+}
+
 impl NgramMapper for OnDemandNgramMapper {
+    fn tops(&self, fraction: f64) -> Box<dyn NgramMapper> {
+        let mut mapper = self.clone();
+        mapper.unigrams = self.unigrams.tops(fraction);
+        mapper.bigrams = self.bigrams.tops(fraction);
+        mapper.trigrams = self.trigrams.tops(fraction);
+        mapper.update_cross_word_split();
+        if !self.shifted_unigrams.grams.is_empty() {
+            mapper.shifted_unigrams = self.shifted_unigrams.tops(fraction);
+        }
+        if !self.shifted_bigrams.grams.is_empty() {
+            mapper.shifted_bigrams = self.shifted_bigrams.tops(fraction);
+        }
+        if !self.shifted_trigrams.grams.is_empty() {
+            mapper.shifted_trigrams = self.shifted_trigrams.tops(fraction);
+        }
+
+        Box::new(mapper)
+    }
+
     fn map_unigrams<'s>(&self, layout: &'s Layout) -> MappedUnigrams<'s> {
+        let translated = self.translated_ngrams(layout);
+        let tr = |c: &char| TranslatedNgrams::symbol(translated.as_deref(), c);
+        let unigrams = translated.as_ref().map_or(&self.unigrams, |t| &t.unigrams);
+
         // map char-based unigrams to LayerKeyIndex
-        let (key_indices, weight_not_found) =
-            self.unigram_mapper.layerkey_indices(&self.unigrams, layout);
-        let weight_found = self.unigrams.total_weight() - weight_not_found;
+        let (mut key_indices, mut weight_not_found) =
+            self.unigram_mapper.layerkey_indices(unigrams, layout);
+        let mut total_weight = self.unigrams.total_weight();
+        if !self.shifted_unigrams.grams.is_empty() {
+            let (shifted, shift_not_found) = shift_ngrams(&self.shifted_unigrams.grams, |c| {
+                shifted_symbol(&tr(c), layout)
+            });
+            let (shifted_indices, not_found) = self
+                .unigram_mapper
+                .layerkey_indices(&Unigrams { grams: shifted }, layout);
+            shifted_indices
+                .into_iter()
+                .for_each(|(k, w)| key_indices.insert_or_add_weight(k, w));
+            weight_not_found += shift_not_found + not_found;
+            total_weight += self.shifted_unigrams.total_weight();
+        }
+        let weight_found = total_weight - weight_not_found;
         // map LayerKeyIndex to &LayerKey
         let grams = OnDemandUnigramMapper::get_layerkeys(&key_indices, layout);
 
@@ -77,14 +571,89 @@ impl NgramMapper for OnDemandNgramMapper {
         }
     }
 
+    fn unmapped_symbols(&self, layout: &Layout) -> Vec<(char, f64)> {
+        let translated = self.translated_ngrams(layout);
+        let tr = |c: &char| TranslatedNgrams::symbol(translated.as_deref(), c);
+        let total_weight = self.unigrams.total_weight();
+
+        let mut symbols: Vec<(char, f64)> = self
+            .unigrams
+            .grams
+            .iter()
+            .filter(|(c, _)| layout.get_layerkey_index_for_symbol(&tr(c)).is_none())
+            .map(|(c, w)| (*c, w / total_weight))
+            .collect();
+        symbols.sort_by(|(c1, w1), (c2, w2)| w2.partial_cmp(w1).unwrap().then(c1.cmp(c2)));
+
+        symbols
+    }
+
     fn map_bigrams<'s>(&self, layout: &'s Layout) -> MappedBigrams<'s> {
+        let translated = self.translated_ngrams(layout);
+        let tr = |c: &char| TranslatedNgrams::symbol(translated.as_deref(), c);
+        let bigrams = translated.as_ref().map_or(&self.bigrams, |t| &t.bigrams);
+        let cross_word_split = match translated.as_deref() {
+            Some(t) => t.cross_word_split.as_ref(),
+            None => self.cross_word_split.as_deref(),
+        };
+
         // map char-based bigrams to LayerKeyIndex
-        let (key_indices, weight_not_found) = self.bigram_mapper.layerkey_indices(
-            &self.bigrams,
-            layout,
-            self.config.exclude_line_breaks,
-        );
-        let weight_found = self.bigrams.total_weight() - weight_not_found;
+        let (mut key_indices, mut weight_not_found, mut total_weight, weight_cross_word) =
+            match cross_word_split {
+                Some(split) => {
+                    let factor = self.config.cross_word_bigrams.factor;
+                    let (mut key_indices, intra_not_found) = self.bigram_mapper.layerkey_indices(
+                        &split.intra_word,
+                        layout,
+                        self.config.exclude_line_breaks,
+                    );
+                    let (cross_indices, cross_not_found) = self.bigram_mapper.layerkey_indices(
+                        &split.cross_word,
+                        layout,
+                        self.config.exclude_line_breaks,
+                    );
+                    cross_indices
+                        .into_iter()
+                        .for_each(|(k, w)| key_indices.insert_or_add_weight(k, factor * w));
+                    let cross_weight = factor * split.cross_word.total_weight();
+                    let cross_not_found = factor * cross_not_found;
+
+                    (
+                        key_indices,
+                        intra_not_found + cross_not_found,
+                        split.intra_word.total_weight() + cross_weight,
+                        Some(cross_weight - cross_not_found),
+                    )
+                }
+                None => {
+                    let (key_indices, not_found) = self.bigram_mapper.layerkey_indices(
+                        bigrams,
+                        layout,
+                        self.config.exclude_line_breaks,
+                    );
+                    (key_indices, not_found, self.bigrams.total_weight(), None)
+                }
+            };
+        if !self.shifted_bigrams.grams.is_empty() {
+            let (shifted, shift_not_found) =
+                shift_ngrams(&self.shifted_bigrams.grams, |(c1, c2)| {
+                    Some((
+                        shifted_symbol(&tr(c1), layout)?,
+                        shifted_symbol(&tr(c2), layout)?,
+                    ))
+                });
+            let (shifted_indices, not_found) = self.bigram_mapper.layerkey_indices(
+                &Bigrams { grams: shifted },
+                layout,
+                self.config.exclude_line_breaks,
+            );
+            shifted_indices
+                .into_iter()
+                .for_each(|(k, w)| key_indices.insert_or_add_weight(k, w));
+            weight_not_found += shift_not_found + not_found;
+            total_weight += self.shifted_bigrams.total_weight();
+        }
+        let weight_found = total_weight - weight_not_found;
         // map LayerKeyIndex to &LayerKey
         let grams = OnDemandBigramMapper::get_filtered_layerkeys(&key_indices, layout);
 
@@ -92,17 +661,41 @@ impl NgramMapper for OnDemandNgramMapper {
             grams,
             weight_not_found,
             weight_found,
+            weight_cross_word,
         }
     }
 
     fn map_trigrams<'s>(&self, layout: &'s Layout) -> MappedTrigrams<'s> {
+        let translated = self.translated_ngrams(layout);
+        let tr = |c: &char| TranslatedNgrams::symbol(translated.as_deref(), c);
+        let trigrams = translated.as_ref().map_or(&self.trigrams, |t| &t.trigrams);
+
         // map char-based trigrams to LayerKeyIndex
-        let (key_indices, weight_not_found) = self.trigram_mapper.layerkey_indices(
-            &self.trigrams,
-            layout,
-            self.config.exclude_line_breaks,
-        );
-        let weight_found = self.trigrams.total_weight() - weight_not_found;
+        let (mut key_indices, mut weight_not_found) =
+            self.trigram_mapper
+                .layerkey_indices(trigrams, layout, self.config.exclude_line_breaks);
+        let mut total_weight = self.trigrams.total_weight();
+        if !self.shifted_trigrams.grams.is_empty() {
+            let (shifted, shift_not_found) =
+                shift_ngrams(&self.shifted_trigrams.grams, |(c1, c2, c3)| {
+                    Some((
+                        shifted_symbol(&tr(c1), layout)?,
+                        shifted_symbol(&tr(c2), layout)?,
+                        shifted_symbol(&tr(c3), layout)?,
+                    ))
+                });
+            let (shifted_indices, not_found) = self.trigram_mapper.layerkey_indices(
+                &Trigrams { grams: shifted },
+                layout,
+                self.config.exclude_line_breaks,
+            );
+            shifted_indices
+                .into_iter()
+                .for_each(|(k, w)| key_indices.insert_or_add_weight(k, w));
+            weight_not_found += shift_not_found + not_found;
+            total_weight += self.shifted_trigrams.total_weight();
+        }
+        let weight_found = total_weight - weight_not_found;
         // map LayerKeyIndex to &LayerKey
         let grams = OnDemandTrigramMapper::get_filtered_layerkeys(&key_indices, layout);
 
@@ -113,3 +706,70 @@ impl NgramMapper for OnDemandNgramMapper {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::UnicodeNormalization;
+    use crate::{
+        ngram_mapper::NgramMapper,
+        test_utils::{eval_params, layout_generator, ngram_mapper, LAYOUT},
+    };
+
+    use keyboard_layout::{
+        config::LayoutConfig, keyboard::Keyboard, layout_generator::LayoutGenerator,
+        neo_layout_generator::NeoLayoutGenerator,
+    };
+
+    use std::{str::FromStr, sync::Arc};
+
+    #[test]
+    fn layout_symbols_are_matched_by_their_normalized_form() {
+        let mut params = eval_params();
+        params.ngram_mapper.unicode_normalization = UnicodeNormalization::Nfc;
+        // the corpus uses the precomposed "Å", one layout the (non-NFC) angstrom sign
+        let mapper = ngram_mapper("\u{c5}ab\u{c5}", &params);
+        let cfg =
+            include_str!("../../../config/keyboard/sval.yml").replace(r#"["q"]"#, "[\"\u{212b}\"]");
+        let cfg = LayoutConfig::from_str(&cfg).unwrap();
+        let angstrom_layout = NeoLayoutGenerator::from_object(
+            cfg.base_layout,
+            Arc::new(Keyboard::from_yaml_object(cfg.keyboard)),
+        )
+        .generate(&LAYOUT.replace('q', "\u{212b}"))
+        .unwrap();
+        let layout = layout_generator().generate(LAYOUT).unwrap();
+
+        for _ in 0..2 {
+            let unigrams = mapper.map_unigrams(&angstrom_layout);
+            assert_eq!(unigrams.weight_not_found, 0.0);
+            assert!(unigrams.grams.iter().any(|(k, _)| k.symbol == '\u{212b}'));
+            assert!(mapper.unmapped_symbols(&angstrom_layout).is_empty());
+
+            let bigrams = mapper.map_bigrams(&angstrom_layout);
+            assert_eq!(bigrams.weight_not_found, 0.0);
+
+            // a layout with other symbols does not reuse the cached translation
+            let unigrams = mapper.map_unigrams(&layout);
+            assert_eq!(unigrams.weight_not_found, 2.0);
+            assert_eq!(mapper.unmapped_symbols(&layout)[0].0, '\u{c5}');
+        }
+    }
+
+    #[test]
+    fn cross_word_split_follows_added_ngrams() {
+        let mut params = eval_params();
+        params.ngram_mapper.cross_word_bigrams.enabled = true;
+        params.ngram_mapper.cross_word_bigrams.factor = 0.5;
+        let mut mapper = ngram_mapper("ab", &params);
+        let layout = layout_generator().generate(LAYOUT).unwrap();
+        assert_eq!(mapper.map_bigrams(&layout).weight_cross_word, Some(0.0));
+
+        mapper.add_text("a b").unwrap();
+        let bigrams = mapper.map_bigrams(&layout);
+        assert_eq!(bigrams.weight_cross_word, Some(1.0));
+        assert_eq!(bigrams.weight_found + bigrams.weight_not_found, 2.0);
+
+        let tops = mapper.tops(0.5).map_bigrams(&layout);
+        assert!(tops.weight_found + tops.weight_not_found < 2.0);
+    }
+}
@@ -96,7 +96,9 @@ impl OnDemandTrigramMapper {
         let trigram_keys = if self.split_modifiers.enabled && layout.has_hold_layers() {
             self.process_hold_modifiers(trigram_keys_vec, layout)
         } else {
-            trigram_keys_vec.into_iter().collect()
+            let mut trigram_keys = fixed_hasher_map(trigram_keys_vec.len());
+            trigram_keys.extend(trigram_keys_vec);
+            trigram_keys
         };
 
         (trigram_keys, not_found_weight)
@@ -145,7 +147,7 @@ impl OnDemandTrigramMapper {
         trigrams: TrigramIndicesVec,
         layout: &Layout,
     ) -> TrigramIndices {
-        let mut trigram_w_map = AHashMap::with_capacity(trigrams.len() / 3);
+        let mut trigram_w_map = fixed_hasher_map(trigrams.len() / 3);
         trigrams.into_iter().for_each(|((k1, k2, k3), w)| {
             let (base1, mods1) = layout.resolve_modifiers(&k1);
             let (base2, mods2) = layout.resolve_modifiers(&k2);
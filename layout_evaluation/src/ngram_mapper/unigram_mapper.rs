@@ -63,7 +63,9 @@ impl OnDemandUnigramMapper {
         let unigram_keys = if self.split_modifiers.enabled && layout.has_hold_layers() {
             Self::process_hold_modifiers(unigram_keys_vec, layout)
         } else {
-            unigram_keys_vec.into_iter().collect()
+            let mut unigram_keys = fixed_hasher_map(unigram_keys_vec.len());
+            unigram_keys.extend(unigram_keys_vec);
+            unigram_keys
         };
 
         (unigram_keys, not_found_weight)
@@ -86,7 +88,7 @@ impl OnDemandUnigramMapper {
     /// Each unigram of a higher-layer symbol will transform into a unigram with the base-layer key and one
     /// for each modifier involved in accessing the higher layer.
     fn process_hold_modifiers(unigrams: UnigramIndicesVec, layout: &Layout) -> UnigramIndices {
-        let mut idx_w_map = AHashMap::with_capacity(unigrams.len() / 3);
+        let mut idx_w_map = fixed_hasher_map(unigrams.len() / 3);
         unigrams.into_iter().for_each(|(k, w)| {
             let (base, mods) = layout.resolve_modifiers(&k);
 
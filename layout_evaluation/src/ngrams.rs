@@ -6,18 +6,106 @@ use crate::ngram_mapper::common::NgramMap;
 
 use ahash::AHashMap;
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     fs::{self, create_dir_all, File},
-    io::{BufWriter, Write},
+    hash::Hash,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    mem,
     path::Path,
 };
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum NgramsError {
+    #[error("Invalid ngram frequencies: Could not parse line '{0}': {1}")]
+    InvalidLine(String, String),
+    #[error("Unable to create directory '{0}'")]
+    CreateDirectory(String, #[source] io::Error),
+    #[error("Unable to create file '{0}'")]
+    CreateFile(String, #[source] io::Error),
+    #[error("'{0}' is not a binary ngram file")]
+    NotBinaryNgrams(String),
+    #[error("Binary ngram file '{0}' has format version {1}, expected {2}")]
+    BinaryVersion(String, u32, u32),
+    #[error("Binary ngram file '{0}' contains {1}-grams, expected {2}-grams")]
+    BinaryNgramLength(String, u8, u8),
+}
+
+/// Bytes identifying binary ngram files.
+const BINARY_MAGIC: [u8; 4] = *b"KLOG";
+
+/// Version of the binary ngram format. Increase it whenever the format changes.
+pub const BINARY_FORMAT_VERSION: u32 = 1;
+
+/// Header preceding the ngrams in binary ngram files.
+#[derive(Serialize, Deserialize, Debug)]
+struct BinaryHeader {
+    magic: [u8; 4],
+    version: u32,
+    ngram_len: u8,
+}
+
+/// Write the ngrams with the given length (number of chars) to a binary file.
+fn save_binary_grams<K: Serialize + Eq + Hash>(
+    grams: &AHashMap<K, f64>,
+    ngram_len: u8,
+    filename: &Path,
+) -> Result<()> {
+    if let Some(dir) = filename.parent() {
+        create_dir_all(dir)
+            .map_err(|e| NgramsError::CreateDirectory(dir.display().to_string(), e))?;
+    }
+
+    let file = File::create(filename)
+        .map_err(|e| NgramsError::CreateFile(filename.display().to_string(), e))?;
+    let mut buf_writer = BufWriter::new(file);
+    let header = BinaryHeader {
+        magic: BINARY_MAGIC,
+        version: BINARY_FORMAT_VERSION,
+        ngram_len,
+    };
+    bincode::serialize_into(&mut buf_writer, &header)?;
+    bincode::serialize_into(&mut buf_writer, grams)?;
+    buf_writer.flush()?;
+
+    Ok(())
+}
+
+/// Read ngrams with the given length (number of chars) from a binary file, validating its header.
+fn load_binary_grams<K: DeserializeOwned + Eq + Hash>(
+    ngram_len: u8,
+    filename: &Path,
+) -> Result<AHashMap<K, f64>> {
+    let name = || filename.display().to_string();
+    let mut reader = BufReader::new(File::open(filename)?);
+
+    let header: BinaryHeader =
+        bincode::deserialize_from(&mut reader).map_err(|_| NgramsError::NotBinaryNgrams(name()))?;
+    if header.magic != BINARY_MAGIC {
+        return Err(NgramsError::NotBinaryNgrams(name()).into());
+    }
+    if header.version != BINARY_FORMAT_VERSION {
+        return Err(
+            NgramsError::BinaryVersion(name(), header.version, BINARY_FORMAT_VERSION).into(),
+        );
+    }
+    if header.ngram_len != ngram_len {
+        return Err(NgramsError::BinaryNgramLength(name(), header.ngram_len, ngram_len).into());
+    }
+
+    Ok(bincode::deserialize_from(&mut reader)?)
+}
 
 /// Configuration parameters for ngram processing
 #[derive(Debug, Clone, Deserialize)]
 pub struct NgramsConfig {
     /// Parameters for the increase in weight of common ngrams (with already high frequency).
     pub increase_common_ngrams: IncreaseCommonNgramsConfig,
+    /// Only keep the most common trigrams up to this combined fraction of their total weight
+    /// (see [`Trigrams::tops_scaled`]), trading accuracy for faster evaluations.
+    #[serde(default)]
+    pub trigram_fraction: Option<f64>,
 }
 
 /// Configuration parameters for process of increasing the weight of common ngrams.
@@ -66,11 +154,16 @@ pub fn increase_common_ngrams<T>(
 }
 
 /// Holds a hashmap of unigrams (single chars) with corresponding frequency (here often called "weight").
-#[derive(Clone, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct Unigrams {
     pub grams: AHashMap<char, f64>,
 }
 
+/// Size of a hashmap entry including the control byte used by the map's implementation.
+fn estimated_entry_bytes<K>(_grams: &AHashMap<K, f64>) -> usize {
+    mem::size_of::<(K, f64)>() + 1
+}
+
 fn process_special_characters(s: &str) -> String {
     s.replace("\\n", "\n").replace("\\\\", "\\")
 }
@@ -79,6 +172,20 @@ fn process_special_characters_inverse(s: &str) -> String {
     s.replace('\\', "\\\\").replace('\n', "\\n")
 }
 
+/// Split a line of an ngram frequencies file into the weight and the (unprocessed) ngram.
+fn parse_frequency_line(line: &str) -> Result<(f64, &str), NgramsError> {
+    let invalid = |reason: &str| NgramsError::InvalidLine(line.to_string(), reason.to_string());
+    let mut parts = line.trim_start().splitn(2, ' ');
+    let weight: f64 = parts
+        .next()
+        .unwrap_or_default()
+        .parse()
+        .map_err(|e| invalid(&format!("invalid weight ({})", e)))?;
+    let ngram = parts.next().ok_or_else(|| invalid("missing ngram"))?;
+
+    Ok((weight, ngram))
+}
+
 impl Unigrams {
     /// Collect unigrams from given text.
     pub fn from_text(text: &str) -> Result<Self> {
@@ -97,9 +204,7 @@ impl Unigrams {
     pub fn from_frequencies_str(data: &str) -> Result<Self> {
         let mut grams = AHashMap::default();
         for line in data.lines() {
-            let mut parts = line.trim_start().splitn(2, ' ');
-            let weight: f64 = parts.next().unwrap().parse().unwrap();
-            let unigram = parts.next().unwrap();
+            let (weight, unigram) = parse_frequency_line(line)?;
             let unigram = process_special_characters(unigram);
             let chars: Vec<char> = unigram.chars().collect();
             if chars.len() != 1 {
@@ -123,6 +228,19 @@ impl Unigrams {
         self.grams.values().sum()
     }
 
+    /// Estimated memory consumption (in bytes) of the unigrams (number of entries times entry size).
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.grams.len() * estimated_entry_bytes(&self.grams)
+    }
+
+    /// Add the weights of another set of unigrams to the existing ones (in place).
+    pub fn merge(&mut self, other: &Self) {
+        other
+            .grams
+            .iter()
+            .for_each(|(gram, weight)| self.grams.insert_or_add_weight(*gram, *weight));
+    }
+
     /// Return a reduced set of the unigrams containing only the most common unigrams up to a
     /// given combined fraction.
     pub fn tops(&self, fraction: f64) -> Self {
@@ -160,21 +278,17 @@ impl Unigrams {
     }
 
     /// Save frequencies to file
-    pub fn save_frequencies<T: AsRef<Path>>(&self, filename: T) -> Result<(), String> {
+    pub fn save_frequencies<T: AsRef<Path>>(&self, filename: T) -> Result<()> {
         let p = filename.as_ref();
-        create_dir_all(p.parent().unwrap()).map_err(|e| {
-            format!(
-                "Unable to create directory '{}': {}",
-                p.to_str().unwrap(),
-                e
-            )
-        })?;
+        let dir = p.parent().unwrap();
+        create_dir_all(dir)
+            .map_err(|e| NgramsError::CreateDirectory(dir.display().to_string(), e))?;
 
         let mut grams: Vec<(char, f64)> = self.grams.iter().map(|(c, w)| (*c, *w)).collect();
         grams.sort_by(|(_, w1), (_, w2)| w2.partial_cmp(w1).unwrap());
 
         let file = File::create(&filename)
-            .map_err(|e| format!("Unable to create file '{}': {}", p.to_str().unwrap(), e))?;
+            .map_err(|e| NgramsError::CreateFile(p.display().to_string(), e))?;
         let mut buf_writer = BufWriter::new(file);
         grams.iter().for_each(|(c, w)| {
             let processed = process_special_characters_inverse(&c.to_string());
@@ -184,6 +298,18 @@ impl Unigrams {
         Ok(())
     }
 
+    /// Save the unigrams in a compact binary format (see [`BINARY_FORMAT_VERSION`]), which loads
+    /// much faster than a frequencies file.
+    pub fn save_binary<T: AsRef<Path>>(&self, filename: T) -> Result<()> {
+        save_binary_grams(&self.grams, 1, filename.as_ref())
+    }
+
+    /// Load unigrams saved with [`Unigrams::save_binary`].
+    pub fn load_binary<T: AsRef<Path>>(filename: T) -> Result<Self> {
+        let grams = load_binary_grams(1, filename.as_ref())?;
+        Ok(Self { grams })
+    }
+
     pub fn increase_common(&self, params: &IncreaseCommonNgramsConfig) -> Self {
         let mut grams = self.grams.clone();
         increase_common_ngrams(&mut grams, params);
@@ -191,14 +317,27 @@ impl Unigrams {
     }
 }
 
+/// Whether a symbol separates words (any whitespace, including line breaks).
+pub fn is_word_separator(c: &char) -> bool {
+    c.is_whitespace()
+}
+
+/// Whether a bigram spans a word boundary, i.e. leads from the end of a word to the separator
+/// (e.g. "e ") or from the separator to the start of the next word (e.g. " t"). Such
+/// transitions are less about the finger flow within words than intra-word bigrams.
+pub fn is_cross_word_bigram((c1, c2): &(char, char)) -> bool {
+    is_word_separator(c1) || is_word_separator(c2)
+}
+
 /// Holds a hashmap of bigrams (two chars) with corresponding frequency (here often called "weight").
-#[derive(Clone, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct Bigrams {
     pub grams: AHashMap<(char, char), f64>,
 }
 
 impl Bigrams {
-    /// Collect bigrams from given text.
+    /// Collect bigrams from given text. Word separators are retained, so that bigrams across
+    /// word boundaries can be told apart (see [`is_cross_word_bigram`]).
     pub fn from_text(text: &str) -> Result<Self> {
         let mut grams = AHashMap::default();
         let chars = text.chars().filter(|c| *c != '\r');
@@ -217,12 +356,16 @@ impl Bigrams {
     pub fn from_frequencies_str(data: &str) -> Result<Self> {
         let mut grams = AHashMap::default();
         for line in data.lines() {
-            let mut parts = line.trim_start().splitn(2, ' ');
-            let weight: f64 = parts.next().unwrap().parse().unwrap();
-            let bigram = parts.next().unwrap();
+            let (weight, bigram) = parse_frequency_line(line)?;
             let bigram = process_special_characters(bigram);
             let c: Vec<char> = bigram.chars().collect();
-            if c.len() != 2 {
+            if c.len() < 2 {
+                return Err(NgramsError::InvalidLine(
+                    line.to_string(),
+                    "bigram with less than two chars".to_string(),
+                )
+                .into());
+            } else if c.len() != 2 {
                 log::info!("Len of bigram {} is unequal two: {:?}", bigram, c);
             }
             grams.insert_or_add_weight((c[0], c[1]), weight);
@@ -242,6 +385,19 @@ impl Bigrams {
         self.grams.values().sum()
     }
 
+    /// Estimated memory consumption (in bytes) of the bigrams (number of entries times entry size).
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.grams.len() * estimated_entry_bytes(&self.grams)
+    }
+
+    /// Add the weights of another set of bigrams to the existing ones (in place).
+    pub fn merge(&mut self, other: &Self) {
+        other
+            .grams
+            .iter()
+            .for_each(|(gram, weight)| self.grams.insert_or_add_weight(*gram, *weight));
+    }
+
     /// Return a reduced set of the bigrams containing only the most common bigrams up to a
     /// given combined fraction.
     pub fn tops(&self, fraction: f64) -> Self {
@@ -284,23 +440,43 @@ impl Bigrams {
         Self { grams }
     }
 
+    /// Split the bigrams into intra-word bigrams and cross-word bigrams (see
+    /// [`is_cross_word_bigram`]).
+    pub fn split_cross_word(&self) -> (Self, Self) {
+        let (cross_word, intra_word): (AHashMap<(char, char), f64>, _) = self
+            .grams
+            .iter()
+            .map(|(gram, w)| (*gram, *w))
+            .partition(|(gram, _)| is_cross_word_bigram(gram));
+
+        (Self { grams: intra_word }, Self { grams: cross_word })
+    }
+
+    /// Total weight of the intra-word and the cross-word bigrams (see
+    /// [`is_cross_word_bigram`]).
+    pub fn cross_word_weights(&self) -> (f64, f64) {
+        self.grams.iter().fold(
+            (0.0, 0.0),
+            |(intra, cross), (gram, w)| match is_cross_word_bigram(gram) {
+                true => (intra, cross + w),
+                false => (intra + w, cross),
+            },
+        )
+    }
+
     /// Save frequencies to file
-    pub fn save_frequencies<T: AsRef<Path>>(&self, filename: T) -> Result<(), String> {
+    pub fn save_frequencies<T: AsRef<Path>>(&self, filename: T) -> Result<()> {
         let p = filename.as_ref();
-        create_dir_all(p.parent().unwrap()).map_err(|e| {
-            format!(
-                "Unable to create directory '{}': {}",
-                p.to_str().unwrap(),
-                e
-            )
-        })?;
+        let dir = p.parent().unwrap();
+        create_dir_all(dir)
+            .map_err(|e| NgramsError::CreateDirectory(dir.display().to_string(), e))?;
 
         let mut grams: Vec<((char, char), f64)> =
             self.grams.iter().map(|(c, w)| (*c, *w)).collect();
         grams.sort_by(|(_, w1), (_, w2)| w2.partial_cmp(w1).unwrap());
 
         let file = File::create(&filename)
-            .map_err(|e| format!("Unable to create file '{}': {}", p.to_str().unwrap(), e))?;
+            .map_err(|e| NgramsError::CreateFile(p.display().to_string(), e))?;
         let mut buf_writer = BufWriter::new(file);
         grams.iter().for_each(|((c1, c2), w)| {
             let processed1 = process_special_characters_inverse(&c1.to_string());
@@ -311,6 +487,18 @@ impl Bigrams {
         Ok(())
     }
 
+    /// Save the bigrams in a compact binary format (see [`BINARY_FORMAT_VERSION`]), which loads
+    /// much faster than a frequencies file.
+    pub fn save_binary<T: AsRef<Path>>(&self, filename: T) -> Result<()> {
+        save_binary_grams(&self.grams, 2, filename.as_ref())
+    }
+
+    /// Load bigrams saved with [`Bigrams::save_binary`].
+    pub fn load_binary<T: AsRef<Path>>(filename: T) -> Result<Self> {
+        let grams = load_binary_grams(2, filename.as_ref())?;
+        Ok(Self { grams })
+    }
+
     pub fn increase_common(&self, params: &IncreaseCommonNgramsConfig) -> Self {
         let mut grams = self.grams.clone();
         increase_common_ngrams(&mut grams, params);
@@ -319,7 +507,7 @@ impl Bigrams {
 }
 
 /// Holds a hashmap of trigrams (three chars) with corresponding frequency (here often called "weight").
-#[derive(Clone, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct Trigrams {
     pub grams: AHashMap<(char, char, char), f64>,
 }
@@ -345,26 +533,47 @@ impl Trigrams {
 
     /// Read trigrams and weights from a string containing lines with trigrams and their weights.
     pub fn from_frequencies_str(data: &str) -> Result<Self> {
+        Trigrams::from_frequencies_reader(data.as_bytes())
+    }
+
+    /// Read trigrams and weights from a reader providing lines with trigrams and their weights.
+    ///
+    /// The data is parsed line by line, so the complete input never needs to be held in memory.
+    pub fn from_frequencies_reader<R: BufRead>(mut reader: R) -> Result<Self> {
         let mut grams = AHashMap::default();
-        for line in data.lines() {
-            let mut parts = line.trim_start().splitn(2, ' ');
-            let weight: f64 = parts.next().unwrap().parse().unwrap();
-            let trigram = parts.next().unwrap();
+        let mut buf = String::new();
+        while reader.read_line(&mut buf)? > 0 {
+            let line = buf.strip_suffix('\n').unwrap_or(&buf);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let (weight, trigram) = parse_frequency_line(line)?;
             let trigram = process_special_characters(trigram);
             let c: Vec<char> = trigram.chars().collect();
-            if c.len() != 3 {
+            if c.len() < 3 {
+                return Err(NgramsError::InvalidLine(
+                    line.to_string(),
+                    "trigram with less than three chars".to_string(),
+                )
+                .into());
+            } else if c.len() != 3 {
                 log::info!("Len of trigram {} is unequal three: {:?}", trigram, c);
             }
             grams.insert_or_add_weight((c[0], c[1], c[2]), weight);
+            buf.clear();
         }
 
         Ok(Trigrams { grams })
     }
 
+    /// Read trigrams and weights from a file containing lines with trigrams and their weights.
+    /// The file is streamed instead of being read into memory as a whole.
+    pub fn from_frequencies_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = File::open(path)?;
+        Trigrams::from_frequencies_reader(BufReader::new(f))
+    }
+
     /// Read trigrams and weights from a file containing lines with trigrams and their weights.
     pub fn from_file(filename: &str) -> Result<Self> {
-        let data = fs::read_to_string(filename)?;
-        Trigrams::from_frequencies_str(&data)
+        Trigrams::from_frequencies_path(filename)
     }
 
     /// Total weight of all combined trigrams
@@ -372,6 +581,19 @@ impl Trigrams {
         self.grams.values().sum()
     }
 
+    /// Estimated memory consumption (in bytes) of the trigrams (number of entries times entry size).
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.grams.len() * estimated_entry_bytes(&self.grams)
+    }
+
+    /// Add the weights of another set of trigrams to the existing ones (in place).
+    pub fn merge(&mut self, other: &Self) {
+        other
+            .grams
+            .iter()
+            .for_each(|(gram, weight)| self.grams.insert_or_add_weight(*gram, *weight));
+    }
+
     /// Return a reduced set of the trigrams containing only the most common trigrams up to a
     /// given combined fraction.
     pub fn tops(&self, fraction: f64) -> Self {
@@ -399,6 +621,21 @@ impl Trigrams {
         Self { grams }
     }
 
+    /// Return a reduced set of the trigrams containing only the most common trigrams up to a
+    /// given combined fraction (like [`Trigrams::tops`]), with their weights scaled up such that
+    /// their total weight equals the original one. This way, the costs of trigram metrics stay
+    /// comparable to those of a full evaluation, regardless of their normalization.
+    pub fn tops_scaled(&self, fraction: f64) -> Self {
+        let mut tops = self.tops(fraction);
+        let retained_weight = tops.total_weight();
+        if retained_weight > 0.0 {
+            let scale = self.total_weight() / retained_weight;
+            tops.grams.values_mut().for_each(|w| *w *= scale);
+        }
+
+        tops
+    }
+
     // Return a reduced set of trigrams filtering out those containing a given character
     pub fn exclude_char(&self, exclude: &char) -> Self {
         let grams: AHashMap<(char, char, char), f64> = self
@@ -416,22 +653,18 @@ impl Trigrams {
     }
 
     /// Save frequencies to file
-    pub fn save_frequencies<T: AsRef<Path>>(&self, filename: T) -> Result<(), String> {
+    pub fn save_frequencies<T: AsRef<Path>>(&self, filename: T) -> Result<()> {
         let p = filename.as_ref();
-        create_dir_all(p.parent().unwrap()).map_err(|e| {
-            format!(
-                "Unable to create directory '{}': {}",
-                p.to_str().unwrap(),
-                e
-            )
-        })?;
+        let dir = p.parent().unwrap();
+        create_dir_all(dir)
+            .map_err(|e| NgramsError::CreateDirectory(dir.display().to_string(), e))?;
 
         let mut grams: Vec<((char, char, char), f64)> =
             self.grams.iter().map(|(c, w)| (*c, *w)).collect();
         grams.sort_by(|(_, w1), (_, w2)| w2.partial_cmp(w1).unwrap());
 
         let file = File::create(&filename)
-            .map_err(|e| format!("Unable to create file '{}': {}", p.to_str().unwrap(), e))?;
+            .map_err(|e| NgramsError::CreateFile(p.display().to_string(), e))?;
         let mut buf_writer = BufWriter::new(file);
         grams.iter().for_each(|((c1, c2, c3), w)| {
             let processed1 = process_special_characters_inverse(&c1.to_string());
@@ -448,9 +681,272 @@ impl Trigrams {
         Ok(())
     }
 
+    /// Save the trigrams in a compact binary format (see [`BINARY_FORMAT_VERSION`]), which loads
+    /// much faster than a frequencies file.
+    pub fn save_binary<T: AsRef<Path>>(&self, filename: T) -> Result<()> {
+        save_binary_grams(&self.grams, 3, filename.as_ref())
+    }
+
+    /// Load trigrams saved with [`Trigrams::save_binary`].
+    pub fn load_binary<T: AsRef<Path>>(filename: T) -> Result<Self> {
+        let grams = load_binary_grams(3, filename.as_ref())?;
+        Ok(Self { grams })
+    }
+
     pub fn increase_common(&self, params: &IncreaseCommonNgramsConfig) -> Self {
         let mut grams = self.grams.clone();
         increase_common_ngrams(&mut grams, params);
         Self { grams }
     }
 }
+
+/// Collect unigrams, bigrams, and trigrams from a stream of chars in a single pass.
+///
+/// Only a window of the last two chars is kept, so the ngrams are identical to those of
+/// `from_text` on the complete text without requiring it to be held in memory.
+pub fn ngrams_from_chars<I: IntoIterator<Item = char>>(chars: I) -> (Unigrams, Bigrams, Trigrams) {
+    let mut unigrams = AHashMap::default();
+    let mut bigrams = AHashMap::default();
+    let mut trigrams = AHashMap::default();
+    let mut window: (Option<char>, Option<char>) = (None, None);
+    chars.into_iter().filter(|c| *c != '\r').for_each(|c| {
+        unigrams.insert_or_add_weight(c, 1.0);
+        if let (c1, Some(c2)) = window {
+            bigrams.insert_or_add_weight((c2, c), 1.0);
+            if let Some(c1) = c1 {
+                trigrams.insert_or_add_weight((c1, c2, c), 1.0);
+            }
+        }
+        window = (window.1, Some(c));
+    });
+
+    (
+        Unigrams { grams: unigrams },
+        Bigrams { grams: bigrams },
+        Trigrams { grams: trigrams },
+    )
+}
+
+/// Collect unigrams, bigrams, and trigrams from a reader providing a (UTF-8) corpus text.
+///
+/// The text is decoded chunk by chunk (as provided by the reader's buffer), so corpora larger
+/// than the available memory can be processed. Ngrams spanning chunk boundaries are counted
+/// just like in the complete text.
+pub fn ngrams_from_reader<R: BufRead>(reader: R) -> Result<(Unigrams, Bigrams, Trigrams)> {
+    let mut chars = ReaderChars::new(reader);
+    let ngrams = ngrams_from_chars(&mut chars);
+    chars.finish()?;
+
+    Ok(ngrams)
+}
+
+/// An iterator over the chars of a reader providing UTF-8 text.
+///
+/// Bytes of a char that is split between two chunks of the reader are kept until the next
+/// chunk is read. Reading stops at the first error, which is returned by
+/// [`ReaderChars::finish`].
+pub struct ReaderChars<R> {
+    reader: R,
+    chars: std::vec::IntoIter<char>,
+    pending: Vec<u8>,
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> ReaderChars<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            chars: Vec::new().into_iter(),
+            pending: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// The error that stopped reading (if any).
+    pub fn finish(self) -> Result<()> {
+        match self.error {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Decode the next chunk of the reader. Returns `false` at the end of the input.
+    fn read_chunk(&mut self) -> io::Result<bool> {
+        loop {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                if !self.pending.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "stream did not end with a complete UTF-8 char",
+                    ));
+                }
+                return Ok(false);
+            }
+            self.pending.extend_from_slice(buf);
+            let len = buf.len();
+            self.reader.consume(len);
+
+            let valid_up_to = match std::str::from_utf8(&self.pending) {
+                Ok(text) => text.len(),
+                // the last char is incomplete, its remaining bytes follow in the next chunk
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            };
+            if valid_up_to > 0 {
+                let chars: Vec<char> = std::str::from_utf8(&self.pending[..valid_up_to])
+                    .unwrap()
+                    .chars()
+                    .collect();
+                self.chars = chars.into_iter();
+                self.pending.drain(..valid_up_to);
+                return Ok(true);
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ReaderChars<R> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        loop {
+            if let Some(c) = self.chars.next() {
+                return Some(c);
+            }
+            if self.error.is_some() {
+                return None;
+            }
+            match self.read_chunk() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => {
+                    self.error = Some(e);
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Holds a hashmap of words with corresponding frequency (here often called "weight").
+///
+/// In contrast to ngrams, words retain the word structure of a corpus, which is required for
+/// word-level metrics.
+#[derive(Clone, Default, Debug)]
+pub struct Words {
+    pub words: AHashMap<String, f64>,
+}
+
+impl Words {
+    /// Collect words from given text. A word is a maximal sequence of alphabetic characters
+    /// (including apostrophes within the word, e.g. "don't").
+    pub fn from_text(text: &str) -> Result<Self> {
+        let mut words = AHashMap::default();
+        text.split(|c: char| !(c.is_alphabetic() || c == '\''))
+            .map(|w| w.trim_matches('\''))
+            .filter(|w| !w.is_empty())
+            .for_each(|w| {
+                *words.entry(w.to_string()).or_insert(0.0) += 1.0;
+            });
+
+        Ok(Self { words })
+    }
+
+    /// Read words and weights from a string containing lines with weights and words.
+    pub fn from_frequencies_str(data: &str) -> Result<Self> {
+        let mut words = AHashMap::default();
+        for line in data.lines() {
+            let (weight, word) = parse_frequency_line(line)?;
+            *words.entry(word.to_string()).or_insert(0.0) += weight;
+        }
+
+        Ok(Self { words })
+    }
+
+    /// Read words and weights from a file containing lines with weights and words.
+    pub fn from_file(filename: &str) -> Result<Self> {
+        let data = fs::read_to_string(filename)?;
+        Words::from_frequencies_str(&data)
+    }
+
+    /// Total weight of all combined words
+    pub fn total_weight(&self) -> f64 {
+        self.words.values().sum()
+    }
+
+    /// Save frequencies to file
+    pub fn save_frequencies<T: AsRef<Path>>(&self, filename: T) -> Result<()> {
+        let p = filename.as_ref();
+        let dir = p.parent().unwrap();
+        create_dir_all(dir)
+            .map_err(|e| NgramsError::CreateDirectory(dir.display().to_string(), e))?;
+
+        let mut words: Vec<(&String, f64)> = self.words.iter().map(|(w, c)| (w, *c)).collect();
+        words.sort_by(|(_, w1), (_, w2)| w2.partial_cmp(w1).unwrap());
+
+        let file = File::create(&filename)
+            .map_err(|e| NgramsError::CreateFile(p.display().to_string(), e))?;
+        let mut buf_writer = BufWriter::new(file);
+        words.iter().for_each(|(word, w)| {
+            writeln!(&mut buf_writer, "{} {}", w, word).unwrap();
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_ngrams_round_trip_and_check_their_header() {
+        let dir = std::env::temp_dir().join(format!("binary_ngrams_{}", std::process::id()));
+        let bigrams = Bigrams::from_text("abcab\nc").unwrap();
+        bigrams.save_binary(dir.join("2-grams.bin")).unwrap();
+
+        let loaded = Bigrams::load_binary(dir.join("2-grams.bin")).unwrap();
+        assert_eq!(loaded.grams, bigrams.grams);
+        assert!(Trigrams::load_binary(dir.join("2-grams.bin")).is_err());
+
+        bigrams.save_frequencies(dir.join("2-grams.txt")).unwrap();
+        assert!(Bigrams::load_binary(dir.join("2-grams.txt")).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn streamed_ngrams_are_identical_to_those_of_the_complete_text() {
+        let text = "Größere Übungen:\r\nÄpfel & Öl — 𝄞 über ßüß.\nEnde";
+        let unigrams = Unigrams::from_text(text).unwrap();
+        let bigrams = Bigrams::from_text(text).unwrap();
+        let trigrams = Trigrams::from_text(text).unwrap();
+
+        // small buffers split multi-byte chars and ngrams across chunks
+        for chunk_size in 1..8 {
+            let reader = BufReader::with_capacity(chunk_size, text.as_bytes());
+            let (u, b, t) = ngrams_from_reader(reader).unwrap();
+            assert_eq!(u.grams, unigrams.grams, "chunk size {}", chunk_size);
+            assert_eq!(b.grams, bigrams.grams, "chunk size {}", chunk_size);
+            assert_eq!(t.grams, trigrams.grams, "chunk size {}", chunk_size);
+        }
+
+        let invalid: &[u8] = &[b'a', b'b', 0xc3];
+        assert!(ngrams_from_reader(BufReader::with_capacity(1, invalid)).is_err());
+    }
+
+    #[test]
+    fn bigrams_split_into_intra_word_and_cross_word() {
+        let bigrams = Bigrams::from_text("ab cd").unwrap();
+        let (intra_word, cross_word) = bigrams.split_cross_word();
+
+        let mut intra: Vec<(char, char)> = intra_word.grams.keys().copied().collect();
+        intra.sort();
+        assert_eq!(intra, vec![('a', 'b'), ('c', 'd')]);
+        let mut cross: Vec<(char, char)> = cross_word.grams.keys().copied().collect();
+        cross.sort();
+        assert_eq!(cross, vec![(' ', 'c'), ('b', ' ')]);
+        assert_eq!(bigrams.cross_word_weights(), (2.0, 2.0));
+    }
+}
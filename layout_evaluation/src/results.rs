@@ -1,8 +1,13 @@
 //! The `results` module contains structs representing the results of metric evaluations.
 
+use crate::calibration::Calibration;
+
+use keyboard_layout::key::MatrixPosition;
+
+use ahash::AHashMap;
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
-use std::{fmt, slice};
+use std::{cmp::Ordering, fmt, slice};
 
 /// The [`NormalizationType`] specifies how the total cost of a metric evaluation shall be normalized.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -17,6 +22,83 @@ pub enum NormalizationType {
     WeightAll(f64),
 }
 
+/// The number of presses of a key, e.g. for heatmaps or keycap wear studies.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KeyPresses {
+    /// Location of the key on the keyboard.
+    pub matrix_position: MatrixPosition,
+    /// Symbol of the key's base layer (if any).
+    pub symbol: Option<char>,
+    /// Number of presses (the summed unigram frequencies of all symbols typed with the key).
+    pub presses: f64,
+}
+
+/// The cost attributed to each character of a layout (see
+/// [`Evaluator::evaluate_layout_with_char_costs`](crate::evaluation::Evaluator::evaluate_layout_with_char_costs)).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CharCosts {
+    /// Weighted and normalized cost attributed to each character.
+    pub costs: AHashMap<char, f64>,
+    /// Part of the total cost that could not be attributed to characters (layout metrics and
+    /// metrics without individual ngram costs).
+    pub unattributed: f64,
+}
+
+impl CharCosts {
+    /// The characters with their costs, sorted by cost (descending).
+    pub fn sorted(&self) -> Vec<(char, f64)> {
+        let mut costs: Vec<(char, f64)> = self.costs.iter().map(|(c, v)| (*c, *v)).collect();
+        costs.sort_by(|(_, v1), (_, v2)| v2.partial_cmp(v1).unwrap_or(Ordering::Equal));
+
+        costs
+    }
+}
+
+/// The costs of all bigrams of keys of a layout (see
+/// [`Evaluator::bigram_cost_matrix`](crate::evaluation::Evaluator::bigram_cost_matrix)).
+///
+/// Keys are indexed in the order of the keyboard's keys (`Keyboard::keys`, i.e. the order of
+/// the keyboard configuration), so `costs[i][j]` is the cost of typing the key with index `i`
+/// followed by the key with index `j`. The matrix position of the key with index `i` is
+/// `matrix_positions[i]`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BigramCostMatrix {
+    /// Matrix positions of the keys (the rows and columns of `costs`).
+    pub matrix_positions: Vec<MatrixPosition>,
+    /// Weighted and normalized cost of each bigram of keys per unit of relative frequency.
+    pub costs: Vec<Vec<f64>>,
+}
+
+impl BigramCostMatrix {
+    /// The index of the key at the given matrix position (if any).
+    pub fn index_of(&self, matrix_position: &MatrixPosition) -> Option<usize> {
+        self.matrix_positions
+            .iter()
+            .position(|pos| pos == matrix_position)
+    }
+
+    /// The cost of the bigram of the keys at the given matrix positions (if both exist).
+    pub fn cost(&self, from: &MatrixPosition, to: &MatrixPosition) -> Option<f64> {
+        Some(self.costs[self.index_of(from)?][self.index_of(to)?])
+    }
+}
+
+/// The coverage of the ngrams by a partial layout (see
+/// [`Evaluator::evaluate_partial_layout`](crate::evaluation::Evaluator::evaluate_partial_layout)).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Coverage {
+    /// Symbols of the unigrams that the layout does not place, with their share of the total
+    /// unigram weight (descending). Ngrams containing any of them are skipped.
+    pub unplaced_symbols: Vec<(char, f64)>,
+}
+
+impl Coverage {
+    /// The share of the total unigram weight of the placed symbols.
+    pub fn unigram_coverage(&self) -> f64 {
+        1.0 - self.unplaced_symbols.iter().map(|(_, w)| w).sum::<f64>()
+    }
+}
+
 /// Specify which data a metric operates on.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum MetricType {
@@ -26,6 +108,31 @@ pub enum MetricType {
     Trigram,
 }
 
+/// Bounds of a metric's contribution to the total cost (after weighting and normalization),
+/// e.g. to cap how much a reward (negative cost) can lower the total cost.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ContributionBounds {
+    /// Lower bound of the contribution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Upper bound of the contribution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+impl ContributionBounds {
+    /// Whether neither bound is set.
+    pub fn is_unbounded(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    /// Clamp the contribution to the bounds.
+    pub fn clamp(&self, contribution: f64) -> f64 {
+        let contribution = self.min.map_or(contribution, |min| contribution.max(min));
+        self.max.map_or(contribution, |max| contribution.min(max))
+    }
+}
+
 /// Describes the result of an individual metric evaluation.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MetricResult {
@@ -39,6 +146,12 @@ pub struct MetricResult {
     pub weight: f64,
     /// The normalization type to apply.
     pub normalization: NormalizationType,
+    /// Wall-clock time (in seconds) spent computing the metric (only recorded when profiling).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// Bounds of the weighted and normalized cost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<ContributionBounds>,
 }
 
 /// Describes the normalized results of an individual metric evaluation
@@ -48,6 +161,9 @@ pub struct NormalizedMetricResult {
     pub core: MetricResult,
     pub weighted_cost: f64,
     pub unweighted_cost: f64,
+    /// Whether the weighted cost was clamped to the metric's bounds.
+    #[serde(default)]
+    pub clamped: bool,
 }
 
 /// Describes a list of metric evaluation results of the same [`MetricType`].
@@ -59,6 +175,10 @@ pub struct MetricResults {
     pub found_weight: f64,
     /// The total amount of weight (ngram frequencies) from ngrams that contained symbols that coult not be mapped by the layout.
     pub not_found_weight: f64,
+    /// The part of the found weight from bigrams across word boundaries (only for bigram
+    /// metrics and if the ngram mapper distinguishes them).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cross_word_weight: Option<f64>,
     /// A list of the individual metric results.
     pub metric_costs: Vec<NormalizedMetricResult>,
 }
@@ -76,13 +196,36 @@ impl fmt::Display for MetricResults {
                 self.not_found_weight + self.found_weight
             )?;
         }
+        if let Some(cross_word_weight) = self.cross_word_weight {
+            writeln!(
+                f,
+                "  Cross-word: {:.4}%, intra-word: {:.4}% of found weight",
+                100.0 * cross_word_weight / self.found_weight,
+                100.0 * (self.found_weight - cross_word_weight) / self.found_weight
+            )?;
+        }
         for metric_cost in self.metric_costs.iter() {
+            let duration = match metric_cost.core.duration {
+                Some(d) => format!("{:>9.3}ms ", 1000.0 * d).dimmed().to_string(),
+                None => "".to_string(),
+            };
+            let clamped = match metric_cost.clamped {
+                true => format!(
+                    "Clamped from {:.2};  ",
+                    self.unbounded_weighted_cost(&metric_cost.core)
+                )
+                .yellow()
+                .to_string(),
+                false => "".to_string(),
+            };
             writeln!(
                 f,
-                "  {} {} | {}",
+                "  {} {} {}| {}{}",
                 // metric_cost.unweighted_cost,
                 format!("{:>7.2}", metric_cost.weighted_cost).green(),
                 format!("{:<35}", metric_cost.core.name).bold(),
+                duration,
+                clamped,
                 metric_cost.core.message.as_ref().unwrap_or(&"".to_string()),
             )?;
         }
@@ -96,22 +239,31 @@ impl MetricResults {
             metric_type,
             found_weight,
             not_found_weight,
+            cross_word_weight: None,
             metric_costs: Vec::new(),
         }
     }
 
+    /// Attach the part of the found weight from bigrams across word boundaries.
+    pub fn with_cross_word_weight(mut self, cross_word_weight: Option<f64>) -> Self {
+        self.cross_word_weight = cross_word_weight;
+        self
+    }
+
     pub fn add_result(&mut self, metric_cost: MetricResult) {
         let weighted_cost = self.compute_metric_cost(&metric_cost, true, true);
         let unweighted_cost = self.compute_metric_cost(&metric_cost, true, false);
+        let clamped = weighted_cost != self.unbounded_weighted_cost(&metric_cost);
         self.metric_costs.push(NormalizedMetricResult {
             core: metric_cost,
             weighted_cost,
             unweighted_cost,
+            clamped,
         })
     }
 
     /// Normalize a metric's cost value with given normalization strategy.
-    fn normalize_value(&self, val: f64, normalization_type: &NormalizationType) -> f64 {
+    pub(crate) fn normalize_value(&self, val: f64, normalization_type: &NormalizationType) -> f64 {
         let mut res = match normalization_type {
             NormalizationType::Fixed(t) => val / t,
             NormalizationType::WeightFound(t) => val / (t * self.found_weight),
@@ -128,6 +280,14 @@ impl MetricResults {
         res
     }
 
+    /// The weighted and normalized cost of a metric before clamping it to its bounds.
+    fn unbounded_weighted_cost(&self, metric_cost: &MetricResult) -> f64 {
+        self.normalize_value(
+            metric_cost.weight * metric_cost.cost,
+            &metric_cost.normalization,
+        )
+    }
+
     /// Helper function for weighting and normalizing individual metric's results.
     fn compute_metric_cost(
         &self,
@@ -140,9 +300,12 @@ impl MetricResults {
             false => metric_cost.cost,
         };
 
-        match normalize {
-            true => self.normalize_value(cost, &metric_cost.normalization),
-            false => cost,
+        match (normalize, weight, &metric_cost.bounds) {
+            (true, true, Some(bounds)) => {
+                bounds.clamp(self.normalize_value(cost, &metric_cost.normalization))
+            }
+            (true, _, _) => self.normalize_value(cost, &metric_cost.normalization),
+            (false, _, _) => cost,
         }
     }
 
@@ -164,10 +327,88 @@ impl MetricResults {
     }
 }
 
+/// The contribution of an individual metric to the total cost, e.g. for plotting bar charts.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MetricContribution {
+    /// Name of the metric.
+    pub label: String,
+    /// Type of the metric.
+    pub metric_type: MetricType,
+    /// Weighted and normalized cost (the contribution to the total cost).
+    pub weighted_value: f64,
+    /// Normalized cost without the weight applied.
+    pub raw_value: f64,
+    /// The metric's weight.
+    pub weight: f64,
+}
+
+/// A candidate layout in a ranking (see
+/// [`Evaluator::rank_layouts`](crate::evaluation::Evaluator::rank_layouts)).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RankedLayout {
+    /// The candidate's layout string.
+    pub layout: String,
+    /// Position in the ranking (starting with 1), missing for invalid candidates.
+    pub rank: Option<usize>,
+    /// Total cost of the layout, missing for invalid candidates.
+    pub cost: Option<f64>,
+    /// Contributions of the individual metrics to the total cost.
+    pub breakdown: Vec<MetricContribution>,
+    /// Why the candidate is invalid (e.g. the layout could not be generated).
+    pub error: Option<String>,
+}
+
+/// Why a [`DeadMetric`] did not contribute to the total cost.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadMetricReason {
+    /// The metric was never evaluated on any ngram, e.g. because the ngrams of its order were
+    /// not loaded or none of them could be mapped to the layout.
+    NoNgrams,
+    /// The metric was evaluated, but its cost was exactly zero, e.g. because no ngram
+    /// triggered it.
+    ZeroCost,
+}
+
+/// A metric with a non-zero weight whose cost was exactly zero, which may indicate a
+/// misconfiguration (see [`EvaluationResult::dead_metrics`]).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeadMetric {
+    /// Name of the metric.
+    pub name: String,
+    /// Type of the metric.
+    pub metric_type: MetricType,
+    pub reason: DeadMetricReason,
+}
+
+impl fmt::Display for DeadMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            DeadMetricReason::NoNgrams => format!(
+                "received no {:?} ngrams (are they loaded and mappable to the layout?)",
+                self.metric_type
+            )
+            .to_lowercase(),
+            DeadMetricReason::ZeroCost => {
+                "has a cost of exactly zero (is it configured as intended?)".to_string()
+            }
+        };
+        write!(f, "Metric '{}' {}", self.name, reason)
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct EvaluationResult {
     layout: String,
     individual_results: Vec<MetricResults>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    char_costs: Option<CharCosts>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lower_bound: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    coverage: Option<Coverage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    calibration: Option<Calibration>,
 }
 
 impl fmt::Display for EvaluationResult {
@@ -178,6 +419,28 @@ impl fmt::Display for EvaluationResult {
                 acc.and_then(|_| writeln!(f, "{}", results))
             })?;
 
+        if let Some(char_costs) = &self.char_costs {
+            writeln!(f, "{}", "Character costs:".bold())?;
+            for (c, cost) in char_costs.sorted() {
+                writeln!(
+                    f,
+                    "  {} {}",
+                    format!("{:>7.2}", cost).green(),
+                    c.escape_debug()
+                )?;
+            }
+            writeln!(
+                f,
+                "  {} {}\n",
+                format!("{:>7.2}", char_costs.unattributed).green(),
+                "(unattributed)".dimmed()
+            )?;
+        }
+
+        for dead_metric in self.dead_metrics() {
+            writeln!(f, "{} {}", "Warning:".yellow().bold(), dead_metric)?;
+        }
+
         writeln!(
             f,
             "Cost: {} (optimization score: {})",
@@ -185,6 +448,44 @@ impl fmt::Display for EvaluationResult {
             self.optimization_score()
         )?;
 
+        if let (Some(calibration), Some(cost)) = (&self.calibration, self.calibrated_total_cost()) {
+            writeln!(f, "Calibrated cost: {:.2} {}", cost, calibration.unit)?;
+        }
+
+        if let Some(lower_bound) = self.lower_bound {
+            let ratio = match self.lower_bound_ratio() {
+                Some(ratio) => format!(" (cost ratio: {:.3})", ratio),
+                None => "".to_string(),
+            };
+            writeln!(f, "Theoretical lower bound: {:.2}{}", lower_bound, ratio)?;
+        }
+
+        if let Some(coverage) = &self.coverage {
+            writeln!(
+                f,
+                "Unigram coverage: {:.2}%",
+                100.0 * coverage.unigram_coverage()
+            )?;
+            if !coverage.unplaced_symbols.is_empty() {
+                let symbols: Vec<String> = coverage
+                    .unplaced_symbols
+                    .iter()
+                    .take(20)
+                    .map(|(c, w)| format!("{} ({:.2}%)", c.escape_debug(), 100.0 * w))
+                    .collect();
+                let more = match coverage.unplaced_symbols.len() > symbols.len() {
+                    true => format!(", ... ({} in total)", coverage.unplaced_symbols.len()),
+                    false => "".to_string(),
+                };
+                writeln!(
+                    f,
+                    "Skipped ngrams containing unplaced symbols: {}{}",
+                    symbols.join(", "),
+                    more
+                )?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -194,9 +495,101 @@ impl EvaluationResult {
         Self {
             layout,
             individual_results,
+            char_costs: None,
+            lower_bound: None,
+            coverage: None,
+            calibration: None,
         }
     }
 
+    /// Attach a calibration converting the costs into a unit.
+    pub fn with_calibration(mut self, calibration: Calibration) -> Self {
+        self.calibration = Some(calibration);
+        self
+    }
+
+    /// The calibration converting the costs into a unit (if any).
+    pub fn calibration(&self) -> Option<&Calibration> {
+        self.calibration.as_ref()
+    }
+
+    /// The total cost converted into the unit of the calibration (if any), i.e. the sum of the
+    /// metrics' weighted costs multiplied with their calibration factors.
+    pub fn calibrated_total_cost(&self) -> Option<f64> {
+        self.calibration.as_ref().map(|calibration| {
+            self.individual_results
+                .iter()
+                .flat_map(|mc| mc.metric_costs.iter())
+                .map(|mc| calibration.factor(&mc.core.name) * mc.weighted_cost)
+                .sum()
+        })
+    }
+
+    /// The metrics with a non-zero weight whose (raw) cost was exactly zero. Such metrics are
+    /// potentially misconfigured. Metrics that were not evaluated on any ngram are
+    /// distinguished from metrics that were evaluated but did not yield any cost.
+    pub fn dead_metrics(&self) -> Vec<DeadMetric> {
+        self.individual_results
+            .iter()
+            .flat_map(|results| {
+                results
+                    .metric_costs
+                    .iter()
+                    .filter(|mc| mc.core.weight != 0.0 && mc.core.cost == 0.0)
+                    .map(move |mc| DeadMetric {
+                        name: mc.core.name.clone(),
+                        metric_type: results.metric_type.clone(),
+                        reason: match results.metric_type != MetricType::Layout
+                            && results.found_weight <= 0.0
+                        {
+                            true => DeadMetricReason::NoNgrams,
+                            false => DeadMetricReason::ZeroCost,
+                        },
+                    })
+            })
+            .collect()
+    }
+
+    /// Attach a theoretical lower bound of the total cost.
+    pub fn with_lower_bound(mut self, lower_bound: f64) -> Self {
+        self.lower_bound = Some(lower_bound);
+        self
+    }
+
+    /// The theoretical lower bound of the total cost (if computed).
+    pub fn lower_bound(&self) -> Option<f64> {
+        self.lower_bound
+    }
+
+    /// The ratio of the total cost to its theoretical lower bound (if computed and positive).
+    pub fn lower_bound_ratio(&self) -> Option<f64> {
+        self.lower_bound
+            .filter(|b| *b > 0.0)
+            .map(|b| self.total_cost() / b)
+    }
+
+    /// Attach the coverage of the ngrams by a partial layout.
+    pub fn with_coverage(mut self, coverage: Coverage) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
+    /// The coverage of the ngrams by a partial layout (if computed).
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Attach the costs attributed to each character.
+    pub fn with_char_costs(mut self, char_costs: CharCosts) -> Self {
+        self.char_costs = Some(char_costs);
+        self
+    }
+
+    /// The costs attributed to each character (if computed).
+    pub fn char_costs(&self) -> Option<&CharCosts> {
+        self.char_costs.as_ref()
+    }
+
     pub fn total_cost(&self) -> f64 {
         let mut cost = 0.0;
         self.individual_results
@@ -207,6 +600,16 @@ impl EvaluationResult {
         cost
     }
 
+    /// Compute the total cost with the weighted cost of each metric scaled by the factor that
+    /// `factor` returns for the metric's name.
+    pub fn scaled_total_cost<F: Fn(&str) -> f64>(&self, factor: F) -> f64 {
+        self.individual_results
+            .iter()
+            .flat_map(|mc| mc.metric_costs.iter())
+            .map(|mc| factor(&mc.core.name) * mc.weighted_cost)
+            .sum()
+    }
+
     pub fn optimization_score(&self) -> usize {
         (1e8 / self.total_cost()) as usize
     }
@@ -214,4 +617,111 @@ impl EvaluationResult {
     pub fn iter(&self) -> slice::Iter<'_, MetricResults> {
         self.individual_results.iter()
     }
+
+    /// Combine results of the same layout (e.g. evaluated on different corpora) into one
+    /// result whose metric costs, ngram weights, and total cost are the weighted sums of the
+    /// given results' values. Metrics are matched by type and name, and keep the weight of
+    /// their first occurrence (the results are expected to stem from the same metric
+    /// configuration). Messages and durations can not be combined and are dropped.
+    ///
+    /// The combined metric costs are already normalized, so they use a fixed normalization.
+    /// The calibration of the first result (if any) is kept.
+    pub fn weighted_combine(results: &[(&EvaluationResult, f64)]) -> EvaluationResult {
+        let layout = results
+            .first()
+            .map(|(res, _)| res.layout.clone())
+            .unwrap_or_default();
+
+        let mut individual_results: Vec<MetricResults> = Vec::new();
+        for (res, factor) in results.iter() {
+            for metric_results in res.individual_results.iter() {
+                let combined = match individual_results
+                    .iter_mut()
+                    .position(|mr| mr.metric_type == metric_results.metric_type)
+                {
+                    Some(i) => &mut individual_results[i],
+                    None => {
+                        individual_results.push(MetricResults::new(
+                            metric_results.metric_type.clone(),
+                            0.0,
+                            0.0,
+                        ));
+                        individual_results.last_mut().unwrap()
+                    }
+                };
+                combined.found_weight += factor * metric_results.found_weight;
+                combined.not_found_weight += factor * metric_results.not_found_weight;
+                if let Some(cross_word_weight) = metric_results.cross_word_weight {
+                    *combined.cross_word_weight.get_or_insert(0.0) += factor * cross_word_weight;
+                }
+
+                for metric_cost in metric_results.metric_costs.iter() {
+                    let combined_cost = match combined
+                        .metric_costs
+                        .iter()
+                        .position(|mc| mc.core.name == metric_cost.core.name)
+                    {
+                        Some(i) => &mut combined.metric_costs[i],
+                        None => {
+                            combined.metric_costs.push(NormalizedMetricResult {
+                                core: MetricResult {
+                                    name: metric_cost.core.name.clone(),
+                                    cost: 0.0,
+                                    message: None,
+                                    weight: metric_cost.core.weight,
+                                    normalization: NormalizationType::Fixed(1.0),
+                                    duration: None,
+                                    bounds: metric_cost.core.bounds.clone(),
+                                },
+                                weighted_cost: 0.0,
+                                unweighted_cost: 0.0,
+                                clamped: false,
+                            });
+                            combined.metric_costs.last_mut().unwrap()
+                        }
+                    };
+                    combined_cost.core.cost += factor * metric_cost.unweighted_cost;
+                    combined_cost.unweighted_cost = combined_cost.core.cost;
+                    let weighted_cost = combined_cost.core.weight * combined_cost.core.cost;
+                    combined_cost.weighted_cost = match &combined_cost.core.bounds {
+                        Some(bounds) => bounds.clamp(weighted_cost),
+                        None => weighted_cost,
+                    };
+                    combined_cost.clamped = combined_cost.weighted_cost != weighted_cost;
+                }
+            }
+        }
+
+        let calibration = results.first().and_then(|(res, _)| res.calibration.clone());
+
+        EvaluationResult {
+            calibration,
+            ..EvaluationResult::new(layout, individual_results)
+        }
+    }
+
+    /// The contributions of all metrics to the total cost, sorted by contribution (descending).
+    pub fn contributions(&self) -> Vec<MetricContribution> {
+        let mut contributions: Vec<MetricContribution> = self
+            .individual_results
+            .iter()
+            .flat_map(|mc| {
+                mc.metric_costs.iter().map(move |c| MetricContribution {
+                    label: c.core.name.clone(),
+                    metric_type: mc.metric_type.clone(),
+                    weighted_value: c.weighted_cost,
+                    raw_value: c.unweighted_cost,
+                    weight: c.core.weight,
+                })
+            })
+            .collect();
+
+        contributions.sort_by(|c1, c2| {
+            c2.weighted_value
+                .partial_cmp(&c1.weighted_value)
+                .unwrap_or(Ordering::Equal)
+        });
+
+        contributions
+    }
 }
@@ -0,0 +1,58 @@
+//! Shared fixtures of the unit tests: The `sval` keyboard, the default evaluation parameters,
+//! and a short English text with many different ngrams as corpus.
+
+use crate::{
+    config::EvaluationParameters,
+    evaluation::Evaluator,
+    ngram_mapper::on_demand_ngram_mapper::OnDemandNgramMapper,
+    ngrams::{Bigrams, Trigrams, Unigrams},
+};
+
+use keyboard_layout::{
+    config::LayoutConfig, keyboard::Keyboard, neo_layout_generator::NeoLayoutGenerator,
+};
+
+use std::{str::FromStr, sync::Arc};
+
+pub const TEXT: &str = "The quick brown fox jumps over the lazy dog. Pack my box with five dozen \
+    liquor jugs! How vexingly quick daft zebras jump; the five boxing wizards jump quickly. \
+    Sphinx of black quartz, judge my vow. Waltz, bad nymph, for quick jigs vex. Glib jocks \
+    quiz nymph to vex dwarf. Jackdaws love my big sphinx of quartz (and 42 other things). \
+    Evaluating keyboard layouts requires many different bigrams and trigrams: there, where, \
+    which, through, thought, strength, rhythm, algorithm, mnemonic, psychology, awkward, \
+    bookkeeper, committee, successfully, misspelled, occasionally, accommodate, queue.";
+
+/// The base layout of the `sval` keyboard.
+pub const LAYOUT: &str = "q0a1zw2sbxe3dtcr4fgvuhj5miyk67onl89p={}([";
+
+/// Layout generator for the `sval` keyboard.
+pub fn layout_generator() -> NeoLayoutGenerator {
+    let cfg = LayoutConfig::from_str(include_str!("../../config/keyboard/sval.yml")).unwrap();
+    let keyboard = Arc::new(Keyboard::from_yaml_object(cfg.keyboard));
+    NeoLayoutGenerator::from_object(cfg.base_layout, keyboard)
+}
+
+/// The default evaluation parameters.
+pub fn eval_params() -> EvaluationParameters {
+    EvaluationParameters::from_str(include_str!("../../config/evaluation/default.yml")).unwrap()
+}
+
+/// Ngram mapper for the ngrams of `text`.
+pub fn ngram_mapper(text: &str, params: &EvaluationParameters) -> OnDemandNgramMapper {
+    OnDemandNgramMapper::with_ngrams(
+        Unigrams::from_text(text).unwrap(),
+        Bigrams::from_text(text).unwrap(),
+        Trigrams::from_text(text).unwrap(),
+        params.ngram_mapper.clone(),
+    )
+}
+
+/// Evaluator with the default metrics of `params` for the ngrams of `text`.
+pub fn evaluator(text: &str, params: &EvaluationParameters) -> Evaluator {
+    Evaluator::default(Box::new(ngram_mapper(text, params))).default_metrics(&params.metrics)
+}
+
+/// Layout generator and evaluator with the default parameters for [`TEXT`].
+pub fn generator_and_evaluator() -> (NeoLayoutGenerator, Evaluator) {
+    (layout_generator(), evaluator(TEXT, &eval_params()))
+}
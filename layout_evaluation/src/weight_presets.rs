@@ -0,0 +1,150 @@
+//! The `weight_presets` module provides named bundles of metric weights reflecting different
+//! philosophies of layout design, so that users can switch between them without adjusting the
+//! weights of many metrics by hand:
+//! - `effort` minimizes the strain of typing: Keys that are hard to reach, an unbalanced
+//!   load of the fingers and hands, and awkward finger movements are penalized strongly, while
+//!   the flow of keystroke sequences (e.g. rolls) is of minor importance.
+//! - `speed` maximizes the speed and flow of typing: Sequences that force a finger to be
+//!   reused quickly (same-finger bigrams and trigrams) are penalized strongly and rolls are
+//!   rewarded, while the load of individual keys and fingers is of minor importance.
+//!
+//! A preset only sets the weights of the metrics it lists (and enables or disables them), all
+//! other metrics and their parameters are taken from the evaluation parameters it is applied
+//! to. The weights are tuned with respect to the normalizations of the default evaluation
+//! parameters (`config/evaluation/default.yml`).
+
+use anyhow::Result;
+use serde_yaml::Value;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum WeightPresetError {
+    #[error("Unknown weight preset '{0}' (available: {})", WEIGHT_PRESETS.join(", "))]
+    UnknownPreset(String),
+    #[error("Evaluation parameters do not configure metric '{0}' of the weight preset")]
+    MissingMetric(&'static str),
+    #[error("Evaluation parameters do not contain a 'metrics' section")]
+    MissingMetricsSection,
+}
+
+/// Names of the metric weight presets (see the module documentation).
+pub const WEIGHT_PRESETS: [&str; 2] = ["effort", "speed"];
+
+/// The metric weights of the preset with the given name, by the metrics' ids in the evaluation
+/// parameters. Metrics with a weight of zero are disabled.
+pub fn weight_preset(name: &str) -> Result<Vec<(&'static str, f64)>, WeightPresetError> {
+    let weights = match name {
+        "effort" => vec![
+            // load of keys, fingers, and hands
+            ("key_costs", 100.0),
+            ("finger_balance", 80.0),
+            ("hand_disbalance", 100.0),
+            ("pinky_off_home", 100.0),
+            ("bottom_row_load", 100.0),
+            ("modifier_usage", 100.0),
+            // awkward movements
+            ("finger_repeats", 1500.0),
+            ("scissoring", 5000.0),
+            ("lateral_stretch", 100.0),
+            ("movement_pattern", 60.0),
+            // flow
+            ("irregularity", 4.0),
+            ("trigram_finger_repeats", 0.0),
+            ("finger_reuse_gaps", 0.0),
+            ("trigram_rolls", 0.0),
+        ],
+        "speed" => vec![
+            // load of keys, fingers, and hands
+            ("key_costs", 30.0),
+            ("finger_balance", 30.0),
+            ("hand_disbalance", 50.0),
+            ("pinky_off_home", 0.0),
+            ("bottom_row_load", 0.0),
+            ("modifier_usage", 100.0),
+            // awkward movements
+            ("finger_repeats", 2500.0),
+            ("scissoring", 3000.0),
+            ("lateral_stretch", 0.0),
+            ("movement_pattern", 150.0),
+            // flow
+            ("irregularity", 12.0),
+            ("trigram_finger_repeats", 10000.0),
+            ("finger_reuse_gaps", 1000.0),
+            ("trigram_rolls", 25000.0),
+        ],
+        _ => return Err(WeightPresetError::UnknownPreset(name.to_string())),
+    };
+
+    Ok(weights)
+}
+
+/// Apply the weight preset with the given name to evaluation parameters (in YAML format, as in
+/// the evaluation config files) and return the resulting parameters in YAML format. Comments
+/// are not preserved.
+///
+/// Returns an error if the parameters do not configure all metrics of the preset.
+pub fn apply_weight_preset(eval_params_str: &str, name: &str) -> Result<String> {
+    let weights = weight_preset(name)?;
+    let mut eval_params: Value = serde_yaml::from_str(eval_params_str)?;
+    let metrics = eval_params
+        .get_mut("metrics")
+        .and_then(Value::as_mapping_mut)
+        .ok_or(WeightPresetError::MissingMetricsSection)?;
+
+    for (id, weight) in weights {
+        let config = metrics
+            .get_mut(Value::from(id))
+            .and_then(Value::as_mapping_mut)
+            .ok_or(WeightPresetError::MissingMetric(id))?;
+        config.insert(Value::from("enabled"), Value::from(weight != 0.0));
+        if weight != 0.0 {
+            config.insert(Value::from("weight"), Value::from(weight));
+        }
+    }
+
+    Ok(serde_yaml::to_string(&eval_params)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_weight_preset, weight_preset, WEIGHT_PRESETS};
+    use crate::{config::EvaluationParameters, metric_info::available_metrics};
+
+    use std::str::FromStr;
+
+    #[test]
+    fn presets_apply_to_the_default_parameters() {
+        let default_params = include_str!("../../config/evaluation/default.yml");
+        let metrics = available_metrics();
+
+        for name in WEIGHT_PRESETS {
+            let weights = weight_preset(name).unwrap();
+            assert!(weights
+                .iter()
+                .all(|(id, _)| metrics.iter().any(|m| m.id == *id)));
+
+            let params =
+                EvaluationParameters::from_str(&apply_weight_preset(default_params, name).unwrap())
+                    .unwrap();
+            let scissoring = params.metrics.scissoring.unwrap();
+            assert!(scissoring.enabled);
+            assert_eq!(
+                scissoring.weight,
+                weights
+                    .iter()
+                    .find(|(id, _)| *id == "scissoring")
+                    .unwrap()
+                    .1
+            );
+        }
+
+        let speed =
+            EvaluationParameters::from_str(&apply_weight_preset(default_params, "speed").unwrap())
+                .unwrap();
+        assert!(speed.metrics.trigram_rolls.unwrap().enabled);
+        assert!(!speed.metrics.bottom_row_load.unwrap().enabled);
+
+        assert!(apply_weight_preset(default_params, "lazy").is_err());
+        assert!(apply_weight_preset("metrics: {}", "effort").is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! This module provides an exact solver for the linear assignment problem (Hungarian
+//! algorithm with potentials, `O(n² m)` for `n` rows and `m` columns).
+
+/// Assign each row of the cost matrix to a distinct column such that the sum of the assigned
+/// costs is minimal. Returns the column assigned to each row.
+///
+/// The matrix needs at least as many columns as rows (all rows need to have the same length).
+pub fn solve(costs: &[Vec<f64>]) -> Vec<usize> {
+    let n = costs.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let m = costs[0].len();
+    assert!(m >= n, "more rows ({}) than columns ({})", n, m);
+
+    // 1-based indices, where the row/column 0 is a virtual starting point
+    let mut row_potentials = vec![0.0; n + 1];
+    let mut col_potentials = vec![0.0; m + 1];
+    let mut col_rows = vec![0; m + 1];
+    let mut prev_cols = vec![0; m + 1];
+
+    for row in 1..=n {
+        col_rows[0] = row;
+        let mut col = 0;
+        let mut min_slacks = vec![f64::INFINITY; m + 1];
+        let mut used = vec![false; m + 1];
+
+        // find an augmenting path from the new row to a free column
+        loop {
+            used[col] = true;
+            let current_row = col_rows[col];
+            let mut delta = f64::INFINITY;
+            let mut next_col = 0;
+            for j in 1..=m {
+                if used[j] {
+                    continue;
+                }
+                let slack =
+                    costs[current_row - 1][j - 1] - row_potentials[current_row] - col_potentials[j];
+                if slack < min_slacks[j] {
+                    min_slacks[j] = slack;
+                    prev_cols[j] = col;
+                }
+                if min_slacks[j] < delta {
+                    delta = min_slacks[j];
+                    next_col = j;
+                }
+            }
+            for j in 0..=m {
+                if used[j] {
+                    row_potentials[col_rows[j]] += delta;
+                    col_potentials[j] -= delta;
+                } else {
+                    min_slacks[j] -= delta;
+                }
+            }
+            col = next_col;
+            if col_rows[col] == 0 {
+                break;
+            }
+        }
+
+        // flip the assignments along the augmenting path
+        while col != 0 {
+            let prev_col = prev_cols[col];
+            col_rows[col] = col_rows[prev_col];
+            col = prev_col;
+        }
+    }
+
+    let mut assignment = vec![0; n];
+    for j in 1..=m {
+        if col_rows[j] > 0 {
+            assignment[col_rows[j] - 1] = j - 1;
+        }
+    }
+
+    assignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total_cost(costs: &[Vec<f64>], assignment: &[usize]) -> f64 {
+        assignment
+            .iter()
+            .enumerate()
+            .map(|(row, col)| costs[row][*col])
+            .sum()
+    }
+
+    #[test]
+    fn solves_square_matrix() {
+        let costs = vec![
+            vec![4.0, 1.0, 3.0],
+            vec![2.0, 0.0, 5.0],
+            vec![3.0, 2.0, 2.0],
+        ];
+        let assignment = solve(&costs);
+
+        assert_eq!(assignment, vec![1, 0, 2]);
+        assert_eq!(total_cost(&costs, &assignment), 5.0);
+    }
+
+    #[test]
+    fn solves_rectangular_matrix() {
+        let costs = vec![vec![10.0, 1.0, 7.0, 3.0], vec![2.0, 1.5, 8.0, 9.0]];
+        let assignment = solve(&costs);
+
+        assert_eq!(assignment, vec![1, 0]);
+        assert_eq!(total_cost(&costs, &assignment), 3.0);
+    }
+}
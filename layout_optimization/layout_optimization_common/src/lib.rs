@@ -1,11 +1,58 @@
-use rand::{seq::SliceRandom, thread_rng};
+pub mod assignment;
 
-#[derive(Clone, Debug)]
+use keyboard_layout::{key::MatrixPosition, layout::Layout, layout_generator::LayoutGenerator};
+use layout_evaluation::{
+    cache::Cache,
+    evaluation::Evaluator,
+    metric_info::available_metrics,
+    metrics::layout_metrics::{home_row_pins, soft_pins},
+    results::{EvaluationResult, NormalizationType},
+};
+
+use ahash::AHashMap;
+use anyhow::Result;
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum OptimizationError {
+    #[error("Invalid optimization parameter '{0}': {1}")]
+    InvalidParameter(&'static str, String),
+    #[error("Unknown optimization preset '{0}' (available: {})", PRESETS.join(", "))]
+    UnknownPreset(String),
+    #[error("Invalid layout '{0}': {1}")]
+    InvalidLayout(String, String),
+}
+
+/// Names of the optimization parameter presets (e.g. `Parameters::preset` of the genetic and
+/// the simulated annealing optimization), from the fastest to the most thorough one. The
+/// presets are tuned for layouts with about 30 to 45 permutable keys.
+pub const PRESETS: [&str; 3] = ["quick", "balanced", "thorough"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct LayoutPermutator {
     perm_keys: Vec<char>,
     perm_indices: Vec<usize>,
     fixed_keys: Vec<char>,
     fixed_indices: Vec<usize>,
+    /// Whether a permutable key belongs to the restricted pool
+    restricted: Vec<bool>,
+    /// Whether a layout position is reserved for the restricted pool
+    restricted_positions: Vec<bool>,
+    /// Positions of the permutable keys in the reference layout of a distance limit
+    reference_indices: Option<Vec<usize>>,
+    /// Maximum number of positions that may differ from the reference layout
+    max_distance: usize,
 }
 
 impl LayoutPermutator {
@@ -24,15 +71,177 @@ impl LayoutPermutator {
                 perm_indices.push(i);
             }
         }
+        let restricted = vec![false; perm_keys.len()];
+        let restricted_positions = vec![false; perm_keys.len() + fixed_keys.len()];
         Self {
             perm_keys,
             perm_indices,
             fixed_keys,
             fixed_indices,
+            restricted,
+            restricted_positions,
+            reference_indices: None,
+            max_distance: 0,
+        }
+    }
+
+    /// Restrict the (permutable) characters of `pool` (e.g. thumb key symbols) to the positions
+    /// they occupy in the layout (e.g. thumb keys). They are then only permutated among each
+    /// other, and no other character is placed at one of these positions.
+    pub fn with_restricted_pool(mut self, pool: &str) -> Self {
+        self.restricted = self.perm_keys.iter().map(|c| pool.contains(*c)).collect();
+        let mut restricted_positions = vec![false; self.perm_keys.len() + self.fixed_keys.len()];
+        self.perm_indices
+            .iter()
+            .zip(self.restricted.iter())
+            .filter(|(_, r)| **r)
+            .for_each(|(i, _)| restricted_positions[*i] = true);
+        self.restricted_positions = restricted_positions;
+
+        self
+    }
+
+    /// Limit the layouts to those that differ from the `reference` layout in at most
+    /// `max_distance` positions (the Hamming distance). The reference needs to contain the same
+    /// characters as the layout, with the fixed characters at the same positions.
+    ///
+    /// The limit is enforced by [`LayoutPermutator::limit_distance`], which is used by the
+    /// permutator's operations generating new permutations (and needs to be applied by other
+    /// operators, e.g. of a genetic algorithm).
+    pub fn with_max_distance(
+        mut self,
+        reference: &str,
+        max_distance: usize,
+    ) -> Result<Self, OptimizationError> {
+        let reference_indices = self.parse_permutation(reference, "max_distance_reference")?;
+
+        self.reference_indices = Some(reference_indices);
+        self.max_distance = max_distance;
+
+        Ok(self)
+    }
+
+    /// The permutation giving the layout string `layout` (ignoring whitespace). The layout needs
+    /// to contain the fixed characters at their positions and each permutable character.
+    /// Errors refer to the given `parameter`.
+    pub fn parse_permutation(
+        &self,
+        layout: &str,
+        parameter: &'static str,
+    ) -> Result<Vec<usize>, OptimizationError> {
+        let layout: Vec<char> = layout.chars().filter(|c| !c.is_whitespace()).collect();
+        let invalid = |msg: String| OptimizationError::InvalidParameter(parameter, msg);
+
+        if layout.len() != self.perm_keys.len() + self.fixed_keys.len() {
+            return Err(invalid(format!(
+                "The layout has {} keys instead of {}",
+                layout.len(),
+                self.perm_keys.len() + self.fixed_keys.len()
+            )));
+        }
+        if let Some((i, c)) = self
+            .fixed_indices
+            .iter()
+            .zip(self.fixed_keys.iter())
+            .find(|(i, c)| layout[**i] != **c)
+        {
+            return Err(invalid(format!(
+                "The fixed character '{}' is not at position {} of the layout",
+                c, i
+            )));
+        }
+
+        self.perm_keys
+            .iter()
+            .map(|c| {
+                layout
+                    .iter()
+                    .position(|l| l == c)
+                    .ok_or_else(|| invalid(format!("The layout does not contain '{}'", c)))
+            })
+            .collect()
+    }
+
+    /// The number of positions in which the layout given by `permutation` differs from the
+    /// reference layout of the distance limit (see [`LayoutPermutator::with_max_distance`]),
+    /// if any.
+    pub fn distance(&self, permutation: &[usize]) -> Option<usize> {
+        self.reference_indices.as_ref().map(|reference| {
+            permutation
+                .iter()
+                .zip(reference.iter())
+                .filter(|(i, r)| i != r)
+                .count()
+        })
+    }
+
+    /// Map a permutation to one within the distance limit (see
+    /// [`LayoutPermutator::with_max_distance`]) by moving randomly chosen keys back to their
+    /// positions in the reference layout (swapping them with the keys occupying these
+    /// positions). Permutations within the limit are returned unchanged.
+    pub fn limit_distance<R: Rng>(&self, permutation: &[usize], rng: &mut R) -> Vec<usize> {
+        let mut indices: Vec<usize> = permutation.to_vec();
+        let reference = match &self.reference_indices {
+            Some(reference) => reference,
+            None => return indices,
+        };
+
+        loop {
+            let differing: Vec<usize> = (0..indices.len())
+                .filter(|j| indices[*j] != reference[*j])
+                .collect();
+            if differing.len() <= self.max_distance {
+                break;
+            }
+
+            let j = *differing.choose(rng).unwrap();
+            let k = indices.iter().position(|i| *i == reference[j]).unwrap();
+            indices.swap(j, k);
         }
+
+        indices
+    }
+
+    /// Map a permutation to one that honors the restricted pool (see
+    /// [`LayoutPermutator::with_restricted_pool`]) by exchanging the positions of pool
+    /// characters outside of the pool's positions with those of other characters inside.
+    /// Valid permutations are returned unchanged.
+    pub fn restrict(&self, permutation: &[usize]) -> Vec<usize> {
+        let mut indices: Vec<usize> = permutation.to_vec();
+
+        let misplaced = |restricted: bool| {
+            (0..indices.len())
+                .filter(|j| {
+                    self.restricted[*j] == restricted
+                        && self.restricted_positions[indices[*j]] != restricted
+                })
+                .collect::<Vec<usize>>()
+        };
+        let misplaced_pool = misplaced(true);
+        let misplaced_others = misplaced(false);
+
+        misplaced_pool
+            .into_iter()
+            .zip(misplaced_others)
+            .for_each(|(j1, j2)| indices.swap(j1, j2));
+
+        indices
+    }
+
+    fn has_restricted_pool(&self) -> bool {
+        self.restricted.iter().any(|r| *r)
     }
 
     pub fn generate_string(&self, permutation: &[usize]) -> String {
+        let restricted;
+        let permutation = match self.has_restricted_pool() {
+            true => {
+                restricted = self.restrict(permutation);
+                &restricted
+            }
+            false => permutation,
+        };
+
         let mut res: Vec<char> = vec!['-'; self.fixed_keys.len() + self.perm_keys.len()];
 
         self.fixed_indices
@@ -48,11 +257,18 @@ impl LayoutPermutator {
         res.iter().collect()
     }
 
+    /// The canonical key of the layout given by `permutation`, i.e. its complete layout string.
+    /// Identical layouts always share the same key, regardless of how they were produced.
+    pub fn cache_key(&self, permutation: &[usize]) -> String {
+        self.generate_string(permutation)
+    }
+
     pub fn generate_random(&self) -> Vec<usize> {
         let mut indices: Vec<usize> = self.perm_indices.to_vec();
-        indices.shuffle(&mut thread_rng());
+        let rng = &mut thread_rng();
+        indices.shuffle(rng);
 
-        indices
+        self.limit_distance(&self.restrict(&indices), rng)
     }
 
     /// Takes in a Layout, switches [nr_switches] keys in that layout, then returns it.
@@ -62,15 +278,20 @@ impl LayoutPermutator {
         let vec: Vec<usize> = (0..permutation.len()).collect();
         let rng = &mut thread_rng();
 
-        // Perform nr_switches switches
+        // Perform nr_switches switches (only among keys of the same pool)
         for _ in 0..nr_switches {
-            let mut sw = vec.choose_multiple(rng, 2);
-            let sw0 = sw.next().unwrap();
-            let sw1 = sw.next().unwrap();
-            indices.swap(*sw0, *sw1);
+            let sw0 = *vec.choose(rng).unwrap();
+            let candidates: Vec<usize> = vec
+                .iter()
+                .filter(|j| **j != sw0 && self.restricted[**j] == self.restricted[sw0])
+                .cloned()
+                .collect();
+            if let Some(sw1) = candidates.choose(rng) {
+                indices.swap(sw0, *sw1);
+            }
         }
 
-        indices
+        self.limit_distance(&indices, rng)
     }
 
     pub fn switch_n_keys(&self, permutation: &[usize], n_keys: usize) -> Vec<usize> {
@@ -87,18 +308,723 @@ impl LayoutPermutator {
             indices[*to] = permutation[*from];
         }
 
-        indices
+        self.limit_distance(&self.restrict(&indices), rng)
+    }
+
+    /// All permutations that differ from `permutation` by a single swap of two keys, generated
+    /// lazily. Fixed keys are never moved, keys of the restricted pool are only swapped among
+    /// each other, and neighbors exceeding the distance limit are skipped.
+    pub fn neighbors<'a>(
+        &'a self,
+        permutation: &'a [usize],
+    ) -> impl Iterator<Item = Vec<usize>> + 'a {
+        let n = permutation.len();
+        (0..n)
+            .flat_map(move |j| (j + 1..n).map(move |k| (j, k)))
+            .filter(move |(j, k)| self.restricted[*j] == self.restricted[*k])
+            .map(move |(j, k)| {
+                let mut indices = permutation.to_vec();
+                indices.swap(j, k);
+                indices
+            })
+            .filter(move |indices| {
+                self.distance(indices)
+                    .map_or(true, |d| d <= self.max_distance)
+            })
     }
 
     pub fn get_permutable_indices(&self) -> Vec<usize> {
         self.perm_indices.clone()
     }
+
+    /// The characters that are permutated (i.e. not fixed).
+    pub fn permutable_chars(&self) -> String {
+        self.perm_keys.iter().collect()
+    }
+
+    /// Number of keys that are permutated (i.e. not fixed).
+    pub fn nr_permutable_keys(&self) -> usize {
+        self.perm_keys.len()
+    }
+
+    /// Decimal logarithm of the number of possible layouts, i.e. `log10(n!)` for
+    /// `n` permutable keys (the number itself quickly exceeds the range of integer types).
+    pub fn log10_search_space_size(&self) -> f64 {
+        (2..=self.perm_keys.len()).map(|i| (i as f64).log10()).sum()
+    }
+}
+
+/// A strictly monotone transformation of a layout's total cost into a fitness value, where a
+/// lower cost always results in a higher fitness.
+///
+/// The genetic optimizer maximizes the fitness (rounded down to an integer and clamped at zero)
+/// and defaults to `Inverse { numerator: 1e8 }` (the layout's "optimization score").
+/// Simulated annealing minimizes the negated fitness and defaults to `Negate { offset: 0.0 }`
+/// (i.e. the total cost itself). Other transforms change the scale of the values the
+/// optimizers operate on, so that e.g. the temperature of simulated annealing needs adjustment.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FitnessTransform {
+    /// `fitness = offset - cost`
+    Negate { offset: f64 },
+    /// `fitness = numerator / cost`
+    Inverse { numerator: f64 },
+    /// `fitness = factor * exp(-cost / scale)`; emphasizes differences among good layouts
+    Exponential { scale: f64, factor: f64 },
+}
+
+impl FitnessTransform {
+    /// Transform a total cost into a fitness value.
+    pub fn fitness(&self, cost: f64) -> f64 {
+        match self {
+            Self::Negate { offset } => offset - cost,
+            Self::Inverse { numerator } => numerator / cost,
+            Self::Exponential { scale, factor } => factor * (-cost / scale).exp(),
+        }
+    }
+
+    /// Checks that the transform is strictly monotone, i.e. that its parameters are positive
+    /// where required.
+    pub fn validate(&self) -> Result<(), OptimizationError> {
+        let positive_params = match *self {
+            Self::Negate { .. } => vec![],
+            Self::Inverse { numerator } => vec![("numerator", numerator)],
+            Self::Exponential { scale, factor } => vec![("scale", scale), ("factor", factor)],
+        };
+
+        match positive_params.iter().find(|(_, value)| *value <= 0.0) {
+            Some((name, value)) => Err(OptimizationError::InvalidParameter(
+                "fitness_transform",
+                format!("The {} needs to be positive, got {}", name, value),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Scales the weight of a metric linearly from `start_factor` (at the start of an optimization)
+/// to `end_factor` (at its end).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MetricSchedule {
+    /// Id of the metric as used in the evaluation parameters (e.g. `key_costs`)
+    pub metric: String,
+    /// Factor applied to the metric's weight at the start of the optimization
+    pub start_factor: f64,
+    /// Factor applied to the metric's weight at the end of the optimization
+    #[serde(default = "default_end_factor")]
+    pub end_factor: f64,
+}
+
+fn default_end_factor() -> f64 {
+    1.0
+}
+
+/// A curriculum that re-weights the metrics of an evaluation as the optimization progresses
+/// according to a list of [`MetricSchedule`]s, e.g. to phase in fine-grained metrics after the
+/// coarse structure of a layout has settled. Metrics without a schedule keep their weight.
+///
+/// The progress is the number of [`Curriculum::step`]s relative to the expected total number
+/// of steps. Clones share the progress, so that it can be advanced by the optimizer while
+/// fitness functions running in other threads read it.
+#[derive(Clone, Debug, Default)]
+pub struct Curriculum {
+    /// Schedules by metric name (as used in evaluation results)
+    schedules: AHashMap<String, MetricSchedule>,
+    total_steps: u64,
+    steps: Arc<AtomicU64>,
+}
+
+impl Curriculum {
+    /// Create a curriculum from the given schedules whose progress reaches its end after
+    /// `total_steps` steps. Returns an error for schedules of unknown metrics.
+    pub fn new(schedules: &[MetricSchedule], total_steps: u64) -> Result<Self, OptimizationError> {
+        let metrics = available_metrics();
+        let schedules = schedules
+            .iter()
+            .map(|schedule| {
+                metrics
+                    .iter()
+                    .find(|m| m.id == schedule.metric)
+                    .map(|m| (m.name.to_string(), schedule.clone()))
+                    .ok_or_else(|| {
+                        OptimizationError::InvalidParameter(
+                            "metric_schedules",
+                            format!("Unknown metric '{}'", schedule.metric),
+                        )
+                    })
+            })
+            .collect::<Result<AHashMap<String, MetricSchedule>, OptimizationError>>()?;
+
+        Ok(Self {
+            schedules,
+            total_steps: total_steps.max(1),
+            steps: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Whether any metric is re-weighted (otherwise, costs equal the regular total costs).
+    pub fn is_active(&self) -> bool {
+        !self.schedules.is_empty()
+    }
+
+    /// Advance the progress by one step.
+    pub fn step(&self) {
+        self.steps.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The current progress between 0 (start) and 1 (end).
+    pub fn progress(&self) -> f64 {
+        (self.steps.load(Ordering::Relaxed) as f64 / self.total_steps as f64).min(1.0)
+    }
+
+    /// The factor applied to the weight of the metric with the given name at `progress`.
+    pub fn factor(&self, metric_name: &str, progress: f64) -> f64 {
+        match self.schedules.get(metric_name) {
+            Some(s) => s.start_factor + (s.end_factor - s.start_factor) * progress,
+            None => 1.0,
+        }
+    }
+
+    /// The total cost of an evaluation result with the weights at the current progress.
+    pub fn total_cost(&self, result: &EvaluationResult) -> f64 {
+        self.total_cost_at(result, self.progress())
+    }
+
+    /// The total cost of an evaluation result with the weights at the given progress.
+    pub fn total_cost_at(&self, result: &EvaluationResult, progress: f64) -> f64 {
+        match self.is_active() {
+            true => result.scaled_total_cost(|name| self.factor(name, progress)),
+            false => result.total_cost(),
+        }
+    }
+}
+
+/// An append-only trail of the best layouts found during an optimization, e.g. for inspecting
+/// intermediate results of long runs. Each record is written as a tab-separated line
+/// (timestamp in seconds since the Unix epoch, id of the optimization run, iteration, cost,
+/// layout string) and flushed immediately, so the best layout survives even a hard crash.
+pub struct BestLayoutLog {
+    id: String,
+    writer: Box<dyn Write + Send>,
+}
+
+impl BestLayoutLog {
+    /// Write the records of the optimization run `id` to the given writer.
+    pub fn new(id: &str, writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            id: id.to_string(),
+            writer,
+        }
+    }
+
+    /// Append the records of the optimization run `id` to the file at `path` (created if it
+    /// does not exist). Several runs may append to the same file.
+    pub fn append_to(id: &str, path: &str) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(id, Box::new(file)))
+    }
+
+    /// Record a new best layout (found in the given iteration) and flush it.
+    pub fn record(&mut self, iteration: u64, cost: f64, layout_str: &str) -> Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        // a single write per record keeps records of concurrent runs on separate lines
+        let line = format!(
+            "{:.3}\t{}\t{}\t{}\t{}\n",
+            timestamp, self.id, iteration, cost, layout_str
+        );
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Returns the restricted pool (see [`LayoutPermutator::with_restricted_pool`]) consisting of
+/// the characters of `pool` and the base layer characters on the keys of the keyboard's
+/// clusters named `pool_clusters` in the `reference` layout.
+pub fn restricted_pool(
+    reference: &Layout,
+    pool: &str,
+    pool_clusters: &[String],
+) -> Result<String, OptimizationError> {
+    let mut pool = pool.to_string();
+    for name in pool_clusters {
+        let cluster = reference.keyboard.cluster(name).ok_or_else(|| {
+            OptimizationError::InvalidParameter(
+                "pool_clusters",
+                format!("Unknown key cluster '{}'", name),
+            )
+        })?;
+        let cluster_chars: Vec<char> = reference
+            .layerkeys
+            .iter()
+            .filter(|k| k.layer == 0 && cluster.keys.contains(&k.key.matrix_position))
+            .map(|k| k.symbol)
+            .collect();
+        cluster_chars.into_iter().for_each(|c| {
+            if !pool.contains(c) {
+                pool.push(c)
+            }
+        });
+    }
+
+    Ok(pool)
+}
+
+/// The base layer characters in the `reference` layout on the keys whose matrix position
+/// satisfies `filter`.
+fn base_layer_chars<F: Fn(&MatrixPosition) -> bool>(reference: &Layout, filter: F) -> String {
+    reference
+        .layerkeys
+        .iter()
+        .filter(|k| k.layer == 0 && filter(&k.key.matrix_position))
+        .map(|k| k.symbol)
+        .collect()
+}
+
+/// The characters on the keys of the matrix row `row` in the `reference` layout. Fixing them
+/// (see [`LayoutPermutator::new`]) keeps the whole row as it is.
+pub fn lock_row(reference: &Layout, row: u8) -> Result<String, OptimizationError> {
+    let chars = base_layer_chars(reference, |pos| pos.1 == row);
+    match chars.is_empty() {
+        true => Err(OptimizationError::InvalidParameter(
+            "lock_rows",
+            format!("The layout has no keys in row {}", row),
+        )),
+        false => Ok(chars),
+    }
+}
+
+/// The characters on the keys of the matrix column `column` in the `reference` layout. Fixing
+/// them (see [`LayoutPermutator::new`]) keeps the whole column as it is.
+pub fn lock_column(reference: &Layout, column: u8) -> Result<String, OptimizationError> {
+    let chars = base_layer_chars(reference, |pos| pos.0 == column);
+    match chars.is_empty() {
+        true => Err(OptimizationError::InvalidParameter(
+            "lock_columns",
+            format!("The layout has no keys in column {}", column),
+        )),
+        false => Ok(chars),
+    }
+}
+
+/// Returns the `fixed_characters` extended by the characters of the locked `rows` and
+/// `columns` (see [`lock_row`] and [`lock_column`]) in the `reference` layout.
+pub fn with_locked_lines(
+    reference: &Layout,
+    fixed_characters: &str,
+    rows: &[u8],
+    columns: &[u8],
+) -> Result<String, OptimizationError> {
+    let mut fixed = fixed_characters.to_string();
+    let rows = rows.iter().map(|row| lock_row(reference, *row));
+    let columns = columns.iter().map(|column| lock_column(reference, *column));
+    for chars in rows.chain(columns) {
+        chars?.chars().for_each(|c| {
+            if !fixed.contains(c) {
+                fixed.push(c)
+            }
+        });
+    }
+
+    Ok(fixed)
+}
+
+/// The matrix positions of the keys holding the `zone` characters in the `reference` layout,
+/// i.e. the part of the keyboard an optimization is restricted to.
+pub fn zone_positions(
+    reference: &Layout,
+    zone: &str,
+) -> Result<Vec<MatrixPosition>, OptimizationError> {
+    zone.chars()
+        .map(|c| {
+            reference
+                .get_layerkey_for_symbol(&c)
+                .map(|k| k.key.matrix_position)
+                .ok_or_else(|| {
+                    OptimizationError::InvalidParameter(
+                        "zone",
+                        format!("The layout does not contain '{}'", c),
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Returns the `fixed_characters` extended by all characters of the `reference` layout that
+/// are outside of the `zone` (see [`zone_positions`]), so that only the zone is permutated. If
+/// no zone characters are given, the `fixed_characters` are returned unchanged.
+pub fn with_zone_fixed(
+    reference: &Layout,
+    fixed_characters: &str,
+    zone: &str,
+) -> Result<String, OptimizationError> {
+    let mut fixed = fixed_characters.to_string();
+    if zone.is_empty() {
+        return Ok(fixed);
+    }
+
+    let positions = zone_positions(reference, zone)?;
+    base_layer_chars(reference, |pos| !positions.contains(pos))
+        .chars()
+        .for_each(|c| {
+            if !fixed.contains(c) {
+                fixed.push(c)
+            }
+        });
+
+    Ok(fixed)
+}
+
+/// Returns a copy of the `evaluator` that only evaluates the ngrams within the `zone` (see
+/// [`zone_positions`] and [`Evaluator::with_zone`]). If no zone characters are given, the
+/// evaluator is returned unchanged.
+pub fn with_zone(
+    evaluator: &Evaluator,
+    reference: &Layout,
+    zone: &str,
+) -> Result<Evaluator, OptimizationError> {
+    let evaluator = evaluator.clone();
+    if zone.is_empty() {
+        return Ok(evaluator);
+    }
+
+    let positions = zone_positions(reference, zone)?;
+    Ok(evaluator.with_zone(Some(&positions)))
+}
+
+/// Returns a copy of the `evaluator` with an additional [`soft_pins::SoftPins`] metric that
+/// adds `cost` for each of the `soft_pinned_chars` that is not at its position in the
+/// `reference` layout. If no characters are given, the evaluator is returned unchanged.
+pub fn with_soft_pins(
+    evaluator: &Evaluator,
+    reference: &Layout,
+    soft_pinned_chars: &str,
+    cost: f64,
+) -> Evaluator {
+    let mut evaluator = evaluator.clone();
+    if !soft_pinned_chars.is_empty() {
+        let params = soft_pins::Parameters {
+            chars: soft_pinned_chars.to_string(),
+            cost,
+        };
+        evaluator.layout_metric(
+            Box::new(soft_pins::SoftPins::new(&params, reference)),
+            1.0,
+            NormalizationType::Fixed(1.0),
+        );
+    }
+
+    evaluator
+}
+
+/// Evaluate the layout given by `permutation` with `f` (receiving the layout string), using
+/// the `cache` (if any) keyed on [`LayoutPermutator::cache_key`].
+pub fn evaluate_cached<T: Clone, F: Fn(&str) -> T>(
+    cache: Option<&Cache<T>>,
+    permutator: &LayoutPermutator,
+    permutation: &[usize],
+    f: F,
+) -> T {
+    let key = permutator.cache_key(permutation);
+    match cache {
+        Some(cache) => cache.get_or_insert_with(&key, || f(&key)),
+        None => f(&key),
+    }
+}
+
+/// The `k` characters among `candidates` whose keys are typed most frequently in the
+/// `reference` layout (counting all layers of a key, e.g. "e" and "E").
+pub fn most_frequent_chars(
+    evaluator: &Evaluator,
+    reference: &Layout,
+    candidates: &str,
+    k: usize,
+) -> String {
+    let unigrams = evaluator.map_unigrams(reference);
+
+    let mut chars: Vec<(char, f64)> = candidates
+        .chars()
+        .filter_map(|c| {
+            reference.get_layerkey_for_symbol(&c).map(|lk| {
+                let weight = unigrams
+                    .grams
+                    .iter()
+                    .filter(|(k, _)| k.key.matrix_position == lk.key.matrix_position)
+                    .map(|(_, w)| w)
+                    .sum();
+                (c, weight)
+            })
+        })
+        .collect();
+    chars.sort_by(|(_, w1), (_, w2)| w2.partial_cmp(w1).unwrap());
+
+    chars.into_iter().take(k).map(|(c, _)| c).collect()
+}
+
+/// Returns a copy of the `evaluator` with an additional [`home_row_pins::HomeRowPins`] metric
+/// that adds `cost` for each of the `top_k` most frequent `permutable_chars` (see
+/// [`most_frequent_chars`]) that is not on the home row. If `top_k` is zero, the evaluator is
+/// returned unchanged.
+pub fn with_home_row_pins(
+    evaluator: &Evaluator,
+    reference: &Layout,
+    permutable_chars: &str,
+    top_k: usize,
+    cost: f64,
+) -> Evaluator {
+    let mut evaluator = evaluator.clone();
+    if top_k > 0 {
+        let chars = most_frequent_chars(&evaluator, reference, permutable_chars, top_k);
+        log::info!("Pinning to the home row: {}", chars);
+
+        let params = home_row_pins::Parameters { chars, cost };
+        evaluator.layout_metric(
+            Box::new(home_row_pins::HomeRowPins::new(&params)),
+            1.0,
+            NormalizationType::Fixed(1.0),
+        );
+    }
+
+    evaluator
+}
+
+/// The layout that optimally assigns the permutable characters of `layout_str` (those not in
+/// `fixed_characters`) to the positions of the permutable keys, considering only the unigram
+/// frequency of each character's key (all its layers, e.g. "e" and "E") times the cost of the
+/// position's key. Bigrams and trigrams are ignored, so the result is an instantly computed
+/// baseline (and starting point) for an optimization.
+pub fn optimal_unigram_assignment(
+    evaluator: &Evaluator,
+    layout_generator: &dyn LayoutGenerator,
+    layout_str: &str,
+    fixed_characters: &str,
+) -> Result<(String, Layout)> {
+    let reference = layout_generator.generate(layout_str)?;
+    let unigrams = evaluator.map_unigrams(&reference);
+    let pm = LayoutPermutator::new(layout_str, fixed_characters);
+    let layout_chars: Vec<char> = layout_str.chars().collect();
+
+    let key_weight = |c: char| -> f64 {
+        reference
+            .get_layerkey_for_symbol(&c)
+            .map(|lk| {
+                unigrams
+                    .grams
+                    .iter()
+                    .filter(|(k, _)| k.key.matrix_position == lk.key.matrix_position)
+                    .map(|(_, w)| w)
+                    .sum()
+            })
+            .unwrap_or(0.0)
+    };
+    let key_cost = |c: char| -> f64 {
+        reference
+            .get_layerkey_for_symbol(&c)
+            .map(|lk| evaluator.key_cost(lk).unwrap_or(lk.key.cost))
+            .unwrap_or(0.0)
+    };
+
+    let positions = pm.get_permutable_indices();
+    let position_costs: Vec<f64> = positions
+        .iter()
+        .map(|i| key_cost(layout_chars[*i]))
+        .collect();
+    let costs: Vec<Vec<f64>> = pm
+        .permutable_chars()
+        .chars()
+        .map(|c| {
+            let weight = key_weight(c);
+            position_costs.iter().map(|cost| weight * cost).collect()
+        })
+        .collect();
+
+    let permutation: Vec<usize> = assignment::solve(&costs)
+        .into_iter()
+        .map(|col| positions[col])
+        .collect();
+    let layout_str = pm.generate_string(&permutation);
+    let layout = layout_generator.generate(&layout_str)?;
+
+    Ok((layout_str, layout))
+}
+
+/// The layouts of all single-swap neighbors of `permutation` (see
+/// [`LayoutPermutator::neighbors`]), generated lazily. Neighbors that the layout generator
+/// rejects are skipped.
+pub fn neighbor_layouts<'a>(
+    permutator: &'a LayoutPermutator,
+    layout_generator: &'a dyn LayoutGenerator,
+    permutation: &'a [usize],
+) -> impl Iterator<Item = Layout> + 'a {
+    permutator.neighbors(permutation).filter_map(move |p| {
+        layout_generator
+            .generate(&permutator.generate_string(&p))
+            .ok()
+    })
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn fitness_transforms_need_positive_parameters() {
+        assert!(FitnessTransform::Negate { offset: -1.0 }.validate().is_ok());
+        assert!(FitnessTransform::Inverse { numerator: 1e8 }
+            .validate()
+            .is_ok());
+        assert!(FitnessTransform::Inverse { numerator: 0.0 }
+            .validate()
+            .is_err());
+        assert!(FitnessTransform::Exponential {
+            scale: 1.0,
+            factor: 1.0
+        }
+        .validate()
+        .is_ok());
+        assert!(FitnessTransform::Exponential {
+            scale: -1.0,
+            factor: 1.0
+        }
+        .validate()
+        .is_err());
+        assert!(FitnessTransform::Exponential {
+            scale: 1.0,
+            factor: 0.0
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn parse_permutation_inverts_generate_string() {
+        let pm = LayoutPermutator::new("abcde", "c");
+        let permutation = pm.parse_permutation("ebc ad", "layout").unwrap();
+
+        assert_eq!(pm.generate_string(&permutation), "ebcad");
+        assert!(pm.parse_permutation("ebacd", "layout").is_err());
+        assert!(pm.parse_permutation("ebcaa", "layout").is_err());
+        assert!(pm.parse_permutation("ebca", "layout").is_err());
+    }
+
+    #[test]
+    fn neighbors_swap_two_permutable_keys() {
+        let pm = LayoutPermutator::new("abcde", "c");
+        let permutation = pm.get_permutable_indices();
+        let neighbors: Vec<String> = pm
+            .neighbors(&permutation)
+            .map(|p| pm.generate_string(&p))
+            .collect();
+
+        assert_eq!(
+            neighbors,
+            vec!["bacde", "dbcae", "ebcda", "adcbe", "aecdb", "abced"]
+        );
+    }
+
+    #[test]
+    fn neighbors_respect_restricted_pool_and_distance() {
+        let pm = LayoutPermutator::new("abcd", "").with_restricted_pool("d");
+        let permutation = pm.get_permutable_indices();
+        assert!(pm
+            .neighbors(&permutation)
+            .all(|p| pm.generate_string(&p).ends_with('d')));
+
+        let pm = LayoutPermutator::new("abcd", "")
+            .with_max_distance("abcd", 2)
+            .unwrap();
+        let permutation = vec![1, 0, 2, 3];
+        let neighbors: Vec<Vec<usize>> = pm.neighbors(&permutation).collect();
+
+        assert_eq!(neighbors, vec![vec![0, 1, 2, 3]]);
+    }
+
+    #[test]
+    fn best_layout_log_writes_one_line_per_record() {
+        #[derive(Clone, Default)]
+        struct SharedBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl Write for SharedBuffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = SharedBuffer::default();
+        let mut log = BestLayoutLog::new("run 1", Box::new(buffer.clone()));
+        log.record(0, 300.5, "abcd").unwrap();
+        log.record(42, 250.0, "bacd").unwrap();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let records: Vec<Vec<&str>> = output
+            .lines()
+            .map(|line| line.split('\t').collect())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0][1..], ["run 1", "0", "300.5", "abcd"]);
+        assert_eq!(records[1][1..], ["run 1", "42", "250", "bacd"]);
+    }
+
+    #[test]
+    fn locked_rows_and_columns_fix_their_characters() {
+        use keyboard_layout::{
+            config::LayoutConfig, keyboard::Keyboard, neo_layout_generator::NeoLayoutGenerator,
+        };
+        use std::str::FromStr;
+
+        let cfg =
+            LayoutConfig::from_str(include_str!("../../../config/keyboard/sval.yml")).unwrap();
+        let keyboard = Arc::new(Keyboard::from_yaml_object(cfg.keyboard));
+        let layout_generator = NeoLayoutGenerator::from_object(cfg.base_layout, keyboard);
+        let layout_str = "q0a1zw2sbxe3dtcr4fgvuhj5miyk67onl89p={}([";
+        let layout = layout_generator.generate(layout_str).unwrap();
+
+        assert_eq!(lock_row(&layout, 1).unwrap(), "qweruiop");
+        assert_eq!(lock_column(&layout, 11).unwrap(), "rfv");
+        assert!(lock_row(&layout, 9).is_err());
+
+        let fixed = with_locked_lines(&layout, "qa", &[1], &[11]).unwrap();
+        assert_eq!(fixed, "qaweruiopfv");
+
+        let pm = LayoutPermutator::new(layout_str, &fixed);
+        assert_eq!(pm.nr_permutable_keys(), layout_str.len() - fixed.len());
+    }
+
+    #[test]
+    fn zone_fixes_all_characters_outside_of_it() {
+        use keyboard_layout::{
+            config::LayoutConfig, keyboard::Keyboard, neo_layout_generator::NeoLayoutGenerator,
+        };
+        use std::str::FromStr;
+
+        let cfg =
+            LayoutConfig::from_str(include_str!("../../../config/keyboard/sval.yml")).unwrap();
+        let keyboard = Arc::new(Keyboard::from_yaml_object(cfg.keyboard));
+        let layout_generator = NeoLayoutGenerator::from_object(cfg.base_layout, keyboard);
+        let layout_str = "q0a1zw2sbxe3dtcr4fgvuhj5miyk67onl89p={}([";
+        let layout = layout_generator.generate(layout_str).unwrap();
+
+        let zone = "qweruiop";
+        let fixed = with_zone_fixed(&layout, "q", zone).unwrap();
+        assert!(fixed.starts_with('q'));
+        assert!(zone.chars().skip(1).all(|c| !fixed.contains(c)));
+
+        let pm = LayoutPermutator::new(layout_str, &fixed);
+        assert_eq!(pm.permutable_chars(), "weruiop");
+
+        assert_eq!(with_zone_fixed(&layout, "q", "").unwrap(), "q");
+        assert!(zone_positions(&layout, "q\u{2603}").is_err());
+    }
 }
@@ -1,16 +1,30 @@
 use keyboard_layout::{layout::Layout, layout_generator::LayoutGenerator};
 use layout_evaluation::{cache::Cache, evaluation::Evaluator};
 
-use layout_optimization_common::LayoutPermutator;
+use layout_optimization_common::{
+    evaluate_cached, restricted_pool, with_home_row_pins, with_locked_lines, with_soft_pins,
+    with_zone, with_zone_fixed, BestLayoutLog, Curriculum, FitnessTransform, LayoutPermutator,
+    MetricSchedule, OptimizationError,
+};
 
+use ahash::AHashMap;
 use anyhow::Result;
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
-use std::{fs::File, sync::Arc};
+use std::{
+    fs::File,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use genevo::{
-    genetic::{Children, FitnessFunction, Parents},
-    operator::{prelude::*, CrossoverOp, GeneticOperator},
+    algorithm::EvaluatedPopulation,
+    genetic::{Children, FitnessFunction, Offspring, Parents},
+    operator::{
+        prelude::*, CrossoverOp, GeneticOperator, MutationOp, ReinsertionOp, SingleObjective,
+    },
     population::Population,
     prelude::*,
     random::SliceRandom,
@@ -18,7 +32,48 @@ use genevo::{
     types::fmt::Display,
 };
 
-#[derive(Serialize, Deserialize, Debug)]
+/// The crossover operator used for recombining two parent layouts.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossoverType {
+    /// Children are copies of their parents (only mutation alters the genomes).
+    NoOp,
+    /// Order crossover (OX1).
+    Order,
+    /// Partially-mapped crossover (PMX).
+    PartiallyMapped,
+    /// Cycle crossover as used in https://github.com/Coletronix/Genetic-Keyboard-Generator
+    Cycle,
+}
+
+impl Default for CrossoverType {
+    fn default() -> Self {
+        CrossoverType::NoOp
+    }
+}
+
+/// The strategy for choosing the individuals of the old population that survive into the next
+/// generation (next to the offspring, see `reinsertion_ratio`).
+///
+/// Independent of the strategy, the `elitism` fittest individuals always survive.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReinsertionType {
+    /// Survivors are chosen uniformly at random.
+    Uniform,
+    /// The fittest individuals survive.
+    Elitist,
+    /// Survivors are chosen randomly with a probability proportional to their fitness.
+    FitnessProportional,
+}
+
+impl Default for ReinsertionType {
+    fn default() -> Self {
+        ReinsertionType::Uniform
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Parameters {
     pub population_size: usize,
     pub generation_limit: u64,
@@ -26,6 +81,84 @@ pub struct Parameters {
     pub selection_ratio: f64,
     pub mutation_rate: f64,
     pub reinsertion_ratio: f64,
+    /// Number of the fittest individuals that are carried over unchanged into the next
+    /// generation
+    #[serde(default)]
+    pub elitism: usize,
+    #[serde(default)]
+    pub crossover: CrossoverType,
+    /// Strategy for choosing the surviving individuals of the old population
+    #[serde(default)]
+    pub reinsertion: ReinsertionType,
+    /// Characters that should preferably stay at their position in the starting layout
+    #[serde(default)]
+    pub soft_pinned_chars: String,
+    /// Cost added for each soft-pinned character that is not at its starting position
+    #[serde(default = "default_soft_pin_cost")]
+    pub soft_pin_cost: f64,
+    /// Number of most frequent (permutable) characters that should preferably be on the home row
+    #[serde(default)]
+    pub home_row_top_k: usize,
+    /// Cost added for each of these characters that is not on the home row
+    #[serde(default = "default_home_row_pin_cost")]
+    pub home_row_pin_cost: f64,
+    /// Characters (e.g. thumb key symbols) that are only permutated among the positions they
+    /// occupy in the starting layout (e.g. thumb keys)
+    #[serde(default)]
+    pub thumb_pool: String,
+    /// Names of key clusters (see the keyboard configuration) whose characters in the starting
+    /// layout are added to the `thumb_pool`
+    #[serde(default)]
+    pub pool_clusters: Vec<String>,
+    /// Matrix rows whose characters in the starting layout are kept at their positions (in
+    /// addition to the fixed characters)
+    #[serde(default)]
+    pub lock_rows: Vec<u8>,
+    /// Matrix columns whose characters in the starting layout are kept at their positions (in
+    /// addition to the fixed characters)
+    #[serde(default)]
+    pub lock_columns: Vec<u8>,
+    /// Characters whose keys in the starting layout form the zone the optimization is
+    /// restricted to (the whole layout if empty): All other characters are kept at their
+    /// positions and ngrams involving keys outside of the zone are not evaluated
+    #[serde(default)]
+    pub zone: String,
+    /// Only consider layouts that differ from the `max_distance_reference` layout in at most
+    /// this many positions
+    #[serde(default)]
+    pub max_distance: Option<usize>,
+    /// Reference layout of the `max_distance` limit (the starting layout if not given)
+    #[serde(default)]
+    pub max_distance_reference: Option<String>,
+    /// Transformation of a layout's cost into the fitness to maximize
+    #[serde(default = "default_fitness_transform")]
+    pub fitness_transform: FitnessTransform,
+    /// Schedules scaling the weights of metrics over the generations (a "curriculum")
+    #[serde(default)]
+    pub metric_schedules: Vec<MetricSchedule>,
+    /// Start from the population in this dump file (see [`PopulationDump`]) instead of random
+    /// layouts or the starting layout
+    #[serde(default)]
+    pub initial_population: Option<String>,
+    /// Write the population of each generation to this dump file (see [`PopulationDump`]),
+    /// e.g. for resuming the optimization later or on another machine
+    #[serde(default)]
+    pub population_dump: Option<String>,
+    /// Append each new best layout to this file (see [`BestLayoutLog`])
+    #[serde(default)]
+    pub best_layout_log: Option<String>,
+}
+
+fn default_soft_pin_cost() -> f64 {
+    10.0
+}
+
+fn default_home_row_pin_cost() -> f64 {
+    10.0
+}
+
+fn default_fitness_transform() -> FitnessTransform {
+    FitnessTransform::Inverse { numerator: 1e8 }
 }
 
 impl Default for Parameters {
@@ -37,41 +170,238 @@ impl Default for Parameters {
             selection_ratio: 0.7,
             mutation_rate: 0.1,
             reinsertion_ratio: 0.7,
+            elitism: 0,
+            crossover: CrossoverType::default(),
+            reinsertion: ReinsertionType::default(),
+            soft_pinned_chars: String::new(),
+            soft_pin_cost: default_soft_pin_cost(),
+            home_row_top_k: 0,
+            home_row_pin_cost: default_home_row_pin_cost(),
+            thumb_pool: String::new(),
+            pool_clusters: Vec::new(),
+            lock_rows: Vec::new(),
+            lock_columns: Vec::new(),
+            zone: String::new(),
+            max_distance: None,
+            max_distance_reference: None,
+            fitness_transform: default_fitness_transform(),
+            metric_schedules: Vec::new(),
+            initial_population: None,
+            population_dump: None,
+            best_layout_log: None,
         }
     }
 }
 
 impl Parameters {
+    /// The parameter preset with the given name (see
+    /// [`PRESETS`](layout_optimization_common::PRESETS)). Parameters that the presets do not
+    /// tune keep their defaults.
+    pub fn preset(name: &str) -> Result<Self, OptimizationError> {
+        let (population_size, generation_limit, mutation_rate, elitism) = match name {
+            "quick" => (200, 300, 0.02, 2),
+            "balanced" => (1000, 2000, 0.01, 5),
+            "thorough" => (2000, 5000, 0.01, 10),
+            _ => return Err(OptimizationError::UnknownPreset(name.to_string())),
+        };
+
+        Ok(Parameters {
+            population_size,
+            generation_limit,
+            selection_ratio: 0.3,
+            mutation_rate,
+            reinsertion_ratio: 0.5,
+            elitism,
+            ..Default::default()
+        })
+    }
+
     pub fn from_yaml(filename: &str) -> Result<Self> {
         let f = File::open(filename)?;
-        Ok(serde_yaml::from_reader(f)?)
+        let params: Self = serde_yaml::from_reader(f)?;
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Checks the parameters for values that would prevent a meaningful optimization.
+    pub fn validate(&self) -> Result<(), OptimizationError> {
+        self.fitness_transform.validate()?;
+        if self.population_size < 2 {
+            return Err(OptimizationError::InvalidParameter(
+                "population_size",
+                format!("Needs to be at least 2, got {}", self.population_size),
+            ));
+        }
+        if self.num_individuals_per_parents < 2 {
+            return Err(OptimizationError::InvalidParameter(
+                "num_individuals_per_parents",
+                format!(
+                    "Needs to be at least 2, got {}",
+                    self.num_individuals_per_parents
+                ),
+            ));
+        }
+        let ratios = [
+            ("selection_ratio", self.selection_ratio),
+            ("mutation_rate", self.mutation_rate),
+            ("reinsertion_ratio", self.reinsertion_ratio),
+        ];
+        if let Some((name, value)) = ratios.iter().find(|(_, v)| !(0.0..=1.0).contains(v)) {
+            return Err(OptimizationError::InvalidParameter(
+                name,
+                format!("Needs to be between 0 and 1, got {}", value),
+            ));
+        }
+        if self.elitism >= self.population_size {
+            return Err(OptimizationError::InvalidParameter(
+                "elitism",
+                format!(
+                    "Needs to be smaller than the population size ({}), got {}",
+                    self.population_size, self.elitism
+                ),
+            ));
+        }
+        self.curriculum()?;
+
+        Ok(())
+    }
+
+    /// The curriculum of the optimization. Its progress advances with each generation, so that
+    /// all individuals of a generation are evaluated with the same weights.
+    fn curriculum(&self) -> Result<Curriculum, OptimizationError> {
+        Curriculum::new(&self.metric_schedules, self.generation_limit)
     }
 }
 
 // The genotype
 type Genotype = Vec<usize>;
 
+/// An individual of a [`PopulationDump`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DumpedIndividual {
+    /// The individual's layout string
+    pub layout: String,
+    /// The individual's fitness (in the dumped optimization)
+    pub fitness: usize,
+}
+
+/// A population of a genetic optimization, e.g. for resuming the optimization in another
+/// process. The individuals are stored as layout strings, so that a dump can be used with any
+/// optimization of the same characters (with the same fixed characters at the same positions).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PopulationDump {
+    /// Generation of the population
+    pub generation: u64,
+    /// The permutable characters of the optimization
+    pub permutable_chars: String,
+    pub individuals: Vec<DumpedIndividual>,
+}
+
+impl PopulationDump {
+    /// Dump the evaluated population of the optimization given by `permutator`.
+    pub fn new(
+        generation: u64,
+        population: &EvaluatedPopulation<Genotype, usize>,
+        permutator: &LayoutPermutator,
+    ) -> Self {
+        let individuals = population
+            .individuals()
+            .iter()
+            .zip(population.fitness_values().iter())
+            .map(|(genome, fitness)| DumpedIndividual {
+                layout: permutator.generate_string(genome),
+                fitness: *fitness,
+            })
+            .collect();
+
+        Self {
+            generation,
+            permutable_chars: permutator.permutable_chars(),
+            individuals,
+        }
+    }
+
+    pub fn from_yaml(filename: &str) -> Result<Self> {
+        let f = File::open(filename)?;
+        let dump: Self = serde_yaml::from_reader(f)?;
+        Ok(dump)
+    }
+
+    pub fn to_yaml(&self, filename: &str) -> Result<()> {
+        let f = File::create(filename)?;
+        serde_yaml::to_writer(f, self)?;
+        Ok(())
+    }
+
+    /// The genomes of the dumped individuals for the optimization given by `permutator`.
+    ///
+    /// Fails if the dump stems from an optimization of other characters or if any of its
+    /// layouts does not fit the permutator (e.g. due to different fixed characters).
+    pub fn genomes(&self, permutator: &LayoutPermutator) -> Result<Vec<Genotype>> {
+        let sorted = |s: &str| {
+            let mut chars: Vec<char> = s.chars().collect();
+            chars.sort_unstable();
+            chars
+        };
+        if sorted(&self.permutable_chars) != sorted(&permutator.permutable_chars()) {
+            return Err(OptimizationError::InvalidParameter(
+                "initial_population",
+                format!(
+                    "The dump permutates the characters '{}' instead of '{}'",
+                    self.permutable_chars,
+                    permutator.permutable_chars()
+                ),
+            )
+            .into());
+        }
+        if self.individuals.is_empty() {
+            return Err(OptimizationError::InvalidParameter(
+                "initial_population",
+                "The dump contains no individuals".to_string(),
+            )
+            .into());
+        }
+
+        let genomes = self
+            .individuals
+            .iter()
+            .map(|individual| {
+                permutator.parse_permutation(&individual.layout, "initial_population")
+            })
+            .collect::<Result<Vec<Genotype>, OptimizationError>>()?;
+
+        Ok(genomes)
+    }
+}
+
 /// The fitness function for [`Genotype`]s.
 #[derive(Clone, Debug)]
 pub struct FitnessCalc {
     evaluator: Arc<Evaluator>,
     permutator: LayoutPermutator,
     layout_generator: Box<dyn LayoutGenerator>,
+    fitness_transform: FitnessTransform,
     result_cache: Option<Cache<usize>>,
+    curriculum: Curriculum,
 }
 
 impl FitnessFunction<Genotype, usize> for FitnessCalc {
     fn fitness_of(&self, genome: &Genotype) -> usize {
-        let layout_str = self.permutator.generate_string(genome);
-        let l = self.layout_generator.generate(&layout_str).unwrap();
+        let fitness = |layout_str: &str| {
+            let l = self.layout_generator.generate(layout_str).unwrap();
+            let cost = self
+                .curriculum
+                .total_cost(&self.evaluator.evaluate_layout(&l));
+            self.fitness_transform.fitness(cost).max(0.0) as usize
+        };
 
         // Get & return the evaluation-result
-        match &self.result_cache {
-            Some(result_cache) => result_cache.get_or_insert_with(&layout_str, || {
-                self.evaluator.evaluate_layout(&l).optimization_score()
-            }),
-            None => self.evaluator.evaluate_layout(&l).optimization_score(),
-        }
+        evaluate_cached(
+            self.result_cache.as_ref(),
+            &self.permutator,
+            genome,
+            fitness,
+        )
     }
 
     fn average(&self, fitness_values: &[usize]) -> usize {
@@ -89,12 +419,14 @@ impl FitnessFunction<Genotype, usize> for FitnessCalc {
 
 struct LayoutBuilder {
     indices: Vec<usize>,
+    permutator: LayoutPermutator,
 }
 
 impl LayoutBuilder {
     fn with_permutable_layout(layout_prototype: &LayoutPermutator) -> Self {
         Self {
             indices: layout_prototype.get_permutable_indices(),
+            permutator: layout_prototype.clone(),
         }
     }
 }
@@ -106,29 +438,31 @@ impl GenomeBuilder<Vec<usize>> for LayoutBuilder {
     {
         let mut s: Vec<usize> = self.indices.clone();
         s.shuffle(rng);
-        s
+        self.permutator.limit_distance(&s, rng)
     }
 }
 
 struct FromGivenLayoutBuilder {
     indices: Vec<usize>,
+    permutator: LayoutPermutator,
 }
 
 impl FromGivenLayoutBuilder {
     fn with_permutable_layout(layout_prototype: &LayoutPermutator) -> Self {
         Self {
             indices: layout_prototype.get_permutable_indices(),
+            permutator: layout_prototype.clone(),
         }
     }
 }
 
 impl GenomeBuilder<Vec<usize>> for FromGivenLayoutBuilder {
-    fn build_genome<R>(&self, _: usize, _rng: &mut R) -> Vec<usize>
+    fn build_genome<R>(&self, _: usize, rng: &mut R) -> Vec<usize>
     where
         R: Rng + Sized,
     {
-        // start with initial layout
-        self.indices.clone()
+        // start with initial layout (moved within the distance limit, if necessary)
+        self.permutator.limit_distance(&self.indices, rng)
     }
 }
 
@@ -206,21 +540,243 @@ impl CrossoverOp<Vec<usize>> for MyCrossover {
     }
 }
 
+/// Crossover operator that dispatches to the operator chosen by a [`CrossoverType`].
+///
+/// Every child is checked to be a permutation of its first parent's genes and repaired
+/// if necessary, so that no key gets lost or duplicated. Children are then moved within the
+/// permutator's distance limit (see [`LayoutPermutator::with_max_distance`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigurableCrossover {
+    crossover_type: CrossoverType,
+    permutator: LayoutPermutator,
+}
+impl ConfigurableCrossover {
+    pub fn new(crossover_type: CrossoverType, permutator: LayoutPermutator) -> Self {
+        ConfigurableCrossover {
+            crossover_type,
+            permutator,
+        }
+    }
+}
+impl GeneticOperator for ConfigurableCrossover {
+    fn name() -> String {
+        "Configurable-Crossover".to_string()
+    }
+}
+impl CrossoverOp<Vec<usize>> for ConfigurableCrossover {
+    fn crossover<R>(&self, parents: Parents<Vec<usize>>, rng: &mut R) -> Children<Vec<usize>>
+    where
+        R: Rng + Sized,
+    {
+        let reference = parents[0].clone();
+        // the cut points of genevo's crossover operators need at least four genes
+        let crossover_type = match reference.len() {
+            0..=3 => CrossoverType::NoOp,
+            _ => self.crossover_type,
+        };
+        let children = match crossover_type {
+            CrossoverType::NoOp => NoOpCrossover::new().crossover(parents, rng),
+            CrossoverType::Order => OrderOneCrossover::new().crossover(parents, rng),
+            CrossoverType::PartiallyMapped => {
+                PartiallyMappedCrossover::new().crossover(parents, rng)
+            }
+            CrossoverType::Cycle => MyCrossover::new().crossover(parents, rng),
+        };
+
+        children
+            .into_iter()
+            .map(|child| {
+                self.permutator
+                    .limit_distance(&repair_permutation(child, &reference), rng)
+            })
+            .collect()
+    }
+}
+
+/// Mutation operator that applies a [`SwapOrderMutator`] and moves the mutated genome within
+/// the permutator's distance limit (see [`LayoutPermutator::with_max_distance`]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistanceLimitedMutator {
+    mutator: SwapOrderMutator,
+    permutator: LayoutPermutator,
+}
+impl DistanceLimitedMutator {
+    pub fn new(mutation_rate: f64, permutator: LayoutPermutator) -> Self {
+        DistanceLimitedMutator {
+            mutator: SwapOrderMutator::new(mutation_rate),
+            permutator,
+        }
+    }
+}
+impl GeneticOperator for DistanceLimitedMutator {
+    fn name() -> String {
+        "Distance-Limited-Swap-Order-Mutation".to_string()
+    }
+}
+impl MutationOp<Vec<usize>> for DistanceLimitedMutator {
+    fn mutate<R>(&self, genome: Vec<usize>, rng: &mut R) -> Vec<usize>
+    where
+        R: Rng + Sized,
+    {
+        let mutated = self.mutator.mutate(genome, rng);
+        self.permutator.limit_distance(&mutated, rng)
+    }
+}
+
+/// Reinsertion operator that fills the new population with a `replace_ratio` share of the
+/// offspring and chooses the surviving individuals of the old population according to a
+/// [`ReinsertionType`]. The `elitism` fittest individuals of the old population are always
+/// carried over unchanged.
+///
+/// In contrast to genevo's [`ElitistReinserter`], the offspring does not need to be evaluated
+/// during reinsertion (which is not parallelized).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigurableReinserter {
+    reinsertion_type: ReinsertionType,
+    replace_ratio: f64,
+    elitism: usize,
+}
+impl ConfigurableReinserter {
+    pub fn new(reinsertion_type: ReinsertionType, replace_ratio: f64, elitism: usize) -> Self {
+        ConfigurableReinserter {
+            reinsertion_type,
+            replace_ratio,
+            elitism,
+        }
+    }
+}
+impl GeneticOperator for ConfigurableReinserter {
+    fn name() -> String {
+        "Configurable-Reinserter".to_string()
+    }
+}
+impl SingleObjective for ConfigurableReinserter {}
+impl ReinsertionOp<Genotype, usize> for ConfigurableReinserter {
+    fn combine<R>(
+        &self,
+        offspring: &mut Offspring<Genotype>,
+        evaluated: &EvaluatedPopulation<Genotype, usize>,
+        rng: &mut R,
+    ) -> Vec<Genotype>
+    where
+        R: Rng + Sized,
+    {
+        let individuals = evaluated.individuals();
+        let fitness_values = evaluated.fitness_values();
+        let mut ranking: Vec<usize> = (0..individuals.len()).collect();
+        ranking.sort_by_key(|i| std::cmp::Reverse(fitness_values[*i]));
+        let n_elite = self.elitism.min(ranking.len());
+
+        if self.reinsertion_type == ReinsertionType::Uniform {
+            let mut new_population =
+                UniformReinserter::new(self.replace_ratio).combine(offspring, evaluated, rng);
+            if n_elite == 0 {
+                return new_population;
+            }
+
+            // the uniform reinserter places the individuals of the old population last
+            new_population.truncate(individuals.len().saturating_sub(n_elite));
+            new_population.extend(ranking[..n_elite].iter().map(|i| individuals[*i].clone()));
+
+            return new_population;
+        }
+
+        let n_offspring = ((individuals.len() as f64 * self.replace_ratio).round() as usize)
+            .min(offspring.len())
+            .min(individuals.len() - n_elite);
+        let n_survivors = individuals.len() - n_offspring;
+
+        offspring.shuffle(rng);
+        let mut new_population: Vec<Genotype> = offspring.drain(..n_offspring).collect();
+
+        // the elite survives in any case, the remaining survivors are chosen by the strategy
+        let (elite, rest) = ranking.split_at(n_elite);
+        let survivors: Vec<usize> = match self.reinsertion_type {
+            ReinsertionType::Elitist => rest.to_vec(),
+            _ => {
+                // weighted sampling without replacement (Efraimidis & Spirakis) with
+                // logarithmic keys, which stay distinguishable for large fitness values
+                let mut keyed: Vec<(f64, usize)> = rest
+                    .iter()
+                    .map(|i| {
+                        let key = match fitness_values[*i] {
+                            0 => f64::NEG_INFINITY,
+                            f => rng.gen::<f64>().ln() / f as f64,
+                        };
+                        (key, *i)
+                    })
+                    .collect();
+                keyed.sort_by(|(k1, _), (k2, _)| k2.partial_cmp(k1).unwrap());
+                keyed.into_iter().map(|(_, i)| i).collect()
+            }
+        };
+
+        new_population.extend(
+            elite
+                .iter()
+                .chain(survivors.iter())
+                .take(n_survivors)
+                .map(|i| individuals[*i].clone()),
+        );
+
+        new_population
+    }
+}
+
+/// Make sure that `child` contains exactly the genes of `reference`. Duplicated or foreign
+/// genes are replaced by the missing ones (in the order they appear in `reference`).
+fn repair_permutation(child: Genotype, reference: &[usize]) -> Genotype {
+    let mut available: AHashMap<usize, usize> = AHashMap::default();
+    reference
+        .iter()
+        .for_each(|g| *available.entry(*g).or_insert(0) += 1);
+
+    let mut repaired: Vec<Option<usize>> = child
+        .into_iter()
+        .take(reference.len())
+        .map(|g| match available.get_mut(&g) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                Some(g)
+            }
+            _ => None,
+        })
+        .collect();
+    repaired.resize(reference.len(), None);
+
+    if repaired.iter().all(|g| g.is_some()) {
+        return repaired.into_iter().flatten().collect();
+    }
+
+    let mut missing = reference.iter().filter(|g| match available.get_mut(g) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            true
+        }
+        _ => false,
+    });
+
+    repaired
+        .into_iter()
+        .map(|g| g.unwrap_or_else(|| *missing.next().unwrap()))
+        .collect()
+}
+
 pub type MySimulator = Simulator<
     GeneticAlgorithm<
         Vec<usize>,
         usize,
         FitnessCalc,
         MaximizeSelector,
-        // PartiallyMappedCrossover,
-        // MyCrossover,
-        NoOpCrossover,
-        SwapOrderMutator,
-        UniformReinserter, // we do not use an elitist reinserter due to performance reasons (non-parallelized evaluation)
+        ConfigurableCrossover,
+        DistanceLimitedMutator,
+        ConfigurableReinserter, // genevo's elitist reinserter would evaluate the offspring without parallelization
     >,
     GenerationLimit,
 >;
 
+/// Initialize a genetic optimization. The returned [`Curriculum`] needs to be advanced with
+/// [`Curriculum::step`] after each step (generation) of the simulator.
 pub fn init_optimization(
     params: &Parameters,
     evaluator: &Evaluator,
@@ -229,52 +785,156 @@ pub fn init_optimization(
     fixed_characters: &str,
     start_with_layout: bool,
     cache_results: bool,
-) -> (MySimulator, LayoutPermutator) {
-    let pm = LayoutPermutator::new(layout_str, fixed_characters);
-    let initial_population: Population<Genotype> = if start_with_layout {
-        build_population()
-            .with_genome_builder(FromGivenLayoutBuilder::with_permutable_layout(&pm))
-            .of_size(params.population_size)
-            .uniform_at_random()
-    } else {
-        build_population()
-            .with_genome_builder(LayoutBuilder::with_permutable_layout(&pm))
-            .of_size(params.population_size)
-            .uniform_at_random()
-    };
-
+) -> Result<(MySimulator, LayoutPermutator, Curriculum), OptimizationError> {
     let result_cache = if cache_results {
         Some(Cache::new())
     } else {
         None
     };
 
+    init_optimization_with_cache(
+        params,
+        evaluator,
+        layout_str,
+        layout_generator.as_ref(),
+        fixed_characters,
+        start_with_layout,
+        result_cache,
+    )
+}
+
+/// Like [`init_optimization`], but uses the given `result_cache` (if any), e.g. to inspect its
+/// hit rate afterwards.
+pub fn init_optimization_with_cache(
+    params: &Parameters,
+    evaluator: &Evaluator,
+    layout_str: &str,
+    layout_generator: &dyn LayoutGenerator,
+    fixed_characters: &str,
+    start_with_layout: bool,
+    result_cache: Option<Cache<usize>>,
+) -> Result<(MySimulator, LayoutPermutator, Curriculum), OptimizationError> {
+    let reference_layout = layout_generator
+        .generate(layout_str)
+        .map_err(|e| OptimizationError::InvalidLayout(layout_str.to_string(), e.to_string()))?;
+    let pool = restricted_pool(&reference_layout, &params.thumb_pool, &params.pool_clusters)?;
+    let fixed_characters = with_locked_lines(
+        &reference_layout,
+        fixed_characters,
+        &params.lock_rows,
+        &params.lock_columns,
+    )?;
+    let fixed_characters = with_zone_fixed(&reference_layout, &fixed_characters, &params.zone)?;
+    let pm = LayoutPermutator::new(layout_str, &fixed_characters).with_restricted_pool(&pool);
+    let pm = match params.max_distance {
+        Some(max_distance) => pm.with_max_distance(
+            params
+                .max_distance_reference
+                .as_deref()
+                .unwrap_or(layout_str),
+            max_distance,
+        )?,
+        None => pm,
+    };
+    let evaluator = with_zone(evaluator, &reference_layout, &params.zone)?;
+    let evaluator = with_soft_pins(
+        &evaluator,
+        &reference_layout,
+        &params.soft_pinned_chars,
+        params.soft_pin_cost,
+    );
+    let evaluator = with_home_row_pins(
+        &evaluator,
+        &reference_layout,
+        &pm.permutable_chars(),
+        params.home_row_top_k,
+        params.home_row_pin_cost,
+    );
+    let initial_population: Population<Genotype> =
+        if let Some(filename) = &params.initial_population {
+            let dump = PopulationDump::from_yaml(filename)
+                .and_then(|dump| dump.genomes(&pm))
+                .map_err(|e| {
+                    OptimizationError::InvalidParameter(
+                        "initial_population",
+                        format!("Could not read population dump '{}': {}", filename, e),
+                    )
+                })?;
+            let mut rng = rand::thread_rng();
+            let genomes: Vec<Genotype> = dump
+                .iter()
+                .map(|genome| pm.limit_distance(genome, &mut rng))
+                .collect();
+            if genomes.len() != params.population_size {
+                log::warn!(
+                    "The population dump contains {} individuals instead of the population size {}",
+                    genomes.len(),
+                    params.population_size
+                );
+            }
+            Population::with_individuals(genomes)
+        } else if start_with_layout {
+            build_population()
+                .with_genome_builder(FromGivenLayoutBuilder::with_permutable_layout(&pm))
+                .of_size(params.population_size)
+                .uniform_at_random()
+        } else {
+            build_population()
+                .with_genome_builder(LayoutBuilder::with_permutable_layout(&pm))
+                .of_size(params.population_size)
+                .uniform_at_random()
+        };
+
+    // fitness values change with the progress of a curriculum and can not be cached
+    let curriculum = params.curriculum()?;
+    let result_cache = match curriculum.is_active() {
+        true => None,
+        false => result_cache,
+    };
+
     let sim = simulate(
         genetic_algorithm()
             .with_evaluation(FitnessCalc {
-                evaluator: Arc::new(evaluator.clone()),
+                evaluator: Arc::new(evaluator),
                 permutator: pm.clone(),
-                layout_generator: layout_generator.clone(),
+                layout_generator: layout_generator.clone_box(),
+                fitness_transform: params.fitness_transform,
                 result_cache,
+                curriculum: curriculum.clone(),
             })
             .with_selection(MaximizeSelector::new(
                 params.selection_ratio,
                 params.num_individuals_per_parents,
             ))
-            // .with_crossover(PartiallyMappedCrossover::new())
-            // .with_crossover(MyCrossover::new())
-            .with_crossover(NoOpCrossover::new())
-            .with_mutation(SwapOrderMutator::new(params.mutation_rate))
-            .with_reinsertion(UniformReinserter::new(params.reinsertion_ratio))
+            .with_crossover(ConfigurableCrossover::new(params.crossover, pm.clone()))
+            .with_mutation(DistanceLimitedMutator::new(
+                params.mutation_rate,
+                pm.clone(),
+            ))
+            .with_reinsertion(ConfigurableReinserter::new(
+                params.reinsertion,
+                params.reinsertion_ratio,
+                params.elitism,
+            ))
             .with_initial_population(initial_population)
             .build(),
     )
     .until(GenerationLimit::new(params.generation_limit))
     .build();
 
-    (sim, pm)
+    Ok((sim, pm, curriculum))
 }
 
+/// Runs the genetic optimization and returns the best layout found.
+///
+/// Milestones (new best layouts, the final result) are logged at the `Info` level and a
+/// summary of each generation at the `Debug` level.
+///
+/// If a `stop` flag is given, it is checked after each generation. As soon as it is set, the
+/// optimization ends and the best layout found so far is returned. This allows callers to wire
+/// up their own signal handling, e.g. by setting the flag from a handler registered with
+/// `ctrlc::set_handler` (instead of exiting the process there).
+#[allow(clippy::too_many_arguments)]
 pub fn optimize(
     params: &Parameters,
     evaluator: &Evaluator,
@@ -283,33 +943,62 @@ pub fn optimize(
     fixed_characters: &str,
     start_with_layout: bool,
     cache_results: bool,
-) -> (String, Layout) {
-    let (mut sim, pm) = init_optimization(
+    stop: Option<Arc<AtomicBool>>,
+) -> Result<(String, Layout), OptimizationError> {
+    let result_cache = if cache_results {
+        Some(Cache::new())
+    } else {
+        None
+    };
+    let (mut sim, pm, curriculum) = init_optimization_with_cache(
         params,
         evaluator,
         layout_str,
-        layout_generator,
+        layout_generator.as_ref(),
         fixed_characters,
         start_with_layout,
-        cache_results,
-    );
+        result_cache.clone(),
+    )?;
+
+    let mut best_layout_log = params.best_layout_log.as_ref().and_then(|path| {
+        BestLayoutLog::append_to("genetic", path)
+            .map_err(|e| log::error!("Could not open the best layout log '{}': {:?}", path, e))
+            .ok()
+    });
+    let mut record_best = |generation: u64, cost: f64, layout_str: &str| {
+        if let Some(best_layout_log) = best_layout_log.as_mut() {
+            if let Err(e) = best_layout_log.record(generation, cost, layout_str) {
+                log::error!("Could not write to the best layout log: {:?}", e);
+            }
+        }
+    };
 
     log::info!("Starting optimization with: {:?}", params);
     let mut all_time_best: Option<(usize, Genotype)> = None;
+    let mut last_best: Option<Genotype> = None;
 
     loop {
         let result = sim.step();
+        curriculum.step();
         match result {
             Ok(SimResult::Intermediate(step)) => {
                 let evaluated_population = step.result.evaluated_population;
+                if let Some(filename) = &params.population_dump {
+                    PopulationDump::new(step.iteration, &evaluated_population, &pm)
+                        .to_yaml(filename)
+                        .unwrap_or_else(|e| {
+                            log::error!("Could not write population dump '{}': {:?}", filename, e)
+                        });
+                }
                 let best_solution = step.result.best_solution;
+                last_best = Some(best_solution.solution.genome.clone());
                 if let Some(king) = &all_time_best {
                     if best_solution.solution.fitness > king.0 {
                         let layout_str = pm.generate_string(&best_solution.solution.genome);
                         let layout = layout_generator.generate(&layout_str).unwrap();
 
                         let evaluation_result = evaluator.evaluate_layout(&layout);
-                        println!(
+                        log::info!(
                             "{}: {} (score: {})\n{}",
                             format!(
                                 "New best in generation {} (pop: {}):",
@@ -322,6 +1011,7 @@ pub fn optimize(
                             format!("{}", evaluation_result.total_cost()).yellow(),
                             layout.plot(),
                         );
+                        record_best(step.iteration, evaluation_result.total_cost(), &layout_str);
 
                         all_time_best = Some((
                             best_solution.solution.fitness,
@@ -329,12 +1019,18 @@ pub fn optimize(
                         ));
                     }
                 } else {
+                    if params.best_layout_log.is_some() {
+                        let layout_str = pm.generate_string(&best_solution.solution.genome);
+                        let layout = layout_generator.generate(&layout_str).unwrap();
+                        let cost = evaluator.evaluate_layout(&layout).total_cost();
+                        record_best(step.iteration, cost, &layout_str);
+                    }
                     all_time_best = Some((
                         best_solution.solution.fitness,
                         best_solution.solution.genome.clone(),
                     ));
                 }
-                log::info!(
+                log::debug!(
                     "{}, average_fitness: {}, \
                      best fitness: {}, all time best: {}, duration: {}, processing_time: {}, generation's best: {}",
                     format!("Generation {}:", step.iteration).yellow().bold(),
@@ -345,11 +1041,19 @@ pub fn optimize(
                     step.processing_time.fmt(),
                     pm.generate_string(&best_solution.solution.genome)
                 );
+                if stop.as_ref().map_or(false, |s| s.load(Ordering::Relaxed)) {
+                    log::info!(
+                        "{} after generation {}",
+                        "Stopped on request".green().bold(),
+                        step.iteration,
+                    );
+                    break;
+                }
             }
             Ok(SimResult::Final(step, processing_time, duration, _stop_reason)) => {
                 let layout_str = pm.generate_string(&all_time_best.as_ref().unwrap().1);
                 let layout = layout_generator.generate(&layout_str).unwrap();
-                println!(
+                log::info!(
                     "{} after generation {}, duration {}, processing time {}\n\n{}\n\n{}\n{}",
                     "Final result".green().bold(),
                     step.iteration,
@@ -359,17 +1063,197 @@ pub fn optimize(
                     layout.plot_compact(),
                     layout.plot()
                 );
+                if let Some(hit_rate) = result_cache.as_ref().and_then(|c| c.hit_rate()) {
+                    log::info!("Cache hit rate: {:.1}%", 100.0 * hit_rate);
+                }
                 break;
             }
             Err(error) => {
-                println!("{}", error);
+                log::error!("{}", error);
                 break;
             }
         }
     }
 
-    let best_layout_str = pm.generate_string(&all_time_best.as_ref().unwrap().1);
+    let mut best_layout_str = pm.generate_string(&all_time_best.as_ref().unwrap().1);
+    if let (true, Some(last_best)) = (curriculum.is_active(), last_best) {
+        // the all time best may stem from a generation with different weights, so compare it
+        // to the last generation's best with the weights at the end of the curriculum
+        let last_best_str = pm.generate_string(&last_best);
+        let final_cost = |layout_str: &str| {
+            let layout = layout_generator.generate(layout_str).unwrap();
+            curriculum.total_cost_at(&evaluator.evaluate_layout(&layout), 1.0)
+        };
+        if final_cost(&last_best_str) < final_cost(&best_layout_str) {
+            best_layout_str = last_best_str;
+        }
+    }
     let best_layout = layout_generator.generate(&best_layout_str).unwrap();
 
-    (best_layout_str, best_layout)
+    Ok((best_layout_str, best_layout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const LAYOUT: &str = "abcdefghijklmnopqrstuvwxyz";
+
+    /// Whether `genome` contains exactly the genes of `reference`.
+    fn is_permutation_of(genome: &[usize], reference: &[usize]) -> bool {
+        let mut genome = genome.to_vec();
+        genome.sort_unstable();
+        let mut reference = reference.to_vec();
+        reference.sort_unstable();
+        genome == reference
+    }
+
+    #[test]
+    fn crossovers_yield_permutations_of_the_parent_genes() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let crossover_types = [
+            CrossoverType::NoOp,
+            CrossoverType::Order,
+            CrossoverType::PartiallyMapped,
+            CrossoverType::Cycle,
+        ];
+
+        for _ in 0..200 {
+            let layout = &LAYOUT[..rng.gen_range(2..=LAYOUT.len())];
+            let fixed: String = layout.chars().filter(|_| rng.gen_bool(0.2)).collect();
+            let pm = LayoutPermutator::new(layout, &fixed);
+            let genes = pm.get_permutable_indices();
+            let mut p1 = genes.clone();
+            p1.shuffle(&mut rng);
+            let mut p2 = genes.clone();
+            p2.shuffle(&mut rng);
+
+            for crossover_type in crossover_types {
+                let children = ConfigurableCrossover::new(crossover_type, pm.clone())
+                    .crossover(vec![p1.clone(), p2.clone()], &mut rng);
+
+                assert!(!children.is_empty());
+                for child in children {
+                    assert!(
+                        is_permutation_of(&child, &genes),
+                        "{:?}: {:?} x {:?} -> {:?}",
+                        crossover_type,
+                        p1,
+                        p2,
+                        child
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn crossovers_respect_the_distance_limit() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let pm = LayoutPermutator::new(LAYOUT, "")
+            .with_max_distance(LAYOUT, 4)
+            .unwrap();
+        let genes = pm.get_permutable_indices();
+
+        for _ in 0..50 {
+            let mut p1 = genes.clone();
+            p1.shuffle(&mut rng);
+            let mut p2 = genes.clone();
+            p2.shuffle(&mut rng);
+
+            let children = ConfigurableCrossover::new(CrossoverType::PartiallyMapped, pm.clone())
+                .crossover(vec![p1, p2], &mut rng);
+            for child in children {
+                assert!(is_permutation_of(&child, &genes));
+                assert!(pm.distance(&child).unwrap() <= 4);
+            }
+        }
+    }
+
+    #[test]
+    fn repair_permutation_replaces_duplicated_and_foreign_genes() {
+        let mut rng = StdRng::seed_from_u64(2);
+
+        for _ in 0..200 {
+            let n = rng.gen_range(1..20);
+            let reference: Vec<usize> = (0..n).collect();
+            let child: Vec<usize> = (0..rng.gen_range(0..n + 3))
+                .map(|_| rng.gen_range(0..2 * n))
+                .collect();
+
+            let repaired = repair_permutation(child.clone(), &reference);
+            assert!(is_permutation_of(&repaired, &reference));
+
+            // valid genes keep their position (unless they occurred before or are cut off)
+            child
+                .iter()
+                .enumerate()
+                .take(n)
+                .filter(|(i, g)| **g < n && !child[..*i].contains(g))
+                .for_each(|(i, g)| assert_eq!(repaired[i], *g));
+        }
+
+        assert_eq!(repair_permutation(vec![2, 2, 0], &[0, 1, 2]), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn reinsertion_keeps_the_population_size_and_the_elite() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let reinsertion_types = [
+            ReinsertionType::Uniform,
+            ReinsertionType::Elitist,
+            ReinsertionType::FitnessProportional,
+        ];
+
+        for _ in 0..100 {
+            let population_size = rng.gen_range(1..30);
+            // distinct fitness values, so that the elite is unique
+            let mut fitness_values: Vec<usize> = (0..population_size).map(|f| 10 * f).collect();
+            fitness_values.shuffle(&mut rng);
+            let individuals: Vec<Genotype> = (0..population_size).map(|i| vec![i]).collect();
+            let evaluated = EvaluatedPopulation::new(
+                std::rc::Rc::new(individuals.clone()),
+                fitness_values.clone(),
+                *fitness_values.iter().max().unwrap(),
+                *fitness_values.iter().min().unwrap(),
+                fitness_values.iter().sum::<usize>() / population_size,
+            );
+            let elitism = rng.gen_range(0..population_size + 2);
+            let replace_ratio = rng.gen::<f64>();
+            let offspring: Offspring<Genotype> = (0..rng.gen_range(0..2 * population_size))
+                .map(|i| vec![population_size + i])
+                .collect();
+
+            let mut ranking: Vec<usize> = (0..population_size).collect();
+            ranking.sort_by_key(|i| std::cmp::Reverse(fitness_values[*i]));
+            let elite = &ranking[..elitism.min(population_size)];
+
+            for reinsertion_type in reinsertion_types {
+                let new_population = ConfigurableReinserter::new(
+                    reinsertion_type,
+                    replace_ratio,
+                    elitism,
+                )
+                .combine(&mut offspring.clone(), &evaluated, &mut rng);
+
+                assert_eq!(
+                    new_population.len(),
+                    population_size,
+                    "{:?} with {} offspring",
+                    reinsertion_type,
+                    offspring.len()
+                );
+                for i in elite {
+                    assert!(
+                        new_population.contains(&individuals[*i]),
+                        "{:?} lost the elite individual {}",
+                        reinsertion_type,
+                        i
+                    );
+                }
+            }
+        }
+    }
 }
@@ -1,4 +1,5 @@
 pub mod optimization;
+pub mod sampling;
 
 #[cfg(test)]
 mod tests {
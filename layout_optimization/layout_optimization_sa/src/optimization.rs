@@ -1,23 +1,34 @@
 use keyboard_layout::{layout::Layout, layout_generator::LayoutGenerator};
 use layout_evaluation::{cache::Cache, evaluation::Evaluator};
 
-use layout_optimization_common::LayoutPermutator;
+use layout_optimization_common::{
+    evaluate_cached, restricted_pool, with_home_row_pins, with_locked_lines, with_soft_pins,
+    with_zone, with_zone_fixed, BestLayoutLog, Curriculum, FitnessTransform, LayoutPermutator,
+    MetricSchedule, OptimizationError,
+};
 
 use anyhow::Result;
 use colored::Colorize;
 use rand_xoshiro::{rand_core::SeedableRng, Xoshiro256PlusPlus};
-use serde::Deserialize;
-use std::{fs::File, sync::Arc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use argmin::{
     core::{
         observers::{Observe, ObserverMode},
-        CostFunction, Error, Executor, IterState, State, KV,
+        CostFunction, Error, Executor, IterState, Problem, Solver, State, TerminationReason, KV,
     },
+    make_kv,
     solver::simulatedannealing::{Anneal, SATempFunc, SimulatedAnnealing},
 };
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Parameters {
     /// Initial temperature. Gets eventually lowered down to (almost) zero during optimization.
     pub init_temp: Option<f64>,
@@ -32,6 +43,122 @@ pub struct Parameters {
     // Parameters for the [Executor].
     /// Set maximum number of iterations (defaults to `std::u64::MAX`)
     pub max_iters: u64,
+
+    // Parameters for reheating.
+    /// Reheat if there was no new best solution after this many iterations (disabled if `None`)
+    #[serde(default)]
+    pub reheat_stall_best: Option<u64>,
+    /// When reheating, multiply the current temperature by this factor
+    #[serde(default = "default_reheat_factor")]
+    pub reheat_factor: f64,
+    /// Reheat at most this many times
+    #[serde(default = "default_max_reheats")]
+    pub max_reheats: u64,
+
+    // Parameters for randomized restarts.
+    /// Restart from a random layout (at the initial temperature) if there was no new best
+    /// solution of the current restart after this many iterations (disabled if `None`)
+    #[serde(default)]
+    pub restart_stall_best: Option<u64>,
+    /// Restart at most this many times
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u64,
+
+    // Parameters for soft pins.
+    /// Characters that should preferably stay at their position in the starting layout
+    #[serde(default)]
+    pub soft_pinned_chars: String,
+    /// Cost added for each soft-pinned character that is not at its starting position
+    #[serde(default = "default_soft_pin_cost")]
+    pub soft_pin_cost: f64,
+    /// Number of most frequent (permutable) characters that should preferably be on the home row
+    #[serde(default)]
+    pub home_row_top_k: usize,
+    /// Cost added for each of these characters that is not on the home row
+    #[serde(default = "default_home_row_pin_cost")]
+    pub home_row_pin_cost: f64,
+    /// Characters (e.g. thumb key symbols) that are only permutated among the positions they
+    /// occupy in the starting layout (e.g. thumb keys)
+    #[serde(default)]
+    pub thumb_pool: String,
+    /// Names of key clusters (see the keyboard configuration) whose characters in the starting
+    /// layout are added to the `thumb_pool`
+    #[serde(default)]
+    pub pool_clusters: Vec<String>,
+    /// Matrix rows whose characters in the starting layout are kept at their positions (in
+    /// addition to the fixed characters)
+    #[serde(default)]
+    pub lock_rows: Vec<u8>,
+    /// Matrix columns whose characters in the starting layout are kept at their positions (in
+    /// addition to the fixed characters)
+    #[serde(default)]
+    pub lock_columns: Vec<u8>,
+    /// Characters whose keys in the starting layout form the zone the optimization is
+    /// restricted to (the whole layout if empty): All other characters are kept at their
+    /// positions and ngrams involving keys outside of the zone are not evaluated
+    #[serde(default)]
+    pub zone: String,
+    /// Only consider layouts that differ from the `max_distance_reference` layout in at most
+    /// this many positions
+    #[serde(default)]
+    pub max_distance: Option<usize>,
+    /// Reference layout of the `max_distance` limit (the (first) starting layout if not given)
+    #[serde(default)]
+    pub max_distance_reference: Option<String>,
+
+    // Parameters for progressive optimization.
+    /// Phases of a progressive (coarse-to-fine) optimization (a single regular run if empty)
+    #[serde(default)]
+    pub phases: Vec<Phase>,
+
+    /// Transformation of a layout's cost into a fitness; the negated fitness is minimized
+    #[serde(default = "default_fitness_transform")]
+    pub fitness_transform: FitnessTransform,
+
+    /// Schedules scaling the weights of metrics over the iterations of a run (a "curriculum")
+    #[serde(default)]
+    pub metric_schedules: Vec<MetricSchedule>,
+
+    /// Append each new best layout to this file (see [`BestLayoutLog`])
+    #[serde(default)]
+    pub best_layout_log: Option<String>,
+}
+
+/// A phase of a progressive (coarse-to-fine) optimization. Each phase starts from the best
+/// layout of the previous one.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Phase {
+    /// Only consider the most common ngrams up to this fraction of their total weight
+    pub ngram_fraction: f64,
+    /// Maximum number of iterations of this phase
+    pub max_iters: u64,
+    /// Initial temperature of this phase (defaults to the global `init_temp`)
+    #[serde(default)]
+    pub init_temp: Option<f64>,
+}
+
+fn default_reheat_factor() -> f64 {
+    10.0
+}
+
+fn default_max_reheats() -> u64 {
+    10
+}
+
+fn default_max_restarts() -> u64 {
+    5
+}
+
+fn default_soft_pin_cost() -> f64 {
+    10.0
+}
+
+fn default_home_row_pin_cost() -> f64 {
+    10.0
+}
+
+fn default_fitness_transform() -> FitnessTransform {
+    FitnessTransform::Negate { offset: 0.0 }
 }
 
 impl Default for Parameters {
@@ -43,14 +170,91 @@ impl Default for Parameters {
             stall_accepted: 5000,
             // Parameters for the [Executor].
             max_iters: 100_000,
+            // Parameters for reheating.
+            reheat_stall_best: None,
+            reheat_factor: default_reheat_factor(),
+            max_reheats: default_max_reheats(),
+            // Parameters for randomized restarts.
+            restart_stall_best: None,
+            max_restarts: default_max_restarts(),
+            // Parameters for soft pins.
+            soft_pinned_chars: String::new(),
+            soft_pin_cost: default_soft_pin_cost(),
+            home_row_top_k: 0,
+            home_row_pin_cost: default_home_row_pin_cost(),
+            thumb_pool: String::new(),
+            pool_clusters: Vec::new(),
+            lock_rows: Vec::new(),
+            lock_columns: Vec::new(),
+            zone: String::new(),
+            max_distance: None,
+            max_distance_reference: None,
+            // Parameters for progressive optimization.
+            phases: Vec::new(),
+            fitness_transform: default_fitness_transform(),
+            metric_schedules: Vec::new(),
+            best_layout_log: None,
         }
     }
 }
 
 impl Parameters {
+    /// The parameter preset with the given name (see
+    /// [`PRESETS`](layout_optimization_common::PRESETS)). All presets let the optimizer
+    /// calculate the initial temperature. Parameters that the presets do not tune keep their
+    /// defaults.
+    pub fn preset(name: &str) -> Result<Self, OptimizationError> {
+        let (stall_accepted, max_iters, reheat_stall_best, restart_stall_best) = match name {
+            "quick" => (2000, 20_000, None, None),
+            "balanced" => (10_000, 200_000, Some(20_000), None),
+            "thorough" => (50_000, 1_000_000, Some(50_000), Some(200_000)),
+            _ => return Err(OptimizationError::UnknownPreset(name.to_string())),
+        };
+
+        Ok(Parameters {
+            init_temp: None,
+            stall_accepted,
+            max_iters,
+            reheat_stall_best,
+            restart_stall_best,
+            ..Default::default()
+        })
+    }
+
     pub fn from_yaml(filename: &str) -> Result<Self> {
         let f = File::open(filename)?;
-        Ok(serde_yaml::from_reader(f)?)
+        let params: Self = serde_yaml::from_reader(f)?;
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Checks the parameters for values that would prevent a meaningful optimization.
+    pub fn validate(&self) -> Result<(), OptimizationError> {
+        self.fitness_transform.validate()?;
+        if self.key_switches == 0 {
+            return Err(OptimizationError::InvalidParameter(
+                "key_switches",
+                "At least one key pair needs to be switched per modification".to_string(),
+            ));
+        }
+        if self.reheat_factor <= 0.0 {
+            return Err(OptimizationError::InvalidParameter(
+                "reheat_factor",
+                format!("Needs to be positive, got {}", self.reheat_factor),
+            ));
+        }
+        if let Some(phase) = self.phases.iter().find(|p| p.ngram_fraction <= 0.0) {
+            return Err(OptimizationError::InvalidParameter(
+                "phases",
+                format!(
+                    "The ngram fraction of each phase needs to be positive, got {}",
+                    phase.ngram_fraction
+                ),
+            ));
+        }
+        Curriculum::new(&self.metric_schedules, self.max_iters)?;
+
+        Ok(())
     }
     /// Makes sure that [self.init_temp] is greater than zero.
     /// => Negative values and zero get turned into `f64::MIN_POSITIVE`.
@@ -71,7 +275,9 @@ pub struct AnnealingStruct {
     permutator: LayoutPermutator,
     layout_generator: Box<dyn LayoutGenerator>,
     key_switches: usize,
+    fitness_transform: FitnessTransform,
     result_cache: Option<Cache<f64>>,
+    curriculum: Curriculum,
 }
 
 impl CostFunction for AnnealingStruct {
@@ -82,17 +288,18 @@ impl CostFunction for AnnealingStruct {
     fn cost(&self, param: &Self::Param) -> Result<Self::Output, Error> {
         let evaluate_layout_str = |layout_str: &str| -> f64 {
             let l = self.layout_generator.generate(layout_str).unwrap();
-            self.evaluator.evaluate_layout(&l).total_cost()
+            let cost = self
+                .curriculum
+                .total_cost(&self.evaluator.evaluate_layout(&l));
+            -self.fitness_transform.fitness(cost)
         };
 
-        let layout_string = self.permutator.generate_string(param);
-        let evaluation_result = match &self.result_cache {
-            Some(result_cache) => result_cache
-                .get_or_insert_with(&layout_string, || evaluate_layout_str(&layout_string)),
-            None => evaluate_layout_str(&layout_string),
-        };
-
-        Ok(evaluation_result)
+        Ok(evaluate_cached(
+            self.result_cache.as_ref(),
+            &self.permutator,
+            param,
+            evaluate_layout_str,
+        ))
     }
 }
 
@@ -103,12 +310,226 @@ impl Anneal for AnnealingStruct {
 
     /// Anneal a parameter vector, slightly changing it.
     fn anneal(&self, param: &Self::Param, _temp: f64) -> Result<Self::Output, Error> {
+        self.curriculum.step();
         Ok(self.permutator.perform_n_swaps(param, self.key_switches))
     }
 }
 
 pub type SaIterState = IterState<Vec<usize>, (), (), (), f64>;
 
+type SaSolver = SimulatedAnnealing<f64, Xoshiro256PlusPlus>;
+
+/// A wrapper around [`SimulatedAnnealing`] that increases the temperature again ("reheating")
+/// if no new best solution has been found for a number of iterations.
+///
+/// In contrast to the reannealing of [`SimulatedAnnealing`] (which starts over at the initial
+/// temperature), a reheat multiplies the current temperature by `reheat_factor` and happens at
+/// most `max_reheats` times.
+///
+/// Optionally, it also restarts from a random layout at the initial temperature if the current
+/// restart did not find a new best solution for a number of iterations. In contrast to a
+/// reheat, a restart leaves the current layout behind. The best layout of all restarts is kept
+/// in the iteration state. Each restart may reheat up to `max_reheats` times again.
+///
+/// Reheats are reported to the observers with the keys `reheat` (whether a reheat happened
+/// in this iteration), `reheats` (number of reheats so far), and `reheat_t` (temperature after
+/// the last reheat). Restarts are reported with the keys `restart` (whether a restart happened
+/// in this iteration), `restarts` (number of restarts so far), and `best_restart` (the restart
+/// that found the overall best layout, with 0 being the initial run).
+///
+/// If a `stop` flag is given, the run is aborted (keeping the best layout so far) as soon as
+/// the flag is set.
+#[derive(Serialize)]
+struct ReheatingAnnealing {
+    solver: SaSolver,
+    rng: Xoshiro256PlusPlus,
+    stall_accepted: u64,
+    reheat_stall_best: Option<u64>,
+    reheat_factor: f64,
+    max_reheats: u64,
+    reheats: u64,
+    init_temp: f64,
+    restart_stall_best: Option<u64>,
+    max_restarts: u64,
+    restarts: u64,
+    best_restart: u64,
+    overall_best_cost: f64,
+    /// Initial temperature of the current (innermost) solver
+    segment_init_temp: f64,
+    /// Number of iterations of the current (innermost) solver
+    segment_iter: u64,
+    stall_iter_best: u64,
+    /// Best cost of the current restart
+    best_cost: f64,
+    #[serde(skip)]
+    stop: Option<Arc<AtomicBool>>,
+}
+
+impl ReheatingAnnealing {
+    fn new(init_temp: f64, params: &Parameters) -> Result<Self, Error> {
+        let mut rng = Xoshiro256PlusPlus::from_entropy();
+        let solver = Self::new_solver(init_temp, params.stall_accepted, &mut rng)?;
+
+        Ok(Self {
+            solver,
+            rng,
+            stall_accepted: params.stall_accepted,
+            reheat_stall_best: params.reheat_stall_best,
+            reheat_factor: params.reheat_factor,
+            max_reheats: params.max_reheats,
+            reheats: 0,
+            init_temp,
+            restart_stall_best: params.restart_stall_best,
+            max_restarts: params.max_restarts,
+            restarts: 0,
+            best_restart: 0,
+            overall_best_cost: f64::INFINITY,
+            segment_init_temp: init_temp,
+            segment_iter: 0,
+            stall_iter_best: 0,
+            best_cost: f64::INFINITY,
+            stop: None,
+        })
+    }
+
+    fn with_stop(mut self, stop: Option<Arc<AtomicBool>>) -> Self {
+        self.stop = stop;
+        self
+    }
+
+    fn stop_requested(&self) -> bool {
+        self.stop
+            .as_ref()
+            .map_or(false, |stop| stop.load(Ordering::Relaxed))
+    }
+
+    fn new_solver(
+        init_temp: f64,
+        stall_accepted: u64,
+        rng: &mut Xoshiro256PlusPlus,
+    ) -> Result<SaSolver, Error> {
+        Ok(
+            SimulatedAnnealing::new_with_rng(init_temp, Xoshiro256PlusPlus::from_rng(rng)?)?
+                // Optional: Define temperature function (defaults to `SATempFunc::TemperatureFast`)
+                .with_temp_func(SATempFunc::TemperatureFast)
+                /////////////////////////
+                // Stopping criteria   //
+                /////////////////////////
+                // Optional: stop if there was no accepted solution after [params.stall_accepted] iterations
+                .with_stall_accepted(stall_accepted),
+        )
+    }
+
+    /// The temperature the current solver will use in its next iteration
+    /// (see [`SATempFunc::TemperatureFast`]).
+    fn current_temp(&self) -> f64 {
+        self.segment_init_temp / (self.segment_iter + 1) as f64
+    }
+
+    fn reheat(&mut self) -> Result<bool, Error> {
+        let reheat_stall_best = match self.reheat_stall_best {
+            Some(n) => n,
+            None => return Ok(false),
+        };
+        if self.stall_iter_best < reheat_stall_best || self.reheats >= self.max_reheats {
+            return Ok(false);
+        }
+
+        let temp = self.current_temp() * self.reheat_factor;
+        self.solver = Self::new_solver(temp, self.stall_accepted, &mut self.rng)?;
+        self.segment_init_temp = temp;
+        self.segment_iter = 0;
+        self.stall_iter_best = 0;
+        self.reheats += 1;
+
+        Ok(true)
+    }
+
+    /// Restart from a random layout if the current restart stagnates. Returns the new state
+    /// and whether a restart happened.
+    fn restart(
+        &mut self,
+        problem: &mut Problem<AnnealingStruct>,
+        state: SaIterState,
+    ) -> Result<(SaIterState, bool), Error> {
+        let restart_stall_best = match self.restart_stall_best {
+            Some(n) => n,
+            None => return Ok((state, false)),
+        };
+        if self.stall_iter_best < restart_stall_best || self.restarts >= self.max_restarts {
+            return Ok((state, false));
+        }
+
+        let param = problem.problem("restart_count", |p| Ok(p.permutator.generate_random()))?;
+        let cost = problem.cost(&param)?;
+        self.solver = Self::new_solver(self.init_temp, self.stall_accepted, &mut self.rng)?;
+        self.segment_init_temp = self.init_temp;
+        self.segment_iter = 0;
+        self.stall_iter_best = 0;
+        self.best_cost = cost;
+        self.reheats = 0;
+        self.restarts += 1;
+
+        Ok((state.param(param).cost(cost), true))
+    }
+}
+
+impl Solver<AnnealingStruct, SaIterState> for ReheatingAnnealing {
+    const NAME: &'static str = "Simulated Annealing with Reheating";
+
+    fn init(
+        &mut self,
+        problem: &mut Problem<AnnealingStruct>,
+        state: SaIterState,
+    ) -> Result<(SaIterState, Option<KV>), Error> {
+        let (state, kv) = self.solver.init(problem, state)?;
+        self.best_cost = state.get_cost();
+        self.overall_best_cost = state.get_cost();
+
+        Ok((state, kv))
+    }
+
+    fn next_iter(
+        &mut self,
+        problem: &mut Problem<AnnealingStruct>,
+        state: SaIterState,
+    ) -> Result<(SaIterState, Option<KV>), Error> {
+        let (state, kv) = self.solver.next_iter(problem, state)?;
+        self.segment_iter += 1;
+
+        if state.get_cost() < self.best_cost {
+            self.best_cost = state.get_cost();
+            self.stall_iter_best = 0;
+        } else {
+            self.stall_iter_best += 1;
+        }
+        if state.get_cost() < self.overall_best_cost {
+            self.overall_best_cost = state.get_cost();
+            self.best_restart = self.restarts;
+        }
+
+        let (state, restart) = self.restart(problem, state)?;
+        let reheat = !restart && self.reheat()?;
+        let reheat_kv = make_kv!(
+            "reheat" => reheat;
+            "reheats" => self.reheats;
+            "reheat_t" => self.segment_init_temp;
+            "restart" => restart;
+            "restarts" => self.restarts;
+            "best_restart" => self.best_restart;
+        );
+
+        Ok((state, Some(kv.unwrap_or_default().merge(reheat_kv))))
+    }
+
+    fn terminate(&mut self, state: &SaIterState) -> TerminationReason {
+        if self.stop_requested() {
+            return TerminationReason::Aborted;
+        }
+        <SaSolver as Solver<AnnealingStruct, SaIterState>>::terminate(&mut self.solver, state)
+    }
+}
+
 /// An observer that outputs important information in a more human-readable format than `Argmin`'s original implementation.
 struct BestObserver {
     id: String,
@@ -135,6 +556,90 @@ impl Observe<SaIterState> for BestObserver {
     }
 }
 
+/// An observer that appends each new best layout (with its minimized cost) to a
+/// [`BestLayoutLog`].
+struct BestLogObserver {
+    log: BestLayoutLog,
+    permutator: LayoutPermutator,
+}
+
+impl Observe<SaIterState> for BestLogObserver {
+    fn observe_iter(&mut self, state: &SaIterState, _kv: &KV) -> Result<(), Error> {
+        let best_layout = self
+            .permutator
+            .generate_string(state.best_param.as_ref().unwrap());
+        if let Err(e) = self.log.record(state.iter, state.best_cost, &best_layout) {
+            log::error!("Could not write to the best layout log: {:?}", e);
+        }
+        Ok(())
+    }
+}
+
+/// An observer that reports reheats of the temperature.
+struct ReheatObserver {
+    id: String,
+}
+
+impl Observe<SaIterState> for ReheatObserver {
+    fn observe_iter(&mut self, state: &SaIterState, kv: &KV) -> Result<(), Error> {
+        let reheated = kv
+            .kv
+            .iter()
+            .any(|(key, value)| *key == "reheat" && value.to_string() == "true");
+        if reheated {
+            let mut temperature = String::from("Not found.");
+            let mut reheats = String::from("Not found.");
+            for (key, value) in &kv.kv {
+                match *key {
+                    "reheat_t" => temperature = format!("{:.5}", value),
+                    "reheats" => reheats = value.to_string(),
+                    _ => {}
+                }
+            }
+            log::info!(
+                "{} {} no new best for a while at iteration {}, temperature raised to {}° (reheat {})",
+                format!("{}:", self.id).yellow().bold(),
+                "Reheating:".magenta(),
+                state.iter,
+                temperature,
+                reheats,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// An observer that reports restarts from random layouts.
+struct RestartObserver {
+    id: String,
+}
+
+impl Observe<SaIterState> for RestartObserver {
+    fn observe_iter(&mut self, state: &SaIterState, kv: &KV) -> Result<(), Error> {
+        let restarted = kv
+            .kv
+            .iter()
+            .any(|(key, value)| *key == "restart" && value.to_string() == "true");
+        if restarted {
+            let restarts = kv
+                .kv
+                .iter()
+                .find(|(key, _)| *key == "restarts")
+                .map(|(_, value)| value.to_string())
+                .unwrap_or_else(|| String::from("Not found."));
+            log::info!(
+                "{} {} no new best for a while at iteration {}, restarting from a random layout ({:>6.1}) (restart {})",
+                format!("{}:", self.id).yellow().bold(),
+                "Restarting:".magenta(),
+                state.iter,
+                state.cost,
+                restarts,
+            );
+        }
+        Ok(())
+    }
+}
+
 pub struct CustomObserver(pub Box<dyn Observe<SaIterState>>);
 
 /// Necessary to avoid errors when importing a `custom_observer` to `optimize()`.
@@ -145,15 +650,20 @@ impl Observe<SaIterState> for CustomObserver {
 }
 
 /// An observer that outputs important information in a more human-readable format than `Argmin`'s original implementation.
+///
+/// Every 100th iteration is logged at the `Debug` level, all others at the `Trace` level.
 struct IterationObserver {
     id: String,
     permutator: LayoutPermutator,
-    log_everything: bool,
 }
 
 impl Observe<SaIterState> for IterationObserver {
     fn observe_iter(&mut self, state: &SaIterState, kv: &KV) -> Result<(), Error> {
-        if state.iter > 0 {
+        let level = match state.iter % 100 {
+            0 => log::Level::Debug,
+            _ => log::Level::Trace,
+        };
+        if state.iter > 0 && log::log_enabled!(level) {
             let layout = self
                 .permutator
                 .generate_string(state.param.as_ref().unwrap());
@@ -196,7 +706,7 @@ impl Observe<SaIterState> for IterationObserver {
                 "temp:".bold(),
                 temperature,
             );
-            if self.log_everything {
+            if log::log_enabled!(log::Level::Trace) {
                 let is_better = state.cost < state.prev_cost;
                 output.push_str(&format!(
                     " {} {}{} {} {}",
@@ -207,7 +717,7 @@ impl Observe<SaIterState> for IterationObserver {
                     accepted
                 ));
             }
-            log::info!("{}", output);
+            log::log!(level, "{}", output);
         }
         Ok(())
     }
@@ -228,8 +738,9 @@ fn get_cost_sd(
     initial_indices: &[usize],
     evaluator: Arc<Evaluator>,
     permutator: &LayoutPermutator,
-    layout_generator: &Box<dyn LayoutGenerator>,
+    layout_generator: &dyn LayoutGenerator,
     key_pair_switches: usize,
+    fitness_transform: &FitnessTransform,
 ) -> f64 {
     const USED_NEIGHBORS: u16 = 100;
 
@@ -243,7 +754,7 @@ fn get_cost_sd(
             .generate(&permutator.generate_string(&current_indices))
             .unwrap();
         let evaluation_result = evaluator.evaluate_layout(&layout);
-        costs.push(evaluation_result.total_cost());
+        costs.push(-fitness_transform.fitness(evaluation_result.total_cost()));
         current_indices = permutator.perform_n_swaps(&current_indices, key_pair_switches);
     }
     let average: f64 = mean(&costs);
@@ -258,6 +769,21 @@ fn get_cost_sd(
 }
 
 /// Performs one run of Simulated Annealing, then returns the best layout found.
+///
+/// If `params.phases` is not empty, a progressive optimization is performed instead: Each phase
+/// runs on a reduced set of the most common ngrams and starts from the best layout of the
+/// previous phase. Soft pins always refer to the given `layout_str`. A `result_cache` and
+/// `custom_observer` are only used in the last phase (earlier phases use a separate cache).
+///
+/// Milestones (new best layouts, reheats, restarts, phases) are logged at the `Info` level,
+/// a summary of every 100th iteration at the `Debug` level, and all other iterations at the
+/// `Trace` level. `log_everything` is a convenience that raises the maximum log level to
+/// `Trace` (the logger's own filter still applies).
+///
+/// If a `stop` flag is given, it is checked in each iteration. As soon as it is set, the
+/// optimization (including all remaining phases) ends and the best layout found so far is
+/// returned. This allows callers to wire up their own signal handling, e.g. by setting the flag
+/// from a handler registered with `ctrlc::set_handler` (instead of exiting the process there).
 #[allow(clippy::too_many_arguments)]
 pub fn optimize(
     process_name: &str,
@@ -270,11 +796,152 @@ pub fn optimize(
     log_everything: bool,
     result_cache: Option<Cache<f64>>,
     custom_observer: Option<CustomObserver>,
-) -> (String, Layout) {
-    let pm = LayoutPermutator::new(layout_str, fixed_characters);
+    stop: Option<Arc<AtomicBool>>,
+) -> Result<(String, Layout), OptimizationError> {
+    if log_everything && log::max_level() < log::LevelFilter::Trace {
+        log::set_max_level(log::LevelFilter::Trace);
+    }
+
+    if params.phases.is_empty() {
+        return optimize_phase(
+            process_name,
+            params,
+            layout_str,
+            layout_str,
+            fixed_characters,
+            layout_generator.as_ref(),
+            start_with_layout,
+            evaluator,
+            result_cache,
+            custom_observer,
+            stop,
+        );
+    }
+
+    let mut result_cache = result_cache;
+    let mut custom_observer = custom_observer;
+    let mut start_with_layout = start_with_layout;
+    let mut best: Option<(String, Layout)> = None;
+    let nr_phases = params.phases.len();
+
+    for (i, phase) in params.phases.iter().enumerate() {
+        let is_last = i + 1 == nr_phases;
+
+        let mut phase_params = params.clone();
+        phase_params.max_iters = phase.max_iters;
+        if phase.init_temp.is_some() {
+            phase_params.init_temp = phase.init_temp;
+            phase_params.correct_init_temp();
+        }
+
+        let phase_evaluator = if phase.ngram_fraction < 1.0 {
+            evaluator.with_ngram_tops(phase.ngram_fraction)
+        } else {
+            evaluator.clone()
+        };
+
+        // costs of different phases are not comparable, so the given cache is only used at last
+        let phase_cache = match is_last {
+            true => result_cache.take(),
+            false => result_cache.as_ref().map(|_| Cache::new()),
+        };
+        let phase_observer = match is_last {
+            true => custom_observer.take(),
+            false => None,
+        };
+
+        log::info!(
+            "{} Starting phase {}/{} with {:.0}% of the ngram weight",
+            format!("{}:", process_name).yellow().bold(),
+            i + 1,
+            nr_phases,
+            100.0 * phase.ngram_fraction.min(1.0),
+        );
+
+        let start_layout_str = match &best {
+            Some((best_layout_str, _)) => best_layout_str.clone(),
+            None => layout_str.to_string(),
+        };
+        best = Some(optimize_phase(
+            process_name,
+            &phase_params,
+            layout_str,
+            &start_layout_str,
+            fixed_characters,
+            layout_generator.as_ref(),
+            start_with_layout,
+            &phase_evaluator,
+            phase_cache,
+            phase_observer,
+            stop.clone(),
+        )?);
+        start_with_layout = true;
+
+        if stop.as_ref().map_or(false, |s| s.load(Ordering::Relaxed)) {
+            break;
+        }
+    }
+
+    Ok(best.unwrap())
+}
+
+/// Performs one run of Simulated Annealing starting from `layout_str` (or a random permutation
+/// of it). Soft pins and home row pins refer to `reference_layout_str`.
+#[allow(clippy::too_many_arguments)]
+fn optimize_phase(
+    process_name: &str,
+    params: &Parameters,
+    reference_layout_str: &str,
+    layout_str: &str,
+    fixed_characters: &str,
+    layout_generator: &dyn LayoutGenerator,
+    start_with_layout: bool,
+    evaluator: &Evaluator,
+    result_cache: Option<Cache<f64>>,
+    custom_observer: Option<CustomObserver>,
+    stop: Option<Arc<AtomicBool>>,
+) -> Result<(String, Layout), OptimizationError> {
+    let reference_layout = layout_generator
+        .generate(reference_layout_str)
+        .map_err(|e| {
+            OptimizationError::InvalidLayout(reference_layout_str.to_string(), e.to_string())
+        })?;
+    let pool = restricted_pool(&reference_layout, &params.thumb_pool, &params.pool_clusters)?;
+    let fixed_characters = with_locked_lines(
+        &reference_layout,
+        fixed_characters,
+        &params.lock_rows,
+        &params.lock_columns,
+    )?;
+    let fixed_characters = with_zone_fixed(&reference_layout, &fixed_characters, &params.zone)?;
+    let pm = LayoutPermutator::new(layout_str, &fixed_characters).with_restricted_pool(&pool);
+    let pm = match params.max_distance {
+        Some(max_distance) => pm.with_max_distance(
+            params
+                .max_distance_reference
+                .as_deref()
+                .unwrap_or(reference_layout_str),
+            max_distance,
+        )?,
+        None => pm,
+    };
+    let evaluator = &with_zone(evaluator, &reference_layout, &params.zone)?;
+    let evaluator = &with_soft_pins(
+        evaluator,
+        &reference_layout,
+        &params.soft_pinned_chars,
+        params.soft_pin_cost,
+    );
+    let evaluator = &with_home_row_pins(
+        evaluator,
+        &reference_layout,
+        &pm.permutable_chars(),
+        params.home_row_top_k,
+        params.home_row_pin_cost,
+    );
     // Get initial Layout.
     let initial_indices = match start_with_layout {
-        true => pm.get_permutable_indices(),
+        true => pm.limit_distance(&pm.get_permutable_indices(), &mut rand::thread_rng()),
         false => pm.generate_random(),
     };
 
@@ -302,6 +969,7 @@ pub fn optimize(
                 &pm,
                 layout_generator,
                 params.key_switches,
+                &params.fitness_transform,
             );
             log::info!(
                 "{} Initial temperature = {}°",
@@ -311,26 +979,27 @@ pub fn optimize(
             init_temp
         }
     };
+    // costs change with the progress of a curriculum and can not be cached
+    let curriculum = Curriculum::new(&params.metric_schedules, params.max_iters)?;
+    let result_cache = match curriculum.is_active() {
+        true => None,
+        false => result_cache,
+    };
     let problem = AnnealingStruct {
         evaluator: Arc::new(evaluator.clone()),
         permutator: pm.clone(),
-        layout_generator: layout_generator.clone(),
+        layout_generator: layout_generator.clone_box(),
         key_switches: params.key_switches,
-        result_cache,
+        fitness_transform: params.fitness_transform,
+        result_cache: result_cache.clone(),
+        curriculum: curriculum.clone(),
     };
 
     // Create new SA solver with some parameters (see docs for details)
     // This essentially just prepares the SA solver. It is not run yet, nor does it know anything about the problem it is about to solve.
-    let rng = Xoshiro256PlusPlus::from_entropy();
-    let solver = SimulatedAnnealing::new_with_rng(init_temp, rng)
-        .unwrap()
-        // Optional: Define temperature function (defaults to `SATempFunc::TemperatureFast`)
-        .with_temp_func(SATempFunc::TemperatureFast)
-        /////////////////////////
-        // Stopping criteria   //
-        /////////////////////////
-        // Optional: stop if there was no accepted solution after [params.stall_accepted] iterations
-        .with_stall_accepted(params.stall_accepted);
+    let solver = ReheatingAnnealing::new(init_temp, params)
+        .map_err(|e| OptimizationError::InvalidParameter("init_temp", e.to_string()))?
+        .with_stop(stop);
 
     // Create and run the executor, which will apply the solver to the problem, given a starting point (`init_param`)
     let mut executor = Executor::new(problem, solver)
@@ -356,17 +1025,31 @@ pub fn optimize(
             let iter_observer = IterationObserver {
                 id: process_name.to_string(),
                 permutator: pm.clone(),
-                log_everything,
             };
-            let iter_observer_mode = if log_everything {
-                ObserverMode::Always
-            } else {
-                ObserverMode::Every(100)
+            let reheat_observer = ReheatObserver {
+                id: process_name.to_string(),
+            };
+            let restart_observer = RestartObserver {
+                id: process_name.to_string(),
             };
             // Optional: Attach a observer
             executor = executor
                 .add_observer(best_observer, ObserverMode::NewBest)
-                .add_observer(iter_observer, iter_observer_mode);
+                .add_observer(iter_observer, ObserverMode::Always)
+                .add_observer(reheat_observer, ObserverMode::Always)
+                .add_observer(restart_observer, ObserverMode::Always);
+        }
+    }
+    if let Some(path) = &params.best_layout_log {
+        match BestLayoutLog::append_to(process_name, path) {
+            Ok(log) => {
+                let log_observer = BestLogObserver {
+                    log,
+                    permutator: pm.clone(),
+                };
+                executor = executor.add_observer(log_observer, ObserverMode::NewBest);
+            }
+            Err(e) => log::error!("Could not open the best layout log '{}': {:?}", path, e),
         }
     }
 
@@ -378,9 +1061,211 @@ pub fn optimize(
     );
     let res = executor.run().unwrap();
 
-    let best_layout_param = res.state().get_best_param().unwrap();
-    let best_layout_str = pm.generate_string(best_layout_param);
+    if res.state().get_termination_reason() == TerminationReason::Aborted {
+        log::info!(
+            "{} Stopped on request after {} iterations",
+            format!("{}:", process_name).yellow().bold(),
+            res.state().get_iter(),
+        );
+    }
+
+    if let Some(hit_rate) = result_cache.as_ref().and_then(|c| c.hit_rate()) {
+        log::info!(
+            "{} Cache hit rate: {:.1}%",
+            format!("{}:", process_name).yellow().bold(),
+            100.0 * hit_rate,
+        );
+    }
+
+    if params.restart_stall_best.is_some() {
+        log::info!(
+            "{} Restarts: {}, best layout found in {}",
+            format!("{}:", process_name).yellow().bold(),
+            res.solver.restarts,
+            match res.solver.best_restart {
+                0 => "the initial run".to_string(),
+                n => format!("restart {}", n),
+            },
+        );
+    }
+
+    let mut best_layout_str = pm.generate_string(res.state().get_best_param().unwrap());
+    if curriculum.is_active() {
+        // the best cost may stem from a time with different weights, so compare the best
+        // and the final layout with the weights at the end of the curriculum
+        let final_layout_str = pm.generate_string(res.state().get_param().unwrap());
+        let final_cost = |layout_str: &str| {
+            let layout = layout_generator.generate(layout_str).unwrap();
+            curriculum.total_cost_at(&evaluator.evaluate_layout(&layout), 1.0)
+        };
+        if final_cost(&final_layout_str) < final_cost(&best_layout_str) {
+            best_layout_str = final_layout_str;
+        }
+    }
     let best_layout = layout_generator.generate(&best_layout_str).unwrap();
 
-    (best_layout_str, best_layout)
+    Ok((best_layout_str, best_layout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::{
+        config::LayoutConfig, keyboard::Keyboard, neo_layout_generator::NeoLayoutGenerator,
+    };
+    use layout_evaluation::{
+        config::EvaluationParameters,
+        ngram_mapper::on_demand_ngram_mapper::OnDemandNgramMapper,
+        ngrams::{Bigrams, Trigrams, Unigrams},
+    };
+    use std::{str::FromStr, sync::Mutex};
+
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog. Pack my box with five dozen \
+        liquor jugs! How vexingly quick daft zebras jump; the five boxing wizards jump quickly.";
+
+    /// The base layout of the `sval` keyboard.
+    const LAYOUT: &str = "q0a1zw2sbxe3dtcr4fgvuhj5miyk67onl89p={}([";
+
+    /// Records whether each iteration reheated, and the current and best costs.
+    struct RecordingObserver {
+        records: Arc<Mutex<Vec<(bool, f64, f64)>>>,
+    }
+
+    impl Observe<SaIterState> for RecordingObserver {
+        fn observe_iter(&mut self, state: &SaIterState, kv: &KV) -> Result<(), Error> {
+            let reheat = kv
+                .kv
+                .iter()
+                .any(|(key, value)| *key == "reheat" && value.to_string() == "true");
+            self.records
+                .lock()
+                .unwrap()
+                .push((reheat, state.get_cost(), state.get_best_cost()));
+            Ok(())
+        }
+    }
+
+    /// Layout generator for the `sval` keyboard and evaluator with the default metrics.
+    fn generator_and_evaluator() -> (Box<dyn LayoutGenerator>, Evaluator) {
+        let cfg =
+            LayoutConfig::from_str(include_str!("../../../config/keyboard/sval.yml")).unwrap();
+        let keyboard = Arc::new(Keyboard::from_yaml_object(cfg.keyboard));
+        let layout_generator = NeoLayoutGenerator::from_object(cfg.base_layout, keyboard);
+        let eval_params =
+            EvaluationParameters::from_str(include_str!("../../../config/evaluation/default.yml"))
+                .unwrap();
+        let ngram_mapper = OnDemandNgramMapper::with_ngrams(
+            Unigrams::from_text(TEXT).unwrap(),
+            Bigrams::from_text(TEXT).unwrap(),
+            Trigrams::from_text(TEXT).unwrap(),
+            eval_params.ngram_mapper,
+        );
+        let evaluator =
+            Evaluator::default(Box::new(ngram_mapper)).default_metrics(&eval_params.metrics);
+
+        (Box::new(layout_generator), evaluator)
+    }
+
+    fn params() -> Parameters {
+        serde_yaml::from_str(include_str!("../../../config/optimization/sa.yml")).unwrap()
+    }
+
+    #[test]
+    fn reheating_keeps_the_best_layout() {
+        let (layout_generator, evaluator) = generator_and_evaluator();
+
+        let mut params = params();
+        params.reheat_stall_best = Some(5);
+        params.reheat_factor = 2.0;
+        params.max_reheats = 3;
+
+        let pm = LayoutPermutator::new(LAYOUT, "");
+        let problem = AnnealingStruct {
+            evaluator: Arc::new(evaluator),
+            permutator: pm.clone(),
+            layout_generator,
+            key_switches: params.key_switches,
+            fitness_transform: params.fitness_transform,
+            result_cache: None,
+            curriculum: Curriculum::new(&params.metric_schedules, 200).unwrap(),
+        };
+        let solver = ReheatingAnnealing::new(5.0, &params).unwrap();
+
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let observer = RecordingObserver {
+            records: records.clone(),
+        };
+        let mut res = Executor::new(problem, solver)
+            .configure(|state| state.param(pm.get_permutable_indices()).max_iters(200))
+            .add_observer(observer, ObserverMode::Always)
+            .timer(false)
+            .run()
+            .unwrap();
+
+        let records = records.lock().unwrap();
+        assert_eq!(res.solver.reheats, 3);
+        assert_eq!(records.iter().filter(|(reheat, _, _)| *reheat).count(), 3);
+
+        // the best cost never gets worse (in particular not with a reheat) and is the lowest
+        // cost of all visited layouts
+        records.windows(2).for_each(|w| assert!(w[1].2 <= w[0].2));
+        let lowest = records
+            .iter()
+            .map(|(_, cost, _)| *cost)
+            .fold(f64::INFINITY, f64::min);
+        let best_cost = res.state.get_best_cost();
+        assert!(best_cost <= lowest);
+        let best_param = res.state.get_best_param().unwrap().clone();
+        assert_eq!(res.problem.cost(&best_param).unwrap(), best_cost);
+    }
+
+    #[test]
+    fn optimize_rejects_invalid_layouts() {
+        let (layout_generator, evaluator) = generator_and_evaluator();
+
+        let res = optimize(
+            "Test",
+            &params(),
+            "not a layout",
+            "",
+            &layout_generator,
+            true,
+            &evaluator,
+            false,
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(res, Err(OptimizationError::InvalidLayout(..))));
+    }
+
+    #[test]
+    fn optimize_rejects_invalid_parameters() {
+        let (layout_generator, evaluator) = generator_and_evaluator();
+        let mut params = params();
+        params.max_distance = Some(4);
+        params.max_distance_reference = Some("abc".to_string());
+
+        let res = optimize(
+            "Test",
+            &params,
+            LAYOUT,
+            "",
+            &layout_generator,
+            true,
+            &evaluator,
+            false,
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(
+            res,
+            Err(OptimizationError::InvalidParameter(
+                "max_distance_reference",
+                _
+            ))
+        ));
+    }
 }
@@ -0,0 +1,160 @@
+//! This module provides a Metropolis sampler over layouts. Instead of searching for the best
+//! layout, it draws layouts (approximately) proportionally to the Boltzmann distribution
+//! `exp(-cost / temperature)`, e.g. for characterizing the cost landscape around good layouts.
+//!
+//! The chain uses the same moves as the simulated annealing optimizer (swapping key pairs),
+//! but keeps its temperature constant. Consecutive states of the chain are correlated, so
+//! usually a `burn_in` (states discarded at the start) and a `thinning` (only every n-th state
+//! is recorded) should be used.
+
+use keyboard_layout::layout_generator::LayoutGenerator;
+use layout_evaluation::{cache::Cache, evaluation::Evaluator};
+use layout_optimization_common::{evaluate_cached, LayoutPermutator, OptimizationError};
+
+use anyhow::Result;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Parameters {
+    /// Temperature of the Boltzmann distribution (in units of the total cost)
+    pub temperature: f64,
+    /// Number of recorded samples
+    pub n_samples: usize,
+    /// Number of initial steps of the chain that are discarded
+    #[serde(default)]
+    pub burn_in: usize,
+    /// Record only every n-th step of the chain (after the burn-in)
+    #[serde(default = "default_thinning")]
+    pub thinning: usize,
+    /// In each proposal, swap this many key-pairs
+    #[serde(default = "default_key_switches")]
+    pub key_switches: usize,
+}
+
+fn default_thinning() -> usize {
+    1
+}
+
+fn default_key_switches() -> usize {
+    1
+}
+
+impl Parameters {
+    pub fn from_yaml(filename: &str) -> Result<Self> {
+        let f = File::open(filename)?;
+        let params: Self = serde_yaml::from_reader(f)?;
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Checks the parameters for values that would prevent a meaningful sampling.
+    pub fn validate(&self) -> Result<(), OptimizationError> {
+        if !(self.temperature.is_finite() && self.temperature > 0.0) {
+            return Err(OptimizationError::InvalidParameter(
+                "temperature",
+                format!("Needs to be finite and positive, got {}", self.temperature),
+            ));
+        }
+        if self.thinning == 0 {
+            return Err(OptimizationError::InvalidParameter(
+                "thinning",
+                "Needs to be at least 1".to_string(),
+            ));
+        }
+        if self.key_switches == 0 {
+            return Err(OptimizationError::InvalidParameter(
+                "key_switches",
+                "At least one key pair needs to be switched per proposal".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A recorded state of the chain.
+#[derive(Serialize, Clone, Debug)]
+pub struct Sample {
+    pub layout: String,
+    pub cost: f64,
+}
+
+/// The recorded states of a chain together with its proposal statistics.
+#[derive(Serialize, Clone, Debug)]
+pub struct Samples {
+    pub samples: Vec<Sample>,
+    /// Number of proposed moves (including the burn-in)
+    pub proposals: usize,
+    /// Number of accepted moves (including the burn-in)
+    pub accepted: usize,
+    /// Fraction of the proposed moves that were accepted
+    pub acceptance_rate: f64,
+}
+
+/// Runs a Metropolis chain starting from `layout_str` (keeping the `fixed_characters` in place)
+/// and returns the recorded samples. A proposal that increases the total cost by `delta` is
+/// accepted with probability `exp(-delta / temperature)`; cheaper proposals are always accepted.
+pub fn sample(
+    params: &Parameters,
+    layout_str: &str,
+    fixed_characters: &str,
+    layout_generator: &dyn LayoutGenerator,
+    evaluator: &Evaluator,
+    result_cache: Option<Cache<f64>>,
+) -> Result<Samples> {
+    params.validate()?;
+
+    // permutations of a valid layout are valid as well
+    layout_generator.generate(layout_str)?;
+    let permutator = LayoutPermutator::new(layout_str, fixed_characters);
+    let cost = |permutation: &[usize]| -> f64 {
+        evaluate_cached(
+            result_cache.as_ref(),
+            &permutator,
+            permutation,
+            |layout_str| {
+                let layout = layout_generator.generate(layout_str).unwrap();
+                evaluator.evaluate_layout(&layout).total_cost()
+            },
+        )
+    };
+
+    let mut rng = rand::thread_rng();
+    let mut current = permutator.get_permutable_indices();
+    let mut current_cost = cost(&current);
+
+    let n_steps = params.burn_in + params.n_samples * params.thinning;
+    let mut samples = Vec::with_capacity(params.n_samples);
+    let mut accepted = 0;
+    for step in 1..=n_steps {
+        let proposal = permutator.perform_n_swaps(&current, params.key_switches);
+        let proposal_cost = cost(&proposal);
+
+        let delta = proposal_cost - current_cost;
+        if delta <= 0.0 || rng.gen::<f64>() < (-delta / params.temperature).exp() {
+            current = proposal;
+            current_cost = proposal_cost;
+            accepted += 1;
+        }
+
+        if step > params.burn_in && (step - params.burn_in) % params.thinning == 0 {
+            samples.push(Sample {
+                layout: permutator.generate_string(&current),
+                cost: current_cost,
+            });
+        }
+    }
+
+    let acceptance_rate = match n_steps {
+        0 => 0.0,
+        n => accepted as f64 / n as f64,
+    };
+
+    Ok(Samples {
+        samples,
+        proposals: n_steps,
+        accepted,
+        acceptance_rate,
+    })
+}
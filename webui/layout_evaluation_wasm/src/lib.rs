@@ -1,32 +1,46 @@
+mod permalink;
 mod utils;
 
 use argmin::core::{observers::Observe, Error, State, KV};
 use genevo::prelude::*;
 use instant::Instant;
-use serde::Serialize;
-use std::{str::FromStr, sync::Arc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, str::FromStr, sync::Arc};
 use wasm_bindgen::prelude::*;
 
 use keyboard_layout::{
-    config::LayoutConfig, keyboard::Keyboard, layout::Layout, layout_generator::LayoutGenerator,
-    neo_layout_generator::NeoLayoutGenerator,
+    config::LayoutConfig,
+    key::{Finger, Hand, MatrixPosition},
+    keyboard::{Keyboard, PlotOptions},
+    layout::Layout,
+    layout_generator::LayoutGenerator,
+    neo_layout_generator::{NeoLayoutGenerator, RepairAction},
 };
 
 use layout_evaluation::{
     cache::Cache,
     config::EvaluationParameters,
-    evaluation::Evaluator,
+    evaluation::{Evaluator, ProgressCallback},
+    metric_influence::metric_influence,
+    metric_info,
+    multi_corpus::MultiCorpusEvaluator,
     ngram_mapper::on_demand_ngram_mapper::OnDemandNgramMapper,
     ngrams::{Bigrams, Trigrams, Unigrams},
-    results::EvaluationResult,
+    results::{DeadMetric, EvaluationResult, MetricContribution, RankedLayout},
+    weight_presets::{self, WEIGHT_PRESETS},
 };
 
-use layout_optimization_common::LayoutPermutator;
+use layout_optimization_common::{
+    optimal_unigram_assignment, restricted_pool, with_locked_lines, with_zone_fixed,
+    zone_positions, Curriculum, LayoutPermutator, PRESETS,
+};
 use layout_optimization_genetic::optimization as genevo_optimization;
 use layout_optimization_sa::optimization::{
     self as sa_optimization, CustomObserver as SaCustomObserver, SaIterState,
 };
 
+use permalink::Permalink;
+
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
 #[cfg(feature = "wee_alloc")]
@@ -43,26 +57,90 @@ extern "C" {
 #[derive(Debug, Clone, Serialize)]
 struct LayoutEvaluation {
     total_cost: f64,
+    contributions: Vec<MetricContribution>,
     details: EvaluationResult,
     printed: Option<String>,
     plot: Option<String>,
     layout: Option<String>,
+    /// Cost of the optimizer's reference layout (if any)
+    reference_cost: Option<f64>,
+    /// Improvement of `total_cost` over `reference_cost` in percent
+    improvement_over_reference: Option<f64>,
+    /// Theoretical lower bound of the total cost (see [`Evaluator::theoretical_lower_bound`])
+    lower_bound: Option<f64>,
+    /// Ratio of `total_cost` to `lower_bound`
+    lower_bound_ratio: Option<f64>,
+    /// Weighted metrics that did not contribute any cost (potentially misconfigured)
+    dead_metrics: Vec<DeadMetric>,
 }
 
 impl From<EvaluationResult> for LayoutEvaluation {
     fn from(res: EvaluationResult) -> Self {
         Self {
             total_cost: res.total_cost(),
+            contributions: res.contributions(),
+            lower_bound: res.lower_bound(),
+            lower_bound_ratio: res.lower_bound_ratio(),
+            dead_metrics: res.dead_metrics(),
             details: res,
             printed: None,
             plot: None,
             layout: None,
+            reference_cost: None,
+            improvement_over_reference: None,
         }
     }
 }
 
+/// The evaluation `details` of a layout (e.g. on one corpus) together with its weight when
+/// combining several evaluations.
+#[derive(Debug, Clone, Deserialize)]
+struct WeightedEvaluation {
+    details: EvaluationResult,
+    weight: f64,
+}
+
+/// An entry of the [`LayoutOptimizer`]'s elite pool.
+#[derive(Debug, Clone, Serialize)]
+struct EliteLayout {
+    layout: String,
+    total_cost: f64,
+}
+
+/// Size of the search space of an optimization.
+#[derive(Debug, Clone, Serialize)]
+struct SearchSpace {
+    permutable_keys: usize,
+    log10_size: f64,
+}
+
+/// A repaired layout string and the changes applied to it.
+#[derive(Debug, Clone, Serialize)]
+struct RepairedLayout {
+    layout: String,
+    actions: Vec<RepairAction>,
+}
+
+/// The best position for a character in a partial layout (see [`Evaluator::best_placement`]).
+#[derive(Debug, Clone, Serialize)]
+struct Placement {
+    /// Index of the position in the layout string
+    position: usize,
+    /// Change of the total cost when placing the character there
+    delta: f64,
+}
+
+/// The keys assigned to a finger (see [`Layout::keys_by_finger`]).
+#[derive(Debug, Clone, Serialize)]
+struct FingerKeys {
+    hand: Hand,
+    finger: Finger,
+    keys: Vec<(MatrixPosition, char)>,
+}
+
 #[wasm_bindgen]
 pub struct LayoutPlotter {
+    keyboard: Arc<Keyboard>,
     layout_generator: NeoLayoutGenerator,
 }
 
@@ -76,9 +154,19 @@ impl LayoutPlotter {
 
         let keyboard = Arc::new(Keyboard::from_yaml_object(layout_cfg.keyboard));
 
-        let layout_generator = NeoLayoutGenerator::from_object(layout_cfg.base_layout, keyboard);
+        let layout_generator =
+            NeoLayoutGenerator::from_object(layout_cfg.base_layout, keyboard.clone());
 
-        Ok(LayoutPlotter { layout_generator })
+        Ok(LayoutPlotter {
+            keyboard,
+            layout_generator,
+        })
+    }
+
+    /// A difficulty score for each key of the keyboard (in the order of its configuration),
+    /// independent of the layout.
+    pub fn position_difficulties(&self) -> Vec<f64> {
+        self.keyboard.position_difficulties()
     }
 
     pub fn plot(&self, layout_str: &str, layer: usize) -> Result<String, JsValue> {
@@ -89,6 +177,46 @@ impl LayoutPlotter {
             .map_err(|e| format!("Could not plot the layout: {:?}", e))?;
         Ok(layout.plot_layer(layer))
     }
+
+    /// Repair a layout string with duplicate, unsupported, or missing characters (see
+    /// [`NeoLayoutGenerator::repair_layout_string`]), e.g. to offer an "auto-fix".
+    pub fn repair_layout_string(&self, layout_str: &str) -> Result<JsValue, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let (layout, actions) = self.layout_generator.repair_layout_string(&layout_str);
+        Ok(JsValue::from_serde(&RepairedLayout { layout, actions })
+            .map_err(|e| format!("Could not serialize repaired layout: {:?}", e))?)
+    }
+
+    /// The layout string of the layout's mirror image (left and right hand swapped).
+    pub fn mirror(&self, layout_str: &str) -> Result<String, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate(&layout_str)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        let mirrored = layout
+            .mirror()
+            .map_err(|e| format!("Could not mirror the layout: {:?}", e))?;
+        Ok(mirrored.as_text())
+    }
+
+    /// Plot a layer with the given [`PlotOptions`], e.g. `{cell_width: 3, labels: {"⇩": "L2"}}`.
+    pub fn plot_with_options(
+        &self,
+        layout_str: &str,
+        layer: usize,
+        plot_options: JsValue,
+    ) -> Result<String, JsValue> {
+        let plot_options: PlotOptions = plot_options
+            .into_serde()
+            .map_err(|e| format!("Could not read plot options: {:?}", e))?;
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate_unchecked(&layout_str)
+            .map_err(|e| format!("Could not plot the layout: {:?}", e))?;
+        Ok(layout.plot_layer_with_options(layer, &plot_options))
+    }
 }
 
 #[wasm_bindgen]
@@ -98,6 +226,7 @@ pub struct NgramProvider {
 
 #[wasm_bindgen]
 impl NgramProvider {
+    /// Ngram orders that are not used by any active metric may be passed as empty strings.
     pub fn with_frequencies(
         eval_params_str: &str,
         unigrams_str: &str,
@@ -120,6 +249,9 @@ impl NgramProvider {
             bigrams = bigrams.increase_common(&ngrams_config.increase_common_ngrams);
             trigrams = trigrams.increase_common(&ngrams_config.increase_common_ngrams);
         }
+        if let Some(fraction) = ngrams_config.trigram_fraction {
+            trigrams = trigrams.tops_scaled(fraction);
+        }
 
         let ngram_provider =
             OnDemandNgramMapper::with_ngrams(unigrams, bigrams, trigrams, eval_params.ngram_mapper);
@@ -128,34 +260,69 @@ impl NgramProvider {
     }
 
     pub fn with_text(eval_params_str: &str, text: &str) -> Result<NgramProvider, JsValue> {
-        let mut unigrams = Unigrams::from_text(text)
+        let eval_params: EvaluationParameters = serde_yaml::from_str(eval_params_str)
+            .map_err(|e| format!("Could not read evaluation parameters: {:?}", e))?;
+
+        let text = eval_params
+            .ngram_mapper
+            .unicode_normalization
+            .normalize_text(text);
+        let mut unigrams = Unigrams::from_text(&text)
             .map_err(|e| format!("Could not generate unigrams from text: {:?}", e))?;
-        let mut bigrams = Bigrams::from_text(text)
+        let mut bigrams = Bigrams::from_text(&text)
             .map_err(|e| format!("Could not generate bigrams from text: {:?}", e))?;
-        let mut trigrams = Trigrams::from_text(text)
+        let mut trigrams = Trigrams::from_text(&text)
             .map_err(|e| format!("Could not generate trigrams from text: {:?}", e))?;
 
-        let eval_params: EvaluationParameters = serde_yaml::from_str(eval_params_str)
-            .map_err(|e| format!("Could not read evaluation parameters: {:?}", e))?;
-
         let ngrams_config = eval_params.ngrams;
         if ngrams_config.increase_common_ngrams.enabled {
             unigrams = unigrams.increase_common(&ngrams_config.increase_common_ngrams);
             bigrams = bigrams.increase_common(&ngrams_config.increase_common_ngrams);
             trigrams = trigrams.increase_common(&ngrams_config.increase_common_ngrams);
         }
+        if let Some(fraction) = ngrams_config.trigram_fraction {
+            trigrams = trigrams.tops_scaled(fraction);
+        }
 
         let ngram_provider =
             OnDemandNgramMapper::with_ngrams(unigrams, bigrams, trigrams, eval_params.ngram_mapper);
 
         Ok(NgramProvider { ngram_provider })
     }
+
+    /// Set separate frequencies of base-layer symbols that were typed with shift held
+    /// (same format as in `with_frequencies`, empty strings are allowed). This needs to be
+    /// called before creating a `LayoutEvaluator` from the provider.
+    pub fn set_shifted_frequencies(
+        &mut self,
+        unigrams_str: &str,
+        bigrams_str: &str,
+        trigrams_str: &str,
+    ) -> Result<(), JsValue> {
+        let unigrams = Unigrams::from_frequencies_str(unigrams_str)
+            .map_err(|e| format!("Could not load shifted unigrams: {:?}", e))?;
+        let bigrams = Bigrams::from_frequencies_str(bigrams_str)
+            .map_err(|e| format!("Could not load shifted bigrams: {:?}", e))?;
+        let trigrams = Trigrams::from_frequencies_str(trigrams_str)
+            .map_err(|e| format!("Could not load shifted trigrams: {:?}", e))?;
+
+        self.ngram_provider
+            .set_shifted_ngrams(unigrams, bigrams, trigrams);
+
+        Ok(())
+    }
+
+    /// Estimated memory consumption (in bytes) of the loaded ngrams.
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.ngram_provider.estimated_memory_bytes()
+    }
 }
 
 #[wasm_bindgen]
 pub struct LayoutEvaluator {
     layout_generator: NeoLayoutGenerator,
     evaluator: Evaluator,
+    corpora: MultiCorpusEvaluator,
 }
 
 #[wasm_bindgen]
@@ -177,22 +344,123 @@ impl LayoutEvaluator {
         let eval_params: EvaluationParameters = serde_yaml::from_str(eval_params_str)
             .map_err(|e| format!("Could not read evaluation parameters: {:?}", e))?;
 
+        // calibration files can not be read here, only inline calibrations are supported
+        let calibration = eval_params
+            .load_calibration()
+            .map_err(|e| format!("Could not load calibration: {:?}", e))?;
         let evaluator = Evaluator::default(Box::new(ngram_provider.ngram_provider.clone()))
-            .default_metrics(&eval_params.metrics);
+            .default_metrics(&eval_params.metrics)
+            .with_typing_speed_weighting(&eval_params.typing_speed_weighting)
+            .map_err(|e| format!("Could not configure the typing-speed weighting: {}", e))?
+            .with_digit_handling(&eval_params.digits)
+            .with_calibration(calibration);
 
         Ok(LayoutEvaluator {
             layout_generator,
+            corpora: MultiCorpusEvaluator::new(evaluator.clone()),
             evaluator,
         })
     }
 
+    /// Enable or disable recording the time spent in each metric. The timings are reported
+    /// as `duration` (in seconds) of each metric's result.
+    pub fn set_profiling(&mut self, profiling: bool) {
+        self.evaluator.set_profiling(profiling);
+    }
+
+    /// Blend the evaluator's ngrams with those of a second corpus. The `ratio` (between 0 and 1)
+    /// gives the share of the second corpus and can be changed with `set_corpus_blend`.
+    ///
+    /// Both corpora are mapped to each evaluated layout, which roughly doubles the evaluation
+    /// time for ratios strictly between 0 and 1.
+    pub fn set_blend_corpus(&mut self, ngram_provider: &NgramProvider, ratio: f64) {
+        self.evaluator = self
+            .evaluator
+            .clone()
+            .with_corpus_blend(Box::new(ngram_provider.ngram_provider.clone()), ratio);
+    }
+
+    /// Change the share (between 0 and 1) of the second corpus set with `set_blend_corpus`
+    /// without rebuilding any ngrams. Optimizers use the ratio at the time of their creation,
+    /// so a running optimization needs to be restarted (e.g. from its best layout) to follow it.
+    pub fn set_corpus_blend(&mut self, ratio: f64) -> Result<(), JsValue> {
+        if self.evaluator.corpus_blend().is_none() {
+            return Err("No second corpus for blending set".into());
+        }
+        self.evaluator.set_corpus_blend(ratio);
+        Ok(())
+    }
+
+    /// Restrict the evaluation to a zone of the keyboard, given by the characters whose keys
+    /// in the given layout form it (e.g. the letters of the alpha block). Ngrams involving keys
+    /// outside of the zone are not evaluated. An empty `zone` evaluates the whole keyboard
+    /// again. To keep the characters outside of the zone fixed in an optimization, use the
+    /// `zone` of the optimization parameters.
+    pub fn set_zone(&mut self, zone: &str, layout_str: &str) -> Result<(), JsValue> {
+        if zone.is_empty() {
+            self.evaluator.set_zone(None);
+            return Ok(());
+        }
+
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate(&layout_str)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        let positions =
+            zone_positions(&layout, zone).map_err(|e| format!("Invalid zone: {}", e))?;
+        self.evaluator.set_zone(Some(&positions));
+
+        Ok(())
+    }
+
+    /// Evaluate a single (active) metric, identified by its id in the evaluation parameters.
+    pub fn evaluate_metric(&self, layout_str: &str, metric_id: &str) -> Result<JsValue, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate(&layout_str)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        let res = self
+            .evaluator
+            .evaluate_metric(&layout, metric_id)
+            .map_err(|e| format!("Could not evaluate metric: {:?}", e))?;
+
+        Ok(JsValue::from_serde(&res)
+            .map_err(|e| format!("Could not serialize metric result: {:?}", e))?)
+    }
+
     pub fn evaluate(&self, layout_str: &str) -> Result<JsValue, JsValue> {
         let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
         let layout = self
             .layout_generator
             .generate(&layout_str)
             .map_err(|e| format!("Could not generate layout: {:?}", e))?;
-        let res = self.evaluator.evaluate_layout(&layout);
+        let res = self
+            .evaluator
+            .evaluate_layout(&layout)
+            .with_lower_bound(self.evaluator.theoretical_lower_bound(&layout));
+        let printed = Some(format!("{}", res));
+        let plot = Some(layout.plot());
+        let layout_str = Some(layout_str);
+
+        let mut res: LayoutEvaluation = res.into();
+        res.printed = printed;
+        res.plot = plot;
+        res.layout = layout_str;
+        Ok(JsValue::from_serde(&res)
+            .map_err(|e| format!("Could not serialize evaluation result: {:?}", e))?)
+    }
+
+    /// Evaluate a partial layout in which the `empty` character marks empty positions. The
+    /// result's details include the coverage, i.e. the symbols that are not placed.
+    pub fn evaluate_partial(&self, layout_str: &str, empty: char) -> Result<JsValue, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate_partial(&layout_str, empty)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        let res = self.evaluator.evaluate_partial_layout(&layout);
         let printed = Some(format!("{}", res));
         let plot = Some(layout.plot());
         let layout_str = Some(layout_str);
@@ -201,7 +469,129 @@ impl LayoutEvaluator {
         res.printed = printed;
         res.plot = plot;
         res.layout = layout_str;
-        Ok(JsValue::from_serde(&res).unwrap())
+        Ok(JsValue::from_serde(&res)
+            .map_err(|e| format!("Could not serialize evaluation result: {:?}", e))?)
+    }
+
+    /// Find the best empty position for placing `ch` into a partial layout in which the `empty`
+    /// character marks empty positions, e.g. to suggest where to put the next letter.
+    pub fn best_placement(
+        &self,
+        layout_str: &str,
+        empty: char,
+        ch: char,
+    ) -> Result<JsValue, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate_partial(&layout_str, empty)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        let (position, delta) = self
+            .evaluator
+            .best_placement(&layout, ch)
+            .map_err(|e| format!("Could not place '{}': {:?}", ch, e))?;
+        Ok(JsValue::from_serde(&Placement { position, delta })
+            .map_err(|e| format!("Could not serialize placement: {:?}", e))?)
+    }
+
+    /// Evaluate the layout and its mirror image (left and right hand swapped) and return the
+    /// evaluation of the cheaper one. Its `layout` tells which one that is.
+    pub fn evaluate_with_mirror(&self, layout_str: &str) -> Result<JsValue, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate(&layout_str)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        let (layout, res) = self
+            .evaluator
+            .evaluate_with_mirror(&layout)
+            .map_err(|e| format!("Could not evaluate the mirrored layout: {:?}", e))?;
+        let printed = Some(format!("{}", res));
+        let plot = Some(layout.plot());
+        let layout_str = Some(layout.as_text());
+
+        let mut res: LayoutEvaluation = res.into();
+        res.printed = printed;
+        res.plot = plot;
+        res.layout = layout_str;
+        Ok(JsValue::from_serde(&res)
+            .map_err(|e| format!("Could not serialize evaluation result: {:?}", e))?)
+    }
+
+    /// Rank an array of layout strings by their total costs (see
+    /// [`Evaluator::rank_layouts`]). Invalid layouts are listed last with their error.
+    /// An optional `progress` function is called with the number of evaluated layouts and the
+    /// total number of layouts after each evaluation.
+    pub fn rank_layouts(
+        &self,
+        layouts: JsValue,
+        progress: Option<js_sys::Function>,
+    ) -> Result<JsValue, JsValue> {
+        let layouts: Vec<String> = layouts
+            .into_serde()
+            .map_err(|e| format!("Could not read layouts: {:?}", e))?;
+        let layouts: Vec<String> = layouts
+            .iter()
+            .map(|l| l.chars().filter(|c| !c.is_whitespace()).collect())
+            .collect();
+        let layouts: Vec<&str> = layouts.iter().map(|l| l.as_str()).collect();
+
+        let report = progress.map(|progress| {
+            move |done: usize, total: usize| {
+                let this = JsValue::null();
+                let _ = progress.call2(&this, &JsValue::from(done), &JsValue::from(total));
+            }
+        });
+        let ranked = self.evaluator.rank_layouts_with_progress(
+            &layouts,
+            &self.layout_generator,
+            report.as_ref().map(|r| r as &ProgressCallback),
+        );
+        Ok(JsValue::from_serde(&ranked)
+            .map_err(|e| format!("Could not serialize ranking: {:?}", e))?)
+    }
+
+    /// Analyze the influence of each metric on a ranking as returned by `rank_layouts` (see
+    /// [`metric_influence`]) for a relative weight `perturbation` (e.g. 0.1 for ±10%). The
+    /// metrics are sorted by their influence.
+    pub fn metric_influence(
+        &self,
+        ranking: JsValue,
+        perturbation: f64,
+    ) -> Result<JsValue, JsValue> {
+        let ranking: Vec<RankedLayout> = ranking
+            .into_serde()
+            .map_err(|e| format!("Could not read ranking: {:?}", e))?;
+        let influences = metric_influence(&ranking, perturbation);
+        Ok(JsValue::from_serde(&influences)
+            .map_err(|e| format!("Could not serialize metric influences: {:?}", e))?)
+    }
+
+    /// Add a named corpus (e.g. a language) for `evaluate_per_corpus`. A previously added
+    /// corpus of the same name is replaced. The corpus is evaluated with the metrics of this
+    /// evaluator (independently of its own corpus and blend).
+    pub fn add_breakdown_corpus(&mut self, name: &str, ngram_provider: &NgramProvider) {
+        self.corpora
+            .add_corpus(name, Box::new(ngram_provider.ngram_provider.clone()));
+    }
+
+    /// Evaluate a layout on each corpus added with `add_breakdown_corpus` (see
+    /// [`MultiCorpusEvaluator`]). Returns an object mapping the corpus names to evaluations.
+    pub fn evaluate_per_corpus(&self, layout_str: &str) -> Result<JsValue, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate(&layout_str)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        let res: BTreeMap<String, LayoutEvaluation> = self
+            .corpora
+            .evaluate_layout(&layout)
+            .into_iter()
+            .map(|(name, res)| (name, res.into()))
+            .collect();
+
+        Ok(JsValue::from_serde(&res)
+            .map_err(|e| format!("Could not serialize evaluation results: {:?}", e))?)
     }
 
     pub fn plot(&self, layout_str: &str, layer: usize) -> Result<String, JsValue> {
@@ -213,9 +603,84 @@ impl LayoutEvaluator {
         Ok(layout.plot_layer(layer))
     }
 
-    pub fn permutable_keys(&self) -> JsValue {
+    /// The keys (matrix position and base layer symbol) assigned to each finger of each hand.
+    pub fn keys_by_finger(&self, layout_str: &str) -> Result<JsValue, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate(&layout_str)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        let finger_keys: Vec<FingerKeys> = layout
+            .keys_by_finger()
+            .into_iter()
+            .map(|((hand, finger), keys)| FingerKeys { hand, finger, keys })
+            .collect();
+
+        Ok(JsValue::from_serde(&finger_keys)
+            .map_err(|e| format!("Could not serialize keys by finger: {:?}", e))?)
+    }
+
+    /// The number of presses of each key (raw unigram frequencies, without costs).
+    pub fn key_presses(&self, layout_str: &str) -> Result<JsValue, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate(&layout_str)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        let key_presses = self.evaluator.key_presses(&layout);
+
+        Ok(JsValue::from_serde(&key_presses)
+            .map_err(|e| format!("Could not serialize key presses: {:?}", e))?)
+    }
+
+    /// The costs attributed to each character of the layout
+    /// (see [`Evaluator::evaluate_layout_with_char_costs`]).
+    pub fn char_costs(&self, layout_str: &str) -> Result<JsValue, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate(&layout_str)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        let res = self.evaluator.evaluate_layout_with_char_costs(&layout);
+
+        Ok(JsValue::from_serde(&res.char_costs())
+            .map_err(|e| format!("Could not serialize character costs: {:?}", e))?)
+    }
+
+    /// A theoretical lower bound of the total cost of any permutation of the layout
+    /// (see [`Evaluator::theoretical_lower_bound`]).
+    pub fn theoretical_lower_bound(&self, layout_str: &str) -> Result<f64, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let layout = self
+            .layout_generator
+            .generate(&layout_str)
+            .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+        Ok(self.evaluator.theoretical_lower_bound(&layout))
+    }
+
+    /// The layout string resulting from optimally assigning the non-fixed characters to the
+    /// keys with respect to unigram frequencies and key costs only (see
+    /// [`optimal_unigram_assignment`]). Serves as a fast baseline or start layout.
+    pub fn optimal_unigram_assignment(
+        &self,
+        layout_str: &str,
+        fixed_characters: &str,
+    ) -> Result<String, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let (layout_str, _) = optimal_unigram_assignment(
+            &self.evaluator,
+            &self.layout_generator,
+            &layout_str,
+            fixed_characters,
+        )
+        .map_err(|e| format!("Could not assign the layout's characters: {:?}", e))?;
+        Ok(layout_str)
+    }
+
+    pub fn permutable_keys(&self) -> Result<JsValue, JsValue> {
         let permutable_keys = self.layout_generator.permutable_keys();
-        JsValue::from_serde(&permutable_keys).unwrap()
+        Ok(JsValue::from_serde(&permutable_keys)
+            .map_err(|e| format!("Could not serialize permutable keys: {:?}", e))?)
     }
 }
 
@@ -224,19 +689,30 @@ pub struct LayoutOptimizer {
     evaluator: Evaluator,
     simulator: genevo_optimization::MySimulator,
     permutator: LayoutPermutator,
+    curriculum: Curriculum,
     layout_generator: Box<dyn LayoutGenerator>,
     all_time_best: Option<(usize, Vec<usize>)>,
+    elite: Vec<(usize, Vec<usize>)>,
+    elite_size: usize,
+    reference_cost: Option<f64>,
     parameters: genevo_optimization::Parameters,
 }
 
 #[wasm_bindgen]
 impl LayoutOptimizer {
+    /// Create a genetic optimizer. The `elite_size` best distinct layouts found so far
+    /// are kept and can be retrieved with `elite_layouts`.
+    ///
+    /// If a `reference_layout` is given, it is evaluated once and each `step` reports its
+    /// cost together with the percentual improvement of the current best layout over it.
     pub fn new(
         layout_str: &str,
         optimization_params_str: &str,
         layout_evaluator: &LayoutEvaluator,
         fixed_characters: &str,
         start_with_layout: bool,
+        elite_size: usize,
+        reference_layout: Option<String>,
     ) -> Result<LayoutOptimizer, JsValue> {
         utils::set_panic_hook();
 
@@ -245,11 +721,33 @@ impl LayoutOptimizer {
         let parameters: genevo_optimization::Parameters =
             serde_yaml::from_str(optimization_params_str)
                 .map_err(|e| format!("Could not read optimization params: {:?}", e))?;
+        parameters
+            .validate()
+            .map_err(|e| format!("Invalid optimization params: {}", e))?;
 
         let layout_generator: Box<dyn LayoutGenerator> =
             Box::new(layout_evaluator.layout_generator.clone());
 
-        let (simulator, permutator) = genevo_optimization::init_optimization(
+        let reference_cost = match reference_layout {
+            Some(reference_layout) => {
+                let reference_layout: String = reference_layout
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect();
+                let layout = layout_generator
+                    .generate(&reference_layout)
+                    .map_err(|e| format!("Could not generate reference layout: {:?}", e))?;
+                Some(
+                    layout_evaluator
+                        .evaluator
+                        .evaluate_layout(&layout)
+                        .total_cost(),
+                )
+            }
+            None => None,
+        };
+
+        let (simulator, permutator, curriculum) = genevo_optimization::init_optimization(
             &parameters,
             &layout_evaluator.evaluator,
             &layout_str,
@@ -257,45 +755,55 @@ impl LayoutOptimizer {
             fixed_characters,
             start_with_layout,
             true,
-        );
+        )
+        .map_err(|e| format!("Could not initialize the optimization: {}", e))?;
 
         Ok(LayoutOptimizer {
             evaluator: layout_evaluator.evaluator.clone(),
             simulator,
             permutator,
+            curriculum,
             layout_generator,
             all_time_best: None,
+            elite: Vec::with_capacity(elite_size + 1),
+            elite_size,
+            reference_cost,
             parameters,
         })
     }
 
-    pub fn parameters(&self) -> JsValue {
-        JsValue::from_serde(&self.parameters).unwrap()
+    pub fn parameters(&self) -> Result<JsValue, JsValue> {
+        Ok(JsValue::from_serde(&self.parameters)
+            .map_err(|e| format!("Could not serialize optimization params: {:?}", e))?)
     }
 
     pub fn step(&mut self) -> Result<JsValue, JsValue> {
         let result = self.simulator.step();
+        self.curriculum.step();
         match result {
             Ok(SimResult::Intermediate(step)) => {
+                let population = &step.result.evaluated_population;
+                population
+                    .individuals()
+                    .iter()
+                    .zip(population.fitness_values())
+                    .for_each(|(genome, fitness)| self.update_elite(*fitness, genome));
+
                 let best_solution = step.result.best_solution;
-                if let Some(king) = &self.all_time_best {
-                    if best_solution.solution.fitness > king.0 {
-                        self.all_time_best = Some((
-                            best_solution.solution.fitness,
-                            best_solution.solution.genome,
-                        ));
-                    }
-                } else {
-                    self.all_time_best = Some((
+                let king = match self.all_time_best.take() {
+                    Some(king) if king.0 >= best_solution.solution.fitness => king,
+                    _ => (
                         best_solution.solution.fitness,
                         best_solution.solution.genome,
-                    ));
-                }
+                    ),
+                };
+                let layout_str = self.permutator.generate_string(&king.1);
+                self.all_time_best = Some(king);
 
-                let layout_str = self
-                    .permutator
-                    .generate_string(&self.all_time_best.as_ref().unwrap().1);
-                let layout = self.layout_generator.generate(&layout_str).unwrap();
+                let layout = self
+                    .layout_generator
+                    .generate(&layout_str)
+                    .map_err(|e| format!("Could not generate layout: {:?}", e))?;
                 let res = self.evaluator.evaluate_layout(&layout);
                 let printed = Some(format!("{}", res));
                 let plot = Some(layout.plot());
@@ -304,11 +812,17 @@ impl LayoutOptimizer {
                 res.printed = printed;
                 res.plot = plot;
                 res.layout = Some(layout_str);
+                if let Some(reference_cost) = self.reference_cost {
+                    res.reference_cost = Some(reference_cost);
+                    res.improvement_over_reference =
+                        Some(100.0 * (reference_cost - res.total_cost) / reference_cost);
+                }
 
-                Ok(JsValue::from_serde(&Some(res)).unwrap())
+                Ok(JsValue::from_serde(&Some(res))
+                    .map_err(|e| format!("Could not serialize evaluation result: {:?}", e))?)
             }
             Ok(SimResult::Final(_, _, _, _)) => {
-                Ok(JsValue::from_serde(&None::<Option<EvaluationResult>>).unwrap())
+                Ok(JsValue::NULL)
                 // break
             }
             Err(error) => {
@@ -317,6 +831,48 @@ impl LayoutOptimizer {
             }
         }
     }
+
+    /// The best distinct layouts found so far (best first) together with their costs.
+    pub fn elite_layouts(&self) -> Result<JsValue, JsValue> {
+        let elite = self
+            .elite
+            .iter()
+            .map(|(_, genome)| {
+                let layout_str = self.permutator.generate_string(genome);
+                let layout = self
+                    .layout_generator
+                    .generate(&layout_str)
+                    .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+                let total_cost = self.evaluator.evaluate_layout(&layout).total_cost();
+                Ok(EliteLayout {
+                    layout: layout_str,
+                    total_cost,
+                })
+            })
+            .collect::<Result<Vec<EliteLayout>, String>>()?;
+
+        Ok(JsValue::from_serde(&elite)
+            .map_err(|e| format!("Could not serialize elite layouts: {:?}", e))?)
+    }
+}
+
+impl LayoutOptimizer {
+    /// Add a genome to the elite pool if it is among the `elite_size` best and not
+    /// already contained (genomes are permutations of the permutable keys).
+    fn update_elite(&mut self, fitness: usize, genome: &[usize]) {
+        if self.elite.len() == self.elite_size
+            && self.elite.last().map_or(true, |(f, _)| fitness <= *f)
+        {
+            return;
+        }
+        if self.elite.iter().any(|(_, g)| g == genome) {
+            return;
+        }
+
+        let pos = self.elite.partition_point(|(f, _)| *f >= fitness);
+        self.elite.insert(pos, (fitness, genome.to_vec()));
+        self.elite.truncate(self.elite_size);
+    }
 }
 
 /// An observer that outputs important information in a more human-readable format than `Argmin`'s original implementation.
@@ -336,27 +892,84 @@ impl Observe<SaIterState> for SaObserver {
             let mut t_string = String::from("Not found.");
             for (key, value) in &kv.kv {
                 if *key == "t" {
-                    let t_num: f32 = value.to_string().parse().unwrap();
-                    let t_long_str = format!("{:.3}", t_num);
-                    t_string = format!("{:.5}", t_long_str);
+                    if let Ok(t_num) = value.to_string().parse::<f32>() {
+                        let t_long_str = format!("{:.3}", t_num);
+                        t_string = format!("{:.5}", t_long_str);
+                    }
                 }
             }
             let t_js = JsValue::from(t_string);
             let _ = self.update_callback.call2(&this, &iter_js, &t_js);
         }
-        if state.is_best() && (state.param != state.prev_best_param) {
-            let this = JsValue::null();
-            let layout_js = JsValue::from(
-                self.permutator
-                    .generate_string(state.param.as_ref().unwrap()),
-            );
-            let cost_js = JsValue::from(state.cost);
-            let _ = self.new_best_callback.call2(&this, &layout_js, &cost_js);
+        if let Some(param) = state.param.as_ref() {
+            if state.is_best() && (state.param != state.prev_best_param) {
+                let this = JsValue::null();
+                let layout_js = JsValue::from(self.permutator.generate_string(param));
+                let cost_js = JsValue::from(state.cost);
+                let _ = self.new_best_callback.call2(&this, &layout_js, &cost_js);
+            }
         }
         Ok(())
     }
 }
 
+/// The number of permutable keys and the decimal logarithm of the number of possible layouts
+/// when optimizing `layout_str` with the given `fixed_characters`.
+#[wasm_bindgen]
+pub fn search_space(layout_str: &str, fixed_characters: &str) -> Result<JsValue, JsValue> {
+    let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+    let permutator = LayoutPermutator::new(&layout_str, fixed_characters);
+    let search_space = SearchSpace {
+        permutable_keys: permutator.nr_permutable_keys(),
+        log10_size: permutator.log10_search_space_size(),
+    };
+
+    Ok(JsValue::from_serde(&search_space)
+        .map_err(|e| format!("Could not serialize search space: {:?}", e))?)
+}
+
+/// Names of the optimization parameter presets (see `genetic_preset` and `sa_preset`).
+#[wasm_bindgen]
+pub fn optimization_presets() -> Result<JsValue, JsValue> {
+    Ok(JsValue::from_serde(&PRESETS)
+        .map_err(|e| format!("Could not serialize presets: {:?}", e))?)
+}
+
+/// The genetic optimization parameters of the preset with the given name as YAML, e.g. for
+/// inspecting and adjusting them before passing them to `LayoutOptimizer::new`.
+#[wasm_bindgen]
+pub fn genetic_preset(name: &str) -> Result<String, JsValue> {
+    let parameters = genevo_optimization::Parameters::preset(name)
+        .map_err(|e| format!("Could not get optimization preset: {}", e))?;
+    Ok(serde_yaml::to_string(&parameters)
+        .map_err(|e| format!("Could not serialize optimization params: {:?}", e))?)
+}
+
+/// The simulated annealing parameters of the preset with the given name as YAML, e.g. for
+/// inspecting and adjusting them before passing them to `sa_optimize`.
+#[wasm_bindgen]
+pub fn sa_preset(name: &str) -> Result<String, JsValue> {
+    let parameters = sa_optimization::Parameters::preset(name)
+        .map_err(|e| format!("Could not get optimization preset: {}", e))?;
+    Ok(serde_yaml::to_string(&parameters)
+        .map_err(|e| format!("Could not serialize optimization params: {:?}", e))?)
+}
+
+/// Names of the metric weight presets (see `apply_weight_preset`).
+#[wasm_bindgen]
+pub fn weight_presets() -> Result<JsValue, JsValue> {
+    Ok(JsValue::from_serde(&WEIGHT_PRESETS)
+        .map_err(|e| format!("Could not serialize weight presets: {:?}", e))?)
+}
+
+/// Apply the metric weight preset with the given name (e.g. "effort" or "speed") to the
+/// evaluation parameters and return the resulting parameters as YAML.
+#[wasm_bindgen]
+pub fn apply_weight_preset(eval_params_str: &str, name: &str) -> Result<String, JsValue> {
+    Ok(weight_presets::apply_weight_preset(eval_params_str, name)
+        .map_err(|e| format!("Could not apply weight preset: {}", e))?)
+}
+
 #[wasm_bindgen]
 pub fn sa_optimize(
     layout_str: &str,
@@ -366,10 +979,12 @@ pub fn sa_optimize(
     start_with_layout: bool,
     update_callback: js_sys::Function,
     new_best_callback: js_sys::Function,
-) {
+) -> Result<(), JsValue> {
     let mut parameters: sa_optimization::Parameters = serde_yaml::from_str(optimization_params_str)
-        .map_err(|e| format!("Could not read optimization params: {:?}", e))
-        .unwrap();
+        .map_err(|e| format!("Could not read optimization params: {:?}", e))?;
+    parameters
+        .validate()
+        .map_err(|e| format!("Invalid optimization params: {}", e))?;
     // Make sure the initial temperature is greater than zero.
     parameters.correct_init_temp();
 
@@ -385,8 +1000,30 @@ pub fn sa_optimize(
     });
     let _ = update_callback.call2(&this, &zero, &init_temp);
 
+    let start_layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+    let start_layout = layout_evaluator
+        .layout_generator
+        .generate(&start_layout_str)
+        .map_err(|e| format!("Could not generate layout: {:?}", e))?;
+    let pool = restricted_pool(
+        &start_layout,
+        &parameters.thumb_pool,
+        &parameters.pool_clusters,
+    )
+    .map_err(|e| format!("Invalid optimization params: {}", e))?;
+    let locked_characters = with_locked_lines(
+        &start_layout,
+        fixed_characters,
+        &parameters.lock_rows,
+        &parameters.lock_columns,
+    )
+    .map_err(|e| format!("Invalid optimization params: {}", e))?;
+    let locked_characters = with_zone_fixed(&start_layout, &locked_characters, &parameters.zone)
+        .map_err(|e| format!("Invalid optimization params: {}", e))?;
+
     let observer = SaObserver {
-        permutator: LayoutPermutator::new(layout_str, fixed_characters),
+        permutator: LayoutPermutator::new(layout_str, &locked_characters)
+            .with_restricted_pool(&pool),
         last_update_call: Instant::now(),
         update_callback: update_callback.clone(),
         new_best_callback,
@@ -407,7 +1044,67 @@ pub fn sa_optimize(
         /* log_everything: */ false,
         Some(Cache::new()),
         Some(SaCustomObserver(Box::new(observer))),
-    );
+        None,
+    )
+    .map_err(|e| format!("Could not run the optimization: {}", e))?;
     let minus_one = JsValue::from(-1);
     let _ = update_callback.call1(&this, &minus_one);
+
+    Ok(())
+}
+
+/// Combine evaluations of the same layout (a list of `details` and `weight` objects, e.g. on
+/// different corpora) into a single evaluation with weighted metric costs
+/// (see [`EvaluationResult::weighted_combine`]).
+#[wasm_bindgen]
+pub fn combine_evaluations(evaluations: JsValue) -> Result<JsValue, JsValue> {
+    let evaluations: Vec<WeightedEvaluation> = evaluations
+        .into_serde()
+        .map_err(|e| format!("Could not read evaluations: {:?}", e))?;
+    let weighted: Vec<(&EvaluationResult, f64)> =
+        evaluations.iter().map(|e| (&e.details, e.weight)).collect();
+    let res = EvaluationResult::weighted_combine(&weighted);
+    let printed = Some(format!("{}", res));
+
+    let mut res: LayoutEvaluation = res.into();
+    res.printed = printed;
+    Ok(JsValue::from_serde(&res)
+        .map_err(|e| format!("Could not serialize evaluation result: {:?}", e))?)
+}
+
+/// Metadata (id, name, description, parameters, and defaults) of all available metrics.
+/// The defaults are taken from the given evaluation parameters.
+#[wasm_bindgen]
+pub fn metric_descriptions(eval_params_str: &str) -> Result<JsValue, JsValue> {
+    let descriptions = metric_info::describe_metrics(eval_params_str)
+        .map_err(|e| format!("Could not describe metrics: {:?}", e))?;
+
+    Ok(JsValue::from_serde(&descriptions)
+        .map_err(|e| format!("Could not serialize metric descriptions: {:?}", e))?)
+}
+
+/// Encode a permalink object (`layout_config`, `eval_params_hash`, `layout`, `seed`)
+/// into a compact, URL-safe string.
+#[wasm_bindgen]
+pub fn encode_permalink(permalink: JsValue) -> Result<String, JsValue> {
+    let permalink: Permalink = permalink
+        .into_serde()
+        .map_err(|e| format!("Could not read permalink: {:?}", e))?;
+
+    Ok(permalink.encode()?)
+}
+
+/// Decode a string produced by `encode_permalink` back into the permalink object.
+#[wasm_bindgen]
+pub fn decode_permalink(permalink_str: &str) -> Result<JsValue, JsValue> {
+    let permalink = Permalink::decode(permalink_str)?;
+
+    Ok(JsValue::from_serde(&permalink)
+        .map_err(|e| format!("Could not serialize permalink: {:?}", e))?)
+}
+
+/// Hash of the evaluation parameters, as stored in permalinks.
+#[wasm_bindgen]
+pub fn eval_params_hash(eval_params_str: &str) -> String {
+    permalink::eval_params_hash(eval_params_str)
 }
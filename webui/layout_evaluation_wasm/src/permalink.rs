@@ -0,0 +1,91 @@
+//! Encoding of all information required for reproducing a result into a compact,
+//! URL-safe string that can be shared as part of a link.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+/// Version of the binary permalink format. Increase whenever [`PermalinkData`] changes.
+const PERMALINK_VERSION: u8 = 1;
+
+/// The state that is shared via a permalink (as exchanged with JavaScript).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Permalink {
+    /// Name of the layout config (keyboard) used for the evaluation
+    pub layout_config: String,
+    /// Hash of the evaluation parameters (hex string as produced by [`eval_params_hash`])
+    pub eval_params_hash: String,
+    /// The layout string
+    pub layout: String,
+    /// Seed of the optimization that produced the layout (if any)
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Compact binary representation of a [`Permalink`].
+#[derive(Debug, Serialize, Deserialize)]
+struct PermalinkData {
+    layout_config: String,
+    eval_params_hash: u64,
+    layout: String,
+    seed: Option<u64>,
+}
+
+/// Stable (FNV-1a) hash of the evaluation parameters string.
+///
+/// The standard library's hasher is not guaranteed to be stable between releases, so it
+/// is not suited for values that end up in links.
+pub fn eval_params_hash(eval_params_str: &str) -> String {
+    let hash = eval_params_str
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+    format!("{:016x}", hash)
+}
+
+impl Permalink {
+    /// Encode the permalink as URL-safe base64 string (prefixed by a version byte).
+    pub fn encode(&self) -> Result<String, String> {
+        let eval_params_hash = u64::from_str_radix(&self.eval_params_hash, 16)
+            .map_err(|e| format!("Invalid evaluation parameters hash: {:?}", e))?;
+        let data = PermalinkData {
+            layout_config: self.layout_config.clone(),
+            eval_params_hash,
+            layout: self.layout.clone(),
+            seed: self.seed,
+        };
+
+        let mut bytes = vec![PERMALINK_VERSION];
+        bincode::DefaultOptions::new()
+            .serialize_into(&mut bytes, &data)
+            .map_err(|e| format!("Could not encode permalink: {:?}", e))?;
+
+        Ok(URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    /// Decode a permalink string as produced by [`Permalink::encode`].
+    pub fn decode(permalink: &str) -> Result<Self, String> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(permalink.trim())
+            .map_err(|e| format!("Could not decode permalink: {:?}", e))?;
+
+        let (version, payload) = bytes
+            .split_first()
+            .ok_or_else(|| "Empty permalink".to_string())?;
+        if *version != PERMALINK_VERSION {
+            return Err(format!("Unsupported permalink version {}", version));
+        }
+
+        let data: PermalinkData = bincode::DefaultOptions::new()
+            .deserialize(payload)
+            .map_err(|e| format!("Could not decode permalink: {:?}", e))?;
+
+        Ok(Permalink {
+            layout_config: data.layout_config,
+            eval_params_hash: format!("{:016x}", data.eval_params_hash),
+            layout: data.layout,
+            seed: data.seed,
+        })
+    }
+}
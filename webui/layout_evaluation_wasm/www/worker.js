@@ -60,13 +60,15 @@ const evaluator = {
     )
   },
 
-  initGenLayoutOptimizer(layout, fixed_chars, optParamsStr) {
+  initGenLayoutOptimizer(layout, fixed_chars, optParamsStr, referenceLayout) {
     this.layoutOptimizer = this.wasm.LayoutOptimizer.new(
       layout,
       optParamsStr,
       this.layoutEvaluator,
       fixed_chars,
       true,
+      10,
+      referenceLayout,
     )
 
     return this.layoutOptimizer.parameters()
@@ -74,6 +76,9 @@ const evaluator = {
   genOptimizationStep() {
     return this.layoutOptimizer.step()
   },
+  genEliteLayouts() {
+    return this.layoutOptimizer.elite_layouts()
+  },
 
   evaluateLayout(layout) {
     let res = this.layoutEvaluator.evaluate(layout)
@@ -84,6 +89,10 @@ const evaluator = {
   permutableKeys() {
     return this.layoutEvaluator.permutable_keys()
   },
+
+  searchSpace(layout, fixed_chars) {
+    return this.wasm.search_space(layout, fixed_chars)
+  },
 }
 
 Comlink.expose(evaluator)
@@ -112,8 +112,15 @@ fn rocket() -> _ {
     let ngram_mapper =
         OnDemandNgramMapper::with_ngrams(unigrams, bigrams, trigrams, ngram_mapper_config);
 
-    let evaluator =
-        Evaluator::default(Box::new(ngram_mapper)).default_metrics(&eval_params.metrics);
+    let calibration = eval_params
+        .load_calibration()
+        .unwrap_or_else(|e| panic!("Could not load calibration: {:?}", e));
+    let evaluator = Evaluator::default(Box::new(ngram_mapper))
+        .default_metrics(&eval_params.metrics)
+        .with_typing_speed_weighting(&eval_params.typing_speed_weighting)
+        .unwrap_or_else(|e| panic!("Could not configure the typing-speed weighting: {}", e))
+        .with_digit_handling(&eval_params.digits)
+        .with_calibration(calibration);
 
     rocket
         .manage(evaluator)